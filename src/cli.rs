@@ -0,0 +1,282 @@
+// Command-line interface: global options plus one subcommand per way of getting a target.
+use crate::interactive::{ColorMode, ThemeChoice};
+use crate::lang::Lang;
+use crate::mi::BackendKind;
+use crate::symbols::SymbolIndexMode;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "gdb-memviz",
+    version,
+    about = "Memory visualizer REPL on top of gdb/MI",
+    override_usage = "gdb-memviz [OPTIONS] <TARGET> [ARGS]...\n       gdb-memviz [OPTIONS] <COMMAND>"
+)]
+pub struct Cli {
+    #[command(flatten)]
+    pub opts: Options,
+    #[command(subcommand)]
+    mode: Option<Mode>,
+    /// Program to debug followed by its arguments (shorthand for `run`)
+    #[arg(
+        value_name = "TARGET",
+        trailing_var_arg = true,
+        allow_hyphen_values = true
+    )]
+    command: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct Options {
+    /// Echo gdb/MI traffic to stderr
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+    /// gdb binary to launch (with --backend lldb, the lldb-mi binary)
+    #[arg(long, env = "GDB", default_value = "gdb", global = true)]
+    pub gdb: String,
+    /// Replay this rr trace directory (`rr replay -i=mi`) instead of running a TARGET
+    #[arg(long, value_name = "TRACE_DIR", global = true)]
+    pub rr: Option<String>,
+    /// Debugger speaking MI: gdb, lldb (lldb-mi, for systems without gdb)
+    #[arg(long, value_parser = parse_backend, global = true)]
+    pub backend: Option<BackendKind>,
+    /// Cap for memory dumps in bytes (0 = unlimited)
+    #[arg(long, env = "MEMVIZ_MAX_DUMP", value_name = "BYTES", global = true)]
+    pub max_dump: Option<usize>,
+    /// Seconds to wait for gdb to answer a command before interrupting it (0 = wait
+    /// forever; default 60). When given, also how long the program may run before it is
+    /// interrupted
+    #[arg(long, env = "MEMVIZ_TIMEOUT", value_name = "SECS", global = true)]
+    pub timeout: Option<u64>,
+    /// Color output: auto, always, never
+    #[arg(long, value_name = "WHEN", value_parser = parse_color, global = true)]
+    pub color: Option<ColorMode>,
+    /// Color theme: dark, light
+    #[arg(long, value_parser = parse_theme, global = true)]
+    pub theme: Option<ThemeChoice>,
+    /// TOML file overriding theme colors
+    #[arg(long, value_name = "PATH", global = true)]
+    pub theme_file: Option<String>,
+    /// Leave program output on the terminal instead of capturing it (`run` only)
+    #[arg(long, global = true)]
+    pub no_capture: bool,
+    /// Run the program on its own pseudo-terminal; its output is shown at stops and the
+    /// REPL's `input` types into it (`run` only)
+    #[arg(long, global = true, conflicts_with = "no_capture")]
+    pub tty: bool,
+    /// Extra compiler flags when TARGET is source files, e.g. "-Iinclude -lm"
+    #[arg(long, value_name = "FLAGS", allow_hyphen_values = true, global = true)]
+    pub cflags: Option<String>,
+    /// Target language: c, rust (default: detected from the executable)
+    #[arg(long, value_parser = parse_lang, global = true)]
+    pub lang: Option<Lang>,
+    /// Language standard for source targets, passed as -std= (e.g. c++17, c11)
+    #[arg(long, value_name = "STD", global = true)]
+    pub std: Option<String>,
+    /// Build command run before launching gdb and again by `run` when sources changed
+    /// ("auto" detects make or CMake)
+    #[arg(long, value_name = "CMD", global = true)]
+    pub build: Option<String>,
+    /// Environment variable for the program, KEY=VAL (repeatable; `run` only)
+    #[arg(long = "env", value_name = "KEY=VAL", value_parser = parse_env, global = true)]
+    pub envs: Vec<String>,
+    /// Working directory for the program (`run` only)
+    #[arg(long, value_name = "DIR", global = true)]
+    pub cwd: Option<String>,
+    /// File fed to the program's standard input (`run` only)
+    #[arg(long, value_name = "FILE", global = true)]
+    pub stdin: Option<String>,
+    /// Function to run to at startup instead of `main` (kept as a breakpoint for `run`)
+    #[arg(long, value_name = "FUNC", global = true)]
+    pub run_to: Option<String>,
+    /// Load the program without starting it; set breakpoints, then `run` (`run` only)
+    #[arg(long, global = true, conflicts_with_all = ["run_to", "stop_at"])]
+    pub no_run: bool,
+    /// Breakpoint to set once the program is stopped at startup (repeatable)
+    #[arg(long = "break", value_name = "LOC", global = true)]
+    pub breaks: Vec<String>,
+    /// Continue to this function after startup (temporary breakpoint)
+    #[arg(long, value_name = "FUNC", global = true)]
+    pub stop_at: Option<String>,
+    /// Print locals, globals, mem, vm and follow results as JSON lines
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// Write a timestamped transcript of commands, gdb/MI traffic and output to this file
+    #[arg(long, value_name = "FILE", global = true)]
+    pub record: Option<String>,
+    /// Replay a --record transcript without gdb (TARGET and startup options come from it)
+    #[arg(long, value_name = "FILE", global = true)]
+    pub replay: Option<String>,
+    /// Run REPL commands from this file (one per line, `#` comments) before the prompt
+    #[arg(long, value_name = "FILE", global = true)]
+    pub script: Option<String>,
+    /// Exit after --script instead of prompting; without --script, read commands from stdin
+    #[arg(long, global = true)]
+    pub batch: bool,
+    /// Symbol index scope: debug-only, debug-and-nondebug
+    #[arg(long, value_name = "MODE", value_parser = parse_symbols, global = true)]
+    pub symbols: Option<SymbolIndexMode>,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    /// Launch a program under gdb and stop at main or --run-to (the default without a subcommand)
+    Run {
+        /// Program followed by its arguments; everything after it goes to the program.
+        /// Leading .c/.cpp files are compiled with -g into a temporary binary first
+        #[arg(
+            value_name = "TARGET",
+            required = true,
+            trailing_var_arg = true,
+            allow_hyphen_values = true
+        )]
+        command: Vec<String>,
+    },
+    /// Attach to a running process
+    Attach {
+        pid: u32,
+        /// Executable for symbols (default: `/proc/<pid>/exe`)
+        #[arg(long)]
+        exe: Option<String>,
+    },
+    /// Inspect a core dump
+    Core { exe: String, core: String },
+    /// Connect to a gdbserver at HOST:PORT and stop at main or --run-to
+    Remote { address: String, exe: String },
+    /// Replay an rr trace (set with --rr rather than as a subcommand)
+    #[command(skip)]
+    Rr { trace_dir: String },
+    /// Check gdb, ptrace, terminal and compiler setup
+    Doctor,
+}
+
+impl Cli {
+    /// The selected mode; a bare target means `run`. Exits with usage when neither is given.
+    pub fn mode(&self) -> Mode {
+        match (&self.mode, self.command.is_empty(), &self.opts.rr) {
+            (Some(mode), _, _) => mode.clone(),
+            (None, true, Some(trace_dir)) => Mode::Rr {
+                trace_dir: trace_dir.clone(),
+            },
+            (None, false, _) => Mode::Run {
+                command: self.command.clone(),
+            },
+            (None, true, None) => {
+                let _ = Cli::command().print_help();
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+fn parse_color(raw: &str) -> Result<ColorMode, String> {
+    ColorMode::parse(raw).ok_or_else(|| "expected one of: auto, always, never".to_string())
+}
+
+fn parse_theme(raw: &str) -> Result<ThemeChoice, String> {
+    match ThemeChoice::parse(raw) {
+        Some(ThemeChoice::Custom) | None => Err("expected one of: dark, light".to_string()),
+        Some(choice) => Ok(choice),
+    }
+}
+
+fn parse_lang(raw: &str) -> Result<Lang, String> {
+    Lang::parse(raw).ok_or_else(|| "expected one of: c, rust".to_string())
+}
+
+fn parse_backend(raw: &str) -> Result<BackendKind, String> {
+    BackendKind::parse(raw).ok_or_else(|| "expected one of: gdb, lldb".to_string())
+}
+
+fn parse_env(raw: &str) -> Result<String, String> {
+    match raw.split_once('=') {
+        Some((key, _)) if !key.is_empty() && !key.contains(char::is_whitespace) => {
+            Ok(raw.to_string())
+        }
+        _ => Err("expected KEY=VAL".to_string()),
+    }
+}
+
+fn parse_symbols(raw: &str) -> Result<SymbolIndexMode, String> {
+    SymbolIndexMode::parse(raw)
+        .ok_or_else(|| "expected one of: debug-only, debug-and-nondebug".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::FromArgMatches;
+
+    /// Parse with the `env` fallbacks (`GDB`, `MEMVIZ_*`) cut off, so defaults do not depend
+    /// on the environment the tests run in.
+    fn try_parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        let cmd = Cli::command().mut_args(|a| a.env(None));
+        Cli::from_arg_matches(&cmd.try_get_matches_from(args)?)
+    }
+
+    fn parse(args: &[&str]) -> Cli {
+        try_parse(args).unwrap()
+    }
+
+    #[test]
+    fn cli_definition_is_consistent() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn everything_after_target_goes_to_the_program() {
+        let cli = parse(&["gdb-memviz", "-v", "./prog", "-v", "--gdb", "x"]);
+        assert!(cli.opts.verbose);
+        assert_eq!(cli.opts.gdb, "gdb");
+        let expected = Mode::Run {
+            command: vec!["./prog".into(), "-v".into(), "--gdb".into(), "x".into()],
+        };
+        assert_eq!(cli.mode(), expected);
+        let cli = parse(&["gdb-memviz", "run", "./prog", "--color", "never"]);
+        assert!(cli.opts.color.is_none());
+        assert_eq!(
+            cli.mode(),
+            Mode::Run {
+                command: vec!["./prog".into(), "--color".into(), "never".into()]
+            }
+        );
+    }
+
+    #[test]
+    fn subcommands_take_global_options() {
+        let cli = parse(&["gdb-memviz", "remote", "--run-to", "setup", "h:1", "./fw"]);
+        assert_eq!(cli.opts.run_to.as_deref(), Some("setup"));
+        assert!(parse(&["gdb-memviz", "--no-run", "./prog"]).opts.no_run);
+        assert!(parse(&["gdb-memviz", "--tty", "./prog"]).opts.tty);
+        assert!(try_parse(&["gdb-memviz", "--tty", "--no-capture", "./p"]).is_err());
+        let cli = parse(&["gdb-memviz", "--backend", "lldb", "./prog"]);
+        assert_eq!(cli.opts.backend, Some(BackendKind::Lldb));
+        let cli = parse(&["gdb-memviz", "--timeout", "0", "core", "./p", "core.1"]);
+        assert_eq!(cli.opts.timeout, Some(0));
+        let cli = parse(&["gdb-memviz", "--rr", "traces/sample-0"]);
+        assert_eq!(
+            cli.mode(),
+            Mode::Rr {
+                trace_dir: "traces/sample-0".into()
+            }
+        );
+        assert!(try_parse(&["gdb-memviz", "--no-run", "--run-to", "f", "./p"]).is_err());
+        let cli = parse(&["gdb-memviz", "attach", "--gdb", "/opt/gdb", "4242"]);
+        assert_eq!(cli.opts.gdb, "/opt/gdb");
+        assert_eq!(
+            cli.mode(),
+            Mode::Attach {
+                pid: 4242,
+                exe: None
+            }
+        );
+        let cli = parse(&["gdb-memviz", "--color", "never", "doctor"]);
+        assert_eq!(cli.mode(), Mode::Doctor);
+        assert!(try_parse(&["gdb-memviz", "--theme", "custom", "./prog"]).is_err());
+        let cli = parse(&["gdb-memviz", "--break", "a.c:3", "--break", "f", "./prog"]);
+        assert_eq!(cli.opts.breaks, vec!["a.c:3", "f"]);
+        let cli = parse(&["gdb-memviz", "--env", "A=1 2", "--env", "B=", "./prog"]);
+        assert_eq!(cli.opts.envs, vec!["A=1 2", "B="]);
+        assert!(try_parse(&["gdb-memviz", "--env", "=x", "./prog"]).is_err());
+    }
+}
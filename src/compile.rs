@@ -0,0 +1,175 @@
+// Building the target: source files compiled directly (`gdb-memviz main.c list.c`), or the
+// project's own build (`--build`), rerun by `run` when sources are newer than the binary.
+use crate::mi::Result;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+
+const C_EXTENSIONS: &[&str] = &["c"];
+const CXX_EXTENSIONS: &[&str] = &["cc", "cpp", "cxx"];
+
+fn has_extension(path: &str, exts: &[&str]) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| exts.contains(&e))
+}
+
+pub fn is_source(path: &str) -> bool {
+    has_extension(path, C_EXTENSIONS) || has_extension(path, CXX_EXTENSIONS)
+}
+
+/// Split a run command into its leading source files and the program arguments after them.
+pub fn split_sources(command: &[String]) -> (Vec<String>, Vec<String>) {
+    let n = command.iter().take_while(|a| is_source(a)).count();
+    (command[..n].to_vec(), command[n..].to_vec())
+}
+
+/// `--cflags` is one string; split it on whitespace like a simple shell would.
+pub fn split_flags(raw: Option<&str>) -> Vec<String> {
+    raw.map(|r| r.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn compiler_args(
+    sources: &[String],
+    cflags: &[String],
+    std: Option<&str>,
+    cxx: bool,
+    out: &Path,
+) -> Vec<String> {
+    let mut args: Vec<String> = ["-g", "-O0", "-o"].map(String::from).to_vec();
+    args.push(out.display().to_string());
+    if let Some(std) = std {
+        args.push(format!("-std={}", std));
+    }
+    for src in sources {
+        // The C++ driver would compile .c files as C++; keep them C.
+        if cxx && has_extension(src, C_EXTENSIONS) {
+            args.extend([
+                "-x".into(),
+                "c".into(),
+                src.clone(),
+                "-x".into(),
+                "none".into(),
+            ]);
+        } else {
+            args.push(src.clone());
+        }
+    }
+    // After the sources so `-l` libraries resolve symbols they use.
+    args.extend(cflags.iter().cloned());
+    args
+}
+
+/// Compile `sources` into one debug binary in the temp dir and return its path, with `$CC`
+/// (default cc). Any C++ source switches to `$CXX` (default g++), which also links
+/// libstdc++. Compiler diagnostics go straight to the terminal.
+pub fn compile_single_source(
+    sources: &[String],
+    cflags: &[String],
+    std: Option<&str>,
+) -> Result<PathBuf> {
+    let stem = Path::new(&sources[0])
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("a");
+    let out = std::env::temp_dir().join(format!("gdb-memviz-{}-{}", stem, std::process::id()));
+    let cxx = sources.iter().any(|s| has_extension(s, CXX_EXTENSIONS));
+    let compiler = if cxx {
+        std::env::var("CXX").unwrap_or_else(|_| "g++".to_string())
+    } else {
+        std::env::var("CC").unwrap_or_else(|_| "cc".to_string())
+    };
+    let args = compiler_args(sources, cflags, std, cxx, &out);
+    println!("# {} {}", compiler, args.join(" "));
+    let status = Command::new(&compiler)
+        .args(&args)
+        .status()
+        .map_err(|e| format!("cannot run {}: {}", compiler, e))?;
+    if !status.success() {
+        return Err(format!("compilation failed ({})", status).into());
+    }
+    Ok(out)
+}
+
+/// `--build auto`: pick the build for the project in `dir`.
+pub fn detect_build_command(dir: &Path) -> Option<String> {
+    if ["Makefile", "makefile", "GNUmakefile"]
+        .iter()
+        .any(|f| dir.join(f).is_file())
+    {
+        Some("make".to_string())
+    } else if dir.join("CMakeLists.txt").is_file() {
+        Some("cmake -S . -B build -DCMAKE_BUILD_TYPE=Debug && cmake --build build".to_string())
+    } else {
+        None
+    }
+}
+
+/// Resolve `--build`: `auto` detects the build system in the current directory.
+pub fn resolve_build_command(raw: &str) -> Result<String> {
+    if raw != "auto" {
+        return Ok(raw.to_string());
+    }
+    detect_build_command(Path::new("."))
+        .ok_or_else(|| "--build auto: no Makefile or CMakeLists.txt in this directory".into())
+}
+
+/// Run a build command through the shell; its output goes straight to the terminal.
+pub fn run_build(cmd: &str) -> Result<()> {
+    println!("# build: {}", cmd);
+    let status = Command::new("sh")
+        .args(["-c", cmd])
+        .status()
+        .map_err(|e| format!("cannot run build: {}", e))?;
+    if !status.success() {
+        return Err(format!("build failed ({})", status).into());
+    }
+    Ok(())
+}
+
+/// Sources modified after `binary` was written (or all of them if it is missing).
+pub fn stale_sources(binary: &Path, sources: &[String]) -> Vec<String> {
+    let mtime = |p: &Path| p.metadata().and_then(|m| m.modified()).ok();
+    let built = mtime(binary).unwrap_or(SystemTime::UNIX_EPOCH);
+    sources
+        .iter()
+        .filter(|s| mtime(Path::new(s)).is_some_and(|t| t > built))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources_lead_the_command_and_flags_follow_them() {
+        let cmd: Vec<String> = ["main.c", "list.cpp", "input.txt", "-v"]
+            .map(String::from)
+            .to_vec();
+        let (sources, args) = split_sources(&cmd);
+        assert_eq!(sources, vec!["main.c", "list.cpp"]);
+        assert_eq!(args, vec!["input.txt", "-v"]);
+        let flags = split_flags(Some(" -Iinclude  -lm "));
+        assert_eq!(
+            compiler_args(&sources, &flags, None, false, Path::new("/tmp/main")),
+            vec![
+                "-g",
+                "-O0",
+                "-o",
+                "/tmp/main",
+                "main.c",
+                "list.cpp",
+                "-Iinclude",
+                "-lm"
+            ]
+        );
+        let args = compiler_args(&sources, &[], Some("c++17"), true, Path::new("/tmp/m"));
+        assert_eq!(
+            args[4..],
+            ["-std=c++17", "-x", "c", "main.c", "-x", "none", "list.cpp"]
+        );
+    }
+}
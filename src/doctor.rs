@@ -0,0 +1,306 @@
+// `gdb-memviz doctor`: environment checks with actionable fixes.
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Oldest gdb with `-symbol-info-variables`, used by the symbol index.
+const MIN_GDB_MAJOR: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Ok,
+    Warn,
+    Fail,
+}
+
+struct Check {
+    level: Level,
+    name: &'static str,
+    detail: String,
+    fix: Option<String>,
+}
+
+impl Check {
+    fn new(level: Level, name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            level,
+            name,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+}
+
+/// Run every check, print a report, and return the process exit code (1 if anything failed).
+pub fn run(gdb_bin: &str) -> i32 {
+    let gdb = check_gdb(gdb_bin);
+    let mi = if gdb.level == Level::Fail {
+        Check::new(Level::Warn, "gdb/MI", "skipped (gdb not available)")
+    } else {
+        check_mi(gdb_bin)
+    };
+    let checks = vec![
+        gdb,
+        mi,
+        check_ptrace(),
+        check_debuginfod(),
+        check_terminal(),
+        check_compiler(),
+    ];
+    println!("gdb-memviz doctor");
+    for c in &checks {
+        let tag = match c.level {
+            Level::Ok => "[ok]  ",
+            Level::Warn => "[warn]",
+            Level::Fail => "[FAIL]",
+        };
+        println!("{} {:<12} {}", tag, c.name, c.detail);
+        if let Some(fix) = &c.fix {
+            println!("       {:<12} fix: {}", "", fix);
+        }
+    }
+    let failed = checks.iter().filter(|c| c.level == Level::Fail).count();
+    let warned = checks.iter().filter(|c| c.level == Level::Warn).count();
+    println!("\n{} failed, {} warning(s)", failed, warned);
+    if failed > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+fn first_line_of(cmd: &str, args: &[&str]) -> Option<String> {
+    let out = Command::new(cmd)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .next()
+        .map(|l| l.trim().to_string())
+}
+
+fn check_gdb(gdb_bin: &str) -> Check {
+    let line = match first_line_of(gdb_bin, &["--version"]) {
+        Some(l) => l,
+        None => {
+            return Check::new(
+                Level::Fail,
+                "gdb",
+                format!("'{}' not found or not runnable", gdb_bin),
+            )
+            .fix("install gdb (e.g. 'sudo apt install gdb') or pass --gdb <path> / GDB=<path>")
+        }
+    };
+    match parse_gdb_version(&line) {
+        Some((major, minor)) if major >= MIN_GDB_MAJOR => Check::new(
+            Level::Ok,
+            "gdb",
+            format!("{} ({}.{})", gdb_bin, major, minor),
+        ),
+        Some((major, minor)) => Check::new(
+            Level::Warn,
+            "gdb",
+            format!(
+                "{} {}.{} is older than {}",
+                gdb_bin, major, minor, MIN_GDB_MAJOR
+            ),
+        )
+        .fix("symbol indexing needs gdb >= 10; upgrade gdb"),
+        None => Check::new(
+            Level::Warn,
+            "gdb",
+            format!("unrecognized version line '{}'", line),
+        ),
+    }
+}
+
+fn check_mi(gdb_bin: &str) -> Check {
+    let child = Command::new(gdb_bin)
+        .args(["--nx", "-q", "--interpreter=mi2"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let mut child = match child {
+        Ok(c) => c,
+        Err(_) => return Check::new(Level::Fail, "gdb/MI", "could not start gdb in MI mode"),
+    };
+    if let Some(stdin) = child.stdin.as_mut() {
+        let _ = stdin.write_all(b"-list-features\n-gdb-exit\n");
+    }
+    let out = match child.wait_with_output() {
+        Ok(o) => String::from_utf8_lossy(&o.stdout).to_string(),
+        Err(e) => return Check::new(Level::Fail, "gdb/MI", format!("gdb exited: {}", e)),
+    };
+    if out.contains("^done,features=") {
+        Check::new(Level::Ok, "gdb/MI", "mi2 interpreter responds")
+    } else {
+        Check::new(Level::Fail, "gdb/MI", "no MI response to -list-features")
+            .fix("use a gdb build with MI support (distribution gdb packages include it)")
+    }
+}
+
+fn check_ptrace() -> Check {
+    let scope = std::fs::read_to_string("/proc/sys/kernel/yama/ptrace_scope")
+        .ok()
+        .and_then(|s| s.trim().parse::<u32>().ok());
+    let seccomp = std::fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|s| {
+            s.lines()
+                .find_map(|l| l.strip_prefix("Seccomp:"))
+                .and_then(|v| v.trim().parse::<u32>().ok())
+        });
+    if seccomp == Some(2) {
+        return Check::new(Level::Warn, "ptrace", "running under a seccomp filter")
+            .fix("in Docker, run with --cap-add=SYS_PTRACE --security-opt seccomp=unconfined");
+    }
+    ptrace_scope_check(scope)
+}
+
+fn ptrace_scope_check(scope: Option<u32>) -> Check {
+    match scope {
+        None => Check::new(Level::Ok, "ptrace", "no Yama restrictions"),
+        Some(0) | Some(1) => Check::new(
+            Level::Ok,
+            "ptrace",
+            format!(
+                "ptrace_scope={} (gdb may trace programs it starts)",
+                scope.unwrap_or(0)
+            ),
+        ),
+        Some(2) => Check::new(Level::Warn, "ptrace", "ptrace_scope=2 (admin-only attach)")
+            .fix("run as root, or 'sudo sysctl kernel.yama.ptrace_scope=1'"),
+        Some(n) => Check::new(
+            Level::Fail,
+            "ptrace",
+            format!("ptrace_scope={} (ptrace disabled)", n),
+        )
+        .fix("ptrace is disabled until reboot; set kernel.yama.ptrace_scope=1 at boot"),
+    }
+}
+
+fn check_debuginfod() -> Check {
+    match std::env::var("DEBUGINFOD_URLS") {
+        Ok(urls) if !urls.trim().is_empty() => Check::new(
+            Level::Warn,
+            "debuginfod",
+            format!(
+                "DEBUGINFOD_URLS={} (first run may download symbols or prompt)",
+                urls
+            ),
+        )
+        .fix("add 'set debuginfod enabled on' (or off) to ~/.gdbinit to avoid the prompt"),
+        _ => Check::new(
+            Level::Ok,
+            "debuginfod",
+            "not configured (library symbols stay local)",
+        ),
+    }
+}
+
+fn check_terminal() -> Check {
+    if !std::io::stdout().is_terminal() {
+        return Check::new(
+            Level::Ok,
+            "terminal",
+            "stdout is not a terminal (colors off by default)",
+        );
+    }
+    let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+    let term = std::env::var("TERM").unwrap_or_default();
+    let truecolor = matches!(colorterm.as_str(), "truecolor" | "24bit");
+    let detail = format!(
+        "TERM={} truecolor={} keyboard-enhancement={}",
+        if term.is_empty() { "?" } else { &term },
+        if truecolor { "yes" } else { "no" },
+        if supports_keyboard_enhancement() {
+            "likely"
+        } else {
+            "unknown"
+        }
+    );
+    if term.is_empty() || term == "dumb" {
+        Check::new(Level::Warn, "terminal", detail).fix("set TERM (e.g. xterm-256color) for colors")
+    } else {
+        Check::new(Level::Ok, "terminal", detail)
+    }
+}
+
+/// Terminals known to implement the kitty keyboard protocol.
+fn supports_keyboard_enhancement() -> bool {
+    let names = [
+        std::env::var("TERM").unwrap_or_default(),
+        std::env::var("TERM_PROGRAM").unwrap_or_default(),
+    ];
+    names.iter().any(|n| {
+        let n = n.to_ascii_lowercase();
+        ["kitty", "wezterm", "foot", "ghostty", "alacritty"]
+            .iter()
+            .any(|k| n.contains(k))
+    })
+}
+
+fn check_compiler() -> Check {
+    let found: Vec<&str> = ["cc", "gcc", "clang"]
+        .into_iter()
+        .filter(|c| first_line_of(c, &["--version"]).is_some())
+        .collect();
+    if found.is_empty() {
+        Check::new(Level::Warn, "compiler", "no C compiler found")
+            .fix("install gcc or clang to build targets with -g")
+    } else {
+        Check::new(Level::Ok, "compiler", found.join(", "))
+    }
+}
+
+/// Pull "major.minor" out of a `gdb --version` first line.
+fn parse_gdb_version(line: &str) -> Option<(u32, u32)> {
+    line.split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .rev()
+        .find_map(|tok| {
+            let mut parts = tok.split('.');
+            let major = parts.next()?.parse().ok()?;
+            // Distribution suffixes follow the minor number ("14.2-1.fc40").
+            let minor = parts.next()?;
+            let digits = minor
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(minor.len());
+            let minor = minor[..digits].parse().ok()?;
+            Some((major, minor))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_distribution_gdb_versions() {
+        assert_eq!(
+            parse_gdb_version("GNU gdb (Ubuntu 12.1-0ubuntu1~22.04) 12.1"),
+            Some((12, 1))
+        );
+        assert_eq!(
+            parse_gdb_version("GNU gdb (GDB) Fedora Linux 14.2-1.fc40"),
+            Some((14, 2))
+        );
+        assert_eq!(parse_gdb_version("no version here"), None);
+    }
+
+    #[test]
+    fn ptrace_scope_levels() {
+        assert_eq!(ptrace_scope_check(Some(1)).level, Level::Ok);
+        assert_eq!(ptrace_scope_check(Some(2)).level, Level::Warn);
+        assert_eq!(ptrace_scope_check(Some(3)).level, Level::Fail);
+    }
+}
@@ -0,0 +1,1147 @@
+use crate::mi::parser::bytes_to_u64;
+use crate::mi::{FrameInfo, MiSession, Result, StoppedLocation};
+use std::collections::BTreeMap;
+
+/// Byte written into guard zones around tracked allocations.
+pub const CANARY_BYTE: u8 = 0xfd;
+/// Default guard size; a multiple of 16 keeps the returned pointer malloc-aligned.
+pub const DEFAULT_GUARD: usize = 16;
+/// Callers of the allocator kept per block.
+const SITE_FRAMES: u32 = 4;
+/// Arenas followed from `main_arena` before the ring is taken as corrupt.
+const MAX_ARENAS: usize = 256;
+/// Chunks walked per free list before it is cut short.
+const MAX_LIST_CHUNKS: usize = 4096;
+/// Flag bits kept in the low bits of a chunk's size field.
+pub const SIZE_BITS: u64 = 0x7;
+/// Size flag: the previous chunk is in use (its `prev_size` word is its data).
+pub const PREV_INUSE: u64 = 0x1;
+/// Size flag: the chunk was mmapped on its own and has no neighbours.
+pub const IS_MMAPPED: u64 = 0x2;
+/// Size flag: the chunk belongs to a thread arena, not `main_arena`.
+pub const NON_MAIN_ARENA: u64 = 0x4;
+/// Chunk bytes dumped by `heap chunk`; bigger chunks show their head.
+const MAX_CHUNK_BYTES: u64 = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HookKind {
+    Malloc,
+    Calloc,
+    Realloc,
+    Free,
+}
+
+/// A live heap block observed through the malloc hooks.
+#[derive(Debug, Clone)]
+pub struct Allocation {
+    /// Pointer handed to the program.
+    pub user_addr: u64,
+    /// Size the program asked for.
+    pub size: usize,
+    /// Guard bytes on each side (0 when the block was allocated without canaries).
+    pub guard: usize,
+    /// Set once a corrupted canary has been reported for this block.
+    pub corrupted: bool,
+    /// Callers of the allocator when the block was made, innermost first.
+    pub backtrace: Vec<FrameInfo>,
+}
+
+impl Allocation {
+    /// Where the block was allocated: "foo.c:42 → bar.c:10", functions for frames without
+    /// source. `None` when no backtrace was captured.
+    pub fn site(&self) -> Option<String> {
+        if self.backtrace.is_empty() {
+            return None;
+        }
+        let frames: Vec<String> = self
+            .backtrace
+            .iter()
+            .map(|f| match (&f.file, f.line, &f.func) {
+                (Some(file), Some(line), _) => format!("{}:{}", file, line),
+                (_, _, Some(func)) => func.clone(),
+                _ => f.addr.clone().unwrap_or_else(|| "??".to_string()),
+            })
+            .collect();
+        Some(frames.join(" → "))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanarySide {
+    Before,
+    After,
+}
+
+/// A guard zone that no longer holds the canary pattern.
+#[derive(Debug, Clone)]
+pub struct CanaryReport {
+    pub user_addr: u64,
+    pub size: usize,
+    pub side: CanarySide,
+    /// Address of the first clobbered guard byte.
+    pub bad_addr: u64,
+    /// True when the corruption was found while the block was being freed.
+    pub on_free: bool,
+}
+
+/// Outcome of servicing an allocator breakpoint.
+#[derive(Debug, Default)]
+pub struct Serviced {
+    pub reports: Vec<CanaryReport>,
+    /// Where the program stopped instead, when running to the caller ended elsewhere (a user
+    /// breakpoint or catchpoint, a signal). That stop belongs to the user.
+    pub stop: Option<StoppedLocation>,
+}
+
+/// Registers used to read/patch allocator arguments and return values.
+struct CallRegs {
+    args: [&'static str; 2],
+    ret: &'static str,
+}
+
+fn call_regs(arch: Option<&str>) -> Option<CallRegs> {
+    let a = arch.unwrap_or("").to_ascii_lowercase();
+    if a.contains("x86-64") || a.contains("x86_64") || a.contains("amd64") {
+        Some(CallRegs {
+            args: ["$rdi", "$rsi"],
+            ret: "$rax",
+        })
+    } else if a.contains("aarch64") {
+        Some(CallRegs {
+            args: ["$x0", "$x1"],
+            ret: "$x0",
+        })
+    } else if a.contains("riscv") {
+        Some(CallRegs {
+            args: ["$a0", "$a1"],
+            ret: "$a0",
+        })
+    } else {
+        None
+    }
+}
+
+/// Tracks heap blocks via internal breakpoints on the allocator entry points, optionally
+/// surrounding each new block with canary guard zones.
+#[derive(Default)]
+pub struct HeapTracker {
+    hooks: Vec<(u32, HookKind)>,
+    /// Guard size for new allocations; `None` when canaries are off.
+    canary: Option<usize>,
+    pub live: BTreeMap<u64, Allocation>,
+}
+
+impl HeapTracker {
+    pub fn is_tracking(&self) -> bool {
+        !self.hooks.is_empty()
+    }
+
+    /// Pointers malloc returned for the live blocks (in front of any canary guard); empty
+    /// when tracking is off.
+    pub fn chunk_pointers(&self) -> Vec<u64> {
+        if !self.is_tracking() {
+            return Vec::new();
+        }
+        self.live
+            .values()
+            .map(|a| a.user_addr - a.guard as u64)
+            .collect()
+    }
+
+    pub fn canary_guard(&self) -> Option<usize> {
+        self.canary
+    }
+
+    /// Number of live blocks that carry guard zones (their frees must still be adjusted).
+    pub fn guarded_live(&self) -> usize {
+        self.live.values().filter(|a| a.guard > 0).count()
+    }
+
+    /// Install the allocator breakpoints (idempotent).
+    pub fn enable(&mut self, session: &mut MiSession) -> Result<()> {
+        if self.is_tracking() {
+            return Ok(());
+        }
+        session.ensure_arch();
+        if call_regs(session.arch.as_deref()).is_none() {
+            return Err(format!(
+                "heap tracking needs x86-64, aarch64 or riscv (arch={})",
+                session.arch.as_deref().unwrap_or("unknown")
+            )
+            .into());
+        }
+        let wanted = [
+            ("malloc", HookKind::Malloc),
+            ("calloc", HookKind::Calloc),
+            ("realloc", HookKind::Realloc),
+            ("free", HookKind::Free),
+        ];
+        for (func, kind) in wanted {
+            match session.break_insert(func) {
+                Ok(bp) => self.hooks.push((bp.number, kind)),
+                Err(e) => {
+                    self.remove_hooks(session);
+                    return Err(format!("could not hook {}: {}", func, e).into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the allocator breakpoints. Refused while guarded blocks are live, because their
+    /// frees must be shifted back to the real chunk start.
+    pub fn disable(&mut self, session: &mut MiSession) -> Result<()> {
+        let guarded = self.guarded_live();
+        if guarded > 0 {
+            return Err(format!(
+                "{} guarded block(s) still live; their frees need the hooks",
+                guarded
+            )
+            .into());
+        }
+        self.canary = None;
+        self.remove_hooks(session);
+        self.live.clear();
+        Ok(())
+    }
+
+    pub fn set_canary(&mut self, guard: Option<usize>) {
+        self.canary = guard;
+    }
+
+    /// Forget all blocks (e.g. after the inferior exited).
+    pub fn reset(&mut self) {
+        self.live.clear();
+    }
+
+    fn remove_hooks(&mut self, session: &mut MiSession) {
+        for (num, _) in self.hooks.drain(..) {
+            let _ = session.break_delete(num);
+        }
+    }
+
+    /// Whether `loc` is one of our internal allocator breakpoints.
+    pub fn owns_stop(&self, loc: &StoppedLocation) -> bool {
+        self.hook_kind(loc).is_some()
+    }
+
+    fn hook_kind(&self, loc: &StoppedLocation) -> Option<HookKind> {
+        let num = loc.bkptno?;
+        self.hooks.iter().find(|(n, _)| *n == num).map(|(_, k)| *k)
+    }
+
+    /// Service an allocator breakpoint: adjust arguments, run to the caller, record the block
+    /// and write canaries. Leaves the inferior stopped in the caller right after the call, or
+    /// wherever it stopped first; the block is then left untracked.
+    pub fn handle_stop(
+        &mut self,
+        session: &mut MiSession,
+        loc: &StoppedLocation,
+    ) -> Result<Serviced> {
+        let kind = match self.hook_kind(loc) {
+            Some(k) => k,
+            None => return Ok(Serviced::default()),
+        };
+        let regs = call_regs(session.arch.as_deref()).ok_or("unsupported architecture")?;
+        let mut reports = Vec::new();
+        let stop;
+        match kind {
+            HookKind::Malloc => {
+                let size = read_reg(session, regs.args[0])? as usize;
+                let guard = fitting_guard(size, self.canary.unwrap_or(0));
+                if guard > 0 {
+                    write_reg(session, regs.args[0], (size + 2 * guard) as u64)?;
+                }
+                let site = call_site(session);
+                stop = finish_call(session)?;
+                if stop.is_none() {
+                    self.record_result(session, &regs, size, guard, site)?;
+                }
+            }
+            HookKind::Calloc => {
+                let n = read_reg(session, regs.args[0])? as usize;
+                let elem = read_reg(session, regs.args[1])? as usize;
+                let size = n.saturating_mul(elem);
+                let guard = fitting_guard(size, self.canary.unwrap_or(0));
+                if guard > 0 {
+                    write_reg(session, regs.args[0], 1)?;
+                    write_reg(session, regs.args[1], (size + 2 * guard) as u64)?;
+                }
+                let site = call_site(session);
+                stop = finish_call(session)?;
+                if stop.is_none() {
+                    self.record_result(session, &regs, size, guard, site)?;
+                }
+            }
+            HookKind::Realloc => {
+                let old = read_reg(session, regs.args[0])?;
+                let size = read_reg(session, regs.args[1])? as usize;
+                let guard = match self.live.remove(&old) {
+                    Some(prev) => {
+                        reports.extend(self.check_block(session, &prev, true));
+                        // The block handed back must be the real one even when the new
+                        // size leaves no room for guards.
+                        if prev.guard > 0 {
+                            write_reg(session, regs.args[0], old - prev.guard as u64)?;
+                        }
+                        let guard = fitting_guard(size, prev.guard);
+                        if guard > 0 {
+                            write_reg(session, regs.args[1], (size + 2 * guard) as u64)?;
+                        }
+                        Some(guard)
+                    }
+                    None if old == 0 => {
+                        let guard = fitting_guard(size, self.canary.unwrap_or(0));
+                        if guard > 0 {
+                            write_reg(session, regs.args[1], (size + 2 * guard) as u64)?;
+                        }
+                        Some(guard)
+                    }
+                    // Block from before tracking started: leave it alone.
+                    None => None,
+                };
+                let site = match guard {
+                    Some(_) => call_site(session),
+                    None => Vec::new(),
+                };
+                stop = finish_call(session)?;
+                if let (Some(guard), None) = (guard, &stop) {
+                    self.record_result(session, &regs, size, guard, site)?;
+                }
+            }
+            HookKind::Free => {
+                let ptr = read_reg(session, regs.args[0])?;
+                if let Some(block) = self.live.remove(&ptr) {
+                    reports.extend(self.check_block(session, &block, true));
+                    if block.guard > 0 {
+                        write_reg(session, regs.args[0], ptr - block.guard as u64)?;
+                    }
+                }
+                stop = finish_call(session)?;
+            }
+        }
+        Ok(Serviced { reports, stop })
+    }
+
+    fn record_result(
+        &mut self,
+        session: &mut MiSession,
+        regs: &CallRegs,
+        size: usize,
+        guard: usize,
+        backtrace: Vec<FrameInfo>,
+    ) -> Result<()> {
+        let base = read_reg(session, regs.ret)?;
+        if base == 0 {
+            return Ok(());
+        }
+        let user_addr = base + guard as u64;
+        if guard > 0 {
+            let pattern = vec![CANARY_BYTE; guard];
+            session.write_memory_bytes(base, &pattern)?;
+            session.write_memory_bytes(user_addr + size as u64, &pattern)?;
+            write_reg(session, regs.ret, user_addr)?;
+        }
+        self.live.insert(
+            user_addr,
+            Allocation {
+                user_addr,
+                size,
+                guard,
+                corrupted: false,
+                backtrace,
+            },
+        );
+        Ok(())
+    }
+
+    /// Check guarded live blocks. Unless `all` is set, blocks already reported are skipped so
+    /// each corruption is reported once, at the first stop that sees it.
+    pub fn check_canaries(&mut self, session: &mut MiSession, all: bool) -> Vec<CanaryReport> {
+        let mut reports = Vec::new();
+        let blocks: Vec<Allocation> = self
+            .live
+            .values()
+            .filter(|a| a.guard > 0 && (all || !a.corrupted))
+            .cloned()
+            .collect();
+        for block in blocks {
+            let found = self.check_block(session, &block, false);
+            if !found.is_empty() {
+                if let Some(a) = self.live.get_mut(&block.user_addr) {
+                    a.corrupted = true;
+                }
+                reports.extend(found);
+            }
+        }
+        reports
+    }
+
+    fn check_block(
+        &self,
+        session: &mut MiSession,
+        block: &Allocation,
+        on_free: bool,
+    ) -> Vec<CanaryReport> {
+        // A block already reported at an earlier stop is not reported again when freed.
+        if block.guard == 0 || (on_free && block.corrupted) {
+            return Vec::new();
+        }
+        let zones = [
+            (CanarySide::Before, block.user_addr - block.guard as u64),
+            (CanarySide::After, block.user_addr + block.size as u64),
+        ];
+        let mut reports = Vec::new();
+        for (side, addr) in zones {
+            if let Ok(bytes) = session.read_memory(addr, block.guard) {
+                if let Some(first_bad) = first_clobbered(&bytes) {
+                    reports.push(CanaryReport {
+                        user_addr: block.user_addr,
+                        size: block.size,
+                        side,
+                        bad_addr: addr + first_bad as u64,
+                        on_free,
+                    });
+                }
+            }
+        }
+        reports
+    }
+}
+
+/// Index of the first byte that no longer matches `CANARY_BYTE`.
+fn first_clobbered(zone: &[u8]) -> Option<usize> {
+    zone.iter().position(|b| *b != CANARY_BYTE)
+}
+
+/// One glibc malloc arena (`struct malloc_state`). Free memory is what the arena can hand out
+/// without asking the kernel: fastbins, bins and the top chunk. As with `mallinfo`, chunks
+/// parked in a thread's tcache count as allocated.
+#[derive(Debug, Clone, Default)]
+pub struct ArenaStats {
+    pub address: u64,
+    /// `main_arena`, which grows `[heap]` with brk; the others carve mmapped heaps.
+    pub is_main: bool,
+    /// Top chunk: the unused tail new chunks are split from.
+    pub top: u64,
+    pub top_size: u64,
+    /// Memory the arena got from the system, and its peak.
+    pub system_mem: u64,
+    pub max_system_mem: u64,
+    pub fast_chunks: usize,
+    pub fast_bytes: u64,
+    /// Unsorted, small and large bins.
+    pub bin_chunks: usize,
+    pub bin_bytes: u64,
+    /// A free list was cut short: too long, or a link that does not read.
+    pub truncated: bool,
+}
+
+impl ArenaStats {
+    pub fn free(&self) -> u64 {
+        self.fast_bytes + self.bin_bytes + self.top_size
+    }
+
+    pub fn allocated(&self) -> u64 {
+        self.system_mem.saturating_sub(self.free())
+    }
+
+    /// End of the top chunk: the top of the arena's heap (the program break for main_arena).
+    pub fn top_end(&self) -> u64 {
+        self.top + self.top_size
+    }
+}
+
+/// A glibc malloc chunk read around a user pointer: the header before it, the start of the
+/// data, and the next chunk's header, whose `PREV_INUSE` bit tells whether this one is in use.
+#[derive(Debug, Clone)]
+pub struct MallocChunk {
+    /// Chunk start: the header, two words before the user pointer.
+    pub addr: u64,
+    pub word: u64,
+    pub prev_size: u64,
+    /// Size with the flag bits, as stored.
+    pub size_field: u64,
+    /// Bytes from `addr`, cut at `MAX_CHUNK_BYTES`.
+    pub bytes: Vec<u8>,
+    /// The next chunk's header; `None` for mmapped chunks or when it does not read.
+    pub next_header: Option<Vec<u8>>,
+    pub next_size_field: Option<u64>,
+}
+
+impl MallocChunk {
+    pub fn size(&self) -> u64 {
+        self.size_field & !SIZE_BITS
+    }
+
+    pub fn user_addr(&self) -> u64 {
+        self.addr + 2 * self.word
+    }
+
+    /// Saturates rather than wrapping; `read_chunk` only returns chunks that end in range.
+    pub fn next_addr(&self) -> u64 {
+        self.addr.saturating_add(self.size())
+    }
+
+    pub fn is_mmapped(&self) -> bool {
+        self.size_field & IS_MMAPPED != 0
+    }
+
+    pub fn flags(&self) -> Vec<&'static str> {
+        chunk_flags(self.size_field)
+    }
+
+    /// Bytes the program may use: up to the next chunk's size field, since an in-use chunk
+    /// also owns the next `prev_size`. Mmapped chunks have no next chunk to borrow from.
+    pub fn usable(&self) -> u64 {
+        let overhead = if self.is_mmapped() { 2 } else { 1 };
+        self.size().saturating_sub(overhead * self.word)
+    }
+
+    /// From the next chunk's `PREV_INUSE`; mmapped chunks are always in use. Chunks in a
+    /// fastbin or tcache still read as in use, as glibc leaves the bit set for them.
+    pub fn in_use(&self) -> Option<bool> {
+        if self.is_mmapped() {
+            return Some(true);
+        }
+        self.next_size_field.map(|s| s & PREV_INUSE != 0)
+    }
+}
+
+/// Names of the flags set in a chunk's size field.
+pub fn chunk_flags(size_field: u64) -> Vec<&'static str> {
+    [
+        (PREV_INUSE, "PREV_INUSE"),
+        (IS_MMAPPED, "IS_MMAPPED"),
+        (NON_MAIN_ARENA, "NON_MAIN_ARENA"),
+    ]
+    .into_iter()
+    .filter(|(bit, _)| size_field & bit != 0)
+    .map(|(_, name)| name)
+    .collect()
+}
+
+/// Read the chunk holding the block malloc returned at `user_addr`.
+pub fn read_chunk(session: &mut MiSession, user_addr: u64) -> Result<MallocChunk> {
+    session.ensure_word_size();
+    session.ensure_endian();
+    let w = session.word_size as u64;
+    if user_addr < 2 * w || !user_addr.is_multiple_of(2 * w) {
+        return Err(format!(
+            "0x{:x} is not a malloc pointer (not a {}-aligned heap address)",
+            user_addr,
+            2 * w
+        )
+        .into());
+    }
+    let addr = user_addr - 2 * w;
+    let header = session.read_memory(addr, 2 * w as usize)?;
+    let prev_size = bytes_to_u64(&header[..w as usize], session.endian);
+    let size_field = bytes_to_u64(&header[w as usize..], session.endian);
+    let size = size_field & !SIZE_BITS;
+    if !plausible_chunk(addr, size, w) {
+        return Err(format!(
+            "0x{:x} does not look like a malloc chunk (size field 0x{:x})",
+            user_addr, size_field
+        )
+        .into());
+    }
+    let bytes = session.read_memory(addr, size.min(MAX_CHUNK_BYTES) as usize)?;
+    let mut chunk = MallocChunk {
+        addr,
+        word: w,
+        prev_size,
+        size_field,
+        bytes,
+        next_header: None,
+        next_size_field: None,
+    };
+    if !chunk.is_mmapped() {
+        if let Ok(next) = session.read_memory(chunk.next_addr(), 2 * w as usize) {
+            chunk.next_size_field = Some(bytes_to_u64(&next[w as usize..], session.endian));
+            chunk.next_header = Some(next);
+        }
+    }
+    Ok(chunk)
+}
+
+/// `guard`, or 0 when a `size`-byte request padded with two guards would overflow; such a
+/// request is passed through unchanged and its block tracked without canaries.
+fn fitting_guard(size: usize, guard: usize) -> usize {
+    match size.checked_add(2 * guard) {
+        Some(_) => guard,
+        None => 0,
+    }
+}
+
+/// Run the allocator call to its caller. `None` when it got there; otherwise the stop that
+/// came first, and the return register holds nothing of ours.
+fn finish_call(session: &mut MiSession) -> Result<Option<StoppedLocation>> {
+    let stop = session.exec_finish()?;
+    if stop.reason.as_deref() == Some("function-finished") {
+        Ok(None)
+    } else {
+        Ok(Some(stop))
+    }
+}
+
+/// Whether a chunk of `size` bytes at `addr` could be real: at least the minimum chunk,
+/// aligned, and ending inside the address space of a `word`-byte target. A garbage size
+/// field fails here before anything reads past it.
+fn plausible_chunk(addr: u64, size: u64, word: u64) -> bool {
+    let limit = if word >= 8 {
+        u64::MAX
+    } else {
+        (1u64 << (8 * word)) - 1
+    };
+    size >= 4 * word
+        && size.is_multiple_of(2 * word)
+        && addr.checked_add(size).is_some_and(|end| end <= limit)
+}
+
+/// Every glibc arena, following `next` from `main_arena` with gdb expressions and reading the
+/// free lists from memory. Needs libc debug info for `struct malloc_state`.
+pub fn read_arenas(session: &mut MiSession) -> Result<Vec<ArenaStats>> {
+    let main = session.eval_expr_u64("(unsigned long)&main_arena")?;
+    session.ensure_word_size();
+    session.ensure_endian();
+    let mut arenas = Vec::new();
+    let mut addr = main;
+    loop {
+        arenas.push(read_arena(session, addr, addr == main)?);
+        let next = format!("(unsigned long)((struct malloc_state *)0x{:x})->next", addr);
+        addr = session.eval_expr_u64(&next)?;
+        if addr == main || addr == 0 || arenas.len() == MAX_ARENAS {
+            break;
+        }
+    }
+    Ok(arenas)
+}
+
+fn read_arena(session: &mut MiSession, addr: u64, is_main: bool) -> Result<ArenaStats> {
+    let state = read_arena_state(session, addr)?;
+    let mut stats = ArenaStats {
+        address: addr,
+        is_main,
+        top: state.top,
+        top_size: state.top_size,
+        system_mem: state.system_mem,
+        max_system_mem: state.max_system_mem,
+        ..ArenaStats::default()
+    };
+    for list in &state.fastbins {
+        stats.fast_chunks += list.chunks.len();
+        stats.fast_bytes += list.bytes();
+        stats.truncated |= list.truncated;
+    }
+    for list in &state.bins {
+        stats.bin_chunks += list.chunks.len();
+        stats.bin_bytes += list.bytes();
+        stats.truncated |= list.truncated;
+    }
+    Ok(stats)
+}
+
+/// Chunks malloc mapped on their own (`mp_`), outside every arena.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MmapStats {
+    pub chunks: u64,
+    pub bytes: u64,
+}
+
+pub fn read_mmap_stats(session: &mut MiSession) -> Result<MmapStats> {
+    Ok(MmapStats {
+        chunks: session.eval_expr_u64("(unsigned long)mp_.n_mmaps")?,
+        bytes: session.eval_expr_u64("(unsigned long)mp_.mmapped_mem")?,
+    })
+}
+
+/// Chunks on one free list, in list order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FreeList {
+    /// (chunk address, chunk size).
+    pub chunks: Vec<(u64, u64)>,
+    /// Cut short: too long, or a link that does not read.
+    pub truncated: bool,
+}
+
+impl FreeList {
+    pub fn bytes(&self) -> u64 {
+        self.chunks.iter().map(|(_, size)| size).sum()
+    }
+}
+
+/// One non-empty list of `heap bins`.
+#[derive(Debug, Clone)]
+pub struct BinList {
+    /// "tcache", "fastbin", "unsorted", "small" or "large".
+    pub kind: &'static str,
+    /// Index within its kind (glibc's bin number for small and large bins).
+    pub index: usize,
+    /// tcache's own count for the size class, kept beside the list.
+    pub count: Option<u64>,
+    pub list: FreeList,
+}
+
+/// Free lists of one arena, plus the selected thread's tcache.
+#[derive(Debug, Clone)]
+pub struct HeapBins {
+    pub arena: u64,
+    /// The thread's `tcache_perthread_struct`; `None` before its first allocation.
+    pub tcache: Option<u64>,
+    pub lists: Vec<BinList>,
+}
+
+/// Bins below this number are small bins (glibc's `NSMALLBINS`); bin 1 is the unsorted bin.
+const SMALL_BINS: usize = 64;
+
+/// Walk the tcache of the selected thread and the fastbins and bins of `arena` (`main_arena`
+/// when `None`), keeping the non-empty lists.
+pub fn read_bins(session: &mut MiSession, arena: Option<u64>) -> Result<HeapBins> {
+    let arena = match arena {
+        Some(addr) => addr,
+        None => session.eval_expr_u64("(unsigned long)&main_arena")?,
+    };
+    session.ensure_word_size();
+    session.ensure_endian();
+    let mut lists = Vec::new();
+    let tcache = read_tcache(session).ok();
+    if let Some((_, classes)) = &tcache {
+        for (index, (count, list)) in classes.iter().enumerate() {
+            if *count > 0 || !list.chunks.is_empty() {
+                lists.push(BinList {
+                    kind: "tcache",
+                    index,
+                    count: Some(*count),
+                    list: list.clone(),
+                });
+            }
+        }
+    }
+    let state = read_arena_state(session, arena)?;
+    for (index, list) in state.fastbins.into_iter().enumerate() {
+        if !list.chunks.is_empty() {
+            lists.push(BinList {
+                kind: "fastbin",
+                index,
+                count: None,
+                list,
+            });
+        }
+    }
+    for (i, list) in state.bins.into_iter().enumerate() {
+        let index = i + 1;
+        let kind = match index {
+            1 => "unsorted",
+            n if n < SMALL_BINS => "small",
+            _ => "large",
+        };
+        if !list.chunks.is_empty() {
+            lists.push(BinList {
+                kind,
+                index,
+                count: None,
+                list,
+            });
+        }
+    }
+    Ok(HeapBins {
+        arena,
+        tcache: tcache.map(|(addr, _)| addr),
+        lists,
+    })
+}
+
+/// What `read_arena_state` reads of a `struct malloc_state`.
+struct ArenaState {
+    top: u64,
+    top_size: u64,
+    system_mem: u64,
+    max_system_mem: u64,
+    fastbins: Vec<FreeList>,
+    /// Unsorted, small and large bins, from glibc's bin 1.
+    bins: Vec<FreeList>,
+}
+
+fn read_arena_state(session: &mut MiSession, addr: u64) -> Result<ArenaState> {
+    // `{0}` is the arena.
+    let state = format!("((struct malloc_state *)0x{:x})", addr);
+    let mut eval = |template: &str| session.eval_expr_u64(&template.replace("{0}", &state));
+    let top = eval("(unsigned long){0}->top")?;
+    let system_mem = eval("{0}->system_mem")?;
+    let max_system_mem = eval("{0}->max_system_mem")?;
+    let fastbins = eval("(unsigned long)&{0}->fastbinsY")?;
+    let nfast = eval("sizeof({0}->fastbinsY) / sizeof({0}->fastbinsY[0])")? as usize;
+    let bins = eval("(unsigned long)&{0}->bins")?;
+    let nbins = eval("sizeof({0}->bins) / sizeof({0}->bins[0])")? as usize;
+
+    let w = session.word_size as u64;
+    let heads = read_words(session, fastbins, nfast, w as usize)?;
+    // bins[] holds fd/bk pairs; each bin's head is a fake chunk whose fd is the pair.
+    let links = read_words(session, bins, nbins, w as usize)?;
+    let mut chunk = |p: u64| chunk_link(session, p);
+    let top_size = match top {
+        0 => 0,
+        _ => chunk(top).map(|(size, _)| size).unwrap_or(0),
+    };
+    let fastbins = heads
+        .into_iter()
+        .map(|first| walk_free_list(first, 0, w, Links::Fast, &mut chunk))
+        .collect();
+    let bins = links
+        .chunks(2)
+        .enumerate()
+        .map(|(i, pair)| {
+            let head = bins + 2 * w * i as u64 - 2 * w;
+            walk_free_list(pair[0], head, w, Links::Plain, &mut chunk)
+        })
+        .collect();
+    Ok(ArenaState {
+        top,
+        top_size,
+        system_mem,
+        max_system_mem,
+        fastbins,
+        bins,
+    })
+}
+
+/// The selected thread's tcache and, per size class, glibc's count and the walked entries.
+fn read_tcache(session: &mut MiSession) -> Result<(u64, Vec<(u64, FreeList)>)> {
+    let base = session.eval_expr_u64("(unsigned long)tcache")?;
+    if base == 0 {
+        return Err("the thread has not allocated yet".into());
+    }
+    let mut eval = |expr: &str| session.eval_expr_u64(expr);
+    let counts = eval("(unsigned long)&tcache->counts")?;
+    let classes = eval("sizeof(tcache->counts) / sizeof(tcache->counts[0])")? as usize;
+    // uint16_t since glibc 2.30, char before.
+    let count_size = eval("sizeof(tcache->counts[0])")? as usize;
+    let entries = eval("(unsigned long)&tcache->entries")?;
+
+    let w = session.word_size as u64;
+    let counts = read_words(session, counts, classes, count_size)?;
+    let heads = read_words(session, entries, classes, w as usize)?;
+    let mut chunk = |p: u64| chunk_link(session, p);
+    let lists = counts
+        .into_iter()
+        .zip(heads)
+        .map(|(count, entry)| {
+            // Entries point at user data, two words into the chunk.
+            let first = entry.saturating_sub(2 * w);
+            let list = match entry {
+                0 => FreeList::default(),
+                _ => walk_free_list(first, 0, w, Links::Tcache, &mut chunk),
+            };
+            (count, list)
+        })
+        .collect();
+    Ok((base, lists))
+}
+
+/// `n` integers of `size` bytes at `addr`.
+fn read_words(session: &mut MiSession, addr: u64, n: usize, size: usize) -> Result<Vec<u64>> {
+    let bytes = session.read_memory(addr, n * size)?;
+    Ok(bytes
+        .chunks(size)
+        .map(|b| bytes_to_u64(b, session.endian))
+        .collect())
+}
+
+/// A chunk's size (flags masked off) and forward link.
+fn chunk_link(session: &mut MiSession, p: u64) -> Option<(u64, u64)> {
+    let w = session.word_size;
+    let fields = read_words(session, p + w as u64, 2, w).ok()?;
+    Some((fields[0] & !SIZE_BITS, fields[1]))
+}
+
+/// How a free list links its chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Links {
+    /// Bins: plain `fd` pointers in a ring closed by the bin head.
+    Plain,
+    /// Fastbins: `fd` to the next chunk, null-terminated, safe-linked since glibc 2.32.
+    Fast,
+    /// tcache: `next` to the next entry's user data, otherwise like fastbins.
+    Tcache,
+}
+
+/// Follow a free list from chunk `first` until `end`. `chunk` reads a chunk's size and
+/// forward link.
+fn walk_free_list(
+    first: u64,
+    end: u64,
+    w: u64,
+    links: Links,
+    chunk: &mut impl FnMut(u64) -> Option<(u64, u64)>,
+) -> FreeList {
+    let mut list = FreeList::default();
+    let mut p = first;
+    while p != end && p != 0 {
+        if list.chunks.len() == MAX_LIST_CHUNKS {
+            list.truncated = true;
+            break;
+        }
+        let Some((size, fd)) = chunk(p) else {
+            list.truncated = true;
+            break;
+        };
+        list.chunks.push((p, size));
+        p = match links {
+            Links::Plain => fd,
+            Links::Fast | Links::Tcache => match reveal_link(p + 2 * w, fd, 2 * w) {
+                Some(next) if links == Links::Tcache => next - 2 * w,
+                Some(next) => next,
+                None => break,
+            },
+        };
+    }
+    list
+}
+
+/// Next pointer from a fastbin or tcache link stored at `field`; `None` at the end of the
+/// list. With safe linking the stored value is `(field >> 12) ^ next`; of the two readings,
+/// the aligned one nearest the link is taken.
+fn reveal_link(field: u64, raw: u64, align: u64) -> Option<u64> {
+    let revealed = (field >> 12) ^ raw;
+    if raw == 0 || revealed == 0 {
+        return None;
+    }
+    [raw, revealed]
+        .into_iter()
+        .filter(|p| p.is_multiple_of(align))
+        .min_by_key(|p| p.abs_diff(field))
+}
+
+/// The allocator's callers, read while stopped at its entry (frame 0 is the allocator).
+fn call_site(session: &mut MiSession) -> Vec<FrameInfo> {
+    let mut frames = session.list_top_frames(SITE_FRAMES + 1).unwrap_or_default();
+    if !frames.is_empty() {
+        frames.remove(0);
+    }
+    frames
+}
+
+fn read_reg(session: &mut MiSession, reg: &str) -> Result<u64> {
+    session.eval_expr_u64(&format!("(unsigned long){}", reg))
+}
+
+fn write_reg(session: &mut MiSession, reg: &str, value: u64) -> Result<()> {
+    session.evaluate_expression(&format!("{} = {}", reg, value))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mi::backend::Gdb;
+    use crate::mi::parser::parse_stopped;
+    use std::collections::HashMap;
+    use std::io;
+
+    #[test]
+    fn a_user_stop_inside_the_allocator_is_handed_back() {
+        let output = concat!(
+            "1^done,value=\"32\"\n(gdb)\n",
+            "2^done,stack=[]\n(gdb)\n",
+            "3^running\n*running,thread-id=\"all\"\n(gdb)\n",
+            "*stopped,reason=\"breakpoint-hit\",bkptno=\"7\",frame={func=\"sbrk\"}\n(gdb)\n",
+        );
+        let mut session = MiSession::replay(
+            Box::new(Gdb),
+            Box::new(io::sink()),
+            Box::new(io::Cursor::new(output.as_bytes().to_vec())),
+            "prog",
+            &[],
+            false,
+        );
+        session.arch = Some("i386:x86-64".to_string());
+        let mut tracker = HeapTracker {
+            hooks: vec![(2, HookKind::Malloc)],
+            ..HeapTracker::default()
+        };
+        let hit =
+            parse_stopped(r#"*stopped,reason="breakpoint-hit",bkptno="2",frame={func="malloc"}"#);
+        let serviced = tracker.handle_stop(&mut session, &hit).unwrap();
+        assert_eq!(serviced.stop.and_then(|s| s.bkptno), Some(7));
+        assert!(tracker.live.is_empty());
+    }
+
+    #[test]
+    fn oversized_requests_are_tracked_without_guards() {
+        // malloc(SIZE_MAX) with canaries on: the argument is left alone, so no write
+        // happens before the finish.
+        let output = concat!(
+            "1^done,value=\"18446744073709551615\"\n(gdb)\n",
+            "2^done,stack=[]\n(gdb)\n",
+            "3^running\n*running,thread-id=\"all\"\n(gdb)\n",
+            "*stopped,reason=\"function-finished\",frame={func=\"main\"}\n(gdb)\n",
+            "4^done,value=\"4096\"\n(gdb)\n",
+        );
+        let mut session = MiSession::replay(
+            Box::new(Gdb),
+            Box::new(io::sink()),
+            Box::new(io::Cursor::new(output.as_bytes().to_vec())),
+            "prog",
+            &[],
+            false,
+        );
+        session.arch = Some("i386:x86-64".to_string());
+        let mut tracker = HeapTracker {
+            hooks: vec![(2, HookKind::Malloc)],
+            canary: Some(DEFAULT_GUARD),
+            ..HeapTracker::default()
+        };
+        let hit =
+            parse_stopped(r#"*stopped,reason="breakpoint-hit",bkptno="2",frame={func="malloc"}"#);
+        let serviced = tracker.handle_stop(&mut session, &hit).unwrap();
+        assert!(serviced.stop.is_none());
+        let block = &tracker.live[&4096];
+        assert_eq!((block.size, block.guard), (usize::MAX, 0));
+        assert_eq!(fitting_guard(usize::MAX - 8, 16), 0);
+        assert_eq!(fitting_guard(64, 16), 16);
+    }
+
+    #[test]
+    fn call_regs_cover_common_abis() {
+        assert_eq!(call_regs(Some("i386:x86-64")).unwrap().args[0], "$rdi");
+        assert_eq!(call_regs(Some("aarch64")).unwrap().ret, "$x0");
+        assert!(call_regs(Some("i386")).is_none());
+    }
+
+    #[test]
+    fn free_lists_follow_plain_and_safe_linked_links() {
+        // Three fastbin chunks of 0x20 at 0x4052a0 -> 0x4052c0 -> 0x4052e0, safe-linked.
+        let mangle = |chunk: u64, next: u64| ((chunk + 16) >> 12) ^ next;
+        let memory: HashMap<u64, (u64, u64)> = [
+            (0x4052a0, (0x21, mangle(0x4052a0, 0x4052c0))),
+            (0x4052c0, (0x21, mangle(0x4052c0, 0x4052e0))),
+            (0x4052e0, (0x21, mangle(0x4052e0, 0))),
+        ]
+        .into();
+        let mut chunk = |p: u64| memory.get(&p).map(|(size, fd)| (size & !SIZE_BITS, *fd));
+        let list = walk_free_list(0x4052a0, 0, 8, Links::Fast, &mut chunk);
+        assert_eq!(
+            list.chunks,
+            [(0x4052a0, 0x20), (0x4052c0, 0x20), (0x4052e0, 0x20)]
+        );
+        assert_eq!(list.bytes(), 0x60);
+        assert!(!list.truncated);
+        // tcache links point at the next entry's user data, two words into its chunk.
+        let memory: HashMap<u64, (u64, u64)> = [
+            (0x405300, (0x31, mangle(0x405300, 0x405340))),
+            (0x405330, (0x31, mangle(0x405330, 0))),
+        ]
+        .into();
+        let mut chunk = |p: u64| memory.get(&p).map(|(size, fd)| (size & !SIZE_BITS, *fd));
+        let list = walk_free_list(0x405300, 0, 8, Links::Tcache, &mut chunk);
+        assert_eq!(list.chunks, [(0x405300, 0x30), (0x405330, 0x30)]);
+        // Pre-2.32 glibc stores the pointer itself.
+        assert_eq!(reveal_link(0x4052b0, 0x4052c0, 16), Some(0x4052c0));
+        assert_eq!(reveal_link(0x4052b0, 0, 16), None);
+        // A bin is a ring closed by its head; a link that does not read cuts the walk.
+        let head = 0x7ffff7e1ace0;
+        let memory: HashMap<u64, (u64, u64)> =
+            [(0x405300, (0x91, 0x405400)), (0x405400, (0x101, head))].into();
+        let mut chunk = |p: u64| memory.get(&p).map(|(size, fd)| (size & !SIZE_BITS, *fd));
+        let list = walk_free_list(0x405300, head, 8, Links::Plain, &mut chunk);
+        assert_eq!(
+            (list.chunks.len(), list.bytes(), list.truncated),
+            (2, 0x190, false)
+        );
+        let list = walk_free_list(0x405300, 0x1, 8, Links::Plain, &mut chunk);
+        assert_eq!(
+            (list.chunks.len(), list.bytes(), list.truncated),
+            (2, 0x190, true)
+        );
+    }
+
+    #[test]
+    fn allocation_site_prefers_source_lines() {
+        let frame = |func: &str, file: Option<&str>, line: Option<u32>| FrameInfo {
+            level: 1,
+            addr: Some("0x401136".to_string()),
+            func: Some(func.to_string()),
+            file: file.map(str::to_string),
+            fullname: None,
+            line,
+        };
+        let mut block = Allocation {
+            user_addr: 0x4052a0,
+            size: 24,
+            guard: 0,
+            corrupted: false,
+            backtrace: Vec::new(),
+        };
+        assert_eq!(block.site(), None);
+        block.backtrace = vec![
+            frame("strdup", None, None),
+            frame("make_node", Some("foo.c"), Some(42)),
+            frame("main", Some("bar.c"), Some(10)),
+        ];
+        assert_eq!(
+            block.site().as_deref(),
+            Some("strdup → foo.c:42 → bar.c:10")
+        );
+    }
+
+    #[test]
+    fn chunk_flags_and_usable_size_come_from_the_size_field() {
+        let chunk = MallocChunk {
+            addr: 0x405290,
+            word: 8,
+            prev_size: 0,
+            size_field: 0x31,
+            bytes: Vec::new(),
+            next_header: None,
+            next_size_field: Some(0x20d51),
+        };
+        assert_eq!(chunk.size(), 0x30);
+        assert_eq!(chunk.user_addr(), 0x4052a0);
+        assert_eq!(chunk.next_addr(), 0x4052c0);
+        assert_eq!(chunk.flags(), ["PREV_INUSE"]);
+        assert_eq!(chunk.usable(), 0x28);
+        assert_eq!(chunk.in_use(), Some(true));
+        let freed = MallocChunk {
+            next_size_field: Some(0x20d50),
+            ..chunk.clone()
+        };
+        assert_eq!(freed.in_use(), Some(false));
+        let mapped = MallocChunk {
+            size_field: 0x21002,
+            next_size_field: None,
+            ..chunk
+        };
+        assert_eq!(mapped.flags(), ["IS_MMAPPED"]);
+        assert_eq!(mapped.usable(), 0x21000 - 16);
+        assert_eq!(mapped.in_use(), Some(true));
+    }
+
+    #[test]
+    fn chunks_that_would_run_off_the_address_space_are_rejected() {
+        assert!(plausible_chunk(0x405290, 0x30, 8));
+        assert!(!plausible_chunk(0x405290, 0x10, 8));
+        assert!(!plausible_chunk(0x405290, 0x38, 8));
+        assert!(!plausible_chunk(0x405290, 0xffff_ffff_ffff_fff0, 8));
+        assert!(!plausible_chunk(0x0804_b000, 0xffff_0000, 4));
+        let garbage = MallocChunk {
+            addr: 0x405290,
+            word: 8,
+            prev_size: 0,
+            size_field: 0xffff_ffff_ffff_fff0,
+            bytes: Vec::new(),
+            next_header: None,
+            next_size_field: None,
+        };
+        assert_eq!(garbage.next_addr(), u64::MAX);
+    }
+
+    #[test]
+    fn first_clobbered_finds_overwritten_byte() {
+        assert_eq!(first_clobbered(&[CANARY_BYTE; 4]), None);
+        assert_eq!(
+            first_clobbered(&[CANARY_BYTE, CANARY_BYTE, 0x41, 0x00]),
+            Some(2)
+        );
+    }
+}
@@ -1,36 +1,332 @@
+mod addr;
+mod args;
+mod changes;
 mod commands;
+mod expand;
 mod follow;
+mod hexedit;
+mod history;
+mod hooks;
+mod json;
+mod maps;
 mod printers;
+mod regs;
+mod source;
+mod stack;
+mod stl;
+mod style;
+mod theme;
+mod track;
+mod watch;
 
-use commands::{execute_command, CommandOutcome};
-use crate::mi::{MiSession, Result};
+use crate::heap::HeapTracker;
+use crate::mi::{interrupt, MiSession, Result};
+use crate::record::{self, Entry};
+use crate::symbols::{
+    build_symbol_index, BackgroundIndex, FoundSymbol, SymbolIndex, SymbolIndexMode,
+};
+use changes::ValueHistory;
+use commands::{execute_command, relaunch, CommandOutcome};
+use history::History;
+use hooks::FuncHooks;
+pub use json::set_enabled as set_json_output;
+use maps::{MapHistory, MapTracker};
+use regs::RegisterHistory;
+use source::SourceCursor;
+use stack::{CanaryWatch, StackUsage};
+use std::collections::{HashMap, HashSet};
 use std::io::{self, Write};
+pub use style::{enabled as color_enabled, set_mode as set_color_mode, ColorMode};
+pub use theme::{load_theme_file, set_theme, ThemeChoice};
+use track::TrackedShape;
+use watch::Watch;
 
-pub fn repl(session: &mut MiSession) -> Result<()> {
+/// State that lives across REPL commands.
+#[derive(Default)]
+pub struct ReplState {
+    pub heap: HeapTracker,
+    pub hooks: FuncHooks,
+    /// Structures registered with `track`, summarized after every stop.
+    pub tracked: Vec<TrackedShape>,
+    /// Expressions registered with `display`.
+    pub watches: Vec<Watch>,
+    pub regs: RegisterHistory,
+    pub maps: MapHistory,
+    /// `vm track`: map changes logged at the syscalls that made them.
+    pub map_track: MapTracker,
+    /// `stack canary on`: stack protector slots re-read at every stop.
+    pub canaries: CanaryWatch,
+    /// Stack depth at every stop of the current run (`stack usage`).
+    pub stack_usage: StackUsage,
+    pub values: ValueHistory,
+    pub symbols: Option<SymbolIndex>,
+    /// Index still being built on a worker thread; moved to `symbols` when done.
+    pub pending_symbols: Option<BackgroundIndex>,
+    /// Shared libraries loaded but not yet added to the symbol index.
+    pub new_libraries: Vec<String>,
+    /// Source files shown folded (header only) in `globals`.
+    pub folded_files: HashSet<String>,
+    /// Results of the last `find`, picked by number with `find <n>`.
+    pub found: Vec<FoundSymbol>,
+    /// Frame selected with `frame`; gdb resets it to 0 at every stop.
+    pub frame: u32,
+    pub source: SourceCursor,
+    /// User-visible stops so far; lets reports name the stop that first saw a problem.
+    pub stop_count: u64,
+    /// The inferior has exited; shown in the prompt and blocks further exec commands.
+    pub exited: bool,
+    /// `--no-run`: the program has not been started yet; cleared by the first stop.
+    pub not_started: bool,
+    pub history: History,
+    /// `output off`: keep capturing program output but stop printing it after each stop.
+    pub output_quiet: bool,
+    /// `--build` command rerun by `run` when sources changed.
+    pub build: Option<String>,
+    /// Expressions printed at every hit, by breakpoint number (`breakpoints log`).
+    pub hit_logs: HashMap<u32, Vec<String>>,
+}
+
+/// How the REPL gets its commands.
+pub struct ReplOptions {
+    pub symbol_mode: SymbolIndexMode,
+    /// Commands run before the prompt (`--script`).
+    pub script: Vec<String>,
+    /// `--batch`: exit after the script; without one, read commands from stdin with no
+    /// prompt. History is neither loaded nor saved.
+    pub batch: bool,
+    /// `--replay`: `script` holds the recorded commands. Each one waits for Enter unless
+    /// `batch` is set, and the REPL ends with them since gdb's recorded output runs out.
+    pub replay: bool,
+    /// Resolved `--build` command.
+    pub build: Option<String>,
+    /// `--no-run`: the program is loaded but not started; `run` starts it.
+    pub no_run: bool,
+}
+
+/// Run the REPL and return how many commands failed, so batch runs can set an exit code.
+pub fn repl(session: &mut MiSession, opts: &ReplOptions) -> Result<usize> {
     // Tiny read-eval-print loop: parse first token as command, rest as args, keep running
     // until EOF or quit.
     let stdin = io::stdin();
     let mut line = String::new();
+    let mut state = ReplState {
+        // Scripted and replayed commands stay out of the user's history file.
+        history: if opts.batch || opts.replay {
+            History::default()
+        } else {
+            History::load()
+        },
+        build: opts.build.clone(),
+        not_started: opts.no_run,
+        ..ReplState::default()
+    };
+    // Ctrl-C pauses the running program instead of ending the session.
+    interrupt::install_handler();
+    // Interactive sessions index on a second gdb; scripts and transcripts (whose MI log
+    // must come from one gdb) wait for it.
+    let background = !opts.batch && !opts.replay && !record::active();
+    match session.sibling().filter(|_| background) {
+        Some(spec) => {
+            println!("indexing symbols in the background...");
+            state.pending_symbols = Some(BackgroundIndex::spawn(spec, opts.symbol_mode));
+        }
+        None => {
+            let index = build_symbol_index(session, opts.symbol_mode);
+            printers::print_symbol_index_summary(&index.stats);
+            state.symbols = Some(index);
+        }
+    }
+    let mut failed = 0;
+    for (i, cmd) in opts.script.iter().enumerate() {
+        if opts.replay && !opts.batch {
+            // Step through the recording: show the next command and run it on Enter.
+            print!("memviz [replay {}/{}]> {}", i + 1, opts.script.len(), cmd);
+            io::stdout().flush()?;
+            line.clear();
+            if stdin.read_line(&mut line)? == 0 || line.trim() == "q" {
+                println!();
+                return finish(&state, failed);
+            }
+        } else {
+            // Echo like a typed command so the output reads as a transcript.
+            println!("memviz> {}", cmd);
+        }
+        match run_line(cmd, session, &mut state) {
+            LineOutcome::Quit => return finish(&state, failed),
+            LineOutcome::Failed => failed += 1,
+            LineOutcome::Done => {}
+        }
+        if session.is_dead() && !offer_relaunch(session, &mut state, opts)? {
+            return finish(&state, failed);
+        }
+    }
+    if opts.replay || (opts.batch && !opts.script.is_empty()) {
+        return finish(&state, failed);
+    }
     loop {
-        print!("memviz> ");
+        state.poll_symbols();
+        if !opts.batch {
+            print!("{}", prompt(&state));
+        }
         io::stdout().flush()?;
         line.clear();
         if stdin.read_line(&mut line)? == 0 {
-            println!();
+            if !opts.batch {
+                println!();
+            }
+            break;
+        }
+        if opts.batch {
+            let cmd = line.trim();
+            if cmd.is_empty() || cmd.starts_with('#') {
+                continue;
+            }
+            println!("memviz> {}", cmd);
+        }
+        match run_line(line.trim(), session, &mut state) {
+            LineOutcome::Quit => break,
+            LineOutcome::Failed => failed += 1,
+            LineOutcome::Done => {}
+        }
+        if session.is_dead() && !offer_relaunch(session, &mut state, opts)? {
             break;
         }
-        let input = line.trim();
-        if input.is_empty() {
-            continue;
+    }
+    finish(&state, failed)
+}
+
+impl ReplState {
+    /// Take in a background index that has finished, with its summary line.
+    fn poll_symbols(&mut self) {
+        if let Some(index) = self.pending_symbols.as_ref().and_then(|p| p.try_take()) {
+            printers::print_symbol_index_summary(&index.stats);
+            self.symbols = Some(index);
+            self.pending_symbols = None;
         }
-        let mut parts = input.splitn(2, char::is_whitespace);
-        let cmd = parts.next().unwrap_or("").trim();
-        let rest = parts.next().unwrap_or("").trim();
-        match execute_command(input, cmd, rest, session) {
-            Ok(CommandOutcome::Quit) => break,
-            Ok(CommandOutcome::Continue) => {}
-            Err(e) => eprintln!("{}", e),
+    }
+
+    /// The symbol index, waiting for a background build to finish first.
+    pub fn symbol_index(&mut self) -> Option<&SymbolIndex> {
+        if let Some(pending) = self.pending_symbols.take() {
+            println!("waiting for the symbol index...");
+            let index = pending.wait();
+            printers::print_symbol_index_summary(&index.stats);
+            self.symbols = Some(index);
         }
+        self.symbols.as_ref()
+    }
+}
+
+fn prompt(state: &ReplState) -> String {
+    let label = if state.exited {
+        "[exited]"
+    } else if state.not_started {
+        "[not started]"
+    } else if state.pending_symbols.is_some() {
+        "[indexing...]"
+    } else {
+        return "memviz> ".to_string();
+    };
+    format!(
+        "memviz {}> ",
+        style::paint(label, theme::theme().unavailable)
+    )
+}
+
+enum LineOutcome {
+    Done,
+    Failed,
+    Quit,
+}
+
+/// Expand history references, record the line, and execute it.
+fn run_line(line: &str, session: &mut MiSession, state: &mut ReplState) -> LineOutcome {
+    let input = match state.history.expand(line) {
+        Ok(expanded) if expanded != line => {
+            println!("{}", expanded);
+            expanded
+        }
+        Ok(expanded) => expanded,
+        Err(e) => {
+            eprintln!("{}", e);
+            return LineOutcome::Failed;
+        }
+    };
+    let input = input.as_str();
+    if input.is_empty() {
+        return LineOutcome::Done;
+    }
+    record::log(Entry::Command, input);
+    state.history.push(input);
+    let mut parts = input.splitn(2, char::is_whitespace);
+    let cmd = parts.next().unwrap_or("").trim();
+    let rest = parts.next().unwrap_or("").trim();
+    match execute_command(input, cmd, rest, session, state) {
+        Ok(CommandOutcome::Quit) => LineOutcome::Quit,
+        Ok(CommandOutcome::Continue) => LineOutcome::Done,
+        Err(e) => {
+            eprintln!("{}", e);
+            LineOutcome::Failed
+        }
+    }
+}
+
+/// gdb died during the last command (its error said how). Interactive sessions are offered
+/// a new one; declining keeps the REPL, with `relaunch` for later. Returns false when the
+/// session should end instead.
+fn offer_relaunch(
+    session: &mut MiSession,
+    state: &mut ReplState,
+    opts: &ReplOptions,
+) -> Result<bool> {
+    let name = session.backend_name();
+    if opts.batch || opts.replay || !session.can_relaunch() {
+        eprintln!("{} is gone; ending the session", name);
+        return Ok(false);
+    }
+    let prompt = format!(
+        "relaunch {} and restore {} breakpoint(s)? [y/N] ",
+        name,
+        session.breakpoints().len()
+    );
+    if confirm(&prompt)? {
+        relaunch(session, state);
+    } else {
+        println!("'relaunch' starts {} later; until then commands fail", name);
+    }
+    Ok(true)
+}
+
+/// Ask a yes/no question on the terminal; anything but yes (or EOF) declines.
+fn confirm(prompt: &str) -> Result<bool> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn finish(state: &ReplState, failed: usize) -> Result<usize> {
+    state.history.save();
+    Ok(failed)
+}
+
+/// Commands from a `--script` file: one per line, blank lines and `#` comments skipped.
+pub fn parse_script(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_skips_blank_lines_and_comments() {
+        let text = "# inspect the list\nlocals\n\n  mem head 32  \n#done\nquit\n";
+        assert_eq!(parse_script(text), vec!["locals", "mem head 32", "quit"]);
     }
-    Ok(())
 }
@@ -0,0 +1,282 @@
+use super::printers::print_access_warnings;
+use super::style;
+use super::theme::theme;
+use crate::mi::{MiSession, Result};
+use crate::types::is_pointer_type;
+use crate::vm::{self, StackHeadroom, VmRegion};
+
+/// Type-name fragments treated as integers whose value is an address.
+const INTEGER_WORDS: &[&str] = &["int", "long", "short", "char", "size_t"];
+
+/// `addr [-f] <expr> [+offset]`: print the numeric address an expression resolves to; `-f`
+/// adds where it is in the mapped file, as `nm` and `objdump` number it.
+pub fn handle_addr(args: &str, session: &mut MiSession) -> Result<()> {
+    let mut parts: Vec<&str> = args.split_whitespace().collect();
+    let file_relative = parts.first() == Some(&"-f");
+    if file_relative {
+        parts.remove(0);
+    }
+    // A trailing "+N"/"-N" token is the offset; everything before it is the expression.
+    let offset = match parts
+        .last()
+        .filter(|p| parts.len() > 1 && is_offset_token(p))
+    {
+        Some(tok) => {
+            let off = parse_offset(tok).ok_or_else(|| format!("invalid offset '{}'", tok))?;
+            parts.pop();
+            off
+        }
+        None => 0,
+    };
+    let expr = parts.join(" ");
+    if expr.is_empty() {
+        println!("usage: addr [-f] <expr> [+offset]");
+        return Ok(());
+    }
+    let base = resolve_address(session, &expr)?;
+    let target = base.wrapping_add_signed(offset);
+    let regions = current_regions(session);
+    if offset != 0 {
+        println!("{} = {} {:+}", paint_addr(target), expr, offset);
+    } else {
+        println!("{} = {}", paint_addr(target), expr);
+    }
+    println!("  {}", describe(&regions, target));
+    if file_relative {
+        let bases = vm::module_bases(&regions);
+        match vm::file_relative(&regions, &bases, target) {
+            Some((module, link_addr, file_offset)) => println!(
+                "  file: {}+0x{:x}, link-time address 0x{:x} (bias 0x{:x})",
+                module.path.rsplit('/').next().unwrap_or(&module.path),
+                file_offset,
+                link_addr,
+                module.bias()
+            ),
+            None => println!("  file: not inside a mapped ELF file"),
+        }
+    }
+    Ok(())
+}
+
+/// `between <a> <b>`: byte distance from `a` to `b`.
+pub fn handle_between(args: &str, session: &mut MiSession) -> Result<()> {
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    if parts.len() != 2 {
+        println!("usage: between <a> <b>");
+        return Ok(());
+    }
+    let a = resolve_address(session, parts[0])?;
+    let b = resolve_address(session, parts[1])?;
+    let regions = current_regions(session);
+    let dist = b.wrapping_sub(a) as i64;
+    println!(
+        "{} ({}) -> {} ({})",
+        parts[0],
+        paint_addr(a),
+        parts[1],
+        paint_addr(b)
+    );
+    println!("  distance: {} bytes", format_signed(dist));
+    let ra = regions.iter().find(|r| r.contains(a));
+    let rb = regions.iter().find(|r| r.contains(b));
+    match (ra, rb) {
+        (Some(x), Some(y)) if x.start == y.start => println!("  both in {}", region_name(x)),
+        _ if regions.is_empty() => {}
+        _ => {
+            println!("  {}: {}", parts[0], describe(&regions, a));
+            println!("  {}: {}", parts[1], describe(&regions, b));
+            println!("  note: addresses are in different regions");
+        }
+    }
+    Ok(())
+}
+
+/// `align <addr> <n>`: alignment of an address to `n` bytes.
+pub fn handle_align(args: &str, session: &mut MiSession) -> Result<()> {
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    if parts.len() != 2 {
+        println!("usage: align <addr> <n>");
+        return Ok(());
+    }
+    let n = match parse_number(parts[1]) {
+        Some(n) if n > 0 => n,
+        _ => {
+            println!("align: <n> must be a positive number");
+            return Ok(());
+        }
+    };
+    let addr = resolve_address(session, parts[0])?;
+    let (down, up) = align_bounds(addr, n);
+    let rem = addr % n;
+    println!("{} ({}) modulo {} = {}", parts[0], paint_addr(addr), n, rem);
+    if rem == 0 {
+        println!("  aligned to {} bytes", n);
+    } else {
+        println!(
+            "  align down: 0x{:x} (-{}), align up: 0x{:x} (+{})",
+            down,
+            rem,
+            up,
+            n - rem
+        );
+    }
+    if !n.is_power_of_two() {
+        println!("  note: {} is not a power of two", n);
+    }
+    Ok(())
+}
+
+/// Numeric literals are taken as-is; pointers and integers yield their value; other objects
+/// yield their address.
+fn resolve_address(session: &mut MiSession, expr: &str) -> Result<u64> {
+    if let Some(v) = parse_number(expr) {
+        return Ok(v);
+    }
+    let (ty, _) = session.eval_expr_type_and_value(expr)?;
+    if is_pointer_type(&ty) || is_integer_type(&ty) {
+        session.eval_expr_u64(&format!("(unsigned long)({})", expr))
+    } else {
+        session.eval_address_of_expr(expr)
+    }
+}
+
+fn is_integer_type(ty: &str) -> bool {
+    let t = ty.trim();
+    if t.contains('[') || t.starts_with("struct") || t.starts_with("union") {
+        return false;
+    }
+    INTEGER_WORDS.iter().any(|k| t.contains(k))
+}
+
+/// Warn when [addr, addr+len) is not fully mapped with permission `need` ('r'/'w').
+pub fn warn_access(session: &mut MiSession, addr: u64, len: usize, need: char, op: &str) {
+    let regions = current_regions(session);
+    if regions.is_empty() {
+        return;
+    }
+    print_access_warnings(&vm::access_issues(&regions, addr, len, need), op);
+}
+
+pub fn current_regions(session: &mut MiSession) -> Vec<VmRegion> {
+    session
+        .inferior_pid()
+        .ok()
+        .and_then(|pid| process_regions(session, pid).ok())
+        .unwrap_or_default()
+}
+
+/// The process map of `pid`, with the stacks of threads other than the main one labeled
+/// `[stack:tid]` instead of anonymous memory and the guard pages below stacks `[guard]`.
+pub fn process_regions(session: &mut MiSession, pid: u32) -> std::io::Result<Vec<VmRegion>> {
+    let mut regions = vm::read_process_map(pid, false)?;
+    label_thread_stacks(session, &mut regions);
+    Ok(regions)
+}
+
+/// Label thread stacks from each thread's stack pointer, then the guard pages below the
+/// stacks; a failed lookup leaves the thread stacks as the kernel describes them.
+pub fn label_thread_stacks(session: &mut MiSession, regions: &mut [VmRegion]) {
+    if let Ok(stacks) = session.thread_stack_pointers() {
+        vm::label_thread_stacks(regions, &stacks);
+    }
+    vm::label_guard_pages(regions);
+}
+
+/// Headroom of the current thread's stack in `regions`, the map of process `pid`.
+pub fn stack_headroom(
+    session: &mut MiSession,
+    regions: &[VmRegion],
+    pid: u32,
+) -> Option<StackHeadroom> {
+    let sp = session.eval_expr_u64("$sp").ok()?;
+    vm::stack_headroom(regions, sp, vm::read_stack_limit(pid))
+}
+
+fn describe(regions: &[VmRegion], addr: u64) -> String {
+    match regions.iter().find(|r| r.contains(addr)) {
+        Some(r) => format!(
+            "{} {} +0x{:x} into 0x{:x}-0x{:x}",
+            region_name(r),
+            r.perms,
+            addr - r.start,
+            r.start,
+            r.end
+        ),
+        None if regions.is_empty() => "region unknown (no process map)".to_string(),
+        None => "not mapped".to_string(),
+    }
+}
+
+fn region_name(r: &VmRegion) -> String {
+    let tag = vm::classify_addr(std::slice::from_ref(r), r.start);
+    let tag = style::paint(&tag, theme().region(&r.label));
+    if r.pathname.is_empty() {
+        tag
+    } else {
+        format!("{} {}", tag, r.pathname)
+    }
+}
+
+fn paint_addr(addr: u64) -> String {
+    style::paint(&format!("0x{:x}", addr), theme().pointer)
+}
+
+fn format_signed(v: i64) -> String {
+    if v < 0 {
+        format!("-0x{:x} (-{})", v.unsigned_abs(), v.unsigned_abs())
+    } else {
+        format!("0x{:x} ({})", v, v)
+    }
+}
+
+fn is_offset_token(tok: &str) -> bool {
+    tok.starts_with('+') || (tok.starts_with('-') && tok.len() > 1)
+}
+
+/// Parse "+16", "-0x20", "+0x8" into a signed offset.
+fn parse_offset(tok: &str) -> Option<i64> {
+    let (neg, body) = match tok.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, tok.strip_prefix('+').unwrap_or(tok)),
+    };
+    let v = parse_number(body)? as i64;
+    Some(if neg { -v } else { v })
+}
+
+/// A numeric literal: decimal, or hex with `0x`.
+pub fn parse_number(tok: &str) -> Option<u64> {
+    match tok.strip_prefix("0x").or_else(|| tok.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => tok.parse::<u64>().ok(),
+    }
+}
+
+fn align_bounds(addr: u64, n: u64) -> (u64, u64) {
+    let down = addr - addr % n;
+    let up = if addr.is_multiple_of(n) {
+        addr
+    } else {
+        down + n
+    };
+    (down, up)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_offset_accepts_sign_and_hex() {
+        assert_eq!(parse_offset("+16"), Some(16));
+        assert_eq!(parse_offset("-0x20"), Some(-32));
+        assert_eq!(parse_offset("+zz"), None);
+        assert!(!is_offset_token("-"));
+    }
+
+    #[test]
+    fn align_bounds_rounds_both_ways() {
+        assert_eq!(align_bounds(0x1003, 8), (0x1000, 0x1008));
+        assert_eq!(align_bounds(0x1000, 16), (0x1000, 0x1000));
+        assert_eq!(align_bounds(25, 12), (24, 36));
+    }
+}
@@ -0,0 +1,274 @@
+// `args [n]`: a frame's arguments (`-stack-list-arguments`) and where each one arrived under
+// the target's calling convention: the registers it was passed in, or its slot above the CFA.
+// Only the common cases are modelled (scalars, pointers, floating point, small aggregates),
+// from the type and size gdb reports. By the time of a stop the argument registers have
+// usually been reused, so values come from the frame, not from the registers named here.
+use super::stack;
+use crate::mi::{LocalVar, MiSession, Result};
+
+/// Where an argument class is passed when the convention does not follow the type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgClass {
+    /// Integers, pointers, enums: integer registers.
+    Integer,
+    /// `float`/`double`: floating-point registers.
+    Float,
+    /// Structs and unions: integer registers when small enough.
+    Aggregate,
+    /// Always on the stack (x87 `long double`).
+    Memory,
+}
+
+/// Argument passing rules for one architecture.
+pub struct Abi {
+    pub name: &'static str,
+    int_regs: &'static [&'static str],
+    float_regs: &'static [&'static str],
+    /// Size and alignment of a stack argument slot.
+    slot: u64,
+    /// Aggregates over two slots are passed as a pointer to a copy instead of on the stack.
+    by_reference: bool,
+    long_double: ArgClass,
+}
+
+const SYSV_X86_64: Abi = Abi {
+    name: "x86-64 System V",
+    int_regs: &["rdi", "rsi", "rdx", "rcx", "r8", "r9"],
+    float_regs: &[
+        "xmm0", "xmm1", "xmm2", "xmm3", "xmm4", "xmm5", "xmm6", "xmm7",
+    ],
+    slot: 8,
+    by_reference: false,
+    long_double: ArgClass::Memory,
+};
+
+const AAPCS64: Abi = Abi {
+    name: "AAPCS64",
+    int_regs: &["x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7"],
+    float_regs: &["v0", "v1", "v2", "v3", "v4", "v5", "v6", "v7"],
+    slot: 8,
+    by_reference: true,
+    long_double: ArgClass::Float,
+};
+
+const RISCV_LP64D: Abi = Abi {
+    name: "RISC-V LP64D",
+    int_regs: &["a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7"],
+    float_regs: &["fa0", "fa1", "fa2", "fa3", "fa4", "fa5", "fa6", "fa7"],
+    slot: 8,
+    by_reference: true,
+    // 128-bit, passed in an integer register pair.
+    long_double: ArgClass::Aggregate,
+};
+
+const I386_CDECL: Abi = Abi {
+    name: "i386 cdecl",
+    int_regs: &[],
+    float_regs: &[],
+    slot: 4,
+    by_reference: false,
+    long_double: ArgClass::Memory,
+};
+
+/// Calling convention for gdb's `architecture` string.
+pub fn abi_for(arch: Option<&str>) -> Option<&'static Abi> {
+    let a = arch.unwrap_or("").to_ascii_lowercase();
+    if a.contains("x86-64") || a.contains("x86_64") || a.contains("amd64") {
+        Some(&SYSV_X86_64)
+    } else if a.contains("aarch64") {
+        Some(&AAPCS64)
+    } else if a.contains("riscv") {
+        Some(&RISCV_LP64D)
+    } else if a.contains("i386") || a.contains("i686") {
+        Some(&I386_CDECL)
+    } else {
+        None
+    }
+}
+
+/// Where an argument was passed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgHome {
+    Registers(Vec<&'static str>),
+    /// At `cfa + offset`.
+    Stack(u64),
+    /// A pointer to the caller's copy was passed, here.
+    Reference(Box<ArgHome>),
+}
+
+impl ArgHome {
+    pub fn describe(&self) -> String {
+        match self {
+            ArgHome::Registers(regs) => format!("in {}", regs.join(":")),
+            ArgHome::Stack(offset) => format!("on the stack at cfa+0x{:x}", offset),
+            ArgHome::Reference(to) => format!("by reference, pointer {}", to.describe()),
+        }
+    }
+
+    /// Offset above the CFA of a stack-passed argument (or of the pointer to it).
+    pub fn stack_offset(&self) -> Option<u64> {
+        match self {
+            ArgHome::Stack(offset) => Some(*offset),
+            ArgHome::Reference(to) => to.stack_offset(),
+            ArgHome::Registers(_) => None,
+        }
+    }
+}
+
+pub struct ArgPlacement {
+    pub arg: LocalVar,
+    /// `sizeof`, when gdb could evaluate it.
+    pub size: Option<u64>,
+    /// `None` for an unknown architecture or size.
+    pub home: Option<ArgHome>,
+}
+
+pub struct FrameArgs {
+    pub level: u32,
+    pub func: Option<String>,
+    pub abi: Option<&'static str>,
+    pub cfa: Option<u64>,
+    pub args: Vec<ArgPlacement>,
+}
+
+impl FrameArgs {
+    /// Stack-passed arguments as (name, slot address).
+    pub fn stack_slots(&self) -> Vec<(String, u64)> {
+        let Some(cfa) = self.cfa else {
+            return Vec::new();
+        };
+        self.args
+            .iter()
+            .filter_map(|p| {
+                let offset = p.home.as_ref()?.stack_offset()?;
+                Some((p.arg.name.clone(), cfa + offset))
+            })
+            .collect()
+    }
+}
+
+/// Arguments of frame `level`, which must be the selected frame (sizes and the CFA are read
+/// there).
+pub fn read_args(session: &mut MiSession, level: u32, func: Option<String>) -> Result<FrameArgs> {
+    let args = session.list_arguments(level)?;
+    session.ensure_arch();
+    let abi = abi_for(session.arch.as_deref());
+    let sizes: Vec<Option<u64>> = args
+        .iter()
+        .map(|a| session.evaluate_sizeof(&a.name).ok().map(|s| s as u64))
+        .collect();
+    let homes = match abi {
+        Some(abi) if sizes.iter().all(Option::is_some) => {
+            let classes: Vec<(ArgClass, u64)> = args
+                .iter()
+                .zip(&sizes)
+                .map(|(a, size)| (classify(a, abi), size.unwrap_or(0)))
+                .collect();
+            place(abi, &classes).into_iter().map(Some).collect()
+        }
+        _ => vec![None; args.len()],
+    };
+    Ok(FrameArgs {
+        level,
+        func,
+        abi: abi.map(|a| a.name),
+        cfa: stack::frame_cfa(session).ok(),
+        args: args
+            .into_iter()
+            .zip(sizes)
+            .zip(homes)
+            .map(|((arg, size), home)| ArgPlacement { arg, size, home })
+            .collect(),
+    })
+}
+
+/// Class of an argument from its type; gdb's simple values leave aggregates without a value.
+fn classify(arg: &LocalVar, abi: &Abi) -> ArgClass {
+    if arg.value.is_none() {
+        return ArgClass::Aggregate;
+    }
+    let ty = arg.ty.as_deref().unwrap_or("");
+    let ty = ty
+        .trim()
+        .trim_start_matches("const ")
+        .trim_start_matches("volatile ");
+    match ty {
+        "float" | "double" | "_Float32" | "_Float64" => ArgClass::Float,
+        "long double" => abi.long_double,
+        _ => ArgClass::Integer,
+    }
+}
+
+/// Assign registers and stack slots in order, as the caller did.
+fn place(abi: &Abi, args: &[(ArgClass, u64)]) -> Vec<ArgHome> {
+    let (mut next_int, mut next_float, mut offset) = (0, 0, 0);
+    let mut on_stack = |size: u64| {
+        let at = offset;
+        offset += size.div_ceil(abi.slot).max(1) * abi.slot;
+        ArgHome::Stack(at)
+    };
+    args.iter()
+        .map(|&(class, size)| {
+            let words = size.div_ceil(abi.slot).max(1) as usize;
+            match class {
+                ArgClass::Float if next_float < abi.float_regs.len() => {
+                    next_float += 1;
+                    ArgHome::Registers(vec![abi.float_regs[next_float - 1]])
+                }
+                ArgClass::Integer | ArgClass::Aggregate
+                    if words <= 2 && next_int + words <= abi.int_regs.len() =>
+                {
+                    next_int += words;
+                    ArgHome::Registers(abi.int_regs[next_int - words..next_int].to_vec())
+                }
+                ArgClass::Aggregate if words > 2 && abi.by_reference => {
+                    let pointer = if next_int < abi.int_regs.len() {
+                        next_int += 1;
+                        ArgHome::Registers(vec![abi.int_regs[next_int - 1]])
+                    } else {
+                        on_stack(abi.slot)
+                    };
+                    ArgHome::Reference(Box::new(pointer))
+                }
+                _ => on_stack(size),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arguments_fill_registers_then_the_stack() {
+        use ArgClass::*;
+        // f(int, double, struct {long a, b}, struct {char buf[40]}, long, long, long, long)
+        let args = [
+            (Integer, 4),
+            (Float, 8),
+            (Aggregate, 16),
+            (Aggregate, 40),
+            (Integer, 8),
+            (Integer, 8),
+            (Integer, 8),
+            (Integer, 8),
+        ];
+        let sysv = place(&SYSV_X86_64, &args);
+        assert_eq!(sysv[0], ArgHome::Registers(vec!["rdi"]));
+        assert_eq!(sysv[1], ArgHome::Registers(vec!["xmm0"]));
+        assert_eq!(sysv[2], ArgHome::Registers(vec!["rsi", "rdx"]));
+        assert_eq!(sysv[3], ArgHome::Stack(0));
+        assert_eq!(sysv[6], ArgHome::Registers(vec!["r9"]));
+        assert_eq!(sysv[7], ArgHome::Stack(40));
+        let arm = place(&AAPCS64, &args);
+        assert_eq!(
+            arm[3],
+            ArgHome::Reference(Box::new(ArgHome::Registers(vec!["x3"])))
+        );
+        assert_eq!(arm[7], ArgHome::Registers(vec!["x7"]));
+        let i386 = place(&I386_CDECL, &args);
+        assert_eq!(i386[1], ArgHome::Stack(4));
+        assert_eq!(i386[4], ArgHome::Stack(68));
+    }
+}
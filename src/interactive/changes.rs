@@ -0,0 +1,101 @@
+use crate::mi::{GlobalVar, LocalVar};
+use std::collections::{HashMap, HashSet};
+
+/// Values of locals/globals at one stop.
+#[derive(Default)]
+struct Snapshot {
+    stop: u64,
+    /// Function stopped in; locals are only compared within the same function.
+    func: Option<String>,
+    locals: HashMap<String, String>,
+    globals: HashMap<String, String>,
+}
+
+/// Locals/globals seen at consecutive stops, for changed-since-last-stop highlighting in
+/// `locals` and `globals`.
+#[derive(Default)]
+pub struct ValueHistory {
+    /// Snapshot at every stop once `locals`/`globals` has been used.
+    pub active: bool,
+    prev: Snapshot,
+    curr: Snapshot,
+}
+
+impl ValueHistory {
+    /// Start a new stop: the current snapshot becomes the baseline.
+    pub fn begin_stop(&mut self, stop: u64, func: Option<String>) {
+        let next = Snapshot {
+            stop,
+            func,
+            ..Snapshot::default()
+        };
+        self.prev = std::mem::replace(&mut self.curr, next);
+    }
+
+    pub fn record_locals(&mut self, locals: &[LocalVar]) {
+        self.curr.locals = locals
+            .iter()
+            .filter_map(|l| Some((l.name.clone(), l.value.clone()?)))
+            .collect();
+    }
+
+    pub fn record_globals(&mut self, globals: &[GlobalVar]) {
+        self.curr.globals = globals
+            .iter()
+            .map(|g| (g.name.clone(), g.value.clone()))
+            .collect();
+    }
+
+    /// Names of locals whose value differs from the previous stop.
+    pub fn changed_locals(&self) -> HashSet<String> {
+        if self.prev.func != self.curr.func || !self.consecutive() {
+            return HashSet::new();
+        }
+        changed(&self.prev.locals, &self.curr.locals)
+    }
+
+    pub fn changed_globals(&self) -> HashSet<String> {
+        if !self.consecutive() {
+            return HashSet::new();
+        }
+        changed(&self.prev.globals, &self.curr.globals)
+    }
+
+    /// The baseline was taken at the stop right before this one.
+    fn consecutive(&self) -> bool {
+        self.prev.stop + 1 == self.curr.stop
+    }
+}
+
+fn changed(prev: &HashMap<String, String>, curr: &HashMap<String, String>) -> HashSet<String> {
+    curr.iter()
+        .filter(|(name, value)| prev.get(*name).is_some_and(|p| p != *value))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(name: &str, value: &str) -> LocalVar {
+        LocalVar {
+            name: name.to_string(),
+            ty: Some("int".to_string()),
+            value: Some(value.to_string()),
+        }
+    }
+
+    #[test]
+    fn locals_compare_only_within_one_function() {
+        let mut h = ValueHistory::default();
+        h.begin_stop(1, Some("main".to_string()));
+        h.record_locals(&[local("i", "0"), local("n", "3")]);
+        h.begin_stop(2, Some("main".to_string()));
+        h.record_locals(&[local("i", "1"), local("n", "3")]);
+        assert_eq!(h.changed_locals(), HashSet::from(["i".to_string()]));
+        h.begin_stop(3, Some("helper".to_string()));
+        h.record_locals(&[local("i", "7")]);
+        assert!(h.changed_locals().is_empty());
+    }
+}
@@ -1,13 +1,47 @@
+use super::addr;
+use super::args;
+use super::confirm;
+use super::expand;
 use super::follow;
+use super::hexedit;
+use super::hooks::HookWhen;
+use super::json;
 use super::printers::{
-    print_breakpoint, print_locals, print_memory_body, print_memory_full, print_stopped,
-    print_vm_locate, print_vm_regions, print_vm_vars, HeapObjectInfo, RegionVarsSummary,
-    SymbolInfo, VmLocateInfo,
+    format_c_string, print_breakpoint, print_breakpoint_table, print_canary_alerts,
+    print_canary_reports, print_frame_args, print_frame_view, print_frames, print_heap_arenas,
+    print_heap_bins, print_heap_blocks, print_locals, print_low_stack_warning, print_malloc_chunk,
+    print_memory_annotated, print_memory_body, print_memory_full, print_module_bases,
+    print_stack_canary, print_stack_headroom, print_stack_usage, print_stopped,
+    print_symbol_index_summary, print_symbol_stats, print_type_tree, print_vm_layout,
+    print_vm_locate, print_vm_map, print_vm_regions, print_vm_stats, print_vm_vars, HeapObjectInfo,
+    LayoutMarker, RegionVarsSummary, SymbolInfo, VmLocateInfo,
+};
+use super::source;
+use super::stack;
+use super::stl;
+use super::style;
+use super::theme;
+use super::track::{ShapeKind, TrackedShape};
+use super::watch::Watch;
+use super::ReplState;
+use crate::compile;
+use crate::heap::{
+    read_arenas, read_bins, read_chunk, read_mmap_stats, HeapTracker, DEFAULT_GUARD,
+};
+use crate::mi::{AddressSymbol, GlobalVar, MemoryDump, MiSession, Result, StoppedLocation};
+use crate::symbols::{build_symbol_index, is_program_library, FoundSymbol, SymbolIndexMode};
+use crate::types::{
+    is_char_pointer, is_pointer_type, normalize_type_name, strip_pointer_suffix, TypeLayout,
 };
-use crate::mi::{MiSession, Result};
-use crate::types::{is_pointer_type, normalize_type_name, strip_pointer_suffix, TypeLayout};
 use crate::vm::{self, VmLabel};
-use std::collections::HashMap;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Results `find` lists.
+const FIND_RESULTS: usize = 20;
+/// Longest string `view` shows behind a `char *`.
+const VIEW_STRING_BYTES: usize = 256;
 
 pub enum CommandOutcome {
     Continue,
@@ -19,114 +53,1792 @@ pub fn execute_command(
     cmd: &str,
     rest: &str,
     session: &mut MiSession,
+    state: &mut ReplState,
 ) -> Result<CommandOutcome> {
     if cmd == "globals" {
-        if !rest.is_empty() {
-            println!("usage: globals");
+        let mut words = rest.split_whitespace();
+        match words.next() {
+            None => handle_globals(None, session, state),
+            Some("fold") => fold_files(words.collect(), true, state),
+            Some("unfold") => fold_files(words.collect(), false, state),
+            Some(_) => match Regex::new(rest) {
+                Ok(re) => handle_globals(Some(&re), session, state),
+                Err(e) => eprintln!("globals: bad pattern: {}", e),
+            },
+        }
+        return Ok(CommandOutcome::Continue);
+    }
+    if let Some(pattern) = input.trim().strip_prefix('/') {
+        if let Err(e) = source::handle_search(pattern.trim(), false, session, &mut state.source) {
+            eprintln!("search: {}", e);
+        }
+        return Ok(CommandOutcome::Continue);
+    }
+    if let Some(pattern) = input.trim().strip_prefix('?') {
+        if let Err(e) = source::handle_search(pattern.trim(), true, session, &mut state.source) {
+            eprintln!("search: {}", e);
+        }
+        return Ok(CommandOutcome::Continue);
+    }
+    // Special-case vm parsing to catch invalid usages.
+    if cmd == "vm" {
+        let parts: Vec<_> = input.split_whitespace().collect();
+        if parts.len() == 1 {
+            handle_vm(session);
+            return Ok(CommandOutcome::Continue);
+        }
+        if parts.len() == 2 && parts[1] == "layout" {
+            handle_vm_layout(session, state);
+            return Ok(CommandOutcome::Continue);
+        }
+        if parts.len() == 2 && parts[1] == "vars" {
+            handle_vm_vars(session, state);
+            return Ok(CommandOutcome::Continue);
+        }
+        if parts.len() == 2 && parts[1] == "stats" {
+            handle_vm_stats(session);
+            return Ok(CommandOutcome::Continue);
+        }
+        if parts.len() == 2 && parts[1] == "map" {
+            handle_vm_map(session);
+            return Ok(CommandOutcome::Continue);
+        }
+        if parts.len() == 2 && parts[1] == "bases" {
+            handle_vm_bases(session);
+            return Ok(CommandOutcome::Continue);
+        }
+        if parts.len() == 3 && parts[1] == "track" {
+            handle_vm_track(parts[2], session, state);
+            return Ok(CommandOutcome::Continue);
+        }
+        if parts.len() <= 3 && parts[1] == "history" {
+            match parts.get(2).map(|n| n.parse::<usize>()) {
+                None => state.map_track.print_history(None),
+                Some(Ok(n)) => state.map_track.print_history(Some(n)),
+                Some(Err(_)) => eprintln!("usage: vm history [n]"),
+            }
+            return Ok(CommandOutcome::Continue);
+        }
+        if parts.len() <= 3 && parts[1] == "diff" {
+            handle_vm_diff(parts.get(2).copied().unwrap_or(""), session, state);
+            return Ok(CommandOutcome::Continue);
+        }
+        if parts.len() >= 2 && parts[1] == "locate" {
+            if parts.len() >= 3 {
+                let expr = parts[2..].join(" ");
+                handle_vm_locate(&expr, session);
+            } else {
+                eprintln!(
+                    "invalid vm usage: '{}'\n  usage: vm\n         vm vars\n         vm layout\n         vm map\n         vm stats\n         vm bases\n         vm diff [on|off]\n         vm track on|off\n         vm history [n]\n         vm locate <expr>",
+                    input.trim()
+                );
+            }
+            return Ok(CommandOutcome::Continue);
+        }
+        eprintln!(
+            "invalid vm usage: '{}'\n  usage: vm\n         vm vars\n         vm layout\n         vm map\n         vm stats\n         vm bases\n         vm diff [on|off]\n         vm track on|off\n         vm history [n]\n         vm locate <expr>",
+            input.trim()
+        );
+        return Ok(CommandOutcome::Continue);
+    }
+
+    match cmd {
+        "quit" | "q" => return Ok(CommandOutcome::Quit),
+        "help" => print_help(),
+        "relaunch" => relaunch(session, state),
+        "locals" => match session.list_locals() {
+            Ok(locals) => {
+                state.values.active = true;
+                // Highlighting compares stop-to-stop values of the innermost frame only.
+                let changed = if state.frame == 0 {
+                    state.values.record_locals(&locals);
+                    state.values.changed_locals()
+                } else {
+                    HashSet::new()
+                };
+                if json::enabled() {
+                    json::emit(json::locals(&locals, &changed));
+                } else {
+                    print_locals(&locals, &changed);
+                }
+            }
+            Err(e) => eprintln!("locals error: {}", e),
+        },
+        "mem" => handle_mem(rest, session, &state.heap),
+        "view" => {
+            if rest.is_empty() {
+                println!("usage: view <symbol>");
+            } else {
+                let symbol = rest.split_whitespace().next().unwrap_or("");
+                if let Err(e) = handle_view(symbol, session, &state.heap) {
+                    eprintln!("{}", e);
+                }
+            }
+        }
+        "cast" => {
+            if let Err(e) = handle_cast(rest, session, &state.heap) {
+                eprintln!("cast error: {}", e);
+            }
+        }
+        "addr" => {
+            if let Err(e) = addr::handle_addr(rest, session) {
+                eprintln!("addr error: {}", e);
+            }
+        }
+        "between" => {
+            if let Err(e) = addr::handle_between(rest, session) {
+                eprintln!("between error: {}", e);
+            }
+        }
+        "align" => {
+            if let Err(e) = addr::handle_align(rest, session) {
+                eprintln!("align error: {}", e);
+            }
+        }
+        "expand" => {
+            if let Err(e) = expand::handle_expand(rest, session) {
+                eprintln!("expand error: {}", e);
+            }
+        }
+        "typetree" => {
+            if rest.is_empty() {
+                println!("usage: typetree <type> [depth]");
+            } else if let Err(e) = handle_typetree(rest, session) {
+                eprintln!("typetree error: {}", e);
+            }
+        }
+        "follow" => {
+            if rest.is_empty() {
+                println!("usage: follow <symbol> [depth]");
+            } else if let Err(e) = follow::handle_follow(rest, session) {
+                eprintln!("{}", e);
+            }
+        }
+        "hexedit" => {
+            if rest.is_empty() {
+                println!("usage: hexedit <expr> [len]");
+            } else if let Err(e) = hexedit::handle_hexedit(rest, session) {
+                eprintln!("hexedit error: {}", e);
+            }
+        }
+        "break" | "b" => {
+            if rest.is_empty() {
+                println!("usage: break <location>");
+            } else {
+                match session.add_breakpoint(rest) {
+                    Ok(info) => print_breakpoint(&info),
+                    Err(e) => eprintln!("break error: {}", e),
+                }
+            }
+        }
+        "catch" => handle_catch(rest, session),
+        "breakpoints" | "bl" => handle_breakpoints(rest, session, state),
+        "enable" | "disable" | "delete" => {
+            if handle_breakpoint_edit(cmd, rest, session) && cmd == "delete" {
+                if let Ok(n) = rest.parse::<u32>() {
+                    state.hit_logs.remove(&n);
+                }
+            }
+        }
+        "heap" => handle_heap(rest, session, state),
+        "on-enter" => handle_hook_add(HookWhen::Enter, rest, session, state),
+        "on-exit" => handle_hook_add(HookWhen::Exit, rest, session, state),
+        "hooks" => handle_hooks(rest, session, state),
+        "track" => handle_track(rest, session, state),
+        "display" => handle_display(rest, session, state),
+        "theme" => handle_theme(rest),
+        "history" => state.history.print(rest),
+        "output" => handle_output(rest, session, state),
+        "input" => {
+            // Typed as a line; the program reads it once it runs again.
+            if let Err(e) = session.send_inferior_input(&format!("{}\n", rest)) {
+                eprintln!("input: {}", e);
+            }
+        }
+        "undisplay" => match rest.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= state.watches.len() => {
+                let w = state.watches.remove(n - 1);
+                println!("display: removed {}", w.expr);
+            }
+            _ => eprintln!("usage: undisplay <n>"),
+        },
+        "regs" => handle_regs(rest, session, state),
+        "sym" => handle_sym(rest, session, state),
+        "find" => handle_find(rest, session, state),
+        "bt" | "backtrace" => match session.list_frames() {
+            Ok(frames) => print_frames(&frames, state.frame),
+            Err(e) => eprintln!("bt error: {}", e),
+        },
+        "frame" | "f" => handle_frame(rest, session, state),
+        "stack" => handle_stack(rest, session, state),
+        "args" => handle_args(rest, session, state),
+        "list" | "l" => {
+            if let Err(e) = source::handle_list(rest, session, &mut state.source) {
+                eprintln!("list error: {}", e);
+            }
+        }
+        "toggle" => {
+            if rest.is_empty() {
+                println!("usage: toggle [<file>:]<line>");
+            } else if let Err(e) = source::handle_toggle(rest, session, &mut state.source) {
+                eprintln!("toggle error: {}", e);
+            }
+        }
+        "next" | "n" => {
+            if let Err(e) = resume(session, state, Resume::Next) {
+                eprintln!("next error: {}", e);
+            }
+        }
+        "step" | "s" => {
+            if let Err(e) = resume(session, state, Resume::Step) {
+                eprintln!("step error: {}", e);
+            }
+        }
+        "continue" | "c" => {
+            if let Err(e) = resume(session, state, Resume::Continue) {
+                eprintln!("continue error: {}", e);
+            }
+        }
+        "run" | "r" => {
+            if let Err(e) = resume(session, state, Resume::Run) {
+                eprintln!("run error: {}", e);
+            }
+        }
+        "finish" | "fin" => {
+            if let Err(e) = resume(session, state, Resume::Finish) {
+                eprintln!("finish error: {}", e);
+            }
+        }
+        "jump" => handle_jump(rest, session, state)?,
+        "reverse-next" | "rn" | "reverse-step" | "rs" | "reverse-continue" | "rc"
+        | "reverse-finish" => {
+            let what = match cmd {
+                "reverse-next" | "rn" => "next",
+                "reverse-step" | "rs" => "step",
+                "reverse-continue" | "rc" => "continue",
+                _ => "finish",
+            };
+            if let Err(e) = resume(session, state, Resume::Reverse(what)) {
+                eprintln!("reverse-{} error: {}", what, e);
+            }
+        }
+        _ => {
+            println!("unknown command: '{}'", input);
+        }
+    }
+    Ok(CommandOutcome::Continue)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Resume<'a> {
+    Next,
+    Step,
+    Continue,
+    Finish,
+    Run,
+    /// `-exec-<what> --reverse`.
+    Reverse(&'static str),
+    /// `-exec-jump <location>`.
+    Jump(&'a str),
+}
+
+impl Resume<'_> {
+    fn exec(self, session: &mut MiSession) -> Result<StoppedLocation> {
+        match self {
+            Resume::Next => session.exec_next(),
+            Resume::Step => session.exec_step(),
+            Resume::Continue => session.exec_continue(),
+            Resume::Finish => session.exec_finish(),
+            Resume::Run => session.exec_run(),
+            Resume::Reverse(what) => session.exec_reverse(what),
+            Resume::Jump(location) => session.exec_jump(location),
+        }
+    }
+
+    /// How to go on after a serviced stop; a run or jump only starts/moves the program once.
+    fn again(self) -> Self {
+        match self {
+            Resume::Run | Resume::Jump(_) => Resume::Continue,
+            how => how,
+        }
+    }
+}
+
+/// Run an exec command, servicing internal stops (allocator and function hooks) until the
+/// inferior stops somewhere the user should see.
+fn resume(session: &mut MiSession, state: &mut ReplState, how: Resume) -> Result<()> {
+    if state.exited && how != Resume::Run {
+        return Err("the program is not being run (use 'run' to start it again)".into());
+    }
+    if state.not_started && how != Resume::Run {
+        return Err("the program has not been started (use 'run' to start it)".into());
+    }
+    if how == Resume::Run {
+        rebuild_if_stale(session, state)?;
+        // Blocks from the previous run are gone with its heap, canary slots with its stack.
+        state.heap.reset();
+        state.canaries.reset();
+        state.stack_usage.reset();
+    }
+    if let Resume::Reverse(_) = how {
+        // Allocator and hook stops are passed over, not serviced: their bookkeeping assumes
+        // time moves forward.
+        state.map_track.arm(session, false);
+        let mut loc = how.exec(session)?;
+        while state.heap.owns_stop(&loc)
+            || state.map_track.owns_stop(&loc)
+            || state.hooks.func_for_stop(&loc).is_some()
+        {
+            loc = how.exec(session)?;
+        }
+        after_stop(session, state, &loc);
+        return Ok(());
+    }
+    let free_running = matches!(how, Resume::Continue | Resume::Run | Resume::Jump(_));
+    state.map_track.arm(session, free_running);
+    let mut loc = run_serviced(session, state, |s| how.exec(s), |s| how.again().exec(s))?;
+    while let Some(func) = state.hooks.func_for_stop(&loc) {
+        let hit = state.hooks.record_hit(&func);
+        run_hooks(session, state, &func, HookWhen::Enter, hit);
+        let exit_cmds = state.hooks.commands(&func, HookWhen::Exit);
+        if exit_cmds.is_empty() && how == Resume::Step {
+            // Stepping into a hooked function: stay there, as step normally would.
+            break;
+        }
+        // Next and finish must not end up inside the hooked callee.
+        if !exit_cmds.is_empty() || how == Resume::Next || how == Resume::Finish {
+            let fin = run_serviced(
+                session,
+                state,
+                MiSession::exec_finish,
+                MiSession::exec_finish,
+            )?;
+            if fin.reason.as_deref() != Some("function-finished") {
+                if !exit_cmds.is_empty() {
+                    println!(
+                        "[on-exit {} #{}] skipped: stopped before it returned",
+                        func, hit
+                    );
+                }
+                loc = fin;
+                break;
+            }
+            run_hooks(session, state, &func, HookWhen::Exit, hit);
+        }
+        let again = |s: &mut MiSession| how.again().exec(s);
+        loc = run_serviced(session, state, again, again)?;
+    }
+    after_stop(session, state, &loc);
+    Ok(())
+}
+
+/// `relaunch`: start a new gdb after the old one died and set up again what the REPL had
+/// installed in it. The program starts over with the next `run`.
+pub(super) fn relaunch(session: &mut MiSession, state: &mut ReplState) {
+    if !session.is_dead() {
+        println!("{} is running; nothing to relaunch", session.backend_name());
+        return;
+    }
+    // Hit logs follow their breakpoints to the numbers the new gdb gives them.
+    let old_locations: HashMap<u32, Option<String>> = session
+        .breakpoints()
+        .iter()
+        .map(|b| (b.number, b.location.clone()))
+        .collect();
+    match session.relaunch() {
+        Ok(warnings) => {
+            for w in warnings {
+                eprintln!("relaunch: {}", w);
+            }
+        }
+        Err(e) => {
+            eprintln!("relaunch failed: {}", e);
+            return;
+        }
+    }
+    // Hook and allocator breakpoints are internal, so the session did not restore them.
+    if let Err(e) = state.hooks.reinstall(session) {
+        eprintln!("relaunch: hooks: {}", e);
+    }
+    let tracking = state.heap.is_tracking();
+    let canary = state.heap.canary_guard();
+    state.heap = HeapTracker::default();
+    if tracking {
+        match state.heap.enable(session) {
+            Ok(()) => state.heap.set_canary(canary),
+            Err(e) => eprintln!("relaunch: heap tracking: {}", e),
+        }
+    }
+    if let Err(e) = state.map_track.reinstall(session, state.stop_count) {
+        eprintln!("relaunch: vm track: {}", e);
+    }
+    state.canaries.reset();
+    state.stack_usage.reset();
+    let logs = std::mem::take(&mut state.hit_logs);
+    for (old, exprs) in logs {
+        let location = old_locations.get(&old).cloned().flatten();
+        let renumbered = session
+            .breakpoints()
+            .iter()
+            .find(|b| location.is_some() && b.location == location)
+            .map(|b| b.number);
+        if let Some(number) = renumbered {
+            state.hit_logs.insert(number, exprs);
+        }
+    }
+    state.frame = 0;
+    state.exited = false;
+    state.not_started = true;
+    print_breakpoint_table(session.breakpoints(), &state.hit_logs);
+    println!(
+        "{} relaunched; the program is not started ('run' starts it)",
+        session.backend_name()
+    );
+}
+
+/// `jump [-y] <loc>`: resume at another line after confirming, since the skipped (or
+/// repeated) code leaves memory in a state the program never reaches on its own.
+fn handle_jump(rest: &str, session: &mut MiSession, state: &mut ReplState) -> Result<()> {
+    let (yes, location) = match rest.strip_prefix("-y") {
+        Some(loc) if loc.is_empty() || loc.starts_with(' ') => (true, loc.trim()),
+        _ => (false, rest),
+    };
+    if location.is_empty() {
+        println!("usage: jump [-y] <location>");
+        return Ok(());
+    }
+    if !state.exited && !state.not_started && !yes {
+        let prompt = format!(
+            "resume at {}? code in between is skipped or run again [y/N] ",
+            location
+        );
+        if !confirm(&prompt)? {
+            println!("jump cancelled");
+            return Ok(());
+        }
+    }
+    if let Err(e) = resume(session, state, Resume::Jump(location)) {
+        eprintln!("jump error: {}", e);
+    }
+    Ok(())
+}
+
+/// Before `run`: when sources are newer than the binary, rebuild with `--build` and reload
+/// it, or tell the user how to.
+fn rebuild_if_stale(session: &mut MiSession, state: &mut ReplState) -> Result<()> {
+    let sources: Vec<String> = session
+        .list_source_files()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|f| f.fullname)
+        .collect();
+    let stale = compile::stale_sources(Path::new(&session.target), &sources);
+    if stale.is_empty() {
+        return Ok(());
+    }
+    let names: Vec<&str> = stale
+        .iter()
+        .map(|s| s.rsplit('/').next().unwrap_or(s))
+        .collect();
+    match &state.build {
+        Some(cmd) => {
+            println!("sources changed ({}); rebuilding", names.join(", "));
+            compile::run_build(cmd)?;
+            session.reload_executable()?;
+        }
+        None => println!(
+            "{}",
+            style::paint(
+                &format!(
+                    "warning: {} changed since {} was built; this run uses the old code \
+                     (rebuild it, or start with --build \"<cmd>\" to rebuild on run)",
+                    names.join(", "),
+                    session.target
+                ),
+                theme::theme().warning
+            )
+        ),
+    }
+    Ok(())
+}
+
+/// Run one exec step, transparently handling heap-tracker and `vm track` stops; after one,
+/// the program goes on with `then` (a `run` must not start it over).
+fn run_serviced(
+    session: &mut MiSession,
+    state: &mut ReplState,
+    exec: impl Fn(&mut MiSession) -> Result<StoppedLocation>,
+    then: impl Fn(&mut MiSession) -> Result<StoppedLocation>,
+) -> Result<StoppedLocation> {
+    let mut loc = exec(session)?;
+    loop {
+        if state.map_track.owns_stop(&loc) {
+            if let Err(e) = state.map_track.handle_stop(session, &loc, state.stop_count) {
+                eprintln!("vm track: {}", e);
+            }
+        } else if state.heap.owns_stop(&loc) {
+            let serviced = state.heap.handle_stop(session, &loc)?;
+            let when = format!("before stop #{}", state.stop_count + 1);
+            print_canary_reports(&serviced.reports, &when);
+            if let Some(stop) = serviced.stop {
+                return Ok(stop);
+            }
         } else {
-            handle_globals(session);
+            return Ok(loc);
+        }
+        loc = then(session)?;
+    }
+}
+
+fn run_hooks(session: &mut MiSession, state: &mut ReplState, func: &str, when: HookWhen, hit: u64) {
+    for line in state.hooks.commands(func, when) {
+        println!("[{} {} #{}] {}", when.label(), func, hit, line);
+        let mut parts = line.splitn(2, char::is_whitespace);
+        let cmd = parts.next().unwrap_or("");
+        let rest = parts.next().unwrap_or("").trim();
+        if let Err(e) = execute_command(&line, cmd, rest, session, state) {
+            eprintln!("{} {}: {}", when.label(), func, e);
+        }
+    }
+}
+
+fn handle_hook_add(when: HookWhen, rest: &str, session: &mut MiSession, state: &mut ReplState) {
+    let mut parts = rest.splitn(2, char::is_whitespace);
+    let func = parts.next().unwrap_or("");
+    let command = parts.next().unwrap_or("").trim();
+    if func.is_empty() || command.is_empty() {
+        println!("usage: {} <func> <command>", when.label());
+        return;
+    }
+    match state.hooks.add(session, when, func, command) {
+        Ok(()) => println!(
+            "hook #{}: {} {} -> {}",
+            state.hooks.hooks.len(),
+            when.label(),
+            func,
+            command
+        ),
+        Err(e) => eprintln!("{}: {}", when.label(), e),
+    }
+}
+
+fn handle_hooks(rest: &str, session: &mut MiSession, state: &mut ReplState) {
+    let parts: Vec<&str> = rest.split_whitespace().collect();
+    match parts.as_slice() {
+        [] => {
+            if state.hooks.hooks.is_empty() {
+                println!("no hooks (add one with on-enter/on-exit)");
+            }
+            for (i, h) in state.hooks.hooks.iter().enumerate() {
+                println!(
+                    "  #{:<3} {:<8} {:<20} {}",
+                    i + 1,
+                    h.when.label(),
+                    h.func,
+                    h.command
+                );
+            }
+        }
+        ["delete", n] => match n.parse::<usize>() {
+            Ok(idx) if idx > 0 => match state.hooks.remove(session, idx - 1) {
+                Ok(h) => println!(
+                    "deleted hook: {} {} -> {}",
+                    h.when.label(),
+                    h.func,
+                    h.command
+                ),
+                Err(e) => eprintln!("hooks: {}", e),
+            },
+            _ => eprintln!("hooks: invalid hook number '{}'", n),
+        },
+        _ => eprintln!("usage: hooks\n       hooks delete <n>"),
+    }
+}
+
+fn after_stop(session: &mut MiSession, state: &mut ReplState, loc: &StoppedLocation) {
+    // Exception catchpoints stop as plain breakpoint hits; name the event from the table.
+    let mut loc = loc.clone();
+    if loc.catch.is_none() {
+        loc.catch = session
+            .breakpoints()
+            .iter()
+            .find(|b| Some(b.number) == loc.bkptno)
+            .and_then(|b| b.catch.clone());
+    }
+    let loc = &loc;
+    state.stop_count += 1;
+    state.frame = 0;
+    state.values.begin_stop(state.stop_count, loc.func.clone());
+    if !state.output_quiet {
+        print_inferior_output(session);
+    }
+    print_stopped(loc);
+    if let Some(n) = loc.bkptno {
+        log_hit(session, state, n);
+    }
+    index_new_libraries(session, state);
+    state.not_started = false;
+    state.exited = is_exit(loc);
+    if state.exited {
+        state.heap.reset();
+        state.canaries.reset();
+        return;
+    }
+    // One -var-update per stop, so `expand` highlights what changed at this stop.
+    if let Err(e) = session.varobj_update() {
+        eprintln!("varobj: {}", e);
+    }
+    if state.values.active {
+        if let Ok(locals) = session.list_locals() {
+            state.values.record_locals(&locals);
+        }
+        if let Ok(globals) = session.list_globals() {
+            state.values.record_globals(&globals);
+        }
+    }
+    check_stack(session, state, loc);
+    if state.canaries.active {
+        // Errors here mean no readable stack at this stop; the next one checks again.
+        if let Ok(alerts) = state.canaries.check(session) {
+            print_canary_alerts(&alerts);
+        }
+    }
+    if state.map_track.is_tracking() {
+        if let Err(e) = state.map_track.observe(session, state.stop_count) {
+            eprintln!("vm track: {}", e);
+        }
+    }
+    if state.maps.active {
+        match state.maps.observe(session, state.stop_count) {
+            Ok(()) if state.maps.auto_show => state.maps.print(true),
+            Ok(()) => {}
+            Err(e) => eprintln!("vm diff: {}", e),
+        }
+    }
+    if state.regs.active {
+        match state.regs.observe(session, state.stop_count) {
+            Ok(()) if state.regs.auto_show => state.regs.print(session.arch.as_deref(), false),
+            Ok(()) => {}
+            Err(e) => eprintln!("regs: {}", e),
+        }
+    }
+    print_watches(session, state);
+    print_dashboards(session, state);
+    let reports = state.heap.check_canaries(session, false);
+    if !reports.is_empty() {
+        let when = format!(
+            "first seen at stop #{} ({})",
+            state.stop_count,
+            stop_label(loc)
+        );
+        print_canary_reports(&reports, &when);
+    }
+}
+
+/// Extend the symbol index with the libraries loaded since the last stop, so the globals
+/// and functions of a `dlopen()`ed plugin show up mid-session. System libraries are left
+/// out like system files are; while a background build runs, the paths wait for it.
+fn index_new_libraries(session: &mut MiSession, state: &mut ReplState) {
+    let loaded = session.take_loaded_libraries();
+    state
+        .new_libraries
+        .extend(loaded.into_iter().filter(|l| is_program_library(l)));
+    if state.new_libraries.is_empty() {
+        return;
+    }
+    let Some(index) = state.symbols.as_mut() else {
+        return;
+    };
+    let libs: Vec<String> = std::mem::take(&mut state.new_libraries)
+        .iter()
+        .map(|l| l.rsplit('/').next().unwrap_or(l).to_string())
+        .collect();
+    match index.extend(session) {
+        Ok(0) => {}
+        Ok(n) => println!("indexed {} new symbol(s) from {}", n, libs.join(", ")),
+        Err(e) => eprintln!("sym: could not index {}: {}", libs.join(", "), e),
+    }
+}
+
+fn handle_frame(rest: &str, session: &mut MiSession, state: &mut ReplState) {
+    let frames = match session.list_frames() {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("frame error: {}", e);
+            return;
+        }
+    };
+    if rest.is_empty() {
+        print_frames(&frames, state.frame);
+        return;
+    }
+    let level = match rest.parse::<u32>() {
+        Ok(l) if frames.iter().any(|f| f.level == l) => l,
+        _ => {
+            eprintln!(
+                "frame: no frame '{}' (0..{})",
+                rest,
+                frames.len().saturating_sub(1)
+            );
+            return;
+        }
+    };
+    match session.select_frame(level) {
+        Ok(()) => {
+            state.frame = level;
+            if let Some(f) = frames.iter().find(|f| f.level == level) {
+                print_frames(std::slice::from_ref(f), level);
+            }
+        }
+        Err(e) => eprintln!("frame error: {}", e),
+    }
+}
+
+/// `stack [n]`: saved return address and frame pointer of frame n (the selected frame by
+/// default), checked against the map.
+fn handle_stack(rest: &str, session: &mut MiSession, state: &mut ReplState) {
+    if let Some(arg) = rest.strip_prefix("canary") {
+        handle_stack_canary(arg.trim(), session, state);
+        return;
+    }
+    match rest {
+        "usage" => {
+            if json::enabled() {
+                json::emit(json::stack_usage(&state.stack_usage));
+            } else {
+                print_stack_usage(&state.stack_usage);
+            }
+            return;
+        }
+        "usage reset" => {
+            state.stack_usage = stack::StackUsage::default();
+            println!("stack usage: cleared");
+            return;
+        }
+        _ => {}
+    }
+    let frames = match session.list_frames() {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("stack: {}", e);
+            return;
+        }
+    };
+    let level = match rest {
+        "" => state.frame,
+        raw => match raw.parse::<u32>() {
+            Ok(l) => l,
+            Err(_) => {
+                eprintln!("usage: stack [frame]");
+                return;
+            }
+        },
+    };
+    let Some(frame) = frames.iter().find(|f| f.level == level) else {
+        eprintln!(
+            "stack: no frame '{}' (0..{})",
+            level,
+            frames.len().saturating_sub(1)
+        );
+        return;
+    };
+    let outermost = frames.last().is_some_and(|f| f.level == level);
+    let pid = match session.inferior_pid() {
+        Ok(pid) => pid,
+        Err(e) => {
+            eprintln!("stack: could not determine inferior pid: {}", e);
+            return;
+        }
+    };
+    let regions = match addr::process_regions(session, pid) {
+        Ok(regions) => regions,
+        Err(e) => {
+            eprintln!("stack: failed to read the map of process {}: {}", pid, e);
+            return;
+        }
+    };
+    // `info frame` describes the selected frame; select n only for the read.
+    let moved = level != state.frame;
+    if moved {
+        if let Err(e) = session.select_frame(level) {
+            eprintln!("stack: {}", e);
+            return;
+        }
+    }
+    let view = stack::read_frame_view(session, &regions, level, frame.func.clone(), outermost);
+    if moved {
+        let _ = session.select_frame(state.frame);
+    }
+    match view {
+        Ok(view) if json::enabled() => json::emit(json::stack_frame(&view)),
+        Ok(view) => print_frame_view(&view, &regions),
+        Err(e) => eprintln!("stack: {}", e),
+    }
+}
+
+/// `args [n]`: arguments of frame n (the selected frame by default) and the register or stack
+/// slot each was passed in.
+fn handle_args(rest: &str, session: &mut MiSession, state: &mut ReplState) {
+    let level = match rest {
+        "" => state.frame,
+        raw => match raw.parse::<u32>() {
+            Ok(l) => l,
+            Err(_) => {
+                eprintln!("usage: args [frame]");
+                return;
+            }
+        },
+    };
+    let frames = match session.list_frames() {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("args: {}", e);
+            return;
+        }
+    };
+    let Some(frame) = frames.iter().find(|f| f.level == level) else {
+        eprintln!(
+            "args: no frame '{}' (0..{})",
+            level,
+            frames.len().saturating_sub(1)
+        );
+        return;
+    };
+    // Sizes and the CFA are read in the selected frame; select n only for the read.
+    let moved = level != state.frame;
+    if moved {
+        if let Err(e) = session.select_frame(level) {
+            eprintln!("args: {}", e);
+            return;
+        }
+    }
+    let args = args::read_args(session, level, frame.func.clone());
+    if moved {
+        let _ = session.select_frame(state.frame);
+    }
+    match args {
+        Ok(args) if json::enabled() => json::emit(json::frame_args(&args)),
+        Ok(args) => print_frame_args(&args),
+        Err(e) => eprintln!("args: {}", e),
+    }
+}
+
+/// `stack canary [on|off]`: the selected frame's stack protector slot; `on` re-reads every
+/// slot seen at a stop and warns when one changes.
+fn handle_stack_canary(arg: &str, session: &mut MiSession, state: &mut ReplState) {
+    let reference = match stack::canary_reference(session) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("stack canary: {}", e);
+            return;
+        }
+    };
+    match arg {
+        "" => {
+            let func = match session.list_frames() {
+                Ok(frames) => frames
+                    .into_iter()
+                    .find(|f| f.level == state.frame)
+                    .and_then(|f| f.func),
+                Err(e) => {
+                    eprintln!("stack canary: {}", e);
+                    return;
+                }
+            };
+            match stack::find_canary(session, reference, func) {
+                Ok(slot) if json::enabled() => {
+                    json::emit(json::stack_canary(state.frame, slot.as_ref(), reference))
+                }
+                Ok(slot) => print_stack_canary(state.frame, slot.as_ref(), reference),
+                Err(e) => eprintln!("stack canary: {}", e),
+            }
+        }
+        "on" => {
+            state.canaries.active = true;
+            match state.canaries.check(session) {
+                Ok(_) => println!(
+                    "stack canary: watching {} slot(s), re-read at every stop",
+                    state.canaries.slots.len()
+                ),
+                Err(e) => eprintln!("stack canary: {}", e),
+            }
+        }
+        "off" => {
+            state.canaries.active = false;
+            state.canaries.reset();
+            println!("stack canary: off");
+        }
+        _ => eprintln!("usage: stack canary [on|off]"),
+    }
+}
+
+fn handle_sym(rest: &str, session: &mut MiSession, state: &mut ReplState) {
+    let parts: Vec<&str> = rest.split_whitespace().collect();
+    match parts.as_slice() {
+        ["statics"] => match state.symbol_index() {
+            Some(index) => {
+                let statics = index.statics();
+                if statics.is_empty() {
+                    println!("no function-scope statics");
+                }
+                for g in statics {
+                    let at = match (&g.file, g.line) {
+                        (Some(file), Some(line)) => format!(" ({}:{})", file, line),
+                        _ => String::new(),
+                    };
+                    println!(
+                        "{} {}{}",
+                        g.type_name.as_deref().unwrap_or("?"),
+                        g.expr(),
+                        at
+                    );
+                }
+            }
+            None => println!("symbol index not built"),
+        },
+        ["stats"] => match state.symbol_index() {
+            Some(index) => print_symbol_stats(&index.stats),
+            None => println!("symbol index not built"),
+        },
+        ["functions" | "funcs", prefix @ ..] if prefix.len() <= 1 => match state.symbol_index() {
+            Some(index) => {
+                let found = index.functions_with_prefix(prefix.first().copied().unwrap_or(""));
+                if found.is_empty() {
+                    println!("no functions");
+                }
+                for f in found {
+                    let at = match f.line {
+                        Some(line) => format!("{}:{}", f.file, line),
+                        None => f.file.clone(),
+                    };
+                    println!(
+                        "{} {} ({})",
+                        f.name,
+                        f.type_name.as_deref().unwrap_or(""),
+                        at
+                    );
+                }
+            }
+            None => println!("symbol index not built"),
+        },
+        ["reindex", mode @ ..] if mode.len() <= 1 => {
+            let mode = match mode.first() {
+                Some(raw) => match SymbolIndexMode::parse(raw) {
+                    Some(m) => m,
+                    None => {
+                        eprintln!("sym: mode must be debug-only or debug-and-nondebug");
+                        return;
+                    }
+                },
+                None => match (&state.symbols, &state.pending_symbols) {
+                    (_, Some(pending)) => pending.mode,
+                    (Some(index), None) => index.stats.mode,
+                    (None, None) => SymbolIndexMode::DebugOnly,
+                },
+            };
+            // A background build still running is superseded, and the new one covers
+            // every library loaded so far.
+            state.pending_symbols = None;
+            state.new_libraries.clear();
+            let index = build_symbol_index(session, mode);
+            print_symbol_index_summary(&index.stats);
+            state.symbols = Some(index);
+        }
+        _ => eprintln!(
+            "usage: sym stats\n       sym functions [prefix]\n       sym statics\n       \
+             sym reindex [debug-only|debug-and-nondebug]"
+        ),
+    }
+}
+
+/// `find <pattern>` lists globals and functions matching fuzzily; `find <n>` shows the
+/// source of result `n` (functions) or its layout and bytes (globals).
+fn handle_find(rest: &str, session: &mut MiSession, state: &mut ReplState) {
+    if rest.is_empty() {
+        eprintln!("usage: find <pattern> | find <n>");
+        return;
+    }
+    if let Ok(n) = rest.parse::<usize>() {
+        let Some(found) = n.checked_sub(1).and_then(|i| state.found.get(i)).cloned() else {
+            eprintln!("find: no result {} (run 'find <pattern>' first)", n);
+            return;
+        };
+        match found {
+            FoundSymbol::Function(f) => {
+                let at = format!("{}:{}", f.file, f.line.unwrap_or(1));
+                if let Err(e) = source::handle_list(&at, session, &mut state.source) {
+                    eprintln!("find: {}", e);
+                }
+            }
+            FoundSymbol::Global(g) => {
+                // Qualified by file, so a static shadowed elsewhere is the one listed.
+                let expr = match &g.file {
+                    Some(file) if g.function.is_none() => format!("'{}'::{}", file, g.name),
+                    _ => g.expr(),
+                };
+                if let Err(e) = handle_view(&expr, session, &state.heap) {
+                    eprintln!("find: {}", e);
+                }
+            }
+        }
+        return;
+    }
+    let Some(index) = state.symbol_index() else {
+        println!("symbol index not built");
+        return;
+    };
+    let mut found = index.search(rest);
+    let total = found.len();
+    found.truncate(FIND_RESULTS);
+    if found.is_empty() {
+        println!("find: nothing matches '{}'", rest);
+    }
+    for (i, f) in found.iter().enumerate() {
+        let (kind, ty, at) = match f {
+            FoundSymbol::Global(g) => (
+                "global",
+                g.type_name.as_deref(),
+                match (&g.file, g.line, &g.section) {
+                    (Some(file), Some(line), Some(sec)) => format!("{}:{}, {}", file, line, sec),
+                    (Some(file), Some(line), None) => format!("{}:{}", file, line),
+                    (Some(file), None, _) => file.clone(),
+                    (None, _, Some(sec)) => format!("no debug info, {}", sec),
+                    (None, _, None) => "no debug info".to_string(),
+                },
+            ),
+            FoundSymbol::Function(func) => (
+                "function",
+                func.type_name.as_deref(),
+                match func.line {
+                    Some(line) => format!("{}:{}", func.file, line),
+                    None => func.file.clone(),
+                },
+            ),
+        };
+        println!(
+            "{:>3}. {:<8} {} {} ({})",
+            i + 1,
+            kind,
+            style::paint(f.name(), theme::theme().header),
+            ty.unwrap_or(""),
+            at
+        );
+    }
+    if total > found.len() {
+        println!("({} more; narrow the pattern)", total - found.len());
+    }
+    if !found.is_empty() {
+        println!("'find <n>' shows a result");
+    }
+    state.found = found;
+}
+
+fn handle_regs(rest: &str, session: &mut MiSession, state: &mut ReplState) {
+    let all = match rest {
+        "" => false,
+        "all" => true,
+        "on" | "off" => {
+            state.regs.auto_show = rest == "on";
+            state.regs.active |= state.regs.auto_show;
+            println!("regs: show after every stop {}", rest);
+            return;
+        }
+        _ => {
+            eprintln!("usage: regs [all|on|off]");
+            return;
+        }
+    };
+    state.regs.active = true;
+    session.ensure_arch();
+    match state.regs.observe(session, state.stop_count) {
+        Ok(()) => state.regs.print(session.arch.as_deref(), all),
+        Err(e) => eprintln!("regs: {}", e),
+    }
+}
+
+/// `breakpoints log <n> ...` output for a stop at breakpoint `n`: its hit count and the
+/// logged expressions' values.
+fn log_hit(session: &mut MiSession, state: &ReplState, n: u32) {
+    let Some(exprs) = state.hit_logs.get(&n) else {
+        return;
+    };
+    let hits = session
+        .breakpoints()
+        .iter()
+        .find(|b| b.number == n)
+        .map_or(0, |b| b.hits);
+    let values: Vec<String> = exprs
+        .iter()
+        .map(|e| match session.evaluate_expression(e) {
+            Ok(v) => format!("{} = {}", e, v),
+            Err(_) => format!("{} = <unavailable>", e),
+        })
+        .collect();
+    println!(
+        "{} {}",
+        style::paint(&format!("[hit {} #{}]", n, hits), theme::theme().header),
+        values.join(", ")
+    );
+}
+
+/// `breakpoints [n]`: list the breakpoint table, or show the source around breakpoint `n`.
+/// `breakpoints log <n> <expr>[, <expr>...]|off`: print those values at every hit of `n`.
+fn handle_breakpoints(rest: &str, session: &mut MiSession, state: &mut ReplState) {
+    if rest.is_empty() {
+        print_breakpoint_table(session.breakpoints(), &state.hit_logs);
+        return;
+    }
+    if let Some(args) = rest.strip_prefix("log ") {
+        const USAGE: &str =
+            "usage: breakpoints log <n> <expr>[, <expr>...]\n       breakpoints log <n> off";
+        let (num, exprs) = args.trim().split_once(' ').unwrap_or((args.trim(), ""));
+        let Ok(number) = num.parse::<u32>() else {
+            eprintln!("{}", USAGE);
+            return;
+        };
+        if !session.breakpoints().iter().any(|b| b.number == number) {
+            eprintln!("breakpoints: no breakpoint {}", number);
+            return;
+        }
+        match exprs.trim() {
+            "" => eprintln!("{}", USAGE),
+            "off" => {
+                state.hit_logs.remove(&number);
+                println!("breakpoint {}: hit log off", number);
+            }
+            list => {
+                let exprs: Vec<String> = list
+                    .split(',')
+                    .map(|e| e.trim().to_string())
+                    .filter(|e| !e.is_empty())
+                    .collect();
+                println!(
+                    "breakpoint {}: logging {} at every hit",
+                    number,
+                    exprs.join(", ")
+                );
+                state.hit_logs.insert(number, exprs);
+            }
+        }
+        return;
+    }
+    let bp = match rest.parse::<u32>() {
+        Ok(n) => session
+            .breakpoints()
+            .iter()
+            .find(|b| b.number == n)
+            .cloned(),
+        Err(_) => {
+            eprintln!(
+                "usage: breakpoints [<n>]\n       breakpoints log <n> <expr>[, <expr>...]|off"
+            );
+            return;
+        }
+    };
+    match bp {
+        Some(bp) => match (&bp.file, bp.line) {
+            (Some(file), Some(line)) => {
+                let target = format!("{}:{}", file, line);
+                if let Err(e) = source::handle_list(&target, session, &mut state.source) {
+                    eprintln!("breakpoints: {}", e);
+                }
+            }
+            _ => println!("breakpoint {} has no source location", bp.number),
+        },
+        None => eprintln!("breakpoints: no breakpoint {}", rest),
+    }
+}
+
+/// `catch throw|rethrow|syscall <name>|fork|vfork|exec`: stop when the event happens.
+fn handle_catch(rest: &str, session: &mut MiSession) {
+    let mut words = rest.split_whitespace();
+    let valid = match words.next() {
+        Some("throw" | "rethrow" | "fork" | "vfork" | "exec") => words.next().is_none(),
+        Some("syscall") => words.next().is_some(),
+        _ => false,
+    };
+    if !valid {
+        eprintln!("usage: catch throw|rethrow|syscall <name>|fork|vfork|exec");
+        return;
+    }
+    match session.add_catchpoint(rest) {
+        Ok(bp) => print_breakpoint(&bp),
+        Err(e) => eprintln!("catch error: {}", e),
+    }
+}
+
+/// `enable <n>`, `disable <n>`, `delete <n>` on the session breakpoint table; true when it
+/// was applied.
+fn handle_breakpoint_edit(cmd: &str, rest: &str, session: &mut MiSession) -> bool {
+    let Ok(number) = rest.parse::<u32>() else {
+        eprintln!("usage: {} <breakpoint number>", cmd);
+        return false;
+    };
+    let result = match cmd {
+        "enable" => session.set_breakpoint_enabled(number, true),
+        "disable" => session.set_breakpoint_enabled(number, false),
+        _ => session.remove_breakpoint(number),
+    };
+    match result {
+        Ok(bp) => {
+            println!("breakpoint {}: {}d", bp.number, cmd);
+            true
+        }
+        Err(e) => {
+            eprintln!("{}: {}", cmd, e);
+            false
+        }
+    }
+}
+
+/// Print program output produced since the last stop, each line tagged `[out]`.
+fn print_inferior_output(session: &mut MiSession) {
+    match session.take_inferior_output() {
+        Ok(text) => {
+            let tag = style::paint("[out]", theme::theme().unavailable);
+            for line in text.lines() {
+                println!("{} {}", tag, line);
+            }
+        }
+        Err(e) => eprintln!("output: {}", e),
+    }
+}
+
+/// `output [on|off|all]`: show new program output now, toggle printing it at each stop,
+/// or dump everything captured so far.
+fn handle_output(rest: &str, session: &mut MiSession, state: &mut ReplState) {
+    let source = match (session.inferior_tty_path(), session.inferior_output_path()) {
+        (Some(tty), _) => tty.to_string(),
+        (None, Some(path)) => path.display().to_string(),
+        (None, None) => {
+            println!("output: capture is off (started with --no-capture)");
+            return;
+        }
+    };
+    match rest {
+        "" => print_inferior_output(session),
+        "on" => {
+            state.output_quiet = false;
+            println!("output: shown after every stop");
+        }
+        "off" => {
+            state.output_quiet = true;
+            println!("output: hidden (still captured from {})", source);
+        }
+        "all" => match session.inferior_output_all() {
+            Ok(text) => print!("{}", text),
+            Err(e) => eprintln!("output: {}", e),
+        },
+        _ => eprintln!("usage: output [on|off|all]"),
+    }
+}
+
+/// `theme [dark|light]`: show or switch the color palette.
+fn handle_theme(rest: &str) {
+    if rest.is_empty() {
+        println!("theme: {}", theme::current_choice().label());
+        return;
+    }
+    match theme::ThemeChoice::parse(rest) {
+        Some(choice) if !theme::set_theme(choice) => {
+            eprintln!("theme: no theme file loaded (start with --theme-file <path>)");
+        }
+        Some(choice) => {
+            println!("theme: {}", choice.label());
+            if !style::enabled() {
+                println!("  note: colors are off (see --color)");
+            }
+        }
+        None => eprintln!("usage: theme [dark|light|custom]"),
+    }
+}
+
+fn print_watches(session: &mut MiSession, state: &mut ReplState) {
+    if state.watches.is_empty() {
+        return;
+    }
+    let regions = addr::current_regions(session);
+    for (i, w) in state.watches.iter_mut().enumerate() {
+        println!("{}", w.render(session, &regions, i + 1, state.stop_count));
+    }
+}
+
+/// `display [--frame <n>] <expr>` registers an expression, optionally pinned to frame `n`;
+/// bare `display` re-evaluates all of them now.
+fn handle_display(rest: &str, session: &mut MiSession, state: &mut ReplState) {
+    if rest.is_empty() {
+        if state.watches.is_empty() {
+            println!("no display expressions (try 'display <expr>')");
+        }
+        print_watches(session, state);
+        return;
+    }
+    let (frame, expr) = match rest.strip_prefix("--frame") {
+        Some(args) => {
+            let (n, expr) = args
+                .trim_start()
+                .split_once(' ')
+                .unwrap_or((args.trim(), ""));
+            match n.parse::<u32>() {
+                Ok(n) if !expr.trim().is_empty() => (Some(n), expr.trim()),
+                _ => {
+                    eprintln!("usage: display [--frame <n>] <expr>");
+                    return;
+                }
+            }
+        }
+        None => (None, rest),
+    };
+    let regions = addr::current_regions(session);
+    let mut w = Watch::new(expr);
+    w.frame = frame;
+    let idx = state.watches.len() + 1;
+    println!("{}", w.render(session, &regions, idx, state.stop_count));
+    state.watches.push(w);
+}
+
+fn print_dashboards(session: &mut MiSession, state: &mut ReplState) {
+    for (i, t) in state.tracked.iter_mut().enumerate() {
+        match t.refresh(session, state.stop_count) {
+            Ok(_) => println!("{}", t.dashboard(i + 1)),
+            Err(e) => println!("[track #{} {}] <unavailable: {}>", i + 1, t.expr, e),
         }
-        return Ok(CommandOutcome::Continue);
     }
-    // Special-case vm parsing to catch invalid usages.
-    if cmd == "vm" {
-        let parts: Vec<_> = input.trim().split_whitespace().collect();
-        if parts.len() == 1 {
-            handle_vm(session);
-            return Ok(CommandOutcome::Continue);
+}
+
+fn handle_track(rest: &str, session: &mut MiSession, state: &mut ReplState) {
+    let mut parts = rest.splitn(2, char::is_whitespace);
+    let sub = parts.next().unwrap_or("");
+    let arg = parts.next().unwrap_or("").trim();
+    let kind = match sub {
+        "" => {
+            if state.tracked.is_empty() {
+                println!("nothing tracked (try 'track list <expr>' or 'track tree <expr>')");
+            }
+            print_dashboards(session, state);
+            return;
         }
-        if parts.len() == 2 && parts[1] == "vars" {
-            handle_vm_vars(session);
-            return Ok(CommandOutcome::Continue);
+        "delete" => {
+            match arg.parse::<usize>() {
+                Ok(n) if n >= 1 && n <= state.tracked.len() => {
+                    let t = state.tracked.remove(n - 1);
+                    println!("track: stopped tracking {}", t.expr);
+                }
+                _ => eprintln!("track: invalid number '{}'", arg),
+            }
+            return;
         }
-        if parts.len() >= 2 && parts[1] == "locate" {
-            if parts.len() >= 3 {
-                let expr = parts[2..].join(" ");
-                handle_vm_locate(&expr, session);
-            } else {
-                eprintln!(
-                    "invalid vm usage: '{}'\n  usage: vm\n         vm vars\n         vm locate <expr>",
-                    input.trim()
-                );
+        "list" => ShapeKind::List,
+        "tree" => ShapeKind::Tree,
+        _ => {
+            eprintln!("usage: track [list|tree <expr>]\n       track delete <n>");
+            return;
+        }
+    };
+    if arg.is_empty() {
+        eprintln!("usage: track {} <expr>", sub);
+        return;
+    }
+    match TrackedShape::new(session, kind, arg) {
+        Ok(mut t) => {
+            let idx = state.tracked.len() + 1;
+            if let Err(e) = t.refresh(session, state.stop_count) {
+                eprintln!("track: {}", e);
             }
-            return Ok(CommandOutcome::Continue);
+            println!("{}", t.dashboard(idx));
+            state.tracked.push(t);
         }
-        eprintln!(
-            "invalid vm usage: '{}'\n  usage: vm\n         vm vars\n         vm locate <expr>",
-            input.trim()
-        );
-        return Ok(CommandOutcome::Continue);
+        Err(e) => eprintln!("track: {}", e),
     }
+}
 
-    match cmd {
-        "quit" | "q" => return Ok(CommandOutcome::Quit),
-        "help" => print_help(),
-        "locals" => match session.list_locals() {
-            Ok(locals) => print_locals(&locals),
-            Err(e) => eprintln!("locals error: {}", e),
+fn is_exit(loc: &StoppedLocation) -> bool {
+    loc.exit.is_some()
+}
+
+fn stop_label(loc: &StoppedLocation) -> String {
+    match (&loc.file, &loc.line, &loc.func) {
+        (Some(f), Some(l), _) => format!("{}:{}", f, l),
+        (_, _, Some(func)) => func.clone(),
+        _ => "unknown location".to_string(),
+    }
+}
+
+fn handle_heap(rest: &str, session: &mut MiSession, state: &mut ReplState) {
+    const USAGE: &str = "usage: heap\n       heap track on|off\n       heap canary on [guard]|off\n       heap check\n       heap arenas\n       heap bins [arena]\n       heap chunk <addr|expr>";
+    let parts: Vec<&str> = rest.split_whitespace().collect();
+    if matches!(parts.as_slice(), ["track", "on"] | ["canary", "on", ..])
+        && state.map_track.is_tracking()
+    {
+        eprintln!("heap: turn 'vm track' off first; its catchpoint would stop the allocator calls the heap hooks run to completion");
+        return;
+    }
+    let heap = &mut state.heap;
+    match parts.as_slice() {
+        [] => {
+            if !heap.is_tracking() {
+                println!("heap tracking is off (try 'heap track on' or 'heap canary on')");
+                return;
+            }
+            match heap.canary_guard() {
+                Some(g) => println!("canaries: on ({} guard bytes each side)", g),
+                None => println!("canaries: off"),
+            }
+            let blocks: Vec<_> = heap.live.values().collect();
+            print_heap_blocks(&blocks);
+        }
+        ["track", "on"] => match heap.enable(session) {
+            Ok(()) => println!("heap: tracking malloc/calloc/realloc/free"),
+            Err(e) => eprintln!("heap: {}", e),
         },
-        "mem" => handle_mem(rest, session),
-        "view" => {
-            if rest.is_empty() {
-                println!("usage: view <symbol>");
-            } else {
-                let symbol = rest.split_whitespace().next().unwrap_or("");
-                if let Err(e) = handle_view(symbol, session) {
-                    eprintln!("{}", e);
-                }
+        ["track", "off"] => match heap.disable(session) {
+            Ok(()) => println!("heap: tracking off"),
+            Err(e) => eprintln!("heap: {}", e),
+        },
+        ["canary", "on", guard @ ..] if guard.len() <= 1 => {
+            let guard = match guard.first() {
+                Some(raw) => match raw.parse::<usize>() {
+                    Ok(g) if g > 0 && g % 16 == 0 => g,
+                    _ => {
+                        eprintln!("heap: guard must be a positive multiple of 16");
+                        return;
+                    }
+                },
+                None => DEFAULT_GUARD,
+            };
+            if let Err(e) = heap.enable(session) {
+                eprintln!("heap: {}", e);
+                return;
             }
+            heap.set_canary(Some(guard));
+            println!(
+                "heap: canaries on ({} guard bytes each side); applies to new allocations",
+                guard
+            );
         }
-        "follow" => {
-            if rest.is_empty() {
-                println!("usage: follow <symbol> [depth]");
-            } else if let Err(e) = follow::handle_follow(rest, session) {
-                eprintln!("{}", e);
+        ["canary", "off"] => {
+            heap.set_canary(None);
+            let guarded = heap.guarded_live();
+            if guarded > 0 {
+                println!(
+                    "heap: canaries off for new allocations; {} guarded block(s) still checked",
+                    guarded
+                );
+            } else {
+                println!("heap: canaries off");
             }
         }
-        "break" | "b" => {
-            if rest.is_empty() {
-                println!("usage: break <location>");
+        ["check"] => {
+            let reports = heap.check_canaries(session, true);
+            if reports.is_empty() {
+                println!(
+                    "heap: {} guarded block(s), all canaries intact",
+                    heap.guarded_live()
+                );
             } else {
-                match session.break_insert(rest) {
-                    Ok(info) => print_breakpoint(&info),
-                    Err(e) => eprintln!("break error: {}", e),
-                }
+                let when = format!("checked at stop #{}", state.stop_count);
+                print_canary_reports(&reports, &when);
             }
         }
-        "next" | "n" => match session.exec_next() {
-            Ok(loc) => print_stopped(&loc),
-            Err(e) => eprintln!("next error: {}", e),
+        ["arenas"] => match read_arenas(session) {
+            Ok(arenas) => {
+                let mmapped = read_mmap_stats(session).ok();
+                if json::enabled() {
+                    json::emit(json::heap_arenas(&arenas, mmapped));
+                } else {
+                    print_heap_arenas(&arenas, mmapped);
+                }
+            }
+            Err(e) => eprintln!("heap arenas: {} (needs glibc debug info)", e),
         },
-        "step" | "s" => match session.exec_step() {
-            Ok(loc) => print_stopped(&loc),
-            Err(e) => eprintln!("step error: {}", e),
+        ["bins", arena @ ..] if arena.len() <= 1 => {
+            let arena = match arena.first() {
+                Some(raw) => match addr::parse_number(raw) {
+                    Some(a) => Some(a),
+                    None => {
+                        eprintln!("heap bins: arena must be an address (see 'heap arenas')");
+                        return;
+                    }
+                },
+                None => None,
+            };
+            match read_bins(session, arena) {
+                Ok(bins) if json::enabled() => {
+                    json::emit(json::heap_bins(&bins, session.word_size as u64))
+                }
+                Ok(bins) => print_heap_bins(&bins, session.word_size as u64),
+                Err(e) => eprintln!("heap bins: {} (needs glibc debug info)", e),
+            }
+        }
+        ["chunk", ..] if parts.len() > 1 => {
+            let expr = rest.trim_start()["chunk".len()..].trim();
+            let ptr = match addr::parse_number(expr) {
+                Some(n) => n,
+                None => match session.eval_expr_u64(expr) {
+                    Ok(n) => n,
+                    Err(e) => {
+                        eprintln!("heap chunk: cannot evaluate '{}': {}", expr, e);
+                        return;
+                    }
+                },
+            };
+            // A guarded block's chunk starts before the guard zone the program does not see.
+            let guard = heap.live.get(&ptr).map(|a| a.guard as u64).unwrap_or(0);
+            match read_chunk(session, ptr - guard) {
+                Ok(chunk) if json::enabled() => json::emit(json::heap_chunk(&chunk)),
+                Ok(chunk) => {
+                    if guard > 0 {
+                        println!(
+                            "(tracked block with {} guard bytes each side; showing the real chunk)",
+                            guard
+                        );
+                    }
+                    print_malloc_chunk(&chunk);
+                }
+                Err(e) => eprintln!("heap chunk: {}", e),
+            }
+        }
+        _ => eprintln!("{}", USAGE),
+    }
+}
+
+/// `typetree <type> [depth]`: the type's full tree from gdb's Python API.
+fn handle_typetree(rest: &str, session: &mut MiSession) -> Result<()> {
+    // The type name may contain spaces ("struct Node"); a trailing number is the depth.
+    let (type_name, depth) = match rest.rsplit_once(' ') {
+        Some((name, d)) if d.parse::<u32>().is_ok() => (name.trim(), d.parse().unwrap_or(2)),
+        _ => (rest, 2),
+    };
+    let tree = session.type_tree(type_name, depth)?;
+    if json::enabled() {
+        json::emit(json::tagged("typetree", tree));
+    } else {
+        print_type_tree(&tree);
+    }
+    Ok(())
+}
+
+fn handle_vm(session: &mut MiSession) {
+    let pid = match session.inferior_pid() {
+        Ok(pid) => pid,
+        Err(e) => {
+            eprintln!("vm: could not determine inferior pid: {}", e);
+            return;
+        }
+    };
+    let regions = match addr::process_regions(session, pid) {
+        Ok(regions) => regions,
+        Err(e) => {
+            eprintln!("vm: failed to read the map of process {}: {}", pid, e);
+            return;
+        }
+    };
+    let stack = addr::stack_headroom(session, &regions, pid);
+    if json::enabled() {
+        json::emit(json::vm(&regions, stack.as_ref()));
+        return;
+    }
+    print_vm_regions(&regions);
+    if let Some(h) = &stack {
+        print_stack_headroom(h);
+    }
+}
+
+/// Record the stop's stack depth for `stack usage`, and warn when the stop left the current
+/// thread's stack nearly full. Thread stacks are bounded by their mapping, so the raw map is
+/// enough; no per-thread lookups. Without a map (remote targets) only the depth is recorded.
+fn check_stack(session: &mut MiSession, state: &mut ReplState, loc: &StoppedLocation) {
+    let Ok(sp) = session.eval_expr_u64("$sp") else {
+        return;
+    };
+    let headroom = session.inferior_pid().ok().and_then(|pid| {
+        let regions = vm::read_process_map(pid, false).ok()?;
+        vm::stack_headroom(&regions, sp, vm::read_stack_limit(pid))
+    });
+    state.stack_usage.observe(
+        sp,
+        headroom.as_ref().map(|h| h.top),
+        loc.func.as_deref(),
+        state.stop_count,
+    );
+    if let Some(h) = headroom.filter(|h| h.is_low()) {
+        print_low_stack_warning(&h);
+    }
+}
+
+/// `vm track on|off`: log every brk/mmap/munmap/mremap that changes the map, with its stack.
+fn handle_vm_track(arg: &str, session: &mut MiSession, state: &mut ReplState) {
+    match arg {
+        "on" if state.heap.is_tracking() => eprintln!(
+            "vm track: turn 'heap track' off first; the catchpoint would stop the allocator \
+             calls the heap hooks run to completion"
+        ),
+        "on" => match state.map_track.enable(session, state.stop_count) {
+            Ok(()) => println!("vm track: logging brk/mmap/munmap/mremap (see 'vm history')"),
+            Err(e) => eprintln!("vm track: {}", e),
         },
-        "continue" | "c" => match session.exec_continue() {
-            Ok(loc) => print_stopped(&loc),
-            Err(e) => eprintln!("continue error: {}", e),
+        "off" => match state.map_track.disable(session) {
+            Ok(()) => println!(
+                "vm track: off; {} change(s) logged",
+                state.map_track.events.len()
+            ),
+            Err(e) => eprintln!("vm track: {}", e),
         },
+        _ => eprintln!("usage: vm track on|off"),
+    }
+}
+
+/// `vm diff [on|off]`: regions created, removed or resized since the previous stop; `on`
+/// prints them after every stop that changed the map.
+fn handle_vm_diff(rest: &str, session: &mut MiSession, state: &mut ReplState) {
+    match rest {
+        "" => {}
+        "on" | "off" => {
+            state.maps.auto_show = rest == "on";
+            if state.maps.auto_show && !state.maps.active {
+                state.maps.active = true;
+                if let Err(e) = state.maps.observe(session, state.stop_count) {
+                    eprintln!("vm diff: {}", e);
+                }
+            }
+            println!("vm diff: show after every stop {}", rest);
+            return;
+        }
         _ => {
-            println!("unknown command: '{}'", input);
+            eprintln!("usage: vm diff [on|off]");
+            return;
         }
     }
-    Ok(CommandOutcome::Continue)
+    state.maps.active = true;
+    match state.maps.observe(session, state.stop_count) {
+        Ok(()) => state.maps.print(false),
+        Err(e) => eprintln!("vm diff: {}", e),
+    }
 }
 
-fn handle_vm(session: &mut MiSession) {
+/// `vm stats`: resident, proportional, dirty and swapped memory per region label.
+fn handle_vm_stats(session: &mut MiSession) {
     let pid = match session.inferior_pid() {
         Ok(pid) => pid,
         Err(e) => {
-            eprintln!("vm: could not determine inferior pid: {}", e);
+            eprintln!("vm stats: could not determine inferior pid: {}", e);
             return;
         }
     };
-    match vm::read_proc_maps(pid) {
-        Ok(regions) => print_vm_regions(&regions),
-        Err(e) => eprintln!("vm: failed to read /proc/{}: {}", pid, e),
+    match vm::read_process_map(pid, true) {
+        Ok(mut regions) => {
+            addr::label_thread_stacks(session, &mut regions);
+            let totals = vm::usage_by_label(&regions);
+            if json::enabled() {
+                json::emit(json::vm_stats(&totals));
+            } else {
+                println!("memory by label (/proc/{}/smaps):", pid);
+                print_vm_stats(&totals);
+            }
+        }
+        Err(e) => eprintln!("vm stats: failed to read the map of process {}: {}", pid, e),
+    }
+}
+
+/// `vm map`: the regions and the gaps between them as a box diagram.
+fn handle_vm_map(session: &mut MiSession) {
+    let regions = addr::current_regions(session);
+    if regions.is_empty() {
+        eprintln!("vm map: no process map (is the program running?)");
+        return;
+    }
+    if json::enabled() {
+        json::emit(json::vm(&regions, None));
+    } else {
+        print_vm_map(&vm::map_blocks(&regions));
+    }
+}
+
+/// `vm bases`: where ASLR loaded the executable and each shared library.
+fn handle_vm_bases(session: &mut MiSession) {
+    let regions = addr::current_regions(session);
+    if regions.is_empty() {
+        eprintln!("vm bases: no process map (is the program running?)");
+        return;
+    }
+    let bases = vm::module_bases(&regions);
+    if json::enabled() {
+        json::emit(json::vm_bases(&bases));
+    } else if bases.is_empty() {
+        println!("vm bases: no mapped ELF files");
+    } else {
+        print_module_bases(&bases);
+    }
+}
+
+fn handle_vm_layout(session: &mut MiSession, state: &mut ReplState) {
+    let regions = addr::current_regions(session);
+    if regions.is_empty() {
+        eprintln!("vm layout: no process map (is the program running?)");
+        return;
+    }
+    let mut markers = Vec::new();
+    for g in global_addresses(session, state) {
+        markers.push(LayoutMarker {
+            kind: "global",
+            name: g.name,
+            addr: g.address,
+        });
+    }
+    for l in session.list_locals().unwrap_or_default() {
+        if let Ok(addr) = session.eval_address_of_expr(&l.name) {
+            markers.push(LayoutMarker {
+                kind: "local",
+                name: l.name.clone(),
+                addr,
+            });
+        }
+        if l.ty.as_deref().is_some_and(is_pointer_type) {
+            let target = session.eval_expr_u64(&l.name).unwrap_or(0);
+            let on_heap = regions
+                .iter()
+                .any(|r| r.label == VmLabel::Heap && r.contains(target));
+            if on_heap {
+                markers.push(LayoutMarker {
+                    kind: "heap",
+                    name: format!("*{}", l.name),
+                    addr: target,
+                });
+            }
+        }
     }
+    print_vm_layout(&regions, &markers);
 }
 
-fn handle_vm_vars(session: &mut MiSession) {
+/// Globals with their addresses for the `vm` views: placed from the symbol index with one
+/// evaluation, or from gdb's variable listing (two evaluations per variable) when the index
+/// has no addresses. Values are not read.
+fn global_addresses(session: &mut MiSession, state: &mut ReplState) -> Vec<GlobalVar> {
+    if let Some(located) = state
+        .symbol_index()
+        .and_then(|index| index.located_globals(session))
+    {
+        return located
+            .into_iter()
+            .map(|(g, address)| GlobalVar {
+                name: g.expr(),
+                type_name: g.type_name.clone().unwrap_or_else(|| "unknown".to_string()),
+                value: String::new(),
+                address,
+                file: g.file.clone(),
+            })
+            .collect();
+    }
+    match session.list_globals() {
+        Ok(globals) => globals,
+        Err(e) => {
+            eprintln!("vm: failed to list globals: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+fn handle_vm_vars(session: &mut MiSession, state: &mut ReplState) {
     let pid = match session.inferior_pid() {
         Ok(pid) => pid,
         Err(e) => {
@@ -134,10 +1846,10 @@ fn handle_vm_vars(session: &mut MiSession) {
             return;
         }
     };
-    let regions = match vm::read_proc_maps(pid) {
+    let regions = match addr::process_regions(session, pid) {
         Ok(r) => r,
         Err(e) => {
-            eprintln!("vm vars: failed to read /proc/{}: {}", pid, e);
+            eprintln!("vm vars: failed to read the map of process {}: {}", pid, e);
             return;
         }
     };
@@ -148,22 +1860,16 @@ fn handle_vm_vars(session: &mut MiSession) {
             return;
         }
     };
-    let globals = match session.list_globals() {
-        Ok(v) => v,
-        Err(e) => {
-            eprintln!("vm vars: failed to list globals: {}", e);
-            return;
-        }
-    };
+    let globals = global_addresses(session, state);
 
     let mut summaries: HashMap<VmLabel, RegionVarsSummary> = HashMap::new();
 
     let classify = |addr: u64| regions.iter().find(|r| r.contains(addr)).map(|r| r.label.clone());
 
-    fn get_summary<'a>(
-        map: &'a mut HashMap<VmLabel, RegionVarsSummary>,
+    fn get_summary(
+        map: &mut HashMap<VmLabel, RegionVarsSummary>,
         label: VmLabel,
-    ) -> &'a mut RegionVarsSummary {
+    ) -> &mut RegionVarsSummary {
         map.entry(label.clone())
             .or_insert_with(|| RegionVarsSummary {
                 label,
@@ -180,7 +1886,6 @@ fn handle_vm_vars(session: &mut MiSession) {
             summary.globals.push(SymbolInfo {
                 name: g.name.clone(),
                 type_name: g.type_name.clone(),
-                addr: g.address,
                 target_label: None,
             });
         }
@@ -202,7 +1907,7 @@ fn handle_vm_vars(session: &mut MiSession) {
                         heap_summary.heap_objects.push(HeapObjectInfo {
                             via: l.name.clone(),
                             type_name: pointee,
-                            addr: ptr_val,
+                            site: state.heap.live.get(&ptr_val).and_then(|a| a.site()),
                         });
                     }
                 }
@@ -211,7 +1916,6 @@ fn handle_vm_vars(session: &mut MiSession) {
             summary.locals.push(SymbolInfo {
                 name: l.name.clone(),
                 type_name: ty,
-                addr,
                 target_label,
             });
         }
@@ -220,17 +1924,19 @@ fn handle_vm_vars(session: &mut MiSession) {
     let mut ordered: Vec<RegionVarsSummary> = summaries.into_values().collect();
     ordered.sort_by_key(|s| match s.label {
         VmLabel::Data => 0,
-        VmLabel::Stack => 1,
+        VmLabel::Stack | VmLabel::ThreadStack(_) => 1,
         VmLabel::Heap => 2,
         VmLabel::Text => 3,
         VmLabel::Lib => 4,
         VmLabel::Anonymous => 5,
-        VmLabel::Other(_) => 6,
+        VmLabel::Other(_) | VmLabel::Guard => 6,
     });
     print_vm_vars(&ordered);
 }
 
-fn handle_globals(session: &mut MiSession) {
+/// `globals [regex]`: with a pattern, only variables whose name or type matches it are
+/// shown (values are still recorded for all, so change highlighting keeps working).
+fn handle_globals(filter: Option<&Regex>, session: &mut MiSession, state: &mut ReplState) {
     let globals = match session.list_globals() {
         Ok(gs) => gs,
         Err(e) => {
@@ -239,16 +1945,113 @@ fn handle_globals(session: &mut MiSession) {
         }
     };
     let vm_regions = match session.inferior_pid() {
-        Ok(pid) => match vm::read_proc_maps(pid) {
+        Ok(pid) => match addr::process_regions(session, pid) {
             Ok(r) => Some(r),
             Err(e) => {
-                eprintln!("globals: failed to read /proc/{}: {}", pid, e);
+                eprintln!("globals: failed to read the map of process {}: {}", pid, e);
                 None
             }
         },
         Err(_) => None,
     };
-    super::printers::print_globals(&globals, vm_regions.as_deref());
+    let statics = function_statics(session, state);
+    let all: Vec<GlobalVar> = globals.iter().chain(&statics).cloned().collect();
+    state.values.active = true;
+    state.values.record_globals(&all);
+    let changed = state.values.changed_globals();
+    let (globals, statics, all) = match filter {
+        Some(re) => {
+            let keep = |gs: &[GlobalVar]| -> Vec<GlobalVar> {
+                gs.iter()
+                    .filter(|g| re.is_match(&g.name) || re.is_match(&g.type_name))
+                    .cloned()
+                    .collect()
+            };
+            (keep(&globals), keep(&statics), keep(&all))
+        }
+        None => (globals, statics, all),
+    };
+    if json::enabled() {
+        let regions = vm_regions.unwrap_or_default();
+        json::emit(json::globals(&all, &regions, &changed));
+        return;
+    }
+    if let Some(re) = filter.filter(|_| all.is_empty()) {
+        println!("no globals match '{}'", re.as_str());
+        return;
+    }
+    let folded = &state.folded_files;
+    super::printers::print_globals(&globals, vm_regions.as_deref(), &changed, folded);
+    if !statics.is_empty() {
+        println!("{}", style::paint("statics:", theme::theme().header));
+        super::printers::print_globals(&statics, vm_regions.as_deref(), &changed, folded);
+    }
+}
+
+/// `globals fold|unfold [file...]`: collapse source files in `globals` to their header, or
+/// open them again. Without files, every indexed file is folded, or all are unfolded.
+fn fold_files(files: Vec<&str>, fold: bool, state: &mut ReplState) {
+    let files: Vec<String> = if !files.is_empty() {
+        files.into_iter().map(str::to_string).collect()
+    } else if fold {
+        match state.symbol_index() {
+            Some(index) => index.globals_by_file.keys().cloned().collect(),
+            None => {
+                eprintln!("globals: no symbol index; name the files to fold");
+                return;
+            }
+        }
+    } else {
+        state.folded_files.clear();
+        println!("all files unfolded");
+        return;
+    };
+    for file in &files {
+        if fold {
+            state.folded_files.insert(file.clone());
+        } else {
+            // Folded by either spelling; unfolding by base name opens both.
+            let base = file.rsplit('/').next().unwrap_or(file);
+            state
+                .folded_files
+                .retain(|f| f != file && f.rsplit('/').next() != Some(base));
+        }
+    }
+    println!(
+        "{} {} file(s)",
+        if fold { "folded" } else { "unfolded" },
+        files.len()
+    );
+}
+
+/// Current values of the indexed function-scope statics, named `function::name`.
+fn function_statics(session: &mut MiSession, state: &mut ReplState) -> Vec<GlobalVar> {
+    let Some(index) = state.symbol_index() else {
+        return Vec::new();
+    };
+    let statics: Vec<(String, String, Option<String>)> = index
+        .statics()
+        .into_iter()
+        .map(|g| {
+            (
+                g.expr(),
+                g.type_name.clone().unwrap_or_default(),
+                g.file.clone(),
+            )
+        })
+        .collect();
+    statics
+        .into_iter()
+        .map(|(expr, type_name, file)| GlobalVar {
+            value: session
+                .evaluate_expression(&expr)
+                .unwrap_or_else(|_| "<unavailable>".to_string()),
+            address: session.eval_address_of_expr(&expr).unwrap_or(0),
+            name: expr,
+            type_name,
+            file,
+        })
+        .collect()
 }
 
 fn handle_vm_locate(sym: &str, session: &mut MiSession) {
@@ -259,10 +2062,13 @@ fn handle_vm_locate(sym: &str, session: &mut MiSession) {
             return;
         }
     };
-    let regions = match vm::read_proc_maps(pid) {
+    let regions = match addr::process_regions(session, pid) {
         Ok(r) => r,
         Err(e) => {
-            eprintln!("vm locate: failed to read /proc/{}: {}", pid, e);
+            eprintln!(
+                "vm locate: failed to read the map of process {}: {}",
+                pid, e
+            );
             return;
         }
     };
@@ -278,6 +2084,23 @@ fn resolve_vm_locate<'a>(
     expr: &str,
     regions: &'a [vm::VmRegion],
 ) -> Result<VmLocateInfo<'a>> {
+    // A pointer value copied from elsewhere: no lvalue to evaluate, just classify it.
+    if let Some(addr) = addr::parse_number(expr) {
+        let region = regions.iter().find(|r| r.contains(addr));
+        return Ok(VmLocateInfo {
+            expr: expr.to_string(),
+            type_name: "address".to_string(),
+            storage_addr: None,
+            storage_region: None,
+            value_addr: Some(addr),
+            value_region: region,
+            storage_symbol: None,
+            value_symbol: file_symbol(session, addr, region),
+            is_pointer: false,
+            is_null: false,
+            is_literal: true,
+        });
+    }
     let (expr_type, _expr_value) = session.eval_expr_type_and_value(expr)?;
     let is_ptr = is_pointer_type(&expr_type);
 
@@ -300,8 +2123,11 @@ fn resolve_vm_locate<'a>(
             storage_region,
             value_addr: if is_null { None } else { Some(ptr_val) },
             value_region,
+            storage_symbol: file_symbol(session, storage_addr, storage_region),
+            value_symbol: file_symbol(session, ptr_val, value_region),
             is_pointer: true,
             is_null,
+            is_literal: false,
         })
     } else {
         let obj_addr = session.eval_address_of_expr(expr)?;
@@ -313,13 +2139,27 @@ fn resolve_vm_locate<'a>(
             storage_region: None,
             value_addr: Some(obj_addr),
             value_region: obj_region,
+            storage_symbol: None,
+            value_symbol: file_symbol(session, obj_addr, obj_region),
             is_pointer: false,
             is_null: false,
+            is_literal: false,
         })
     }
 }
 
-fn handle_mem(rest: &str, session: &mut MiSession) {
+/// Symbol containing `addr` when it lies in a mapped file; heap, stack and anonymous
+/// memory have none, so the debugger is not asked.
+fn file_symbol(
+    session: &mut MiSession,
+    addr: u64,
+    region: Option<&vm::VmRegion>,
+) -> Option<AddressSymbol> {
+    region?.file_offset(addr)?;
+    session.symbol_at(addr).ok().flatten()
+}
+
+fn handle_mem(rest: &str, session: &mut MiSession, heap: &HeapTracker) {
     if rest.is_empty() {
         println!("usage: mem <expr> [len]");
         return;
@@ -337,12 +2177,37 @@ fn handle_mem(rest: &str, session: &mut MiSession) {
         None => None,
     };
     match session.memory_dump(expr, override_len) {
-        Ok(dump) => print_memory_full(&dump),
-        Err(e) => eprintln!("mem error: {}", e),
+        Ok(dump) if json::enabled() => json::emit(json::mem(&dump)),
+        Ok(dump) => {
+            if let Ok(base) = u64::from_str_radix(dump.address.trim_start_matches("0x"), 16) {
+                addr::warn_access(session, base, dump.bytes.len(), 'r', "mem");
+            }
+            // Layout lookups cost extra MI round trips; only pay them when we can color.
+            let layout = if style::enabled() {
+                dump_layout(session, &dump)
+            } else {
+                None
+            };
+            print_memory_full(&dump, layout.as_ref(), &heap.chunk_pointers())
+        }
+        Err(e) => {
+            // Explain a failed read with the region map instead of only gdb's message.
+            // Skipped under --json: the warnings are plain text on stdout.
+            let base = if json::enabled() {
+                None
+            } else {
+                session.eval_address_of_expr(expr).ok()
+            };
+            if let Some(base) = base {
+                let len = override_len.unwrap_or(1);
+                addr::warn_access(session, base, len, 'r', "mem");
+            }
+            eprintln!("mem error: {}", e)
+        }
     }
 }
 
-fn handle_view(symbol: &str, session: &mut MiSession) -> Result<()> {
+fn handle_view(symbol: &str, session: &mut MiSession, heap: &HeapTracker) -> Result<()> {
     // Make sure endian is resolved before printing layout info.
     session.ensure_endian();
     let size = match session.evaluate_sizeof(symbol) {
@@ -359,6 +2224,7 @@ fn handle_view(symbol: &str, session: &mut MiSession) -> Result<()> {
             return Ok(());
         }
     };
+    addr::warn_access(session, addr, size, 'r', "view");
     let ptype_line = session
         .ptype_text(symbol)
         .ok()
@@ -396,18 +2262,111 @@ fn handle_view(symbol: &str, session: &mut MiSession) -> Result<()> {
             println!("\nraw:");
             let dump = session.memory_dump(symbol, Some(size))?;
             print_memory_body(&dump);
+            if is_char_pointer(tline) {
+                print_pointed_string(session, symbol);
+            }
             return Ok(());
         }
     }
 
     print_layout(&layout);
+    if let Some(kind) = stl::detect(&type_display) {
+        let summary = stl::summarize(session, symbol, kind);
+        stl::print_summary(&type_display, kind, &summary);
+    }
 
     println!("\nraw:");
     let dump = session.memory_dump(symbol, Some(size))?;
-    print_memory_body(&dump);
+    print_memory_annotated(&dump, Some(&layout), &heap.chunk_pointers());
+    print_string_fields(session, addr, &layout);
+    Ok(())
+}
+
+/// The string a `char *` expression points to, under its dump.
+fn print_pointed_string(session: &mut MiSession, expr: &str) {
+    let ptr = match session.eval_expr_u64(&format!("(unsigned long)({})", expr)) {
+        Ok(0) | Err(_) => return,
+        Ok(p) => p,
+    };
+    match session.read_c_string(ptr, VIEW_STRING_BYTES) {
+        Ok(s) => println!("\nstring: {}", format_c_string(&s)),
+        Err(e) => println!("\nstring: <unreadable at 0x{:x}: {}>", ptr, e),
+    }
+}
+
+/// Contents of the struct's `char *` fields, after the raw bytes.
+fn print_string_fields(session: &mut MiSession, base: u64, layout: &TypeLayout) {
+    let TypeLayout::Struct { fields, .. } = layout else {
+        return;
+    };
+    let strings = follow::string_fields(session, base, fields);
+    if strings.is_empty() {
+        return;
+    }
+    println!("\nstrings:");
+    for (field, s) in &strings {
+        println!("  {:<12} {}", field, format_c_string(s));
+    }
+}
+
+fn handle_cast(rest: &str, session: &mut MiSession, heap: &HeapTracker) -> Result<()> {
+    let mut parts = rest.splitn(2, char::is_whitespace);
+    let addr_expr = parts.next().unwrap_or("");
+    let ty = parts.next().unwrap_or("").trim();
+    if addr_expr.is_empty() || ty.is_empty() {
+        println!("usage: cast <addr> <type>");
+        return Ok(());
+    }
+    session.ensure_endian();
+    let addr = match session.eval_expr_u64(addr_expr) {
+        Ok(v) => v,
+        Err(e) => {
+            println!("cast: could not resolve address '{}': {}", addr_expr, e);
+            return Ok(());
+        }
+    };
+    if addr == 0 {
+        println!("cast: refusing to read through a NULL address");
+        return Ok(());
+    }
+    let size = match session.evaluate_sizeof(ty) {
+        Ok(sz) => sz,
+        Err(e) => {
+            println!("cast: sizeof({}) failed: {}", ty, e);
+            return Ok(());
+        }
+    };
+    let layout = session
+        .fetch_layout_for_type(ty)
+        .unwrap_or(TypeLayout::Scalar {
+            type_name: ty.to_string(),
+            size,
+        });
+
+    println!("cast: ({}) @ 0x{:016x}", normalize_type_name(ty), addr);
+    addr::warn_access(session, addr, size, 'r', "cast");
+    println!("size: {} bytes (word size = {})", size, session.word_size);
+    print_layout(&layout);
+
+    println!("\nraw:");
+    // Dereferencing a cast pointer lets the regular dump path handle size/type/caps.
+    let expr = format!("*({} *)0x{:x}", ty, addr);
+    let dump = session.memory_dump(&expr, Some(size))?;
+    print_memory_annotated(&dump, Some(&layout), &heap.chunk_pointers());
+    print_string_fields(session, addr, &layout);
     Ok(())
 }
 
+/// Layout of the dumped object, used to attribute bytes to fields. Pointers are skipped
+/// because ptype reports the pointee's layout, not the pointer's own bytes.
+fn dump_layout(session: &mut MiSession, dump: &MemoryDump) -> Option<TypeLayout> {
+    if dump.ty.as_deref().map(is_pointer_type).unwrap_or(true) {
+        return None;
+    }
+    let size = session.evaluate_sizeof(&dump.expr).ok()?;
+    session.fetch_layout(&dump.expr, size)
+}
+
 fn type_name(layout: &TypeLayout) -> String {
     match layout {
         TypeLayout::Scalar { type_name, .. } => type_name.clone(),
@@ -484,17 +2443,105 @@ fn print_layout(layout: &TypeLayout) {
 fn print_help() {
     println!("Commands:");
     println!("  locals                - list locals in current frame");
-    println!("  globals               - list global/static variables");
-    println!("  mem <expr> [len]      - hex+ASCII dump sizeof(<expr>) bytes (capped) at &<expr>; len overrides size");
+    println!("  args [n]              - arguments of frame n and the register or stack slot each arrived in");
+    println!("  globals [regex]       - list global/static variables by source file (matching name or type)");
+    println!("  globals fold|unfold [file...] - collapse files in globals to their header, or open them (all without files)");
+    println!("  mem <expr> [len]      - hex+ASCII dump sizeof(<expr>) bytes (capped by --max-dump) at &<expr>; len overrides size");
     println!("  view <symbol>         - show type-based layout for symbol (struct/array) plus raw dump");
+    println!("  cast <addr> <type>    - show sizeof(<type>) bytes at a raw address with <type>'s field layout");
+    println!("  expand <expr> [depth] - show a struct/array/pointer as a tree of children; [+] marks collapsed nodes");
+    println!("  typetree <type> [d]   - full type tree from gdb's Python API (fields, offsets, pointer targets d deep)");
     println!("  follow <sym> [d]      - follow pointer chain for symbol up to optional depth (default ~8)");
+    println!("  hexedit <expr> [len]  - edit bytes at &<expr> in a sub-prompt; changes are written on 'commit'");
+    println!("  track list|tree <expr> - show a length/height/key-range dashboard for a structure after every stop");
+    println!("  track [delete <n>]    - show or remove tracked structures");
+    println!("  addr <expr> [+off]    - resolve an expression to a numeric address (pointer value or &object) with its region");
+    println!("  addr -f <expr> [+off] - also translate it to its file: link-time address and file offset");
+    println!("  between <a> <b>       - byte distance from <a> to <b>");
+    println!("  align <addr> <n>      - show <addr> modulo <n> and the nearest aligned addresses");
+    println!("  regs [all]            - show general-purpose (or all) registers; changed since last stop are highlighted");
+    println!("  regs on|off           - show registers after every stop");
+    println!("  sym stats             - show symbol index coverage (files/variables per mode, skipped files, timing)");
+    println!("  sym functions [prefix] - list indexed functions (name, signature, file:line)");
+    println!("  sym statics           - list statics declared inside functions (function::name)");
+    println!("  find <pattern> | <n>  - fuzzy-search indexed globals/functions; <n> lists or views a result");
+    println!(
+        "  sym reindex [mode]    - rebuild the symbol index (debug-only | debug-and-nondebug)"
+    );
     println!("  vm                    - show process memory map from /proc/<pid>/maps");
     println!("  vm vars               - show locals/globals grouped by VM region");
-    println!("  vm locate <symbol>    - show which VM region contains the given symbol");
+    println!(
+        "  vm layout             - draw regions top-down with size bars and the variables in each"
+    );
+    println!("  vm map                - memory layout diagram: regions and gaps as boxes, heights on a log scale");
+    println!("  vm diff [on|off]      - regions created, removed or resized since the previous stop; on: after every stop");
+    println!(
+        "  vm stats              - sum RSS/PSS/dirty/swap per region label from /proc/<pid>/smaps"
+    );
+    println!(
+        "  vm bases              - load address and ASLR bias of the executable and each library"
+    );
+    println!("  vm track on|off       - catch brk/mmap/munmap/mremap and log each region change with a backtrace");
+    println!("  vm history [n]        - the logged region changes (last n), oldest first");
+    println!(
+        "  vm locate <expr|addr> - show which VM region contains an expression or a raw address"
+    );
+    println!("  heap                  - list heap blocks seen through the malloc/free hooks");
+    println!("  heap track on|off     - hook malloc/calloc/realloc/free to track live blocks");
+    println!("  heap canary on [g]|off - surround new blocks with g (default 16) canary bytes, checked at every stop and on free");
+    println!("  heap check            - check all guarded blocks now");
+    println!("  heap arenas           - every glibc arena: allocated vs free (bins, fastbins, top), top of heap, mmapped chunks");
+    println!("  heap bins [arena]     - tcache, fastbin and bin free lists with the freed chunks they hold");
+    println!("  heap chunk <addr>     - decode the glibc chunk header before a heap pointer and dump the chunk's bounds");
+    println!("  on-enter <func> <cmd> - run a memviz command each time <func> is entered (e.g. 'on-enter process_packet mem buf 64')");
+    println!("  on-exit <func> <cmd>  - run a memviz command each time <func> returns");
+    println!(
+        "  display [<expr>]      - show an expression's value, type and region after every stop"
+    );
+    println!(
+        "  display --frame <n> <expr> - same, evaluated in frame n without selecting it"
+    );
+    println!("  undisplay <n>         - remove a display expression");
+    println!("  hooks [delete <n>]    - list or delete on-enter/on-exit hooks");
     println!("  break <loc> | b       - set breakpoint (e.g. 'break main', 'b file.c:42')");
+    println!("  catch <event>         - stop on 'throw', 'rethrow', 'syscall <name>', 'fork', 'vfork' or 'exec'");
+    println!("  breakpoints [n] | bl  - list breakpoints with enabled state and hit counts, or show source at n");
+    println!(
+        "  breakpoints log <n> <expr>[, ...]|off - print these values at every hit of breakpoint n"
+    );
+    println!("  enable|disable|delete <n> - enable, disable or delete breakpoint n");
+    println!("  bt | backtrace        - list stack frames (* marks the selected frame)");
+    println!("  frame <n> | f <n>     - select frame n; locals/mem/view then use that frame until the next stop");
+    println!("  stack [n]             - saved return address and frame pointer of frame n, checked against the map");
+    println!(
+        "  stack usage [reset]   - deepest stack seen at stops this run, overall and per function"
+    );
+    println!("  stack canary [on|off] - stack protector slot of the selected frame; on: warn when a slot changes");
+    println!("  list [[file:]line] | l - show source around a line; ● marks breakpoints, > the current line");
+    println!(
+        "  /text | ?text         - search the listed source forward/backward (bare / or ? repeats)"
+    );
+    println!("  toggle [file:]line    - set or remove a breakpoint on a line (defaults to the listed file)");
     println!("  next | n              - execute next line (step over)");
     println!("  step | s              - step into functions");
     println!("  continue | c          - continue execution until next breakpoint");
+    println!("  rn | rs | rc          - reverse-next/step/continue (also reverse-finish; --rr)");
+    println!("  run | r               - restart the program from the beginning (stops at the startup breakpoint)");
+    println!(
+        "  finish | fin          - run until the selected frame returns and show the return value"
+    );
+    println!(
+        "  jump [-y] <loc>       - resume at another line after confirming (skip or re-run code)"
+    );
+    println!(
+        "  theme [dark|light|custom] - show or switch the color theme (custom = --theme-file)"
+    );
+    println!("  output [on|off|all]   - show program output since the last stop, toggle it per stop, or dump all of it");
+    println!("  input <text>          - type a line into the program's terminal (--tty); read when it next runs");
+    println!("  history [n]           - show the last n commands (default 20); recall with !!, !<n>, !<prefix>");
+    println!(
+        "  relaunch              - start gdb again after it died, restoring breakpoints and hooks"
+    );
     println!("  help                  - show this message");
     println!("  quit | q              - exit");
 }
@@ -0,0 +1,123 @@
+use super::printers::prettify_value;
+use super::style;
+use super::theme::theme;
+use crate::mi::{MiSession, Result, VarNode};
+
+/// Levels shown when `expand` is given no depth.
+const DEFAULT_DEPTH: u32 = 1;
+/// Deepest tree `expand` will walk.
+const MAX_DEPTH: u32 = 8;
+/// Children listed per node before the rest are summarized.
+const MAX_CHILDREN: usize = 64;
+
+/// `expand <expr> [depth]`: print a struct/array/pointer as an indented tree of its
+/// children using the session's managed variable objects. `[+]` marks nodes that have more
+/// levels below; values that changed at the last stop are highlighted.
+pub fn handle_expand(args: &str, session: &mut MiSession) -> Result<()> {
+    let mut parts: Vec<&str> = args.split_whitespace().collect();
+    let depth = match parts.last().filter(|_| parts.len() > 1) {
+        Some(tok) => match tok.parse::<u32>() {
+            Ok(d) => {
+                parts.pop();
+                d.clamp(1, MAX_DEPTH)
+            }
+            Err(_) => DEFAULT_DEPTH,
+        },
+        None => DEFAULT_DEPTH,
+    };
+    let expr = parts.join(" ");
+    if expr.is_empty() {
+        println!("usage: expand <expr> [depth]");
+        return Ok(());
+    }
+    let root = session.varobj(&expr)?;
+    let changed = session.varobj_changed(&root.name);
+    println!("{}", node_line(&root, &expr, depth > 0, changed));
+    print_children(session, &root, 1, depth)
+}
+
+fn print_children(session: &mut MiSession, node: &VarNode, level: u32, depth: u32) -> Result<()> {
+    if node.numchild == 0 || level > depth {
+        return Ok(());
+    }
+    let children = visible_children(session, node)?;
+    for child in children.iter().take(MAX_CHILDREN) {
+        let label = child_label(&child.exp, node.type_name.as_deref());
+        let indent = "  ".repeat(level as usize);
+        let changed = session.varobj_changed(&child.name);
+        println!(
+            "{}{}",
+            indent,
+            node_line(child, &label, level < depth, changed)
+        );
+        print_children(session, child, level + 1, depth)?;
+    }
+    if children.len() > MAX_CHILDREN {
+        let indent = "  ".repeat(level as usize);
+        println!("{}... {} more", indent, children.len() - MAX_CHILDREN);
+    }
+    Ok(())
+}
+
+/// Children with C++ access-specifier pseudo nodes (`public`/`private`/`protected`) flattened.
+fn visible_children(session: &mut MiSession, node: &VarNode) -> Result<Vec<VarNode>> {
+    let mut out = Vec::new();
+    for child in session.varobj_children(&node.name)? {
+        let is_access = child.type_name.is_none()
+            && matches!(child.exp.as_str(), "public" | "private" | "protected");
+        if is_access {
+            out.extend(session.varobj_children(&child.name)?);
+        } else {
+            out.push(child);
+        }
+    }
+    Ok(out)
+}
+
+fn node_line(node: &VarNode, label: &str, expanded: bool, changed: bool) -> String {
+    let ty = node.type_name.as_deref().unwrap_or("?");
+    let value = match &node.value {
+        // Aggregates report "{...}" or "[N]"; the children carry the content.
+        Some(v) if node.numchild == 0 || !expanded => prettify_value(v),
+        _ => String::new(),
+    };
+    let value = if changed && !value.is_empty() {
+        style::paint(&value, theme().changed)
+    } else {
+        value
+    };
+    let more = if node.numchild > 0 && !expanded {
+        format!(" {}", style::paint("[+]", theme().unavailable))
+    } else {
+        String::new()
+    };
+    if value.is_empty() {
+        format!("{} ({}){}", label, ty, more)
+    } else {
+        format!("{} ({}) = {}{}", label, ty, value, more)
+    }
+}
+
+/// `.field` for struct members, `[i]` for array elements, `*name` for a dereferenced pointer.
+fn child_label(exp: &str, parent_type: Option<&str>) -> String {
+    let parent = parent_type.unwrap_or("").trim();
+    if exp.starts_with('*') {
+        exp.to_string()
+    } else if exp.parse::<i64>().is_ok() && parent.ends_with(']') {
+        format!("[{}]", exp)
+    } else {
+        format!(".{}", exp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn child_labels_follow_parent_kind() {
+        assert_eq!(child_label("next", Some("struct Node *")), ".next");
+        assert_eq!(child_label("2", Some("int [4]")), "[2]");
+        assert_eq!(child_label("*p", Some("int *")), "*p");
+    }
+}
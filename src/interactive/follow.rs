@@ -1,12 +1,24 @@
-use super::printers::prettify_value;
-use crate::mi::{MiSession, Result};
+use super::json::{self, FollowStep};
+use super::printers::{format_c_string, prettify_value};
+use super::style;
+use super::theme::theme;
+use crate::mi::parser::bytes_to_u64;
+use crate::mi::{CStringRead, Endian, MiSession, Result};
 use crate::types::{
-    find_pointer_field, is_pointer_type, normalize_pointer_type, strip_pointer_suffix, TypeLayout,
+    find_pointer_field, is_char_pointer, is_pointer_type, normalize_pointer_type,
+    strip_pointer_suffix, FieldLayout, TypeLayout,
 };
 
+/// Bytes of a `char *` field shown in each node.
+const STRING_PREVIEW: usize = 64;
+/// Scalar fields shown per node, besides its strings and the link.
+const SUMMARY_FIELDS: usize = 4;
+/// Levels indented before deeper nodes stay in the last column.
+const MAX_INDENT: usize = 12;
+
 pub fn handle_follow(args: &str, session: &mut MiSession) -> Result<()> {
     // Minimal pointer-chain walker: validates the symbol, figures out pointee layout,
-    // then repeatedly evaluates the struct value and reads the chosen link field.
+    // then reads each node's bytes for its summary line and follows the chosen link field.
     let mut parts = args.split_whitespace();
     let symbol = match parts.next() {
         Some(s) if !s.is_empty() => s,
@@ -19,12 +31,10 @@ pub fn handle_follow(args: &str, session: &mut MiSession) -> Result<()> {
         Some(raw) => match raw.parse::<usize>() {
             Ok(v) if v > 0 => v,
             Ok(_) => {
-                println!("follow: depth must be positive");
-                return Ok(());
+                return Err("follow: depth must be positive".into());
             }
             Err(_) => {
-                println!("follow: invalid depth '{}'", raw);
-                return Ok(());
+                return Err(format!("follow: invalid depth '{}'", raw).into());
             }
         },
         None => 8,
@@ -32,32 +42,27 @@ pub fn handle_follow(args: &str, session: &mut MiSession) -> Result<()> {
     let locals = match session.list_locals() {
         Ok(l) => l,
         Err(e) => {
-            eprintln!("follow: failed to list locals: {}", e);
-            return Ok(());
+            return Err(format!("follow: failed to list locals: {}", e).into());
         }
     };
     let var = match locals.iter().find(|v| v.name == symbol) {
         Some(v) => v,
         None => {
-            println!("follow: symbol '{}' not found in locals", symbol);
-            return Ok(());
+            return Err(format!("follow: symbol '{}' not found in locals", symbol).into());
         }
     };
     let ty = match &var.ty {
         Some(t) => t.trim(),
         None => {
-            println!("follow: type for '{}' unavailable", symbol);
-            return Ok(());
+            return Err(format!("follow: type for '{}' unavailable", symbol).into());
         }
     };
     if !is_pointer_type(ty) {
-        println!("follow: '{}' is not a pointer type (got '{}')", symbol, ty);
-        return Ok(());
+        return Err(format!("follow: '{}' is not a pointer type (got '{}')", symbol, ty).into());
     }
     let pointee_type = strip_pointer_suffix(ty);
     if pointee_type.is_empty() {
-        println!("follow: cannot obtain layout for pointee type '{}'", ty);
-        return Ok(());
+        return Err(format!("follow: cannot obtain layout for pointee type '{}'", ty).into());
     }
     let ptr_display = normalize_pointer_type(ty);
 
@@ -70,8 +75,7 @@ pub fn handle_follow(args: &str, session: &mut MiSession) -> Result<()> {
     let raw_value = match value_text {
         Some(v) => v,
         None => {
-            println!("follow: value for '{}' unavailable", symbol);
-            return Ok(());
+            return Err(format!("follow: value for '{}' unavailable", symbol).into());
         }
     };
     let mut addr_opt = parse_pointer_address(&raw_value);
@@ -83,33 +87,32 @@ pub fn handle_follow(args: &str, session: &mut MiSession) -> Result<()> {
     let mut addr = match addr_opt {
         Some(a) => a,
         None => {
-            println!(
+            return Err(format!(
                 "follow: could not parse pointer value for '{}' (value '{}')",
                 symbol, raw_value
-            );
-            return Ok(());
+            )
+            .into());
         }
     };
     if addr == 0 {
-        println!("follow: '{}' is NULL", symbol);
-        return Ok(());
+        return Err(format!("follow: '{}' is NULL", symbol).into());
     }
 
     let layout = match session.fetch_layout_for_type(&pointee_type) {
         Some(l @ TypeLayout::Struct { .. }) => l,
         Some(_) => {
-            println!(
+            return Err(format!(
                 "follow: cannot obtain layout for pointee type '{}'",
                 pointee_type
-            );
-            return Ok(());
+            )
+            .into());
         }
         None => {
-            println!(
+            return Err(format!(
                 "follow: cannot obtain layout for pointee type '{}'",
                 pointee_type
-            );
-            return Ok(());
+            )
+            .into());
         }
     };
     let struct_name = match &layout {
@@ -120,55 +123,148 @@ pub fn handle_follow(args: &str, session: &mut MiSession) -> Result<()> {
     let link_field = match find_pointer_field(&layout).cloned() {
         Some(f) => f,
         None => {
-            println!(
+            return Err(format!(
                 "follow: struct {} has no pointer field to follow (expected e.g. 'next')",
                 struct_name
-            );
-            return Ok(());
+            )
+            .into());
         }
     };
 
-    let mut expr_display = symbol.to_string();
-    for i in 0..depth {
+    let (size, fields) = match &layout {
+        TypeLayout::Struct { size, fields, .. } => (*size, fields.clone()),
+        _ => (0, Vec::new()),
+    };
+    session.ensure_endian();
+    let endian = session.endian;
+    if !json::enabled() {
         println!(
-            "[{}] {} ({}) = {}",
-            i,
-            expr_display,
+            "{} ({}) = {}, linked by .{}",
+            style::paint(symbol, theme().header),
             ptr_display,
-            format_addr(addr)
+            format_addr(addr),
+            link_field.name
         );
+    }
+
+    let mut expr_display = symbol.to_string();
+    let mut steps = Vec::new();
+    let mut ended = false;
+    for i in 0..depth {
         if addr == 0 {
-            println!("    -> NULL (stopped)");
+            if !json::enabled() {
+                println!("{} NULL", branch(i));
+            }
+            steps.push(FollowStep {
+                expr: expr_display,
+                addr,
+                value: None,
+                error: None,
+                strings: Vec::new(),
+            });
+            ended = true;
             break;
         }
-        match session.evaluate_expression(&format!("* ({} *) (0x{:x})", pointee_type, addr)) {
-            Ok(val) => println!("    -> {} {}", pointee_type, prettify_value(&val)),
-            Err(e) => println!("    -> <eval error: {}>", e),
+        let mut step = FollowStep {
+            expr: expr_display.clone(),
+            addr,
+            value: None,
+            error: None,
+            strings: Vec::new(),
+        };
+        step.strings = string_fields(session, addr, &fields);
+        if json::enabled() {
+            match session.evaluate_expression(&format!("* ({} *) (0x{:x})", pointee_type, addr)) {
+                Ok(val) => step.value = Some(prettify_value(&val)),
+                Err(e) => step.error = Some(format!("eval error: {}", e)),
+            }
+        } else {
+            let summary = match session.read_memory(addr, size) {
+                Ok(bytes) => format!(
+                    "{{{}}}",
+                    node_summary(&fields, &bytes, endian, &link_field.name, &step.strings)
+                        .join(", ")
+                ),
+                Err(e) => format!("<{}>", e),
+            };
+            println!(
+                "{} [{}] {} {}",
+                branch(i),
+                i,
+                style::paint(&format_addr(addr), theme().pointer),
+                summary
+            );
         }
+        steps.push(step);
         // Read the link field directly from memory to avoid parsing the evaluated struct.
-        let field_addr = match addr.checked_add(link_field.offset as u64) {
-            Some(v) => v,
-            None => {
-                println!("    -> overflow computing address for {}", link_field.name);
-                break;
-            }
-        };
-        let next_addr = match session.read_pointer_at(field_addr, Some(link_field.size)) {
+        let next = addr
+            .checked_add(link_field.offset as u64)
+            .ok_or_else(|| format!("overflow computing address for {}", link_field.name))
+            .and_then(|field_addr| {
+                session
+                    .read_pointer_at(field_addr, Some(link_field.size))
+                    .map_err(|e| {
+                        format!("failed to read {}.{}: {}", struct_name, link_field.name, e)
+                    })
+            });
+        let next_addr = match next {
             Ok(v) => v,
             Err(e) => {
-                println!(
-                    "    -> failed to read {}.{}: {}",
-                    struct_name, link_field.name, e
-                );
+                if !json::enabled() {
+                    println!("{} {}", branch(i + 1), e);
+                }
+                if let Some(last) = steps.last_mut() {
+                    last.error.get_or_insert(e);
+                }
+                ended = true;
                 break;
             }
         };
         expr_display = format!("{}->{}", expr_display, link_field.name);
         addr = next_addr;
     }
+    if !ended && !json::enabled() {
+        println!(
+            "{} {} ... (depth {} reached; 'follow {} <depth>' for more)",
+            branch(depth),
+            format_addr(addr),
+            depth,
+            symbol
+        );
+    }
+    if json::enabled() {
+        json::emit(json::follow(
+            symbol,
+            &pointee_type,
+            &link_field.name,
+            &steps,
+        ));
+    }
     Ok(())
 }
 
+/// Read the strings behind the `char *` fields of the struct at `base`; NULL and unreadable
+/// pointers are left out.
+pub fn string_fields(
+    session: &mut MiSession,
+    base: u64,
+    fields: &[FieldLayout],
+) -> Vec<(String, CStringRead)> {
+    let mut out = Vec::new();
+    for f in fields.iter().filter(|f| is_char_pointer(&f.type_name)) {
+        let Ok(ptr) = session.read_pointer_at(base + f.offset as u64, Some(f.size)) else {
+            continue;
+        };
+        if ptr == 0 {
+            continue;
+        }
+        if let Ok(s) = session.read_c_string(ptr, STRING_PREVIEW) {
+            out.push((f.name.clone(), s));
+        }
+    }
+    out
+}
+
 fn parse_pointer_address(value: &str) -> Option<u64> {
     // Try hex form first; fall back to decimal if hex is absent.
     if let Ok(re) = regex::Regex::new(r"0x[0-9a-fA-F]+") {
@@ -188,6 +284,84 @@ fn parse_pointer_address(value: &str) -> Option<u64> {
     None
 }
 
+/// Tree branch for the node at `depth`; deep chains stop indenting at `MAX_INDENT`.
+fn branch(depth: usize) -> String {
+    format!("{}└─", "   ".repeat(depth.min(MAX_INDENT)))
+}
+
+/// `name=value` for the node's first few scalar fields, its strings and the link pointer, in
+/// declaration order, decoded from the node's bytes.
+fn node_summary(
+    fields: &[FieldLayout],
+    bytes: &[u8],
+    endian: Endian,
+    link: &str,
+    strings: &[(String, CStringRead)],
+) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut scalars = 0;
+    for f in fields {
+        let Some(raw) = bytes.get(f.offset..f.offset + f.size) else {
+            continue;
+        };
+        if f.name == link {
+            out.push(format!("{}=0x{:x}", f.name, bytes_to_u64(raw, endian)));
+        } else if let Some((_, s)) = strings.iter().find(|(name, _)| *name == f.name) {
+            out.push(format!("{}={}", f.name, format_c_string(s)));
+        } else if scalars < SUMMARY_FIELDS {
+            if let Some(value) = format_scalar(&f.type_name, raw, endian) {
+                out.push(format!("{}={}", f.name, value));
+                scalars += 1;
+            }
+        }
+    }
+    out
+}
+
+/// An integer, floating-point or boolean field's value; `None` for anything else.
+fn format_scalar(ty: &str, raw: &[u8], endian: Endian) -> Option<String> {
+    if raw.is_empty() || raw.len() > 8 {
+        return None;
+    }
+    let bits = bytes_to_u64(raw, endian);
+    let ty = ty
+        .trim()
+        .trim_start_matches("const ")
+        .trim_start_matches("volatile ");
+    let unsigned = ty.starts_with("unsigned")
+        || (ty.starts_with("uint") && ty.ends_with("_t"))
+        || matches!(ty, "size_t" | "u8" | "u16" | "u32" | "u64" | "usize");
+    let signed = ty.starts_with("enum ")
+        || (ty.starts_with("int") && ty.ends_with("_t"))
+        || matches!(
+            ty,
+            "char"
+                | "signed char"
+                | "short"
+                | "int"
+                | "long"
+                | "long long"
+                | "ssize_t"
+                | "ptrdiff_t"
+                | "i8"
+                | "i16"
+                | "i32"
+                | "i64"
+                | "isize"
+        );
+    match ty {
+        "float" | "f32" if raw.len() == 4 => Some(f32::from_bits(bits as u32).to_string()),
+        "double" | "f64" if raw.len() == 8 => Some(f64::from_bits(bits).to_string()),
+        "bool" | "_Bool" => Some((bits != 0).to_string()),
+        _ if unsigned => Some(bits.to_string()),
+        _ if signed => {
+            let shift = 64 - 8 * raw.len() as u32;
+            Some((((bits << shift) as i64) >> shift).to_string())
+        }
+        _ => None,
+    }
+}
+
 fn format_addr(addr: u64) -> String {
     format!("0x{:x}", addr)
 }
@@ -209,6 +383,42 @@ mod tests {
         assert_eq!(strip_pointer_suffix("struct Node *"), "struct Node");
     }
 
+    #[test]
+    fn node_summary_decodes_scalars_strings_and_the_link() {
+        let field = |name: &str, ty: &str, offset, size| FieldLayout {
+            name: name.to_string(),
+            type_name: ty.to_string(),
+            offset,
+            size,
+        };
+        let fields = [
+            field("value", "int", 0, 4),
+            field("flag", "unsigned char", 4, 1),
+            field("name", "char *", 8, 8),
+            field("next", "struct Node *", 16, 8),
+            field("inner", "struct Point", 24, 8),
+        ];
+        let mut bytes = vec![0u8; 32];
+        bytes[..4].copy_from_slice(&(-3i32).to_le_bytes());
+        bytes[4] = 200;
+        bytes[16..24].copy_from_slice(&0x4052c0u64.to_le_bytes());
+        let name = CStringRead {
+            text: "alpha".to_string(),
+            truncated: false,
+        };
+        let summary = node_summary(
+            &fields,
+            &bytes,
+            Endian::Little,
+            "next",
+            &[("name".to_string(), name)],
+        );
+        assert_eq!(
+            summary,
+            ["value=-3", "flag=200", "name=\"alpha\"", "next=0x4052c0"]
+        );
+    }
+
     #[test]
     fn normalize_pointer_flattens_spaces() {
         assert_eq!(normalize_pointer_type("struct Node *"), "struct Node*");
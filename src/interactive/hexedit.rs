@@ -0,0 +1,322 @@
+use super::addr::warn_access;
+use super::confirm;
+use super::printers::print_memory_edits;
+use crate::mi::{MiSession, Result};
+use std::io::{self, Write};
+
+/// Pending edits over a snapshot of target memory. Nothing touches the inferior until commit.
+struct EditBuffer {
+    base: u64,
+    original: Vec<u8>,
+    current: Vec<u8>,
+    // Each entry is one `set` command: (offset, previous byte) pairs, replayed in reverse on undo.
+    history: Vec<Vec<(usize, u8)>>,
+}
+
+impl EditBuffer {
+    fn new(base: u64, bytes: Vec<u8>) -> Self {
+        Self {
+            base,
+            original: bytes.clone(),
+            current: bytes,
+            history: Vec::new(),
+        }
+    }
+
+    fn set(&mut self, offset: usize, bytes: &[u8]) -> std::result::Result<(), String> {
+        let end = offset.saturating_add(bytes.len());
+        if end > self.current.len() {
+            return Err(format!(
+                "edit +0x{:x}..+0x{:x} is outside the {}-byte range",
+                offset,
+                end,
+                self.current.len()
+            ));
+        }
+        let mut undo = Vec::with_capacity(bytes.len());
+        for (i, b) in bytes.iter().enumerate() {
+            undo.push((offset + i, self.current[offset + i]));
+            self.current[offset + i] = *b;
+        }
+        self.history.push(undo);
+        Ok(())
+    }
+
+    fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some(entries) => {
+                for (off, prev) in entries.into_iter().rev() {
+                    self.current[off] = prev;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.current = self.original.clone();
+        self.history.clear();
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.current != self.original
+    }
+}
+
+pub fn handle_hexedit(args: &str, session: &mut MiSession) -> Result<()> {
+    let mut parts = args.split_whitespace();
+    let expr = match parts.next() {
+        Some(e) => e,
+        None => {
+            println!("usage: hexedit <expr> [len]");
+            return Ok(());
+        }
+    };
+    let override_len = match parts.next() {
+        Some(raw) => match raw.parse::<usize>() {
+            Ok(v) => Some(v),
+            Err(_) => {
+                println!("hexedit: invalid length '{}'", raw);
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+    let dump = session.memory_dump(expr, override_len)?;
+    if dump.unreadable.iter().any(|u| *u) {
+        println!("hexedit: range contains unreadable bytes; pick a range inside one mapping");
+        return Ok(());
+    }
+    let base = u64::from_str_radix(dump.address.trim_start_matches("0x"), 16)
+        .map_err(|e| format!("hexedit: bad address '{}': {}", dump.address, e))?;
+    if let Some(orig) = dump.truncated_from {
+        println!(
+            "hexedit: editing first {} of {} bytes (raise the cap with --max-dump)",
+            dump.bytes.len(),
+            orig
+        );
+    }
+    let mut buf = EditBuffer::new(base, dump.bytes.clone());
+    let mut view = dump;
+
+    println!(
+        "hexedit: {} @ 0x{:x} ({} bytes). Type 'help' for edit commands.",
+        expr,
+        base,
+        buf.current.len()
+    );
+    print_memory_edits(&view, &buf.original);
+
+    let stdin = io::stdin();
+    let mut line = String::new();
+    let mut quit_armed = false;
+    loop {
+        print!("hexedit> ");
+        io::stdout().flush()?;
+        line.clear();
+        if stdin.read_line(&mut line)? == 0 {
+            println!();
+            break;
+        }
+        let input = line.trim();
+        let mut words = input.splitn(2, char::is_whitespace);
+        let cmd = words.next().unwrap_or("");
+        let rest = words.next().unwrap_or("").trim();
+        if cmd != "quit" && cmd != "q" {
+            quit_armed = false;
+        }
+        match cmd {
+            "" | "show" => {
+                view.bytes = buf.current.clone();
+                print_memory_edits(&view, &buf.original);
+            }
+            "set" => match parse_set_args(rest) {
+                Ok((offset, bytes)) => match buf.set(offset, &bytes) {
+                    Ok(()) => {
+                        view.bytes = buf.current.clone();
+                        print_memory_edits(&view, &buf.original);
+                    }
+                    Err(e) => println!("hexedit: {}", e),
+                },
+                Err(e) => println!("hexedit: {}\n  usage: set <offset> <hex bytes>", e),
+            },
+            "undo" => {
+                if buf.undo() {
+                    view.bytes = buf.current.clone();
+                    print_memory_edits(&view, &buf.original);
+                } else {
+                    println!("hexedit: nothing to undo");
+                }
+            }
+            "reset" => {
+                buf.reset();
+                println!("hexedit: discarded all uncommitted edits");
+            }
+            "diff" => print_diff(&buf),
+            "commit" => {
+                if !buf.is_dirty() {
+                    println!("hexedit: no changes to commit");
+                    continue;
+                }
+                print_diff(&buf);
+                warn_access(session, buf.base, buf.current.len(), 'w', "commit");
+                if !confirm("write these bytes to the inferior? [y/N] ")? {
+                    println!("hexedit: commit cancelled");
+                    continue;
+                }
+                match commit(&buf, session) {
+                    Ok(written) => {
+                        println!("hexedit: wrote {} byte(s)", written);
+                        buf = EditBuffer::new(buf.base, buf.current.clone());
+                    }
+                    Err(e) => eprintln!("hexedit: {}", e),
+                }
+            }
+            "quit" | "q" => {
+                if buf.is_dirty() && !quit_armed {
+                    println!("hexedit: uncommitted edits will be lost; 'quit' again to leave");
+                    quit_armed = true;
+                    continue;
+                }
+                break;
+            }
+            "help" => print_hexedit_help(),
+            _ => println!("hexedit: unknown command '{}' (try 'help')", cmd),
+        }
+    }
+    Ok(())
+}
+
+fn commit(buf: &EditBuffer, session: &mut MiSession) -> Result<usize> {
+    let mut written = 0;
+    for (start, end) in changed_runs(&buf.original, &buf.current) {
+        let addr = buf.base.wrapping_add(start as u64);
+        session.write_memory_bytes(addr, &buf.current[start..end])?;
+        written += end - start;
+    }
+    Ok(written)
+}
+
+fn print_diff(buf: &EditBuffer) {
+    let runs = changed_runs(&buf.original, &buf.current);
+    if runs.is_empty() {
+        println!("no changes");
+        return;
+    }
+    println!("pending changes:");
+    for (start, end) in runs {
+        let old: Vec<String> = buf.original[start..end]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let new: Vec<String> = buf.current[start..end]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        println!(
+            "  +0x{:04x} (0x{:x}): {} -> {}",
+            start,
+            buf.base.wrapping_add(start as u64),
+            old.join(" "),
+            new.join(" ")
+        );
+    }
+}
+
+/// Contiguous [start, end) ranges where `current` differs from `original`.
+fn changed_runs(original: &[u8], current: &[u8]) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut start: Option<usize> = None;
+    for i in 0..current.len() {
+        let changed = original.get(i) != current.get(i);
+        match (changed, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                runs.push((s, i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        runs.push((s, current.len()));
+    }
+    runs
+}
+
+/// Parse `"<offset> <hex bytes>"`, accepting "41 42 43" as well as "414243" or "0x41".
+fn parse_set_args(rest: &str) -> std::result::Result<(usize, Vec<u8>), String> {
+    let mut parts = rest.split_whitespace();
+    let off_raw = parts.next().ok_or("missing offset")?;
+    let offset = match off_raw
+        .strip_prefix("+")
+        .unwrap_or(off_raw)
+        .strip_prefix("0x")
+    {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => off_raw.trim_start_matches('+').parse::<usize>(),
+    }
+    .map_err(|_| format!("invalid offset '{}'", off_raw))?;
+
+    let mut bytes = Vec::new();
+    for tok in parts {
+        let hex = tok.strip_prefix("0x").unwrap_or(tok);
+        if hex.is_empty() || !hex.len().is_multiple_of(2) {
+            return Err(format!("invalid hex bytes '{}'", tok));
+        }
+        for i in (0..hex.len()).step_by(2) {
+            let b = u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| format!("invalid hex bytes '{}'", tok))?;
+            bytes.push(b);
+        }
+    }
+    if bytes.is_empty() {
+        return Err("missing bytes".to_string());
+    }
+    Ok((offset, bytes))
+}
+
+fn print_hexedit_help() {
+    println!("Edit commands:");
+    println!("  show                  - show the buffer (edited bytes marked with ^^)");
+    println!("  set <off> <hex..>     - overwrite bytes at offset (e.g. 'set 0x8 41 42', 'set 4 deadbeef')");
+    println!("  undo                  - revert the last set");
+    println!("  reset                 - discard all uncommitted edits");
+    println!("  diff                  - list pending changes (old -> new)");
+    println!("  commit                - write pending changes to the inferior after confirmation");
+    println!("  quit | q              - leave hexedit");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_set_args_accepts_spaced_and_packed_hex() {
+        assert_eq!(parse_set_args("0x8 41 42").unwrap(), (8, vec![0x41, 0x42]));
+        assert_eq!(
+            parse_set_args("4 deadbeef").unwrap(),
+            (4, vec![0xde, 0xad, 0xbe, 0xef])
+        );
+        assert!(parse_set_args("4 abc").is_err());
+        assert!(parse_set_args("4").is_err());
+    }
+
+    #[test]
+    fn changed_runs_groups_contiguous_bytes() {
+        let orig = [0u8, 1, 2, 3, 4, 5];
+        let cur = [0u8, 9, 9, 3, 4, 9];
+        assert_eq!(changed_runs(&orig, &cur), vec![(1, 3), (5, 6)]);
+    }
+
+    #[test]
+    fn undo_restores_previous_bytes() {
+        let mut buf = EditBuffer::new(0x1000, vec![1, 2, 3]);
+        buf.set(1, &[0xaa, 0xbb]).unwrap();
+        assert!(buf.is_dirty());
+        assert!(buf.undo());
+        assert_eq!(buf.current, vec![1, 2, 3]);
+        assert!(buf.set(2, &[0, 0]).is_err());
+    }
+}
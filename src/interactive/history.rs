@@ -0,0 +1,105 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Entries kept in memory and in the history file.
+const MAX_ENTRIES: usize = 1000;
+
+/// Executed REPL commands, persisted across sessions (`MEMVIZ_HISTFILE`, default
+/// `~/.gdb-memviz_history`).
+#[derive(Default)]
+pub struct History {
+    entries: Vec<String>,
+    path: Option<PathBuf>,
+}
+
+impl History {
+    /// Load the history file; a missing or unreadable file just starts empty.
+    pub fn load() -> Self {
+        let path = std::env::var_os("MEMVIZ_HISTFILE")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".gdb-memviz_history"))
+            });
+        let mut entries: Vec<String> = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .map(|text| text.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+        if entries.len() > MAX_ENTRIES {
+            entries.drain(..entries.len() - MAX_ENTRIES);
+        }
+        Self { entries, path }
+    }
+
+    /// Record a command, skipping immediate repeats, and append it to the file.
+    pub fn push(&mut self, line: &str) {
+        if self.entries.last().is_some_and(|l| l == line) {
+            return;
+        }
+        self.entries.push(line.to_string());
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        if let Some(path) = &self.path {
+            let file = OpenOptions::new().create(true).append(true).open(path);
+            if let Ok(mut f) = file {
+                let _ = writeln!(f, "{}", line);
+            }
+        }
+    }
+
+    /// Rewrite the file with only the retained entries, so it does not grow forever.
+    pub fn save(&self) {
+        if let Some(path) = &self.path {
+            let mut text = self.entries.join("\n");
+            text.push('\n');
+            let _ = fs::write(path, text);
+        }
+    }
+
+    /// Expand `!!`, `!<n>` and `!<prefix>` into the recalled command.
+    pub fn expand(&self, input: &str) -> Result<String, String> {
+        let Some(spec) = input.strip_prefix('!') else {
+            return Ok(input.to_string());
+        };
+        let found = if spec == "!" {
+            self.entries.last()
+        } else if let Ok(n) = spec.parse::<usize>() {
+            n.checked_sub(1).and_then(|i| self.entries.get(i))
+        } else {
+            self.entries.iter().rev().find(|e| e.starts_with(spec))
+        };
+        found
+            .cloned()
+            .ok_or_else(|| format!("!{}: event not found", spec))
+    }
+
+    /// `history [n]`: the last `n` (default 20) entries with their numbers.
+    pub fn print(&self, args: &str) {
+        let count = args.parse::<usize>().unwrap_or(20);
+        let start = self.entries.len().saturating_sub(count);
+        for (i, e) in self.entries.iter().enumerate().skip(start) {
+            println!("{:>5}  {}", i + 1, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_recalls_by_number_prefix_and_last() {
+        let mut h = History::default();
+        h.push("mem buf 32");
+        h.push("view node");
+        h.push("view node");
+        assert_eq!(h.entries.len(), 2);
+        assert_eq!(h.expand("!!").unwrap(), "view node");
+        assert_eq!(h.expand("!1").unwrap(), "mem buf 32");
+        assert_eq!(h.expand("!me").unwrap(), "mem buf 32");
+        assert!(h.expand("!9").is_err());
+        assert_eq!(h.expand("locals").unwrap(), "locals");
+    }
+}
@@ -0,0 +1,166 @@
+use crate::mi::{MiSession, Result, StoppedLocation};
+
+/// Commands that move the inferior or open a sub-prompt cannot run from inside a hook.
+const FORBIDDEN_IN_HOOK: &[&str] = &[
+    "next", "n", "step", "s", "continue", "c", "quit", "q", "hexedit", "on-enter", "on-exit",
+    "hooks",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookWhen {
+    Enter,
+    Exit,
+}
+
+impl HookWhen {
+    pub fn label(self) -> &'static str {
+        match self {
+            HookWhen::Enter => "on-enter",
+            HookWhen::Exit => "on-exit",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FuncHook {
+    pub when: HookWhen,
+    pub func: String,
+    pub command: String,
+}
+
+/// Breakpoint-backed hooks that run a memviz command when a function is entered or returns.
+/// One breakpoint is shared by every hook on the same function, since gdb only reports a
+/// single `bkptno` per stop.
+#[derive(Default)]
+pub struct FuncHooks {
+    bps: Vec<(u32, String)>,
+    pub hooks: Vec<FuncHook>,
+    /// Per-function hit counters, used to number log entries.
+    hits: Vec<(String, u64)>,
+}
+
+impl FuncHooks {
+    pub fn add(
+        &mut self,
+        session: &mut MiSession,
+        when: HookWhen,
+        func: &str,
+        command: &str,
+    ) -> Result<()> {
+        let first = command.split_whitespace().next().unwrap_or("");
+        if first.is_empty() {
+            return Err(format!("usage: {} <func> <command>", when.label()).into());
+        }
+        if FORBIDDEN_IN_HOOK.contains(&first) {
+            return Err(format!("'{}' cannot run inside a hook", first).into());
+        }
+        if !self.bps.iter().any(|(_, f)| f == func) {
+            let bp = session.break_insert(func)?;
+            self.bps.push((bp.number, func.to_string()));
+        }
+        self.hooks.push(FuncHook {
+            when,
+            func: func.to_string(),
+            command: command.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Set the hook breakpoints again in a new debugger; the old numbers died with the old one.
+    pub fn reinstall(&mut self, session: &mut MiSession) -> Result<()> {
+        for (number, func) in &mut self.bps {
+            *number = session.break_insert(func)?.number;
+        }
+        Ok(())
+    }
+
+    /// Remove hook `idx` (0-based), dropping the breakpoint when it was the last one.
+    pub fn remove(&mut self, session: &mut MiSession, idx: usize) -> Result<FuncHook> {
+        if idx >= self.hooks.len() {
+            return Err(format!("no hook #{}", idx + 1).into());
+        }
+        let hook = self.hooks.remove(idx);
+        if !self.hooks.iter().any(|h| h.func == hook.func) {
+            if let Some(pos) = self.bps.iter().position(|(_, f)| *f == hook.func) {
+                let (num, _) = self.bps.remove(pos);
+                session.break_delete(num)?;
+            }
+        }
+        Ok(hook)
+    }
+
+    /// Function whose hook breakpoint produced `loc`, if any.
+    pub fn func_for_stop(&self, loc: &StoppedLocation) -> Option<String> {
+        let num = loc.bkptno?;
+        self.bps
+            .iter()
+            .find(|(n, _)| *n == num)
+            .map(|(_, f)| f.clone())
+    }
+
+    pub fn commands(&self, func: &str, when: HookWhen) -> Vec<String> {
+        self.hooks
+            .iter()
+            .filter(|h| h.func == func && h.when == when)
+            .map(|h| h.command.clone())
+            .collect()
+    }
+
+    /// Bump and return the hit counter for `func`.
+    pub fn record_hit(&mut self, func: &str) -> u64 {
+        match self.hits.iter_mut().find(|(f, _)| f == func) {
+            Some((_, n)) => {
+                *n += 1;
+                *n
+            }
+            None => {
+                self.hits.push((func.to_string(), 1));
+                1
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_filter_by_function_and_kind() {
+        let mut hooks = FuncHooks::default();
+        hooks.bps.push((3, "process_packet".to_string()));
+        for (when, cmd) in [
+            (HookWhen::Enter, "mem buf 32"),
+            (HookWhen::Exit, "locals"),
+            (HookWhen::Enter, "view hdr"),
+        ] {
+            hooks.hooks.push(FuncHook {
+                when,
+                func: "process_packet".to_string(),
+                command: cmd.to_string(),
+            });
+        }
+        assert_eq!(
+            hooks.commands("process_packet", HookWhen::Enter),
+            vec!["mem buf 32", "view hdr"]
+        );
+        assert_eq!(
+            hooks.commands("other", HookWhen::Exit),
+            Vec::<String>::new()
+        );
+        let loc = StoppedLocation {
+            func: None,
+            file: None,
+            line: None,
+            reason: Some("breakpoint-hit".to_string()),
+            arch: None,
+            bkptno: Some(3),
+            return_value: None,
+            exit: None,
+            catch: None,
+        };
+        assert_eq!(hooks.func_for_stop(&loc).as_deref(), Some("process_packet"));
+        assert_eq!(hooks.record_hit("process_packet"), 1);
+        assert_eq!(hooks.record_hit("process_packet"), 2);
+    }
+}
@@ -0,0 +1,378 @@
+// `--json`: structured output for commands other tools consume. Each command prints one
+// JSON object per line; errors stay on stderr as text. Addresses are hex strings because
+// 64-bit values do not survive JSON number parsers.
+use super::args::FrameArgs;
+use super::stack::{CanarySlot, FrameView, SavedSlot, StackUsage, UsagePeak};
+use crate::heap::{ArenaStats, HeapBins, MallocChunk, MmapStats};
+use crate::mi::{CStringRead, Endian, GlobalVar, LocalVar, MemoryDump};
+use crate::vm::{LabelUsage, ModuleBase, StackHeadroom, VmRegion};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(on: bool) {
+    ENABLED.store(on, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// One hop of `follow`. `value` is the dereferenced struct (none for NULL); `error` is set
+/// when the struct or its link field could not be read.
+pub struct FollowStep {
+    pub expr: String,
+    pub addr: u64,
+    pub value: Option<String>,
+    pub error: Option<String>,
+    /// `char *` fields of the node, read as C strings.
+    pub strings: Vec<(String, CStringRead)>,
+}
+
+pub fn emit(value: Value) {
+    println!("{}", value);
+}
+
+/// A Python bridge result with the command name added: `{"command": ..., "result": ...}`.
+pub fn tagged(command: &str, result: Value) -> Value {
+    json!({ "command": command, "result": result })
+}
+
+fn hex(addr: u64) -> String {
+    format!("0x{:x}", addr)
+}
+
+fn region_of(regions: &[VmRegion], addr: u64) -> Value {
+    regions
+        .iter()
+        .find(|r| r.contains(addr))
+        .map_or(Value::Null, |r| r.label.name().into())
+}
+
+pub fn locals(locals: &[LocalVar], changed: &HashSet<String>) -> Value {
+    let vars: Vec<Value> = locals
+        .iter()
+        .map(|l| {
+            json!({
+                "name": l.name,
+                "type": l.ty,
+                "value": l.value,
+                "changed": changed.contains(&l.name),
+            })
+        })
+        .collect();
+    json!({ "command": "locals", "locals": vars })
+}
+
+pub fn globals(globals: &[GlobalVar], regions: &[VmRegion], changed: &HashSet<String>) -> Value {
+    let vars: Vec<Value> = globals
+        .iter()
+        .map(|g| {
+            json!({
+                "name": g.name,
+                "type": g.type_name,
+                "value": g.value,
+                "address": hex(g.address),
+                "file": g.file,
+                "region": region_of(regions, g.address),
+                "changed": changed.contains(&g.name),
+            })
+        })
+        .collect();
+    json!({ "command": "globals", "globals": vars })
+}
+
+pub fn mem(dump: &MemoryDump) -> Value {
+    // Unreadable bytes become null rather than a made-up value.
+    let bytes: Vec<Value> = dump
+        .bytes
+        .iter()
+        .enumerate()
+        .map(|(i, b)| {
+            if dump.unreadable.get(i).copied().unwrap_or(false) {
+                Value::Null
+            } else {
+                (*b).into()
+            }
+        })
+        .collect();
+    let endian = match dump.endian {
+        Endian::Little => "little",
+        Endian::Big => "big",
+        Endian::Unknown => "unknown",
+    };
+    json!({
+        "command": "mem",
+        "expr": dump.expr,
+        "type": dump.ty,
+        "address": dump.address,
+        "length": dump.bytes.len(),
+        "truncated_from": dump.truncated_from,
+        "word_size": dump.word_size,
+        "endian": endian,
+        "arch": dump.arch,
+        "bytes": bytes,
+    })
+}
+
+pub fn vm(regions: &[VmRegion], stack: Option<&StackHeadroom>) -> Value {
+    let items: Vec<Value> = regions
+        .iter()
+        .map(|r| {
+            json!({
+                "start": hex(r.start),
+                "end": hex(r.end),
+                "size": r.size(),
+                "perms": r.perms,
+                "path": r.pathname,
+                "label": r.label.name(),
+            })
+        })
+        .collect();
+    let stack = stack.map(|h| {
+        json!({
+            "sp": hex(h.sp),
+            "floor": hex(h.floor),
+            "top": hex(h.top),
+            "left": h.left(),
+            "total": h.total(),
+            "guarded": h.guarded,
+            "low": h.is_low(),
+        })
+    });
+    json!({ "command": "vm", "regions": items, "stack": stack })
+}
+
+pub fn vm_stats(totals: &[LabelUsage]) -> Value {
+    let items: Vec<Value> = totals
+        .iter()
+        .map(|t| {
+            json!({
+                "label": t.label.name(),
+                "regions": t.regions,
+                "size": t.size,
+                "rss": t.usage.rss,
+                "pss": t.usage.pss,
+                "dirty": t.usage.dirty,
+                "swap": t.usage.swap,
+            })
+        })
+        .collect();
+    json!({ "command": "vm stats", "labels": items })
+}
+
+pub fn vm_bases(bases: &[ModuleBase]) -> Value {
+    let items: Vec<Value> = bases
+        .iter()
+        .map(|b| {
+            json!({
+                "path": b.path,
+                "start": hex(b.start),
+                "link_base": hex(b.link_base),
+                "bias": hex(b.bias()),
+            })
+        })
+        .collect();
+    json!({ "command": "vm bases", "modules": items })
+}
+
+pub fn heap_arenas(arenas: &[ArenaStats], mmapped: Option<MmapStats>) -> Value {
+    let items: Vec<Value> = arenas
+        .iter()
+        .map(|a| {
+            json!({
+                "address": hex(a.address),
+                "main": a.is_main,
+                "system_mem": a.system_mem,
+                "max_system_mem": a.max_system_mem,
+                "allocated": a.allocated(),
+                "free": a.free(),
+                "top": hex(a.top),
+                "top_size": a.top_size,
+                "top_end": hex(a.top_end()),
+                "bins": { "chunks": a.bin_chunks, "bytes": a.bin_bytes },
+                "fastbins": { "chunks": a.fast_chunks, "bytes": a.fast_bytes },
+                "truncated": a.truncated,
+            })
+        })
+        .collect();
+    let mmapped = mmapped.map(|m| json!({ "chunks": m.chunks, "bytes": m.bytes }));
+    json!({ "command": "heap arenas", "arenas": items, "mmapped": mmapped })
+}
+
+pub fn stack_frame(view: &FrameView) -> Value {
+    let slot = |s: &SavedSlot| json!({ "register": s.register, "slot": hex(s.slot), "value": s.value.map(hex) });
+    json!({
+        "command": "stack",
+        "level": view.level,
+        "func": view.func,
+        "cfa": view.cfa.map(hex),
+        "from_fp_chain": view.from_fp_chain,
+        "return": view.ret.as_ref().map(slot),
+        "return_symbol": view.ret_symbol.as_ref().map(|s| json!({ "name": s.name, "offset": s.offset })),
+        "saved_fp": view.saved_fp.as_ref().map(slot),
+        "saved": view.others.iter().map(slot).collect::<Vec<_>>(),
+        "stack_args": view.stack_args.iter().map(|(name, at)| json!({ "name": name, "slot": hex(*at) })).collect::<Vec<_>>(),
+        "issues": view.issues,
+    })
+}
+
+pub fn frame_args(args: &FrameArgs) -> Value {
+    let items: Vec<Value> = args
+        .args
+        .iter()
+        .map(|p| {
+            let stack = p.home.as_ref().and_then(|h| h.stack_offset());
+            json!({
+                "name": p.arg.name,
+                "type": p.arg.ty,
+                "value": p.arg.value,
+                "size": p.size,
+                "home": p.home.as_ref().map(|h| h.describe()),
+                "slot": stack.zip(args.cfa).map(|(offset, cfa)| hex(cfa + offset)),
+            })
+        })
+        .collect();
+    json!({
+        "command": "args",
+        "level": args.level,
+        "func": args.func,
+        "abi": args.abi,
+        "cfa": args.cfa.map(hex),
+        "args": items,
+    })
+}
+
+pub fn stack_usage(usage: &StackUsage) -> Value {
+    let peak = |p: &UsagePeak| json!({ "bytes": p.bytes, "sp": hex(p.sp), "func": p.func, "stop": p.stop });
+    let funcs: Vec<Value> = usage
+        .functions()
+        .into_iter()
+        .map(|(func, bytes)| json!({ "func": func, "bytes": bytes }))
+        .collect();
+    json!({
+        "command": "stack usage",
+        "samples": usage.samples,
+        "from_map": usage.from_map,
+        "peak": usage.peak.as_ref().map(peak),
+        "functions": funcs,
+        "previous_run": usage.previous.as_ref().map(peak),
+    })
+}
+
+pub fn stack_canary(level: u32, slot: Option<&CanarySlot>, reference: u64) -> Value {
+    json!({
+        "command": "stack canary",
+        "level": level,
+        "reference": hex(reference),
+        "slot": slot.map(|s| json!({ "func": s.func, "cfa": hex(s.cfa), "slot": hex(s.slot), "value": hex(s.value) })),
+    })
+}
+
+pub fn heap_bins(bins: &HeapBins, word: u64) -> Value {
+    let lists: Vec<Value> = bins
+        .lists
+        .iter()
+        .map(|b| {
+            let chunks: Vec<Value> = b
+                .list
+                .chunks
+                .iter()
+                .map(|(chunk, size)| json!({ "chunk": hex(*chunk), "user": hex(chunk + 2 * word), "size": size }))
+                .collect();
+            json!({
+                "kind": b.kind,
+                "index": b.index,
+                "count": b.count,
+                "chunks": chunks,
+                "truncated": b.list.truncated,
+            })
+        })
+        .collect();
+    json!({
+        "command": "heap bins",
+        "arena": hex(bins.arena),
+        "tcache": bins.tcache.map(hex),
+        "lists": lists,
+    })
+}
+
+pub fn heap_chunk(chunk: &MallocChunk) -> Value {
+    let hex_bytes = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+    };
+    json!({
+        "command": "heap chunk",
+        "chunk": hex(chunk.addr),
+        "user": hex(chunk.user_addr()),
+        "prev_size": chunk.prev_size,
+        "size": chunk.size(),
+        "flags": chunk.flags(),
+        "usable": chunk.usable(),
+        "in_use": chunk.in_use(),
+        "next": hex(chunk.next_addr()),
+        "next_size_field": chunk.next_size_field.map(hex),
+        "bytes": hex_bytes(&chunk.bytes),
+    })
+}
+
+pub fn follow(symbol: &str, pointee_type: &str, link: &str, steps: &[FollowStep]) -> Value {
+    let items: Vec<Value> = steps
+        .iter()
+        .map(|s| {
+            json!({
+                "expr": s.expr,
+                "address": hex(s.addr),
+                "value": s.value,
+                "error": s.error,
+                "strings": s
+                    .strings
+                    .iter()
+                    .map(|(field, c)| {
+                        json!({ "field": field, "text": c.text, "truncated": c.truncated })
+                    })
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    json!({
+        "command": "follow",
+        "symbol": symbol,
+        "type": pointee_type,
+        "link": link,
+        "steps": items,
+        // Whether the chain reached NULL within the depth limit.
+        "terminated": steps.last().is_some_and(|s| s.addr == 0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mem_reports_unreadable_bytes_as_null() {
+        let dump = MemoryDump {
+            expr: "buf".into(),
+            ty: Some("char [3]".into()),
+            address: "0x1000".into(),
+            bytes: vec![0x41, 0, 0x43],
+            word_size: 8,
+            requested: 3,
+            endian: Endian::Little,
+            arch: None,
+            truncated_from: None,
+            unreadable: vec![false, true, false],
+            previous: None,
+        };
+        let v = mem(&dump);
+        assert_eq!(v["bytes"], json!([65, null, 67]));
+        assert_eq!(v["endian"], "little");
+        assert_eq!(v["length"], 3);
+    }
+}
@@ -0,0 +1,287 @@
+// Process map snapshots at consecutive stops, so `vm diff` shows what mmap, munmap, brk
+// and stack growth did in between; and `vm track`, which catches those syscalls to log
+// every change with the stack that made it.
+use super::addr;
+use super::printers::{print_map_event, print_region_changes};
+use crate::mi::{FrameInfo, MiSession, Result, StoppedLocation};
+use crate::vm::{self, diff_regions, RegionChange, VmRegion};
+use std::collections::VecDeque;
+
+/// Changes kept by `vm track`; the oldest are dropped first.
+const MAX_EVENTS: usize = 512;
+/// Frames kept with each logged change.
+const BACKTRACE_FRAMES: u32 = 8;
+/// Catchpoint for the syscalls that create, remove or move mappings.
+const MAP_SYSCALLS: &str = "syscall brk mmap munmap mremap";
+
+#[derive(Default)]
+pub struct MapHistory {
+    /// Snapshot once `vm diff` has been used, so stops do not pay for the map otherwise.
+    pub active: bool,
+    /// Print the changes after every stop that has some (`vm diff on`).
+    pub auto_show: bool,
+    // (pid, regions): a new process is not compared with the old one.
+    prev: Option<(u32, Vec<VmRegion>)>,
+    curr: Option<(u32, Vec<VmRegion>)>,
+    curr_stop: Option<u64>,
+}
+
+impl MapHistory {
+    /// Take a snapshot for `stop` unless one was already taken.
+    pub fn observe(&mut self, session: &mut MiSession, stop: u64) -> Result<()> {
+        if self.curr_stop == Some(stop) {
+            return Ok(());
+        }
+        let pid = session.inferior_pid()?;
+        let regions = addr::process_regions(session, pid)
+            .map_err(|e| format!("failed to read the map of process {}: {}", pid, e))?;
+        self.prev = self.curr.replace((pid, regions));
+        self.curr_stop = Some(stop);
+        Ok(())
+    }
+
+    /// Changes since the previous snapshot; `quiet` prints nothing when there are none or
+    /// there is nothing to compare with (after every stop).
+    pub fn print(&self, quiet: bool) {
+        let (Some((old_pid, old)), Some((pid, new))) = (&self.prev, &self.curr) else {
+            if !quiet {
+                println!("vm diff: map saved; the next stop is compared with this one");
+            }
+            return;
+        };
+        if old_pid != pid {
+            if !quiet {
+                println!(
+                    "vm diff: new process {}; the next stop is compared with this one",
+                    pid
+                );
+            }
+            return;
+        }
+        let changes = diff_regions(old, new);
+        if changes.is_empty() {
+            if !quiet {
+                println!("vm diff: no region changes since the previous stop");
+            }
+            return;
+        }
+        print_region_changes(&changes);
+    }
+}
+
+/// One change of the map logged by `vm track`.
+pub struct MapEvent {
+    /// Syscall that made it; `None` when it was only noticed at a stop (after stepping, or
+    /// stack growth, which takes no syscall).
+    pub syscall: Option<String>,
+    pub changes: Vec<RegionChange>,
+    /// Innermost frames when the syscall was entered, or at the stop.
+    pub backtrace: Vec<FrameInfo>,
+    /// User-visible stops before the change.
+    pub after_stop: u64,
+}
+
+/// `vm track`: an internal catchpoint on brk, mmap, munmap and mremap. The catchpoint is
+/// armed only while the program runs freely; a catch in the middle of `next` would end the
+/// step inside libc, so changes made while stepping are logged at the stop instead.
+#[derive(Default)]
+pub struct MapTracker {
+    catchpoint: Option<u32>,
+    armed: bool,
+    // (pid, regions) as of the last logged change or stop.
+    snapshot: Option<(u32, Vec<VmRegion>)>,
+    // Stack at syscall entry, logged with the changes seen at its return.
+    entry: Option<Vec<FrameInfo>>,
+    pub events: VecDeque<MapEvent>,
+}
+
+impl MapTracker {
+    pub fn is_tracking(&self) -> bool {
+        self.catchpoint.is_some()
+    }
+
+    /// Install the catchpoint (idempotent) and take the first snapshot when the program runs.
+    pub fn enable(&mut self, session: &mut MiSession, stop: u64) -> Result<()> {
+        if self.is_tracking() {
+            return Ok(());
+        }
+        let bp = session.insert_catchpoint(MAP_SYSCALLS)?;
+        self.catchpoint = Some(bp.number);
+        self.armed = true;
+        // Not started yet: the first catch takes the snapshot.
+        let _ = self.observe(session, stop);
+        Ok(())
+    }
+
+    /// Remove the catchpoint; the log is kept for `vm history`.
+    pub fn disable(&mut self, session: &mut MiSession) -> Result<()> {
+        if let Some(number) = self.catchpoint.take() {
+            session.break_delete(number)?;
+        }
+        self.snapshot = None;
+        self.entry = None;
+        Ok(())
+    }
+
+    /// Set the catchpoint up again in a relaunched debugger.
+    pub fn reinstall(&mut self, session: &mut MiSession, stop: u64) -> Result<()> {
+        if self.catchpoint.take().is_none() {
+            return Ok(());
+        }
+        self.snapshot = None;
+        self.entry = None;
+        self.enable(session, stop)
+    }
+
+    /// Catch the syscalls while the program runs freely (`on`), not while it steps.
+    pub fn arm(&mut self, session: &mut MiSession, on: bool) {
+        let Some(number) = self.catchpoint else {
+            return;
+        };
+        if self.armed != on && session.break_enable(number, on).is_ok() {
+            self.armed = on;
+        }
+    }
+
+    /// Whether `loc` is a stop at our catchpoint.
+    pub fn owns_stop(&self, loc: &StoppedLocation) -> bool {
+        self.catchpoint.is_some() && loc.bkptno == self.catchpoint
+    }
+
+    /// Service a catch: keep the caller's stack at entry, log the change at return.
+    pub fn handle_stop(
+        &mut self,
+        session: &mut MiSession,
+        loc: &StoppedLocation,
+        stop: u64,
+    ) -> Result<()> {
+        if loc.reason.as_deref() == Some("syscall-entry") {
+            self.entry = Some(backtrace(session));
+            return Ok(());
+        }
+        // "syscall brk (return)"
+        let syscall = loc
+            .catch
+            .as_deref()
+            .and_then(|c| c.strip_prefix("syscall "))
+            .and_then(|c| c.split_whitespace().next())
+            .unwrap_or("syscall")
+            .to_string();
+        let entry = self.entry.take();
+        self.log(session, Some(syscall), entry, stop)
+    }
+
+    /// At a user stop: log what changed since the last catch.
+    pub fn observe(&mut self, session: &mut MiSession, stop: u64) -> Result<()> {
+        self.log(session, None, None, stop)
+    }
+
+    fn log(
+        &mut self,
+        session: &mut MiSession,
+        syscall: Option<String>,
+        entry: Option<Vec<FrameInfo>>,
+        stop: u64,
+    ) -> Result<()> {
+        let pid = session.inferior_pid()?;
+        let regions = vm::read_process_map(pid, false)
+            .map_err(|e| format!("failed to read the map of process {}: {}", pid, e))?;
+        let prev = self.snapshot.replace((pid, regions));
+        let (Some((old_pid, old)), Some((_, new))) = (prev, &self.snapshot) else {
+            return Ok(());
+        };
+        if old_pid != pid {
+            return Ok(());
+        }
+        let changes = diff_regions(&old, new);
+        if changes.is_empty() {
+            return Ok(());
+        }
+        if self.events.len() == MAX_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(MapEvent {
+            syscall,
+            changes,
+            backtrace: entry.unwrap_or_else(|| backtrace(session)),
+            after_stop: stop,
+        });
+        Ok(())
+    }
+
+    /// `vm history [n]`: the last `n` logged changes (all by default), oldest first.
+    pub fn print_history(&self, last: Option<usize>) {
+        if self.events.is_empty() {
+            if self.is_tracking() {
+                println!("vm history: no region changes logged yet");
+            } else {
+                println!("vm history: nothing logged (start with 'vm track on')");
+            }
+            return;
+        }
+        let skip = self.events.len() - last.unwrap_or(self.events.len()).min(self.events.len());
+        for (i, event) in self.events.iter().enumerate().skip(skip) {
+            print_map_event(i + 1, event);
+        }
+    }
+}
+
+fn backtrace(session: &mut MiSession) -> Vec<FrameInfo> {
+    session
+        .list_top_frames(BACKTRACE_FRAMES)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mi::backend::Gdb;
+    use crate::mi::parser::parse_stopped;
+    use std::io;
+
+    #[test]
+    fn catches_log_map_changes_with_the_entry_backtrace() {
+        // This test process stands in for the program: its own map is read.
+        let pid = std::process::id();
+        let output = format!(
+            concat!(
+                "~\"process {0}\\n\"\n1^done\n(gdb)\n",
+                "2^done,stack=[frame={{level=\"0\",func=\"mmap64\"}},frame={{level=\"1\",func=\"main\",file=\"big.c\",line=\"7\"}}]\n(gdb)\n",
+                "~\"process {0}\\n\"\n3^done\n(gdb)\n",
+            ),
+            pid
+        );
+        let mut session = MiSession::replay(
+            Box::new(Gdb),
+            Box::new(io::sink()),
+            Box::new(io::Cursor::new(output.into_bytes())),
+            "prog",
+            &[],
+            false,
+        );
+        let mut tracker = MapTracker {
+            catchpoint: Some(5),
+            ..MapTracker::default()
+        };
+        tracker.observe(&mut session, 1).unwrap();
+        assert!(tracker.events.is_empty());
+        let entry = parse_stopped(
+            r#"*stopped,reason="syscall-entry",bkptno="5",syscall-number="9",syscall-name="mmap",frame={func="mmap64"}"#,
+        );
+        assert!(tracker.owns_stop(&entry));
+        tracker.handle_stop(&mut session, &entry, 1).unwrap();
+        // Far above the mmap threshold, so the allocation is a mapping of its own.
+        let block = vec![1u8; 64 << 20];
+        let ret = parse_stopped(
+            r#"*stopped,reason="syscall-return",bkptno="5",syscall-number="9",syscall-name="mmap",frame={func="mmap64"}"#,
+        );
+        tracker.handle_stop(&mut session, &ret, 1).unwrap();
+        drop(block);
+        assert_eq!(tracker.events.len(), 1);
+        let event = &tracker.events[0];
+        assert_eq!(event.syscall.as_deref(), Some("mmap"));
+        assert!(!event.changes.is_empty());
+        assert_eq!(event.backtrace[1].func.as_deref(), Some("main"));
+        assert_eq!(event.after_stop, 1);
+    }
+}
@@ -1,19 +1,43 @@
-use crate::mi::{BreakpointInfo, Endian, GlobalVar, LocalVar, MemoryDump, StoppedLocation};
-use crate::types::{normalize_pointer_type, normalize_type_name};
-use crate::vm::{classify_addr, VmLabel, VmRegion};
+use super::args::FrameArgs;
+use super::maps::MapEvent;
+use super::stack::{CanaryAlert, CanarySlot, FrameView, SavedSlot, StackUsage};
+use super::style::{self, Color};
+use super::theme::theme;
+use crate::heap::{
+    chunk_flags, Allocation, ArenaStats, CanaryReport, CanarySide, HeapBins, MallocChunk,
+    MmapStats, PREV_INUSE, SIZE_BITS,
+};
+use crate::mi::{
+    AddressSymbol, BreakpointInfo, CStringRead, Endian, FrameInfo, GlobalVar, LocalVar, MemoryDump,
+    StoppedLocation,
+};
+use crate::symbols::{IndexStats, SymbolIndexMode};
+use crate::types::{
+    byte_owners, mark_chunk_headers, normalize_pointer_type, normalize_type_name, ByteOwner,
+    TypeLayout,
+};
+use crate::vm::{
+    classify_addr, AccessIssue, LabelUsage, MapBlock, ModuleBase, RegionChange, RegionUsage,
+    StackHeadroom, VmLabel, VmRegion,
+};
 use regex::Regex;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
 
-pub fn print_locals(locals: &[LocalVar]) {
+/// `changed` names are painted as changed since the previous stop.
+pub fn print_locals(locals: &[LocalVar], changed: &HashSet<String>) {
     if locals.is_empty() {
         println!("no locals");
         return;
     }
     for (i, var) in locals.iter().enumerate() {
-        let value = var
-            .value
-            .as_ref()
-            .map(|v| prettify_value(v))
-            .unwrap_or_else(|| "<unavailable>".to_string());
+        let value = match var.value.as_ref() {
+            Some(v) if changed.contains(&var.name) => {
+                style::paint(&prettify_value(v), theme().changed)
+            }
+            Some(v) => paint_value(&prettify_value(v), style::enabled()),
+            None => style::paint("<unavailable>", theme().unavailable),
+        };
         let prefix = match var.ty.as_deref() {
             Some(ty) => format!("{} {}", normalize_type_name(ty), var.name),
             None => var.name.clone(),
@@ -22,10 +46,14 @@ pub fn print_locals(locals: &[LocalVar]) {
     }
 }
 
-pub fn print_memory_full(dump: &MemoryDump) {
+pub fn print_memory_full(dump: &MemoryDump, layout: Option<&TypeLayout>, chunks: &[u64]) {
     let ty = dump.ty.as_deref().unwrap_or("unknown");
-    println!("symbol: {} ({})", dump.expr, normalize_type_name(ty));
-    println!("address: {}", dump.address);
+    println!(
+        "symbol: {} ({})",
+        style::paint(&dump.expr, theme().header),
+        normalize_type_name(ty)
+    );
+    println!("address: {}", style::paint(&dump.address, theme().pointer));
     let size = dump.bytes.len();
     let words = (size + dump.word_size - 1) / dump.word_size.max(1);
     println!(
@@ -41,19 +69,41 @@ pub fn print_memory_full(dump: &MemoryDump) {
     println!("layout: {} (arch={})", endian_str, arch_str);
     if let Some(orig) = dump.truncated_from {
         if orig > size {
-            println!("(truncated to {} bytes from {})", size, orig);
+            println!(
+                "(truncated to {} bytes from {}; raise the cap with --max-dump <bytes>)",
+                size, orig
+            );
         }
     }
     if dump.bytes.is_empty() {
         println!("bytes(0): (no bytes read)");
         return;
     }
+    let unreadable = dump.unreadable.iter().filter(|u| **u).count();
+    if unreadable > 0 {
+        println!(
+            "({} of {} bytes unreadable, shown as ??)",
+            unreadable,
+            dump.bytes.len()
+        );
+    }
+    let changed = changed_mask(dump).iter().filter(|c| **c).count();
+    if changed > 0 {
+        println!(
+            "({} byte(s) changed since the previous dump of this address)",
+            changed
+        );
+    }
     println!();
     println!("raw:");
-    print_memory_body(dump);
+    print_memory_annotated(dump, layout, chunks);
 }
 
 pub fn print_breakpoint(bp: &BreakpointInfo) {
+    if let Some(event) = &bp.catch {
+        println!("catchpoint {} ({})", bp.number, event);
+        return;
+    }
     let loc = match (&bp.file, &bp.line, &bp.func) {
         (Some(f), Some(l), _) => format!("{}:{}", f, l),
         (_, _, Some(func)) => func.clone(),
@@ -62,41 +112,843 @@ pub fn print_breakpoint(bp: &BreakpointInfo) {
     println!("breakpoint {} at {}", bp.number, loc);
 }
 
+/// `breakpoints`: the session's user breakpoint table, with the expressions logged per hit.
+pub fn print_breakpoint_table(bps: &[BreakpointInfo], logs: &HashMap<u32, Vec<String>>) {
+    if bps.is_empty() {
+        println!("no breakpoints");
+        return;
+    }
+    println!(
+        "{}",
+        style::paint("  num  enb  hits  location", theme().header)
+    );
+    for bp in bps {
+        let loc = match (&bp.catch, &bp.file, &bp.line, &bp.func) {
+            (Some(event), ..) => format!("catch {}", event),
+            (_, Some(f), Some(l), Some(func)) => format!("{}:{} ({})", f, l, func),
+            (_, Some(f), Some(l), None) => format!("{}:{}", f, l),
+            (_, _, _, Some(func)) => func.clone(),
+            _ => "<unknown>".to_string(),
+        };
+        let (mark, loc) = if bp.enabled {
+            ("y", loc)
+        } else {
+            ("n", style::paint(&loc, theme().unavailable))
+        };
+        println!("  {:>3}  {:^3}  {:>4}  {}", bp.number, mark, bp.hits, loc);
+        if let Some(exprs) = logs.get(&bp.number) {
+            println!("                  log: {}", exprs.join(", "));
+        }
+    }
+}
+
+pub fn print_heap_blocks(blocks: &[&Allocation]) {
+    if blocks.is_empty() {
+        println!("no tracked heap blocks");
+        return;
+    }
+    println!("{}", style::paint("tracked heap blocks:", theme().header));
+    for block in blocks {
+        let guard = if block.guard > 0 {
+            format!("guard {}", block.guard)
+        } else {
+            "no guard".to_string()
+        };
+        let mut line = format!(
+            "  {}  size={:<8} {}",
+            style::paint(&format!("0x{:016x}", block.user_addr), theme().pointer),
+            block.size,
+            guard
+        );
+        if block.corrupted {
+            line.push_str(&format!("  {}", style::paint("CORRUPTED", theme().changed)));
+        }
+        if let Some(site) = block.site() {
+            line.push_str(&format!("  allocated at {}", site));
+        }
+        println!("{}", line);
+    }
+}
+
+/// `heap arenas`: each glibc arena's memory split into allocated and free, and its top; then
+/// the chunks malloc mapped outside the arenas.
+pub fn print_heap_arenas(arenas: &[ArenaStats], mmapped: Option<MmapStats>) {
+    println!(
+        "{} {}",
+        style::paint("glibc arenas:", theme().header),
+        arenas.len()
+    );
+    for a in arenas {
+        let name = if a.is_main { "main_arena" } else { "arena" };
+        println!(
+            "  {} {}  system {} (peak {})",
+            name,
+            style::paint(&format!("0x{:x}", a.address), theme().pointer),
+            format_size(a.system_mem),
+            format_size(a.max_system_mem)
+        );
+        println!(
+            "    allocated {}, free {}: top {}, bins {} in {} chunk(s), fastbins {} in {} chunk(s)",
+            format_size(a.allocated()),
+            format_size(a.free()),
+            format_size(a.top_size),
+            format_size(a.bin_bytes),
+            a.bin_chunks,
+            format_size(a.fast_bytes),
+            a.fast_chunks
+        );
+        if a.top != 0 {
+            println!(
+                "    top of heap 0x{:x} (top chunk 0x{:x}..0x{:x})",
+                a.top_end(),
+                a.top,
+                a.top_end()
+            );
+        }
+        if a.truncated {
+            println!(
+                "    {}",
+                style::paint(
+                    "a free list was cut short; free bytes are a lower bound",
+                    theme().changed
+                )
+            );
+        }
+    }
+    if arenas.len() > 1 {
+        let system: u64 = arenas.iter().map(|a| a.system_mem).sum();
+        let free: u64 = arenas.iter().map(ArenaStats::free).sum();
+        println!(
+            "  total: system {}, allocated {}, free {}",
+            format_size(system),
+            format_size(system.saturating_sub(free)),
+            format_size(free)
+        );
+    }
+    if let Some(m) = mmapped {
+        println!("  mmapped chunks: {} ({})", m.chunks, format_size(m.bytes));
+    }
+}
+
+/// `heap bins`: each non-empty free list in order, as the user pointers its chunks held.
+pub fn print_heap_bins(bins: &HeapBins, word: u64) {
+    let tcache = match bins.tcache {
+        Some(t) => format!("tcache 0x{:x} (selected thread)", t),
+        None => "no tcache".to_string(),
+    };
+    println!(
+        "{} arena {}, {}",
+        style::paint("free lists:", theme().header),
+        style::paint(&format!("0x{:x}", bins.arena), theme().pointer),
+        tcache
+    );
+    if bins.lists.is_empty() {
+        println!("  no free chunks");
+        return;
+    }
+    for bin in &bins.lists {
+        let name = match bin.kind {
+            "unsorted" => "unsorted".to_string(),
+            kind => format!("{}[{}]", kind, bin.index),
+        };
+        let mut notes = Vec::new();
+        if let Some(count) = bin.count.filter(|c| *c != bin.list.chunks.len() as u64) {
+            notes.push(format!("count says {}", count));
+        }
+        if bin.list.truncated {
+            notes.push("cut short".to_string());
+        }
+        let mut entries: Vec<String> = bin
+            .list
+            .chunks
+            .iter()
+            .take(BIN_ENTRIES_SHOWN)
+            .map(|(chunk, size)| {
+                format!(
+                    "{} (0x{:x})",
+                    style::paint(&format!("0x{:x}", chunk + 2 * word), theme().pointer),
+                    size
+                )
+            })
+            .collect();
+        let more = bin.list.chunks.len().saturating_sub(BIN_ENTRIES_SHOWN);
+        if more > 0 {
+            entries.push(format!("... {} more", more));
+        }
+        let notes = if notes.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", notes.join(", "))
+        };
+        println!(
+            "  {:<12} {} chunk(s){}: {}",
+            name,
+            bin.list.chunks.len(),
+            notes,
+            entries.join(" -> ")
+        );
+    }
+}
+
+/// `heap chunk`: the chunk's header decoded, then a dump from the header through the next
+/// chunk's header with the boundaries marked.
+pub fn print_malloc_chunk(chunk: &MallocChunk) {
+    let w = chunk.word as usize;
+    let state = match chunk.in_use() {
+        Some(true) => "in use",
+        Some(false) => "free",
+        None => "state unknown",
+    };
+    println!(
+        "{} {} (user pointer {})",
+        style::paint("chunk", theme().header),
+        style::paint(&format!("0x{:x}", chunk.addr), theme().pointer),
+        style::paint(&format!("0x{:x}", chunk.user_addr()), theme().pointer)
+    );
+    let flags = chunk.flags();
+    println!(
+        "  size 0x{:x} ({} bytes), flags {}; {} usable bytes; {}",
+        chunk.size(),
+        chunk.size(),
+        if flags.is_empty() {
+            "none".to_string()
+        } else {
+            flags.join("|")
+        },
+        chunk.usable(),
+        state
+    );
+    let size_note = |size_field: u64| {
+        let mut note = format!("size 0x{:x}", size_field & !SIZE_BITS);
+        for flag in chunk_flags(size_field) {
+            note.push_str(" | ");
+            note.push_str(flag);
+        }
+        note
+    };
+    let prev_note = if chunk.size_field & PREV_INUSE != 0 {
+        "prev_size (previous chunk's data)".to_string()
+    } else {
+        format!("prev_size 0x{:x} (previous chunk is free)", chunk.prev_size)
+    };
+    let mut rows = chunk.bytes.chunks(w);
+    print_chunk_row(chunk.addr, rows.next().unwrap_or(&[]), &prev_note);
+    print_chunk_row(
+        chunk.addr + w as u64,
+        rows.next().unwrap_or(&[]),
+        &size_note(chunk.size_field),
+    );
+    print_chunk_marker(&format!("user data 0x{:x}", chunk.user_addr()));
+    let mut addr = chunk.user_addr();
+    for row in rows {
+        print_chunk_row(addr, row, "");
+        addr += row.len() as u64;
+    }
+    if addr < chunk.next_addr() {
+        println!("  ... {} more bytes", chunk.next_addr() - addr);
+    }
+    if let (Some(next), Some(size_field)) = (&chunk.next_header, chunk.next_size_field) {
+        print_chunk_marker(&format!("next chunk 0x{:x}", chunk.next_addr()));
+        let prev_note = if chunk.in_use() == Some(true) {
+            "prev_size (this chunk's data)"
+        } else {
+            "prev_size (this chunk's size)"
+        };
+        print_chunk_row(chunk.next_addr(), &next[..w.min(next.len())], prev_note);
+        print_chunk_row(
+            chunk.next_addr() + w as u64,
+            next.get(w..).unwrap_or(&[]),
+            &size_note(size_field),
+        );
+    }
+}
+
+fn print_chunk_marker(text: &str) {
+    println!(
+        "  {}",
+        style::paint(&format!("── {} ──", text), theme().header)
+    );
+}
+
+fn print_chunk_row(addr: u64, bytes: &[u8], note: &str) {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    let line = format!(
+        "  0x{:012x}: {} | {}",
+        addr,
+        hex.join(" "),
+        ascii_repr(bytes)
+    );
+    if note.is_empty() {
+        println!("{}", line);
+    } else {
+        println!("{}  {}", line, style::paint(note, theme().padding));
+    }
+}
+
+/// Render a type tree from the Python bridge: one line per field, pointer and array targets
+/// indented under their owner.
+pub fn print_type_tree(tree: &Value) {
+    let mut lines = Vec::new();
+    type_tree_lines(tree, "", 0, &mut lines);
+    for line in lines {
+        println!("{}", line);
+    }
+}
+
+fn type_tree_lines(node: &Value, label: &str, indent: usize, out: &mut Vec<String>) {
+    let name = node["name"].as_str().unwrap_or("?");
+    let size = match node["size"].as_u64() {
+        Some(n) => format!(" ({} bytes)", n),
+        None => String::new(),
+    };
+    let len = match node["len"].as_u64() {
+        Some(n) => format!(" [{}]", n),
+        None => String::new(),
+    };
+    out.push(format!(
+        "{}{}{}{}{}",
+        "  ".repeat(indent),
+        label,
+        style::paint(name, theme().header),
+        len,
+        size
+    ));
+    if let Some(fields) = node["fields"].as_array() {
+        for f in fields {
+            let offset = match f["offset"].as_u64() {
+                Some(off) => format!("+{:<4} ", off),
+                None => "static ".to_string(),
+            };
+            let bits = match f["bitsize"].as_u64() {
+                Some(b) => format!(":{} ", b),
+                None => String::new(),
+            };
+            let field = format!("{}{}{}: ", offset, f["name"].as_str().unwrap_or("?"), bits);
+            type_tree_lines(&f["type"], &field, indent + 1, out);
+        }
+    }
+    if let Some(values) = node["values"].as_object() {
+        for (name, v) in values {
+            out.push(format!("{}{} = {}", "  ".repeat(indent + 1), name, v));
+        }
+    }
+    if !node["target"].is_null() {
+        type_tree_lines(&node["target"], "-> ", indent + 1, out);
+    }
+}
+
+/// Report clobbered canaries; `when` names the stop (or free) that detected them.
+pub fn print_canary_reports(reports: &[CanaryReport], when: &str) {
+    for r in reports {
+        let side = match r.side {
+            CanarySide::Before => "underflow before",
+            CanarySide::After => "overflow past the end of",
+        };
+        let origin = if r.on_free { " (found on free)" } else { "" };
+        let msg = format!(
+            "canary: heap {} 0x{:x} [{} bytes]: guard byte 0x{:x} clobbered{}, {}",
+            side, r.user_addr, r.size, r.bad_addr, origin, when
+        );
+        println!("{}", style::paint(&msg, theme().changed));
+    }
+}
+
+/// Explain why (part of) an access would fault in the program, using the region's perms.
+pub fn print_access_warnings(issues: &[AccessIssue], op: &str) {
+    for issue in issues {
+        let msg = match issue {
+            AccessIssue::Unmapped { start, end } => format!(
+                "warning: {} touches unmapped gap 0x{:x}-0x{:x} ({} bytes)",
+                op,
+                start,
+                end,
+                end - start
+            ),
+            AccessIssue::Denied { region, need } => {
+                let what = if *need == 'w' { "writable" } else { "readable" };
+                let tag = classify_addr(std::slice::from_ref(region), region.start);
+                format!(
+                    "warning: {} targets {} 0x{:x}-0x{:x} {} {} which is not {}",
+                    op, tag, region.start, region.end, region.perms, region.pathname, what
+                )
+            }
+        };
+        println!("{}", style::paint(msg.trim_end(), theme().warning));
+    }
+}
+
+/// One-line startup note; details are left to `sym stats`.
+pub fn print_symbol_index_summary(stats: &IndexStats) {
+    match &stats.error {
+        Some(e) => println!("symbol index unavailable ({}); see 'sym stats'", e),
+        None => println!(
+            "indexed {} variable(s) in {} file(s) and {} function(s) [{}] in {:.1?}",
+            stats.debug_vars + stats.nondebug_vars,
+            stats.files_indexed,
+            stats.functions,
+            stats.mode.label(),
+            stats.elapsed
+        ),
+    }
+}
+
+pub fn print_symbol_stats(stats: &IndexStats) {
+    println!("{}", style::paint("symbol index:", theme().header));
+    println!("  mode:              {}", stats.mode.label());
+    println!("  indexing time:     {:.1?}", stats.elapsed);
+    println!("  files indexed:     {}", stats.files_indexed);
+    println!("  debug variables:   {}", stats.debug_vars);
+    if stats.mode == SymbolIndexMode::DebugAndNondebug {
+        println!("  non-debug symbols: {}", stats.nondebug_vars);
+    }
+    println!("  functions:         {}", stats.functions);
+    println!("  function statics:  {}", stats.statics);
+    println!("  with addresses:    {}", stats.addresses);
+    if let Some(e) = &stats.address_error {
+        println!(
+            "  {}",
+            style::paint(&format!("addresses unavailable: {}", e), theme().warning)
+        );
+    }
+    if let Some(e) = &stats.static_error {
+        println!(
+            "  {}",
+            style::paint(
+                &format!("function statics unavailable: {}", e),
+                theme().warning
+            )
+        );
+    }
+    if let Some(e) = &stats.function_error {
+        println!(
+            "  {}",
+            style::paint(&format!("functions unavailable: {}", e), theme().warning)
+        );
+    }
+    if let Some(e) = &stats.error {
+        println!(
+            "  {}",
+            style::paint(&format!("error: {}", e), theme().warning)
+        );
+    }
+    if !stats.skipped_files.is_empty() {
+        println!("  skipped {} file(s):", stats.skipped_files.len());
+        for (file, reason) in &stats.skipped_files {
+            println!("    {} ({})", file, reason);
+        }
+    }
+    if stats.error.is_none() && stats.mode == SymbolIndexMode::DebugOnly && stats.debug_vars == 0 {
+        println!(
+            "  hint: no variables with debug info were found; rebuild the target with -g \
+             (e.g. gcc -g -O0) or try 'sym reindex debug-and-nondebug'"
+        );
+    }
+}
+
+pub fn print_frames(frames: &[FrameInfo], selected: u32) {
+    if frames.is_empty() {
+        println!("no stack");
+        return;
+    }
+    for f in frames {
+        let marker = if f.level == selected { "*" } else { " " };
+        let func = f.func.as_deref().unwrap_or("??");
+        let loc = match (&f.file, f.line) {
+            (Some(file), Some(line)) => {
+                style::paint(&format!("{}:{}", file, line), theme().location)
+            }
+            _ => f.addr.clone().unwrap_or_default(),
+        };
+        println!("{}#{:<2} {:<24} {}", marker, f.level, func, loc);
+    }
+}
+
+/// `stack`: a frame's saved return address and frame pointer, where they point, and anything
+/// that looks overwritten.
+pub fn print_frame_view(view: &FrameView, regions: &[VmRegion]) {
+    let cfa = match view.cfa {
+        Some(cfa) => format!(", cfa 0x{:x}", cfa),
+        None => String::new(),
+    };
+    let source = if view.from_fp_chain {
+        "assumed from the frame pointer"
+    } else {
+        "from the unwinder"
+    };
+    println!(
+        "{} #{} {}{} (slots {})",
+        style::paint("frame", theme().header),
+        view.level,
+        view.func.as_deref().unwrap_or("??"),
+        cfa,
+        source
+    );
+    let row = |what: &str, slot: &SavedSlot, symbol: Option<&AddressSymbol>| {
+        let value = match slot.value {
+            Some(v) => format!(
+                "{} {}",
+                style::paint(&format!("0x{:016x}", v), theme().pointer),
+                classify_addr(regions, v)
+            ),
+            None => "(unreadable)".to_string(),
+        };
+        let symbol = match symbol {
+            Some(s) if s.offset > 0 => format!(" {}+0x{:x}", s.name, s.offset),
+            Some(s) => format!(" {}", s.name),
+            None => String::new(),
+        };
+        println!(
+            "  0x{:012x}  {:<14} {:<4} {}{}",
+            slot.slot, what, slot.register, value, symbol
+        );
+    };
+    // Highest address first: stack arguments sit above the return address.
+    for (name, at) in view.stack_args.iter().rev() {
+        let offset = view.cfa.map(|cfa| format!(" (cfa+0x{:x})", at - cfa));
+        println!(
+            "  0x{:012x}  {:<14} {}{}",
+            at,
+            "argument",
+            name,
+            offset.unwrap_or_default()
+        );
+    }
+    match &view.ret {
+        Some(slot) => row("return address", slot, view.ret_symbol.as_ref()),
+        None => println!("  no saved return address listed"),
+    }
+    if let Some(slot) = &view.saved_fp {
+        row("saved fp", slot, None);
+    }
+    if !view.others.is_empty() {
+        let others: Vec<String> = view
+            .others
+            .iter()
+            .map(|s| format!("{} at 0x{:x}", s.register, s.slot))
+            .collect();
+        println!("  also saved: {}", others.join(", "));
+    }
+    if view.issues.is_empty() {
+        println!("  return address and frame pointer check out");
+    }
+    for issue in &view.issues {
+        println!("  {} {}", style::paint("!", theme().changed), issue);
+    }
+}
+
+/// `args`: each argument with the register or stack slot it was passed in.
+pub fn print_frame_args(args: &FrameArgs) {
+    let mut context = vec![args.abi.unwrap_or("unknown ABI").to_string()];
+    if let Some(cfa) = args.cfa {
+        context.push(format!("cfa 0x{:x}", cfa));
+    }
+    println!(
+        "{} #{} {} ({})",
+        style::paint("args of frame", theme().header),
+        args.level,
+        args.func.as_deref().unwrap_or("??"),
+        context.join(", ")
+    );
+    if args.args.is_empty() {
+        println!("no arguments");
+        return;
+    }
+    for (i, p) in args.args.iter().enumerate() {
+        let prefix = match p.arg.ty.as_deref() {
+            Some(ty) => format!("{} {}", normalize_type_name(ty), p.arg.name),
+            None => p.arg.name.clone(),
+        };
+        let value = match (&p.arg.value, p.size) {
+            (Some(v), _) => paint_value(&prettify_value(v), style::enabled()),
+            (None, Some(size)) => format!("({})", format_size(size)),
+            (None, None) => style::paint("<unavailable>", theme().unavailable),
+        };
+        let home = match &p.home {
+            Some(home) => {
+                let slot = match (home.stack_offset(), args.cfa) {
+                    (Some(offset), Some(cfa)) => format!(" (0x{:x})", cfa + offset),
+                    _ => String::new(),
+                };
+                format!("{}{}", home.describe(), slot)
+            }
+            None => "passed where the ABI is not known".to_string(),
+        };
+        println!(
+            "{}: {} = {}  {}",
+            i,
+            prefix,
+            value,
+            style::paint(&home, theme().padding)
+        );
+    }
+}
+
+/// `stack usage`: the deepest stop of the run, then every function by its deepest stop.
+pub fn print_stack_usage(usage: &StackUsage) {
+    let Some(peak) = &usage.peak else {
+        println!("stack usage: no stops recorded in this run");
+        if let Some(prev) = &usage.previous {
+            println!(
+                "previous run: peak {} in {}",
+                format_size(prev.bytes),
+                prev.func.as_deref().unwrap_or("??")
+            );
+        }
+        return;
+    };
+    let base = if usage.from_map {
+        "top of the stack mapping"
+    } else {
+        "sp at the first stop"
+    };
+    println!(
+        "{} {} at sp 0x{:x} in {} (stop {}), over {} stop(s); measured from the {}",
+        style::paint("peak stack usage", theme().header),
+        format_size(peak.bytes),
+        peak.sp,
+        peak.func.as_deref().unwrap_or("??"),
+        peak.stop,
+        usage.samples,
+        base
+    );
+    for (func, bytes) in usage.functions() {
+        println!("  {:>10}  {}", format_size(bytes), func);
+    }
+    if let Some(prev) = &usage.previous {
+        println!(
+            "previous run: peak {} in {}",
+            format_size(prev.bytes),
+            prev.func.as_deref().unwrap_or("??")
+        );
+    }
+}
+
+/// `stack canary`: the selected frame's stack protector slot.
+pub fn print_stack_canary(level: u32, slot: Option<&CanarySlot>, reference: u64) {
+    println!(
+        "{} 0x{:016x}",
+        style::paint("reference canary", theme().header),
+        reference
+    );
+    match slot {
+        Some(s) => println!(
+            "  frame #{} {}: slot 0x{:x} (cfa-0x{:x}) = 0x{:016x}, intact",
+            level,
+            s.func.as_deref().unwrap_or("??"),
+            s.slot,
+            s.cfa - s.slot,
+            s.value
+        ),
+        None => println!(
+            "  frame #{}: no copy of the canary between sp and the cfa (not protected, or \
+             already overwritten)",
+            level
+        ),
+    }
+}
+
+/// After a stop that changed a watched canary slot; red, since the function will abort in
+/// `__stack_chk_fail` when it returns.
+pub fn print_canary_alerts(alerts: &[CanaryAlert]) {
+    for a in alerts {
+        let verdict = if a.slot.value == a.reference {
+            "restored"
+        } else {
+            "overwritten"
+        };
+        let msg = format!(
+            "warning: stack canary of {} at 0x{:x} {}: 0x{:016x} -> 0x{:016x} (expected 0x{:016x})",
+            a.slot.func.as_deref().unwrap_or("??"),
+            a.slot.slot,
+            verdict,
+            a.old,
+            a.slot.value,
+            a.reference
+        );
+        println!("{}", style::paint(&msg, theme().changed));
+    }
+}
+
 pub fn print_memory_body(dump: &MemoryDump) {
+    print_memory_rows(dump, None);
+}
+
+/// Raw dump with each byte colored by what it belongs to in `layout` (field, element,
+/// padding, out-of-bounds) and by the malloc chunk headers in front of the heap blocks
+/// `chunks`, followed by a legend. Plain dump when styling is off.
+pub fn print_memory_annotated(dump: &MemoryDump, layout: Option<&TypeLayout>, chunks: &[u64]) {
+    if !style::enabled() {
+        print_memory_rows(dump, None);
+        return;
+    }
+    let len = dump.bytes.len();
+    let mut owners = match layout {
+        Some(l) => byte_owners(l, len),
+        None => vec![ByteOwner::Value; len],
+    };
+    if let Ok(base) = u64::from_str_radix(dump.address.trim_start_matches("0x"), 16) {
+        mark_chunk_headers(&mut owners, base, chunks, dump.word_size);
+    }
+    print_memory_rows(dump, Some(&owners));
+    print_owner_legend(layout, &owners);
+}
+
+/// Per-byte flags for bytes that differ from `dump.previous` (empty when there is none).
+fn changed_mask(dump: &MemoryDump) -> Vec<bool> {
+    match &dump.previous {
+        Some(prev) => dump
+            .bytes
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                let unreadable = dump.unreadable.get(i).copied().unwrap_or(false);
+                !unreadable && prev.get(i).is_some_and(|p| p != b)
+            })
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+fn print_memory_rows(dump: &MemoryDump, owners: Option<&[ByteOwner]>) {
     let w = dump.word_size.max(1);
+    let changed = changed_mask(dump);
     for (i, chunk) in dump.bytes.chunks(w).enumerate() {
         let offset = i * w;
         let mut hex: Vec<String> = Vec::new();
-        let mut ascii_bytes: Vec<u8> = Vec::new();
+        let mut ascii = String::new();
         for j in 0..w {
-            if let Some(b) = chunk.get(j) {
-                hex.push(format!("{:02x}", b));
-                ascii_bytes.push(*b);
+            let unreadable = dump.unreadable.get(offset + j).copied().unwrap_or(false);
+            let color = if changed.get(offset + j).copied().unwrap_or(false) {
+                Some(theme().changed)
             } else {
-                hex.push("..".to_string());
-                ascii_bytes.push(b'.');
+                owners
+                    .and_then(|o| o.get(offset + j))
+                    .and_then(|o| owner_color(*o))
+            };
+            let (h, a) = match chunk.get(j) {
+                Some(_) if unreadable => ("??".to_string(), "?".to_string()),
+                Some(b) => (format!("{:02x}", b), ascii_repr(&[*b])),
+                None => ("..".to_string(), ".".to_string()),
+            };
+            match color {
+                Some(c) => {
+                    hex.push(style::paint(&h, c));
+                    ascii.push_str(&style::paint(&a, c));
+                }
+                None => {
+                    hex.push(h);
+                    ascii.push_str(&a);
+                }
             }
         }
         println!(
             "  +0x{:04x}: {} | ascii=\"{}\"",
             offset,
             hex.join(" "),
-            ascii_repr(&ascii_bytes)
+            ascii
         );
     }
 }
 
+fn owner_color(owner: ByteOwner) -> Option<Color> {
+    match owner {
+        ByteOwner::Field(i) => Some(theme().field(i)),
+        ByteOwner::Element(i) => Some(theme().field(i % 2)),
+        ByteOwner::Padding => Some(theme().padding),
+        ByteOwner::OutOfBounds => Some(theme().out_of_bounds),
+        ByteOwner::ChunkHeader => Some(theme().chunk_header),
+        ByteOwner::Value => None,
+    }
+}
+
+fn print_owner_legend(layout: Option<&TypeLayout>, owners: &[ByteOwner]) {
+    let mut entries: Vec<String> = Vec::new();
+    match layout {
+        Some(TypeLayout::Struct { fields, .. }) => {
+            for (idx, f) in fields.iter().enumerate() {
+                if owners.contains(&ByteOwner::Field(idx)) {
+                    entries.push(style::paint(&f.name, theme().field(idx)));
+                }
+            }
+        }
+        Some(TypeLayout::Array { .. }) => {
+            entries.push(style::paint("[even]", theme().field(0)));
+            entries.push(style::paint("[odd]", theme().field(1)));
+        }
+        Some(TypeLayout::Scalar { .. }) | None => {}
+    }
+    if owners.contains(&ByteOwner::Padding) {
+        entries.push(style::paint("padding", theme().padding));
+    }
+    if owners.contains(&ByteOwner::OutOfBounds) {
+        entries.push(style::paint("out-of-bounds", theme().out_of_bounds));
+    }
+    if owners.contains(&ByteOwner::ChunkHeader) {
+        entries.push(style::paint("chunk header", theme().chunk_header));
+    }
+    if !entries.is_empty() {
+        println!("  legend: {}", entries.join("  "));
+    }
+}
+
+/// Like `print_memory_body`, but marks bytes that differ from `original` with `^^` below the row.
+pub fn print_memory_edits(dump: &MemoryDump, original: &[u8]) {
+    let w = dump.word_size.max(1);
+    for (i, chunk) in dump.bytes.chunks(w).enumerate() {
+        let offset = i * w;
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        println!(
+            "  +0x{:04x}: {} | ascii=\"{}\"",
+            offset,
+            hex.join(" "),
+            ascii_repr(chunk)
+        );
+        let marks: Vec<&str> = chunk
+            .iter()
+            .enumerate()
+            .map(|(j, b)| {
+                if original.get(offset + j) != Some(b) {
+                    "^^"
+                } else {
+                    "  "
+                }
+            })
+            .collect();
+        if marks.contains(&"^^") {
+            println!("           {}", marks.join(" ").trim_end());
+        }
+    }
+}
+
 pub fn print_stopped(loc: &StoppedLocation) {
+    if let Some(exit) = &loc.exit {
+        let banner = match (exit.code, &exit.signal) {
+            (Some(0), _) => "program exited normally".to_string(),
+            (Some(code), _) => format!("program exited with code {}", code),
+            (None, Some(signal)) => format!("program terminated by signal {}", signal),
+            (None, None) => "program exited".to_string(),
+        };
+        println!("{}", style::paint(&banner, theme().header));
+        println!("  'run' restarts it; locals/memory are unavailable until then");
+        return;
+    }
     let where_str = match (&loc.file, &loc.line, &loc.func) {
         (Some(f), Some(l), Some(func)) => format!("stopped at {}:{} ({})", f, l, func),
         (Some(f), Some(l), None) => format!("stopped at {}:{}", f, l),
         _ => "stopped (location unknown)".to_string(),
     };
+    let where_str = style::paint(&where_str, theme().location);
     if let Some(reason) = &loc.reason {
         println!("{} | reason: {}", where_str, reason);
     } else {
         println!("{}", where_str);
     }
+    if let Some(event) = &loc.catch {
+        println!("  caught: {}", style::paint(event, theme().changed));
+    }
+    if let Some(value) = &loc.return_value {
+        println!("  returned: {}", prettify_value(value));
+    }
 }
 
 fn ascii_repr(bytes: &[u8]) -> String {
@@ -154,12 +1006,18 @@ fn normalize_display_type(ty: &str) -> String {
     }
 }
 
+/// Chunks listed per free list before the rest is counted.
+const BIN_ENTRIES_SHOWN: usize = 8;
+
 fn format_size(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = 1024 * KB;
     const GB: u64 = 1024 * MB;
+    const TB: u64 = 1024 * GB;
 
-    if bytes >= GB {
+    if bytes >= TB {
+        format!("{:.1} TB", bytes as f64 / TB as f64)
+    } else if bytes >= GB {
         format!("{:.1} GB", bytes as f64 / GB as f64)
     } else if bytes >= MB {
         format!("{:.1} MB", bytes as f64 / MB as f64)
@@ -170,6 +1028,40 @@ fn format_size(bytes: u64) -> String {
     }
 }
 
+/// Bracketed region tag (e.g. "[heap]"), padded to `width` and colored by region kind.
+fn painted_label(label: &VmLabel, width: usize) -> String {
+    style::paint(
+        &format!("{:<width$}", label.tag(), width = width),
+        theme().region(label),
+    )
+}
+
+/// A C string as a quoted literal, with `...` after it when it was cut.
+pub fn format_c_string(s: &CStringRead) -> String {
+    let quoted = format!("{:?}", s.text);
+    if s.truncated {
+        format!("{}...", quoted)
+    } else {
+        quoted
+    }
+}
+
+/// Color a leading pointer value ("0x...") in a gdb value string.
+fn paint_value(value: &str, color: bool) -> String {
+    if !value.starts_with("0x") {
+        return value.to_string();
+    }
+    let end = value[2..]
+        .find(|c: char| !c.is_ascii_hexdigit())
+        .map(|i| i + 2)
+        .unwrap_or(value.len());
+    format!(
+        "{}{}",
+        style::paint_if(color, &value[..end], theme().pointer),
+        &value[end..]
+    )
+}
+
 fn format_region_desc(region: &VmRegion) -> String {
     if region.pathname == "[heap]" {
         "(heap)".to_string()
@@ -183,32 +1075,309 @@ fn format_region_desc(region: &VmRegion) -> String {
 pub fn print_vm_regions(regions: &[VmRegion]) {
     println!("regions:");
     for r in regions {
-        let label = match &r.label {
-            VmLabel::Text => "[text]",
-            VmLabel::Data => "[data]",
-            VmLabel::Heap => "[heap]",
-            VmLabel::Stack => "[stack]",
-            VmLabel::Lib => "[lib]",
-            VmLabel::Anonymous => "[anon]",
-            VmLabel::Other(_) => "[other]",
-        };
+        let label = painted_label(&r.label, 8);
         let size_str = format_size(r.size());
         let desc = format_region_desc(r);
 
         if desc.is_empty() {
             println!(
-                "  {:<8} 0x{:016x}-0x{:016x} ({}) {}",
+                "  {} 0x{:016x}-0x{:016x} ({}) {}",
                 label, r.start, r.end, size_str, r.perms,
             );
         } else {
             println!(
-                "  {:<8} 0x{:016x}-0x{:016x} ({}) {} {}",
+                "  {} 0x{:016x}-0x{:016x} ({}) {} {}",
                 label, r.start, r.end, size_str, r.perms, desc,
             );
         }
     }
 }
 
+/// `stack: 7.9 MB of 8.0 MB left below sp 0x...`, in the warning color when nearly full.
+pub fn print_stack_headroom(h: &StackHeadroom) {
+    if h.is_low() {
+        print_low_stack_warning(h);
+        return;
+    }
+    let guard = if h.guarded { ", guard page below" } else { "" };
+    println!(
+        "stack: {} of {} left below sp 0x{:016x}{}",
+        format_size(h.left()),
+        format_size(h.total()),
+        h.sp,
+        guard
+    );
+}
+
+/// After a stop that left the current thread's stack nearly full.
+pub fn print_low_stack_warning(h: &StackHeadroom) {
+    let msg = format!(
+        "warning: stack nearly exhausted: {} of {} left below sp 0x{:016x} (runaway recursion?)",
+        format_size(h.left()),
+        format_size(h.total()),
+        h.sp
+    );
+    println!("{}", style::paint(&msg, theme().warning));
+}
+
+/// `vm bases`: runtime base, link-time base and load bias per mapped ELF file.
+pub fn print_module_bases(bases: &[ModuleBase]) {
+    println!(
+        "  {:<18} {:<18} {:<18} file",
+        "loaded at", "linked at", "bias"
+    );
+    for b in bases {
+        println!(
+            "  {} 0x{:016x} 0x{:016x} {}",
+            style::paint(&format!("0x{:016x}", b.start), theme().pointer),
+            b.link_base,
+            b.bias(),
+            b.path
+        );
+    }
+}
+
+/// `vm diff`: `+` new, `-` removed, `~` resized regions since the previous stop.
+pub fn print_region_changes(changes: &[RegionChange]) {
+    println!("regions changed since the previous stop:");
+    for change in changes {
+        print_region_change(change);
+    }
+}
+
+/// `vm history`: one logged change with the syscall and the stack that made it.
+pub fn print_map_event(n: usize, event: &MapEvent) {
+    let cause = match &event.syscall {
+        Some(syscall) => syscall.as_str(),
+        None => "seen at stop",
+    };
+    println!(
+        "{} {}, after stop #{}",
+        style::paint(&format!("#{}", n), theme().header),
+        cause,
+        event.after_stop
+    );
+    for change in &event.changes {
+        print_region_change(change);
+    }
+    let frames: Vec<String> = event
+        .backtrace
+        .iter()
+        .map(|f| {
+            let func = f.func.as_deref().unwrap_or("??");
+            match (&f.file, f.line) {
+                (Some(file), Some(line)) => format!("{} ({}:{})", func, file, line),
+                _ => func.to_string(),
+            }
+        })
+        .collect();
+    if !frames.is_empty() {
+        println!("    at {}", frames.join(" <- "));
+    }
+}
+
+fn print_region_change(change: &RegionChange) {
+    let (mark, r, size) = match change {
+        RegionChange::Added(r) => ("+", r, format_size(r.size())),
+        RegionChange::Removed(r) => ("-", r, format_size(r.size())),
+        RegionChange::Resized { old, new } => (
+            "~",
+            new,
+            format!("{} -> {}", format_size(old.size()), format_size(new.size())),
+        ),
+    };
+    let color = match change {
+        RegionChange::Added(_) => theme().changed,
+        RegionChange::Removed(_) => theme().unavailable,
+        RegionChange::Resized { .. } => theme().warning,
+    };
+    println!(
+        "  {} {} 0x{:016x}-0x{:016x} ({}) {} {}",
+        style::paint(mark, color),
+        painted_label(&r.label, 8),
+        r.start,
+        r.end,
+        size,
+        r.perms,
+        format_region_desc(r)
+    );
+}
+
+/// `vm stats`: real memory use per region label, with a total line.
+pub fn print_vm_stats(totals: &[LabelUsage]) {
+    println!(
+        "  {:<8} {:>7} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "label", "regions", "mapped", "rss", "pss", "dirty", "swap"
+    );
+    let row = |label: String, regions: usize, size: u64, u: &RegionUsage| {
+        println!(
+            "  {} {:>7} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            label,
+            regions,
+            format_size(size),
+            format_size(u.rss),
+            format_size(u.pss),
+            format_size(u.dirty),
+            format_size(u.swap)
+        );
+    };
+    let (mut regions, mut size, mut usage) = (0, 0, RegionUsage::default());
+    for t in totals {
+        row(painted_label(&t.label, 8), t.regions, t.size, &t.usage);
+        regions += t.regions;
+        size += t.size;
+        usage.add(&t.usage);
+    }
+    let total = style::paint(&format!("{:<8}", "total"), theme().header);
+    row(total, regions, size, &usage);
+}
+
+/// Widest bar drawn by `vm layout`.
+const LAYOUT_BAR_WIDTH: usize = 32;
+/// Names listed per region before collapsing into "+N more".
+const LAYOUT_MAX_NAMES: usize = 4;
+
+/// A named address shown next to its region in `vm layout`.
+pub struct LayoutMarker {
+    /// Short kind tag: "local", "global" or "heap".
+    pub kind: &'static str,
+    pub name: String,
+    pub addr: u64,
+}
+
+/// Regions from the highest address down, with log-scaled size bars and the variables
+/// that live in each.
+pub fn print_vm_layout(regions: &[VmRegion], markers: &[LayoutMarker]) {
+    let mut ordered: Vec<&VmRegion> = regions.iter().collect();
+    ordered.sort_by_key(|r| std::cmp::Reverse(r.start));
+    let largest = ordered.iter().map(|r| r.size()).max().unwrap_or(0);
+    println!("layout (high addresses first):");
+    for r in ordered {
+        let width = layout_bar_width(r.size(), largest, LAYOUT_BAR_WIDTH);
+        let bar = style::paint(&"█".repeat(width), theme().region(&r.label));
+        println!(
+            "  0x{:012x} {} {}{} {:>9} {}",
+            r.start,
+            painted_label(&r.label, 7),
+            bar,
+            " ".repeat(LAYOUT_BAR_WIDTH - width),
+            format_size(r.size()),
+            format_region_desc(r)
+        );
+        let inside: Vec<String> = markers
+            .iter()
+            .filter(|m| r.contains(m.addr))
+            .map(|m| format!("{} {}", m.kind, m.name))
+            .collect();
+        if inside.is_empty() {
+            continue;
+        }
+        let mut names = inside[..inside.len().min(LAYOUT_MAX_NAMES)].join(", ");
+        if inside.len() > LAYOUT_MAX_NAMES {
+            names.push_str(&format!(", +{} more", inside.len() - LAYOUT_MAX_NAMES));
+        }
+        println!("  {:>14} <- {}", "", names);
+    }
+}
+
+/// Inside width of the `vm map` boxes.
+const MAP_BOX_WIDTH: usize = 30;
+/// Rows of the tallest `vm map` box; the smallest gets one.
+const MAP_MAX_ROWS: usize = 4;
+
+/// `vm map`: the classic memory layout diagram, high addresses on top. Each box is a run of
+/// mappings or a gap, its height log-scaled between the smallest and largest.
+pub fn print_vm_map(blocks: &[MapBlock]) {
+    let Some(top) = blocks.last() else {
+        return;
+    };
+    let logs: Vec<f64> = blocks.iter().map(|b| (b.size() as f64).log2()).collect();
+    let lo = logs.iter().copied().fold(f64::INFINITY, f64::min);
+    let hi = logs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let rule = "─".repeat(MAP_BOX_WIDTH + 2);
+    println!("memory map (high addresses first, heights on a log scale):");
+    println!("  0x{:016x} ┌{}┐", top.end(), rule);
+    for (i, (b, log)) in blocks.iter().zip(&logs).enumerate().rev() {
+        let rows = if hi > lo {
+            1 + ((log - lo) / (hi - lo) * (MAP_MAX_ROWS - 1) as f64).round() as usize
+        } else {
+            1
+        };
+        let (side, tag, name) = match b {
+            MapBlock::Mapped {
+                label,
+                pathname,
+                regions,
+                ..
+            } => {
+                // "[heap]" would only repeat the tag.
+                let mut name = match pathname.rsplit('/').next() {
+                    Some(file) if *file != label.tag() => file.to_string(),
+                    _ => String::new(),
+                };
+                if *regions > 1 {
+                    name = format!("{} ({} maps)", name, regions)
+                        .trim_start()
+                        .to_string();
+                }
+                ("│", Some(label), name)
+            }
+            MapBlock::Gap { .. } => ("┆", None, "unmapped".to_string()),
+        };
+        for row in 0..rows {
+            let (text, visible) = match (row, tag) {
+                (0, Some(label)) => {
+                    let tag = label.tag();
+                    let room = MAP_BOX_WIDTH.saturating_sub(tag.len() + 1);
+                    let name: String = name.chars().take(room).collect();
+                    let painted = style::paint(&tag, theme().region(label));
+                    if name.is_empty() {
+                        (painted, tag.len())
+                    } else {
+                        (
+                            format!("{} {}", painted, name),
+                            tag.len() + 1 + name.chars().count(),
+                        )
+                    }
+                }
+                (0, None) => (style::paint(&name, theme().unavailable), name.len()),
+                _ => (String::new(), 0),
+            };
+            let size = if row == 0 {
+                format_size(b.size())
+            } else {
+                String::new()
+            };
+            let line = format!(
+                "  {:18} {} {}{} {} {}",
+                "",
+                side,
+                text,
+                " ".repeat(MAP_BOX_WIDTH - visible),
+                side,
+                size
+            );
+            println!("{}", line.trim_end());
+        }
+        let corner = if i == 0 {
+            ("└", "┘")
+        } else {
+            ("├", "┤")
+        };
+        println!("  0x{:016x} {}{}{}", b.start(), corner.0, rule, corner.1);
+    }
+}
+
+/// Bar length on a log scale so an 8 KB data segment stays visible next to a 1 GB mapping.
+fn layout_bar_width(size: u64, largest: u64, max_width: usize) -> usize {
+    if size == 0 || largest == 0 {
+        return 0;
+    }
+    let scale = |v: u64| (v as f64).log2().max(1.0);
+    let width = (scale(size) / scale(largest) * max_width as f64).round() as usize;
+    width.clamp(1, max_width)
+}
+
 pub struct VmLocateInfo<'a> {
     pub expr: String,
     pub type_name: String,
@@ -216,8 +1385,13 @@ pub struct VmLocateInfo<'a> {
     pub storage_region: Option<&'a VmRegion>,
     pub value_addr: Option<u64>,
     pub value_region: Option<&'a VmRegion>,
+    /// Function or object containing the address, for file-backed regions.
+    pub storage_symbol: Option<AddressSymbol>,
+    pub value_symbol: Option<AddressSymbol>,
     pub is_pointer: bool,
     pub is_null: bool,
+    /// `expr` is a numeric address, classified as is.
+    pub is_literal: bool,
 }
 
 pub fn print_vm_locate(info: &VmLocateInfo<'_>) {
@@ -227,15 +1401,7 @@ pub fn print_vm_locate(info: &VmLocateInfo<'_>) {
         if let Some(addr) = info.storage_addr {
             println!("    addr:   0x{:016x}", addr);
             if let Some(region) = info.storage_region {
-                let label = match &region.label {
-                    VmLabel::Text => "[text]",
-                    VmLabel::Data => "[data]",
-                    VmLabel::Heap => "[heap]",
-                    VmLabel::Stack => "[stack]",
-                    VmLabel::Lib => "[lib]",
-                    VmLabel::Anonymous => "[anon]",
-                    VmLabel::Other(_) => "[other]",
-                };
+                let label = painted_label(&region.label, 0);
                 let desc = format_region_desc(region);
                 if desc.is_empty() {
                     println!(
@@ -250,6 +1416,7 @@ pub fn print_vm_locate(info: &VmLocateInfo<'_>) {
                 }
                 let offset = addr.saturating_sub(region.start);
                 println!("    offset: +0x{:x} from region base", offset);
+                print_inside(addr, region, info.storage_symbol.as_ref());
             }
         }
         println!("  value:");
@@ -258,15 +1425,7 @@ pub fn print_vm_locate(info: &VmLocateInfo<'_>) {
         } else if let Some(vaddr) = info.value_addr {
             println!("    ptr:    0x{:016x}", vaddr);
             if let Some(region) = info.value_region {
-                let label = match &region.label {
-                    VmLabel::Text => "[text]",
-                    VmLabel::Data => "[data]",
-                    VmLabel::Heap => "[heap]",
-                    VmLabel::Stack => "[stack]",
-                    VmLabel::Lib => "[lib]",
-                    VmLabel::Anonymous => "[anon]",
-                    VmLabel::Other(_) => "[other]",
-                };
+                let label = painted_label(&region.label, 0);
                 let desc = format_region_desc(region);
                 if desc.is_empty() {
                     println!(
@@ -281,6 +1440,7 @@ pub fn print_vm_locate(info: &VmLocateInfo<'_>) {
                 }
                 let offset = vaddr.saturating_sub(region.start);
                 println!("    offset: +0x{:x} from region base", offset);
+                print_inside(vaddr, region, info.value_symbol.as_ref());
             } else {
                 println!("    region: <unknown>");
             }
@@ -288,19 +1448,11 @@ pub fn print_vm_locate(info: &VmLocateInfo<'_>) {
             println!("    ptr:    <unavailable>");
         }
     } else {
-        println!("  object:");
+        println!("  {}:", if info.is_literal { "address" } else { "object" });
         if let Some(vaddr) = info.value_addr {
             println!("    addr:   0x{:016x}", vaddr);
             if let Some(region) = info.value_region {
-                let label = match &region.label {
-                    VmLabel::Text => "[text]",
-                    VmLabel::Data => "[data]",
-                    VmLabel::Heap => "[heap]",
-                    VmLabel::Stack => "[stack]",
-                    VmLabel::Lib => "[lib]",
-                    VmLabel::Anonymous => "[anon]",
-                    VmLabel::Other(_) => "[other]",
-                };
+                let label = painted_label(&region.label, 0);
                 let desc = format_region_desc(region);
                 if desc.is_empty() {
                     println!(
@@ -315,6 +1467,7 @@ pub fn print_vm_locate(info: &VmLocateInfo<'_>) {
                 }
                 let offset = vaddr.saturating_sub(region.start);
                 println!("    offset: +0x{:x} from region base", offset);
+                print_inside(vaddr, region, info.value_symbol.as_ref());
             } else {
                 println!("    region: <unknown>");
             }
@@ -324,31 +1477,97 @@ pub fn print_vm_locate(info: &VmLocateInfo<'_>) {
     }
 }
 
+/// `inside: libc.so.6 (file offset 0x2a040), malloc+0x40` for an address in a mapped file.
+fn print_inside(addr: u64, region: &VmRegion, symbol: Option<&AddressSymbol>) {
+    let Some(file_offset) = region.file_offset(addr) else {
+        return;
+    };
+    let file = region
+        .pathname
+        .rsplit('/')
+        .next()
+        .unwrap_or(&region.pathname);
+    let mut line = format!("    inside: {} (file offset 0x{:x})", file, file_offset);
+    if let Some(sym) = symbol {
+        line.push_str(", ");
+        line.push_str(&style::paint(&sym.name, theme().header));
+        if sym.offset > 0 {
+            line.push_str(&format!("+0x{:x}", sym.offset));
+        }
+    }
+    println!("{}", line);
+}
+
 #[allow(dead_code)]
-fn label_for_global(regions: Option<&[VmRegion]>, addr: u64) -> &'static str {
+fn label_for_global(regions: Option<&[VmRegion]>, addr: u64) -> String {
     if let Some(rs) = regions {
         classify_addr(rs, addr)
     } else {
-        "[unknown]"
+        "[unknown]".to_string()
     }
 }
 
-pub fn print_globals(globals: &[GlobalVar], _vm_regions: Option<&[VmRegion]>) {
-    if globals.is_empty() {
-        return;
+/// Globals under a header per source file (▾ open, ▸ folded with `globals fold`). Numbers
+/// count every variable, folded or not, so they stay the same as files are folded.
+pub fn print_globals(
+    globals: &[GlobalVar],
+    _vm_regions: Option<&[VmRegion]>,
+    changed: &HashSet<String>,
+    folded: &HashSet<String>,
+) {
+    for (file, vars) in group_by_file(globals) {
+        if let Some(file) = file {
+            let shut = is_folded(folded, file);
+            let marker = if shut { "▸" } else { "▾" };
+            println!(
+                "{} {} ({}{})",
+                marker,
+                style::paint(file, theme().header),
+                vars.len(),
+                if shut { ", folded" } else { "" }
+            );
+            if shut {
+                continue;
+            }
+        }
+        for (idx, g) in vars {
+            let mut value = prettify_value(&g.value);
+            if changed.contains(&g.name) {
+                value = style::paint(&value, theme().changed);
+            }
+            let ty = normalize_display_type(&g.type_name);
+            println!("{}: {} {} = {}", idx, ty, g.name, value);
+        }
     }
+}
+
+/// A source file and its globals, each with its position in the listing.
+type FileGroup<'a> = (Option<&'a str>, Vec<(usize, &'a GlobalVar)>);
+
+/// Globals grouped by file in order of first appearance; variables without a file come
+/// last.
+fn group_by_file(globals: &[GlobalVar]) -> Vec<FileGroup<'_>> {
+    let mut groups: Vec<FileGroup> = Vec::new();
     for (idx, g) in globals.iter().enumerate() {
-        let value = prettify_value(&g.value);
-        let ty = normalize_display_type(&g.type_name);
-        println!("{}: {} {} = {}", idx, ty, g.name, value);
+        let file = g.file.as_deref();
+        match groups.iter_mut().find(|(f, _)| *f == file) {
+            Some((_, vars)) => vars.push((idx, g)),
+            None => groups.push((file, vec![(idx, g)])),
+        }
     }
+    groups.sort_by_key(|(f, _)| f.is_none());
+    groups
+}
+
+/// `file` was folded, by the name gdb shows or by its base name.
+pub fn is_folded(folded: &HashSet<String>, file: &str) -> bool {
+    folded.contains(file) || file.rsplit('/').next().is_some_and(|b| folded.contains(b))
 }
 
 #[derive(Debug, Clone)]
 pub struct SymbolInfo {
     pub name: String,
     pub type_name: String,
-    pub addr: u64,
     pub target_label: Option<VmLabel>,
 }
 
@@ -356,7 +1575,8 @@ pub struct SymbolInfo {
 pub struct HeapObjectInfo {
     pub via: String,
     pub type_name: String,
-    pub addr: u64,
+    /// Where the block was allocated, when `heap track` saw it.
+    pub site: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -374,39 +1594,31 @@ pub fn print_vm_vars(summaries: &[RegionVarsSummary]) {
     }
     println!("vm vars (by region):\n");
 
-    let label_str = |l: &VmLabel| match l {
-        VmLabel::Data => "data",
-        VmLabel::Stack => "stack",
-        VmLabel::Heap => "heap",
-        VmLabel::Text => "text",
-        VmLabel::Lib => "lib",
-        VmLabel::Anonymous => "anon",
-        VmLabel::Other(_) => "other",
-    };
-
     let tgt_str = |l: &VmLabel| match l {
         VmLabel::Data => "data",
-        VmLabel::Stack => "stack",
+        VmLabel::Stack | VmLabel::ThreadStack(_) => "stack",
         VmLabel::Heap => "heap",
         VmLabel::Text => "text",
         VmLabel::Lib => "lib",
         VmLabel::Anonymous => "anon",
         VmLabel::Other(_) => "other",
+        VmLabel::Guard => "guard",
     };
 
     let mut items: Vec<&RegionVarsSummary> = summaries.iter().collect();
     items.sort_by_key(|s| match s.label {
         VmLabel::Data => 0,
-        VmLabel::Stack => 1,
+        VmLabel::Stack | VmLabel::ThreadStack(_) => 1,
         VmLabel::Heap => 2,
         VmLabel::Text => 3,
         VmLabel::Lib => 4,
         VmLabel::Anonymous => 5,
-        VmLabel::Other(_) => 6,
+        VmLabel::Other(_) | VmLabel::Guard => 6,
     });
 
     for rs in items {
-        println!("[{}]", label_str(&rs.label));
+        let header = rs.label.tag();
+        println!("{}", style::paint(&header, theme().region(&rs.label)));
 
         if !rs.globals.is_empty() {
             println!("  globals:");
@@ -432,7 +1644,10 @@ pub fn print_vm_vars(summaries: &[RegionVarsSummary]) {
             println!("  objects (reachable via pointers):");
             for o in &rs.heap_objects {
                 let ty = normalize_display_type(&o.type_name);
-                println!("    - *{:<14} ({})", o.via, ty);
+                match &o.site {
+                    Some(site) => println!("    - *{:<14} ({}) allocated at {}", o.via, ty, site),
+                    None => println!("    - *{:<14} ({})", o.via, ty),
+                }
             }
         }
 
@@ -457,6 +1672,55 @@ mod tests {
         assert_eq!(super::normalize_display_type("int [5]"), "int[5]");
     }
 
+    #[test]
+    fn paint_value_keeps_text_without_color() {
+        assert_eq!(paint_value("0x1234 <node0>", false), "0x1234 <node0>");
+        assert_eq!(paint_value("42", true), "42");
+        let painted = paint_value("0x1234 <node0>", true);
+        assert!(painted.starts_with("\x1b[") && painted.ends_with("\x1b[0m <node0>"));
+    }
+
+    #[test]
+    fn globals_group_by_file_with_unknown_files_last() {
+        let var = |name: &str, file: Option<&str>| GlobalVar {
+            name: name.to_string(),
+            type_name: "int".to_string(),
+            value: "0".to_string(),
+            address: 0,
+            file: file.map(str::to_string),
+        };
+        let globals = [
+            var("a", Some("src/list.c")),
+            var("b", None),
+            var("c", Some("main.c")),
+            var("d", Some("src/list.c")),
+        ];
+        let groups = group_by_file(&globals);
+        let shape: Vec<(Option<&str>, Vec<usize>)> = groups
+            .iter()
+            .map(|(f, vars)| (*f, vars.iter().map(|(i, _)| *i).collect()))
+            .collect();
+        assert_eq!(
+            shape,
+            [
+                (Some("src/list.c"), vec![0, 3]),
+                (Some("main.c"), vec![2]),
+                (None, vec![1])
+            ]
+        );
+        let folded: HashSet<String> = ["list.c".to_string()].into();
+        assert!(is_folded(&folded, "src/list.c"));
+        assert!(!is_folded(&folded, "main.c"));
+    }
+
+    #[test]
+    fn layout_bars_are_log_scaled() {
+        assert_eq!(layout_bar_width(1 << 30, 1 << 30, 30), 30);
+        assert_eq!(layout_bar_width(1 << 15, 1 << 30, 30), 15);
+        assert_eq!(layout_bar_width(1, 1 << 30, 30), 1);
+        assert_eq!(layout_bar_width(0, 1 << 30, 30), 0);
+    }
+
     #[test]
     fn ascii_repr_replaces_non_printable() {
         assert_eq!(ascii_repr(&[0x41, 0x0, 0x7f]), "A..");
@@ -474,6 +1738,8 @@ mod tests {
             endian: Endian::Little,
             arch: None,
             truncated_from: None,
+            unreadable: vec![false, true, false, false],
+            previous: Some(vec![0x01, 0x02, 0x00, 0x41]),
         };
         // Smoke-test: ensure it doesn't panic and lines are sensible.
         print_memory_body(&dump);
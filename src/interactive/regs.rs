@@ -0,0 +1,103 @@
+use super::style;
+use super::theme::theme;
+use crate::mi::{MiSession, RegisterValue, Result};
+
+/// Registers per row in the compact listing.
+const COLUMNS: usize = 3;
+
+/// Register snapshots taken at consecutive stops, for changed-since-last-stop highlighting.
+#[derive(Default)]
+pub struct RegisterHistory {
+    /// Snapshot once `regs` has been used, so stops do not pay for registers nobody looks at.
+    pub active: bool,
+    /// Print general-purpose registers after every stop (`regs on`).
+    pub auto_show: bool,
+    prev: Vec<RegisterValue>,
+    curr: Vec<RegisterValue>,
+    curr_stop: Option<u64>,
+}
+
+impl RegisterHistory {
+    /// Take a snapshot for `stop` unless one was already taken.
+    pub fn observe(&mut self, session: &mut MiSession, stop: u64) -> Result<()> {
+        if self.curr_stop == Some(stop) {
+            return Ok(());
+        }
+        let regs = session.list_registers()?;
+        self.prev = std::mem::replace(&mut self.curr, regs);
+        self.curr_stop = Some(stop);
+        Ok(())
+    }
+
+    pub fn print(&self, arch: Option<&str>, all: bool) {
+        let shown: Vec<&RegisterValue> = self
+            .curr
+            .iter()
+            .filter(|r| all || is_general_purpose(&r.name, arch))
+            .collect();
+        if shown.is_empty() {
+            println!("no registers");
+            return;
+        }
+        for row in shown.chunks(COLUMNS) {
+            let cells: Vec<String> = row.iter().map(|r| self.cell(r)).collect();
+            println!("  {}", cells.join("  "));
+        }
+    }
+
+    fn cell(&self, reg: &RegisterValue) -> String {
+        let changed = self
+            .prev
+            .iter()
+            .find(|p| p.name == reg.name)
+            .is_some_and(|p| p.value != reg.value);
+        let value = format!("{:<18}", reg.value);
+        let value = if changed {
+            style::paint(&value, theme().changed)
+        } else {
+            value
+        };
+        format!("{:>6} {}", reg.name, value)
+    }
+}
+
+/// Whether `name` is a general-purpose (integer, pc, flags) register for `arch`.
+fn is_general_purpose(name: &str, arch: Option<&str>) -> bool {
+    let arch = arch.unwrap_or("").to_ascii_lowercase();
+    if arch.contains("x86-64") || arch.contains("x86_64") {
+        const X86_64: &[&str] = &[
+            "rax", "rbx", "rcx", "rdx", "rsi", "rdi", "rbp", "rsp", "r8", "r9", "r10", "r11",
+            "r12", "r13", "r14", "r15", "rip", "eflags",
+        ];
+        return X86_64.contains(&name);
+    }
+    if arch.contains("aarch64") {
+        return matches!(name, "sp" | "pc" | "cpsr")
+            || name
+                .strip_prefix('x')
+                .is_some_and(|n| n.parse::<u8>().is_ok_and(|n| n <= 30));
+    }
+    if arch.contains("riscv") {
+        return !name.starts_with('f') && !name.starts_with('v') && name.len() <= 4;
+    }
+    // Unknown arch: skip vector/float/segment banks, which are rarely what users want first.
+    const SKIP: &[&str] = &["xmm", "ymm", "zmm", "st", "mm", "k", "fs", "gs", "f", "v"];
+    !SKIP.iter().any(|p| name.starts_with(p))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn general_purpose_filter_per_arch() {
+        let x86 = Some("i386:x86-64");
+        assert!(is_general_purpose("rip", x86));
+        assert!(!is_general_purpose("xmm0", x86));
+        assert!(!is_general_purpose("fs_base", x86));
+        let arm = Some("aarch64");
+        assert!(is_general_purpose("x30", arm));
+        assert!(!is_general_purpose("x31", arm));
+        assert!(!is_general_purpose("v0", arm));
+    }
+}
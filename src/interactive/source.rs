@@ -0,0 +1,265 @@
+use super::style;
+use super::theme::theme;
+use crate::mi::session::same_file;
+use crate::mi::{FrameInfo, MiSession, Result};
+use std::fs;
+
+/// Lines shown on each side of the focus line.
+const CONTEXT: u32 = 5;
+
+/// File and line last shown by `list`, so `toggle <line>` and bare `list` know where they are.
+#[derive(Default)]
+pub struct SourceCursor {
+    /// Name as gdb reports it (used for breakpoint locations).
+    file: Option<String>,
+    /// Path used to read the file.
+    path: Option<String>,
+    line: u32,
+    /// Last `/` or `?` pattern, highlighted in listings.
+    pattern: Option<String>,
+}
+
+/// `list [line | file:line]`: print source around a line with a breakpoint gutter.
+pub fn handle_list(args: &str, session: &mut MiSession, cursor: &mut SourceCursor) -> Result<()> {
+    let frame = current_frame(session);
+    match args.rsplit_once(':') {
+        Some((file, line)) => {
+            // Prefer gdb's absolute path when the file is the one we are stopped in.
+            let full = frame
+                .as_ref()
+                .filter(|f| f.file.as_deref().is_some_and(|ff| same_file(ff, file)))
+                .and_then(|f| f.fullname.clone());
+            cursor.file = Some(file.to_string());
+            cursor.path = Some(full.unwrap_or_else(|| file.to_string()));
+            cursor.line = parse_line(line)?;
+        }
+        None if !args.is_empty() => {
+            cursor.line = parse_line(args)?;
+        }
+        None => {
+            let f = frame
+                .clone()
+                .ok_or("no source location for the current frame")?;
+            cursor.file = f.file.clone();
+            cursor.path = f.fullname.or(f.file);
+            cursor.line = f.line.unwrap_or(1);
+        }
+    }
+    show(session, cursor, frame.as_ref())
+}
+
+/// `/text` searches forward from the listed line, `?text` backward; a bare `/` or `?`
+/// repeats the last pattern. Matches are highlighted until the next search.
+pub fn handle_search(
+    pattern: &str,
+    backward: bool,
+    session: &mut MiSession,
+    cursor: &mut SourceCursor,
+) -> Result<()> {
+    let frame = current_frame(session);
+    if !pattern.is_empty() {
+        cursor.pattern = Some(pattern.to_string());
+    }
+    let pattern = cursor.pattern.clone().ok_or("no previous search pattern")?;
+    if cursor.path.is_none() {
+        let f = frame
+            .clone()
+            .ok_or("no source file selected; use 'list <file>:<line>'")?;
+        cursor.file = f.file.clone();
+        cursor.path = f.fullname.or(f.file);
+        cursor.line = f.line.unwrap_or(1);
+    }
+    let text = read_source(cursor)?;
+    let lines: Vec<&str> = text.lines().collect();
+    match find_match(&lines, cursor.line, &pattern, backward) {
+        Some(line) => {
+            if (line <= cursor.line) != backward {
+                println!("search wrapped around");
+            }
+            cursor.line = line;
+        }
+        None => return Err(format!("pattern not found: {}", pattern).into()),
+    }
+    show(session, cursor, frame.as_ref())
+}
+
+fn current_frame(session: &mut MiSession) -> Option<FrameInfo> {
+    session.list_frames().ok().and_then(|frames| {
+        frames
+            .into_iter()
+            .find(|f| f.file.is_some() && f.line.is_some())
+    })
+}
+
+fn read_source(cursor: &SourceCursor) -> Result<String> {
+    let path = cursor
+        .path
+        .as_deref()
+        .ok_or("no source file selected; use 'list <file>:<line>'")?;
+    fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e).into())
+}
+
+/// Print the window around `cursor.line`.
+fn show(session: &mut MiSession, cursor: &SourceCursor, frame: Option<&FrameInfo>) -> Result<()> {
+    let file = match &cursor.file {
+        Some(f) => f.clone(),
+        None => return Err("no source file selected; use 'list <file>:<line>'".into()),
+    };
+    let text = read_source(cursor)?;
+    let bp_lines: Vec<(u32, bool)> = session
+        .breakpoints()
+        .iter()
+        .filter(|b| b.file.as_deref().is_some_and(|bf| same_file(bf, &file)))
+        .filter_map(|b| Some((b.line?, b.enabled)))
+        .collect();
+    let current_line = frame
+        .filter(|f| f.file.as_deref().is_some_and(|ff| same_file(ff, &file)))
+        .and_then(|f| f.line);
+
+    let first = cursor.line.saturating_sub(CONTEXT).max(1);
+    let last = cursor.line + CONTEXT;
+    println!("{}", style::paint(&file, theme().header));
+    for (idx, src) in text.lines().enumerate() {
+        let no = idx as u32 + 1;
+        if no < first || no > last {
+            continue;
+        }
+        let src = match &cursor.pattern {
+            Some(p) => highlight(src, p),
+            None => src.to_string(),
+        };
+        let gutter = gutter(no, &bp_lines, current_line, style::enabled());
+        println!("{} {:>5}  {}", gutter, no, src);
+    }
+    Ok(())
+}
+
+/// `toggle <line>` / `toggle <file>:<line>`: flip a breakpoint, defaulting to the listed file.
+pub fn handle_toggle(args: &str, session: &mut MiSession, cursor: &mut SourceCursor) -> Result<()> {
+    let (file, line) = match args.rsplit_once(':') {
+        Some((file, line)) => (file.to_string(), parse_line(line)?),
+        None => {
+            let file = cursor
+                .file
+                .clone()
+                .ok_or("no file listed yet; use 'toggle <file>:<line>'")?;
+            (file, parse_line(args)?)
+        }
+    };
+    let (bp, added) = session.toggle_breakpoint(&file, line)?;
+    if added {
+        println!("breakpoint {} set at {}:{}", bp.number, file, line);
+    } else {
+        println!("breakpoint {} at {}:{} removed", bp.number, file, line);
+    }
+    Ok(())
+}
+
+fn parse_line(raw: &str) -> Result<u32> {
+    match raw.trim().parse::<u32>() {
+        Ok(l) if l > 0 => Ok(l),
+        _ => Err(format!("invalid line number '{}'", raw.trim()).into()),
+    }
+}
+
+/// Smart case: a pattern with no uppercase letters matches case-insensitively.
+fn line_matches(line: &str, pattern: &str) -> bool {
+    if pattern.chars().any(|c| c.is_uppercase()) {
+        line.contains(pattern)
+    } else {
+        line.to_lowercase().contains(pattern)
+    }
+}
+
+/// Next (or previous) 1-based line after `from` containing `pattern`, wrapping at the ends.
+fn find_match(lines: &[&str], from: u32, pattern: &str, backward: bool) -> Option<u32> {
+    let n = lines.len();
+    if n == 0 {
+        return None;
+    }
+    let start = (from as usize).clamp(1, n) - 1;
+    (1..=n)
+        .map(|step| {
+            if backward {
+                (start + n - step % n) % n
+            } else {
+                (start + step) % n
+            }
+        })
+        .find(|&i| line_matches(lines[i], pattern))
+        .map(|i| i as u32 + 1)
+}
+
+fn highlight(line: &str, pattern: &str) -> String {
+    if pattern.is_empty() || !style::enabled() {
+        return line.to_string();
+    }
+    // Lowercasing can change byte lengths outside ASCII; only highlight when it does not.
+    let smart = !pattern.chars().any(|c| c.is_uppercase());
+    let hay = if smart {
+        line.to_lowercase()
+    } else {
+        line.to_string()
+    };
+    if hay.len() != line.len() {
+        return line.to_string();
+    }
+    let mut out = String::new();
+    let mut pos = 0;
+    for (start, m) in hay.match_indices(pattern) {
+        out.push_str(&line[pos..start]);
+        out.push_str(&style::paint(
+            &line[start..start + m.len()],
+            theme().search_match,
+        ));
+        pos = start + m.len();
+    }
+    out.push_str(&line[pos..]);
+    out
+}
+
+/// Two-column gutter: breakpoint marker (● enabled, ○ disabled) then current-line arrow.
+fn gutter(line: u32, bp_lines: &[(u32, bool)], current: Option<u32>, color: bool) -> String {
+    let enabled = bp_lines
+        .iter()
+        .filter(|(l, _)| *l == line)
+        .map(|(_, e)| *e)
+        .reduce(|a, b| a || b);
+    let bp = match enabled {
+        Some(true) => style::paint_if(color, "●", theme().changed),
+        Some(false) => style::paint_if(color, "○", theme().unavailable),
+        None => " ".to_string(),
+    };
+    let here = if current == Some(line) {
+        style::paint_if(color, ">", theme().location)
+    } else {
+        " ".to_string()
+    };
+    format!("{}{}", bp, here)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gutter_marks_breakpoints_and_current_line() {
+        let bps = [(3, true), (9, true), (12, false)];
+        assert_eq!(gutter(3, &bps, Some(3), false), "●>");
+        assert_eq!(gutter(9, &bps, Some(3), false), "● ");
+        assert_eq!(gutter(4, &bps, Some(3), false), "  ");
+        assert_eq!(gutter(12, &bps, None, false), "○ ");
+        let painted = gutter(3, &bps, Some(3), true);
+        assert!(painted.starts_with("\x1b[") && painted.contains('●'));
+    }
+
+    #[test]
+    fn search_wraps_and_uses_smart_case() {
+        let lines = ["int main(void)", "  Node *head;", "  free(head);", "}"];
+        assert_eq!(find_match(&lines, 2, "head", false), Some(3));
+        assert_eq!(find_match(&lines, 3, "head", false), Some(2));
+        assert_eq!(find_match(&lines, 2, "head", true), Some(3));
+        assert_eq!(find_match(&lines, 1, "node", false), Some(2));
+        assert_eq!(find_match(&lines, 1, "NODE", false), None);
+    }
+}
@@ -0,0 +1,461 @@
+// `stack [n]`: a frame's saved return address and frame pointer, read from the slots the
+// unwinder names (`info frame`) or, without it, from the frame pointer chain. Both are checked
+// against the process map: a return address must land in executable code, a saved frame
+// pointer higher up on the same stack. Anything else is what an overflow leaves behind.
+//
+// `stack usage`: the stack depth sampled at every stop, with the deepest point overall and
+// per function, so a run can be checked against a small stack budget.
+//
+// `stack canary`: the stack protector's slot, found by scanning the frame for the thread's
+// reference canary; watched slots are re-read at every stop, so an overflow shows up at the
+// stop after it happens instead of as `__stack_chk_fail` at the function's return.
+use super::args::read_args;
+use crate::mi::parser::bytes_to_u64;
+use crate::mi::{AddressSymbol, MiSession, Result};
+use crate::vm::VmRegion;
+use std::collections::HashMap;
+
+/// Frame pointer registers across ABIs (x86-64, i386, aarch64, riscv, arm).
+const FRAME_POINTERS: &[&str] = &["rbp", "ebp", "x29", "fp", "s0", "r11", "r7"];
+/// Where the reference canary lives, tried in order.
+const CANARY_SOURCES: &[&str] = &[
+    // x86-64 glibc: `stack_guard` in the thread control block.
+    "*(unsigned long *)($fs_base + 0x28)",
+    // i386 glibc.
+    "*(unsigned int *)($gs_base + 0x14)",
+    // aarch64, arm, riscv and others keep a global.
+    "(unsigned long)__stack_chk_guard",
+];
+/// Largest frame scanned for the canary.
+const MAX_FRAME_SCAN: u64 = 64 * 1024;
+
+/// A saved register and the stack slot holding it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedSlot {
+    pub register: String,
+    pub slot: u64,
+    /// `None` when the slot does not read.
+    pub value: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FrameView {
+    pub level: u32,
+    pub func: Option<String>,
+    /// Canonical frame address: the caller's stack pointer before the call.
+    pub cfa: Option<u64>,
+    /// The slots were assumed from the frame pointer, not listed by the unwinder.
+    pub from_fp_chain: bool,
+    pub ret: Option<SavedSlot>,
+    pub saved_fp: Option<SavedSlot>,
+    /// Other callee-saved registers.
+    pub others: Vec<SavedSlot>,
+    /// Symbol of the return address, when it lies in a mapped file.
+    pub ret_symbol: Option<AddressSymbol>,
+    /// Arguments the caller passed on the stack, as (name, slot).
+    pub stack_args: Vec<(String, u64)>,
+    /// Why the saved values look wrong; empty when they check out.
+    pub issues: Vec<String>,
+}
+
+/// Read the selected frame's saved slots and check them against `regions`. `outermost` frames
+/// may end the chain with a zero return address or frame pointer.
+pub fn read_frame_view(
+    session: &mut MiSession,
+    regions: &[VmRegion],
+    level: u32,
+    func: Option<String>,
+    outermost: bool,
+) -> Result<FrameView> {
+    session.ensure_word_size();
+    let w = session.word_size as u64;
+    let record = session.frame_record().unwrap_or_default();
+    let mut view = FrameView {
+        level,
+        func,
+        cfa: record.cfa,
+        from_fp_chain: record.saved.is_empty(),
+        ret: None,
+        saved_fp: None,
+        others: Vec::new(),
+        ret_symbol: None,
+        stack_args: Vec::new(),
+        issues: Vec::new(),
+    };
+    if view.from_fp_chain {
+        // No unwinder listing (lldb, or no CFI): assume the usual frame record, the caller's
+        // frame pointer at [fp] and the return address right above it.
+        let fp = session.eval_expr_u64("$fp")?;
+        view.saved_fp = Some(saved_slot(session, "fp", fp));
+        view.ret = Some(saved_slot(session, "pc", fp + w));
+        view.cfa = Some(fp + 2 * w);
+    } else {
+        for (register, slot) in &record.saved {
+            let saved = saved_slot(session, register, *slot);
+            if record.pc_register.as_deref() == Some(register.as_str()) {
+                view.ret = Some(saved);
+            } else if view.saved_fp.is_none() && FRAME_POINTERS.contains(&register.as_str()) {
+                view.saved_fp = Some(saved);
+            } else {
+                view.others.push(saved);
+            }
+        }
+    }
+    if let Some(ret) = view.ret.as_ref().and_then(|s| s.value) {
+        let in_file = regions
+            .iter()
+            .find(|r| r.contains(ret))
+            .and_then(|r| r.file_offset(ret));
+        if in_file.is_some() {
+            view.ret_symbol = session.symbol_at(ret).ok().flatten();
+        }
+    }
+    if let Ok(args) = read_args(session, level, view.func.clone()) {
+        view.stack_args = args.stack_slots();
+    }
+    view.issues = frame_issues(regions, &view, outermost);
+    Ok(view)
+}
+
+fn saved_slot(session: &mut MiSession, register: &str, slot: u64) -> SavedSlot {
+    SavedSlot {
+        register: register.to_string(),
+        slot,
+        value: session.read_pointer_at(slot, None).ok(),
+    }
+}
+
+/// What is wrong with the frame's saved return address and frame pointer.
+fn frame_issues(regions: &[VmRegion], view: &FrameView, outermost: bool) -> Vec<String> {
+    let mut issues = Vec::new();
+    let region = |addr: u64| regions.iter().find(|r| r.contains(addr));
+    match &view.ret {
+        Some(SavedSlot {
+            slot, value: None, ..
+        }) => issues.push(format!("return address slot 0x{:x} does not read", slot)),
+        Some(SavedSlot {
+            value: Some(ret), ..
+        }) => match region(*ret) {
+            None if *ret == 0 && outermost => {}
+            None => issues.push(format!(
+                "return address 0x{:x} is not mapped: the slot was likely overwritten",
+                ret
+            )),
+            Some(r) if !r.perms.contains('x') => issues.push(format!(
+                "return address 0x{:x} points into non-executable {}: the slot was likely overwritten",
+                ret,
+                r.label.tag()
+            )),
+            Some(r) if r.perms.contains('w') => issues.push(format!(
+                "return address 0x{:x} points into writable and executable {}",
+                ret,
+                r.label.tag()
+            )),
+            Some(_) => {}
+        },
+        None => {}
+    }
+    if let Some(SavedSlot {
+        register,
+        slot,
+        value: Some(fp),
+    }) = &view.saved_fp
+    {
+        let stack = region(*slot);
+        if *fp != 0 {
+            if stack.is_none_or(|s| !s.contains(*fp)) {
+                issues.push(format!(
+                    "saved {} 0x{:x} is not on this stack (fine if the caller uses it as a general register)",
+                    register, fp
+                ));
+            } else if fp <= slot {
+                issues.push(format!(
+                    "saved {} 0x{:x} is below its own slot; the caller's frame should be higher",
+                    register, fp
+                ));
+            }
+        }
+    }
+    issues
+}
+
+/// A frame's stack protector slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanarySlot {
+    pub func: Option<String>,
+    pub cfa: u64,
+    pub slot: u64,
+    /// Value at the last read.
+    pub value: u64,
+}
+
+/// A watched canary slot whose value changed since the previous stop.
+#[derive(Debug, Clone)]
+pub struct CanaryAlert {
+    /// The slot, with the new value.
+    pub slot: CanarySlot,
+    pub old: u64,
+    pub reference: u64,
+}
+
+/// `stack canary on`: slots found at stops so far, re-read at every stop while their frame
+/// is live.
+#[derive(Default)]
+pub struct CanaryWatch {
+    pub active: bool,
+    pub slots: Vec<CanarySlot>,
+}
+
+impl CanaryWatch {
+    /// Forget the slots (the program exited or was restarted).
+    pub fn reset(&mut self) {
+        self.slots.clear();
+    }
+
+    /// Re-read the slots of frames still on the stack, then add frame 0's. Returns the slots
+    /// whose value changed.
+    pub fn check(&mut self, session: &mut MiSession) -> Result<Vec<CanaryAlert>> {
+        let reference = canary_reference(session)?;
+        let sp = session.eval_expr_u64("$sp")?;
+        let live = session.list_frames()?;
+        // Frames below the stack pointer, or no longer in the backtrace, have returned.
+        self.slots
+            .retain(|s| s.slot >= sp && live.iter().any(|f| f.func == s.func));
+        let mut alerts = Vec::new();
+        for s in &mut self.slots {
+            let Ok(now) = session.read_pointer_at(s.slot, None) else {
+                continue;
+            };
+            if now != s.value {
+                let old = std::mem::replace(&mut s.value, now);
+                alerts.push(CanaryAlert {
+                    slot: s.clone(),
+                    old,
+                    reference,
+                });
+            }
+        }
+        let func = live.first().and_then(|f| f.func.clone());
+        if let Some(found) = find_canary(session, reference, func)? {
+            if !self.slots.iter().any(|s| s.slot == found.slot) {
+                self.slots.push(found);
+            }
+        }
+        Ok(alerts)
+    }
+}
+
+/// The thread's reference canary, which every protected frame copies into its slot.
+pub fn canary_reference(session: &mut MiSession) -> Result<u64> {
+    for source in CANARY_SOURCES {
+        match session.eval_expr_u64(source) {
+            Ok(value) if value != 0 => return Ok(value),
+            _ => {}
+        }
+    }
+    Err("no stack protector canary found (fs/gs thread block or __stack_chk_guard)".into())
+}
+
+/// The selected frame's canary slot: the highest word between the stack pointer and the CFA
+/// holding `reference`. `None` when the function is not protected (or the slot was already
+/// overwritten).
+pub fn find_canary(
+    session: &mut MiSession,
+    reference: u64,
+    func: Option<String>,
+) -> Result<Option<CanarySlot>> {
+    session.ensure_word_size();
+    let w = session.word_size as u64;
+    let sp = session.eval_expr_u64("$sp")?;
+    let cfa = frame_cfa(session)?;
+    if cfa <= sp || cfa - sp > MAX_FRAME_SCAN {
+        return Ok(None);
+    }
+    let bytes = session.read_memory(sp, (cfa - sp) as usize)?;
+    let words: Vec<u64> = bytes
+        .chunks_exact(w as usize)
+        .map(|b| bytes_to_u64(b, session.endian))
+        .collect();
+    Ok(canary_index(&words, reference).map(|i| CanarySlot {
+        func,
+        cfa,
+        slot: sp + i as u64 * w,
+        value: reference,
+    }))
+}
+
+/// The selected frame's CFA, from the unwinder or else assumed from the frame pointer.
+pub fn frame_cfa(session: &mut MiSession) -> Result<u64> {
+    if let Some(cfa) = session.frame_record().ok().and_then(|r| r.cfa) {
+        return Ok(cfa);
+    }
+    session.ensure_word_size();
+    Ok(session.eval_expr_u64("$fp")? + 2 * session.word_size as u64)
+}
+
+/// Index of the highest word equal to `reference`: compilers put the canary right below the
+/// saved registers, above the locals it protects.
+fn canary_index(words: &[u64], reference: u64) -> Option<usize> {
+    words.iter().rposition(|w| *w == reference)
+}
+
+/// Deepest point of the stack at a stop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsagePeak {
+    pub bytes: u64,
+    pub sp: u64,
+    pub func: Option<String>,
+    pub stop: u64,
+}
+
+/// `stack usage`: how deep the stack was at each stop of the current run.
+#[derive(Default)]
+pub struct StackUsage {
+    /// `$sp` at the first stop: the base when the stack's mapping is unknown (remote targets).
+    first_sp: Option<u64>,
+    pub samples: u64,
+    pub peak: Option<UsagePeak>,
+    /// Deepest usage seen while stopped in each function.
+    pub by_func: HashMap<String, u64>,
+    /// The last depth was measured from the top of the stack's mapping, not the first stop.
+    pub from_map: bool,
+    /// Peak of the previous run.
+    pub previous: Option<UsagePeak>,
+}
+
+impl StackUsage {
+    /// Record a stop in `func` with the stack pointer at `sp`; `top` is the highest address
+    /// of the stack's mapping, when known.
+    pub fn observe(&mut self, sp: u64, top: Option<u64>, func: Option<&str>, stop: u64) {
+        let first = *self.first_sp.get_or_insert(sp);
+        self.from_map = top.is_some();
+        let bytes = top.unwrap_or(first).saturating_sub(sp);
+        self.samples += 1;
+        if let Some(func) = func {
+            let deepest = self.by_func.entry(func.to_string()).or_default();
+            *deepest = (*deepest).max(bytes);
+        }
+        if self.peak.as_ref().is_none_or(|p| bytes > p.bytes) {
+            self.peak = Some(UsagePeak {
+                bytes,
+                sp,
+                func: func.map(str::to_string),
+                stop,
+            });
+        }
+    }
+
+    /// Start over for a new run, keeping this run's peak as `previous`.
+    pub fn reset(&mut self) {
+        let previous = self.peak.take().or(self.previous.take());
+        *self = StackUsage {
+            previous,
+            ..Default::default()
+        };
+    }
+
+    /// Functions by the deepest stack seen in them, deepest first.
+    pub fn functions(&self) -> Vec<(&str, u64)> {
+        let mut funcs: Vec<(&str, u64)> = self
+            .by_func
+            .iter()
+            .map(|(f, bytes)| (f.as_str(), *bytes))
+            .collect();
+        funcs.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        funcs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::VmLabel;
+
+    fn region(start: u64, end: u64, perms: &str, label: VmLabel) -> VmRegion {
+        VmRegion {
+            start,
+            end,
+            perms: perms.to_string(),
+            offset: None,
+            pathname: String::new(),
+            label,
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn stack_usage_keeps_the_deepest_stop_per_function() {
+        let mut usage = StackUsage::default();
+        // No mapping: depths count from the first stop.
+        usage.observe(0x2000_8000, None, Some("main"), 1);
+        usage.observe(0x2000_7e00, None, Some("parse"), 2);
+        usage.observe(0x2000_7f00, None, Some("parse"), 3);
+        usage.observe(0x2000_7f80, None, Some("main"), 4);
+        assert_eq!(usage.functions(), [("parse", 0x200), ("main", 0x80)]);
+        let peak = usage.peak.clone().unwrap();
+        assert_eq!((peak.bytes, peak.stop), (0x200, 2));
+        usage.reset();
+        assert_eq!(usage.previous, Some(peak));
+        assert_eq!(usage.samples, 0);
+        usage.observe(0x7ffe_f000, Some(0x7fff_0000), None, 1);
+        assert_eq!(usage.peak.unwrap().bytes, 0x1000);
+    }
+
+    #[test]
+    fn canary_is_the_highest_matching_word() {
+        let reference = 0x9c3a_51d0_77e2_4b00;
+        // locals, a stale copy of the canary, the slot, saved rbp, return address
+        let words = [
+            0x41,
+            reference,
+            0x4141,
+            reference,
+            0x7ffe_0000_e110,
+            0x401136,
+        ];
+        assert_eq!(canary_index(&words, reference), Some(3));
+        assert_eq!(canary_index(&words[..1], reference), None);
+    }
+
+    #[test]
+    fn smashed_return_addresses_and_frame_pointers_are_flagged() {
+        let regions = vec![
+            region(0x401000, 0x402000, "r-xp", VmLabel::Text),
+            region(0x405000, 0x426000, "rw-p", VmLabel::Heap),
+            region(0x7ffffffde000, 0x7ffffffff000, "rw-p", VmLabel::Stack),
+        ];
+        let slot = |register: &str, slot: u64, value: u64| SavedSlot {
+            register: register.to_string(),
+            slot,
+            value: Some(value),
+        };
+        let mut view = FrameView {
+            level: 1,
+            func: Some("parse".to_string()),
+            cfa: Some(0x7fffffffe0f0),
+            from_fp_chain: false,
+            ret: Some(slot("rip", 0x7fffffffe0e8, 0x401136)),
+            saved_fp: Some(slot("rbp", 0x7fffffffe0e0, 0x7fffffffe110)),
+            others: Vec::new(),
+            ret_symbol: None,
+            stack_args: Vec::new(),
+            issues: Vec::new(),
+        };
+        assert!(frame_issues(&regions, &view, false).is_empty());
+        view.ret = Some(slot("rip", 0x7fffffffe0e8, 0x4141414141414141));
+        view.saved_fp = Some(slot("rbp", 0x7fffffffe0e0, 0x4141414141414141));
+        let issues = frame_issues(&regions, &view, false);
+        assert_eq!(issues.len(), 2);
+        assert!(issues[0].contains("not mapped"));
+        assert!(issues[1].contains("not on this stack"));
+        view.ret = Some(slot("rip", 0x7fffffffe0e8, 0x7fffffffe200));
+        view.saved_fp = Some(slot("rbp", 0x7fffffffe0e0, 0x7fffffffe0c0));
+        let issues = frame_issues(&regions, &view, false);
+        assert!(issues[0].contains("non-executable [stack]"));
+        assert!(issues[1].contains("below its own slot"));
+        // The outermost frame ends the chain with zeros.
+        view.ret = Some(slot("rip", 0x7fffffffe0e8, 0));
+        view.saved_fp = Some(slot("rbp", 0x7fffffffe0e0, 0));
+        assert!(frame_issues(&regions, &view, true).is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+// libstdc++ containers: recognized by type name and shown as what they hold (length,
+// capacity, elements) next to the raw bytes. Counts come from libstdc++'s member layout;
+// elements from gdb's own value printing, which uses the pretty printers when loaded.
+use super::style;
+use super::theme::theme;
+use crate::mi::MiSession;
+
+/// Elements read by hand for a vector when gdb prints it as a raw struct.
+const MAX_ELEMENTS: u64 = 16;
+/// Longest logical value shown before it is cut.
+const MAX_VALUE_CHARS: usize = 400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StlKind {
+    String,
+    Vector,
+    /// map, multimap, set, multiset (red-black tree).
+    Tree,
+    List,
+    /// unordered_map, unordered_set and their multi variants (hashtable).
+    Hash,
+    Deque,
+}
+
+/// Container kind for a type name as gdb prints it (`std::vector<int, std::allocator<int> >`,
+/// `std::__cxx11::basic_string<char, ...>`, typedefs like `std::string`).
+pub fn detect(type_name: &str) -> Option<StlKind> {
+    let t = type_name
+        .trim()
+        .trim_start_matches("const ")
+        .trim_start_matches("volatile ")
+        // `ptype` headers: "class std::vector<int, ...> : protected std::_Vector_base<...>"
+        .trim_start_matches("class ")
+        .trim_start_matches("struct ")
+        .replace("std::__cxx11::", "std::")
+        .replace("std::__debug::", "std::");
+    if t.ends_with('*') || t.ends_with('&') {
+        return None;
+    }
+    let name = t.split('<').next().unwrap_or("").trim();
+    match name {
+        "std::string" | "std::basic_string" | "std::wstring" => Some(StlKind::String),
+        "std::vector" => Some(StlKind::Vector),
+        "std::map" | "std::multimap" | "std::set" | "std::multiset" => Some(StlKind::Tree),
+        "std::list" => Some(StlKind::List),
+        "std::unordered_map"
+        | "std::unordered_multimap"
+        | "std::unordered_set"
+        | "std::unordered_multiset" => Some(StlKind::Hash),
+        "std::deque" => Some(StlKind::Deque),
+        _ => None,
+    }
+}
+
+/// Logical view of one container.
+#[derive(Debug, Default)]
+pub struct StlSummary {
+    pub size: Option<u64>,
+    pub capacity: Option<u64>,
+    /// Start of the element storage (string/vector).
+    pub data: Option<u64>,
+    /// Elements as gdb prints them.
+    pub elements: Option<String>,
+}
+
+/// Read the container's counts from libstdc++'s members and its elements from gdb.
+pub fn summarize(session: &mut MiSession, expr: &str, kind: StlKind) -> StlSummary {
+    // `{0}` is the container expression.
+    let mut eval = |template: &str| {
+        session
+            .eval_expr_u64(&template.replace("{0}", &format!("({})", expr)))
+            .ok()
+    };
+    let mut s = StlSummary::default();
+    match kind {
+        StlKind::String => {
+            s.size = eval("{0}._M_string_length");
+            s.data = eval("{0}._M_dataplus._M_p");
+            // Short strings live in the object itself (SSO), in `_M_local_buf`.
+            s.capacity = if s.data.is_some() && s.data == eval("&{0}._M_local_buf") {
+                Some(15)
+            } else {
+                eval("{0}._M_allocated_capacity")
+            };
+        }
+        StlKind::Vector => {
+            s.data = eval("{0}._M_impl._M_start");
+            s.size = eval("{0}._M_impl._M_finish - {0}._M_impl._M_start");
+            s.capacity = eval("{0}._M_impl._M_end_of_storage - {0}._M_impl._M_start");
+        }
+        StlKind::Tree => s.size = eval("{0}._M_t._M_impl._M_node_count"),
+        StlKind::List => s.size = eval("{0}._M_impl._M_node._M_size"),
+        StlKind::Hash => {
+            s.size = eval("{0}._M_h._M_element_count");
+            s.capacity = eval("{0}._M_h._M_bucket_count");
+        }
+        StlKind::Deque => {}
+    }
+    s.elements = session.evaluate_expression(expr).ok();
+    // Without pretty printers a vector prints as its _M_impl struct; read the elements.
+    if kind == StlKind::Vector && s.elements.as_deref().is_none_or(|v| v.contains("_M_impl")) {
+        if let Some(n) = s.size.filter(|n| *n > 0) {
+            let shown = n.min(MAX_ELEMENTS);
+            let read = format!("*({})._M_impl._M_start@{}", expr, shown);
+            s.elements = session.evaluate_expression(&read).ok().map(|v| {
+                if shown < n {
+                    format!("{} ... ({} more)", v, n - shown)
+                } else {
+                    v
+                }
+            });
+        }
+    }
+    s
+}
+
+/// Print the logical view under `view`'s layout.
+pub fn print_summary(type_name: &str, kind: StlKind, s: &StlSummary) {
+    let mut parts = Vec::new();
+    if let Some(n) = s.size {
+        parts.push(format!("size {}", n));
+    }
+    if let Some(c) = s.capacity {
+        let label = if kind == StlKind::Hash {
+            "buckets"
+        } else {
+            "capacity"
+        };
+        parts.push(format!("{} {}", label, c));
+    }
+    if let Some(d) = s.data {
+        parts.push(format!(
+            "data {}",
+            style::paint(&format!("0x{:x}", d), theme().pointer)
+        ));
+    }
+    println!(
+        "\n{} {}",
+        style::paint("logical (libstdc++):", theme().header),
+        type_name
+    );
+    if !parts.is_empty() {
+        println!("  {}", parts.join(", "));
+    }
+    if let Some(v) = &s.elements {
+        let shown: String = v.chars().take(MAX_VALUE_CHARS).collect();
+        let cut = if shown.len() < v.len() { " ..." } else { "" };
+        println!("  = {}{}", shown, cut);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_containers_from_gdb_type_names() {
+        assert_eq!(
+            detect("std::vector<int, std::allocator<int> >"),
+            Some(StlKind::Vector)
+        );
+        assert_eq!(
+            detect(
+                "std::__cxx11::basic_string<char, std::char_traits<char>, std::allocator<char> >"
+            ),
+            Some(StlKind::String)
+        );
+        assert_eq!(detect("const std::string"), Some(StlKind::String));
+        assert_eq!(
+            detect(
+                "class std::vector<int, std::allocator<int> > : protected std::_Vector_base<int>"
+            ),
+            Some(StlKind::Vector)
+        );
+        assert_eq!(
+            detect("std::map<int, std::__cxx11::basic_string<char> >"),
+            Some(StlKind::Tree)
+        );
+        assert_eq!(detect("std::unordered_set<long>"), Some(StlKind::Hash));
+        assert_eq!(detect("std::vector<int> *"), None);
+        assert_eq!(detect("struct Node"), None);
+    }
+}
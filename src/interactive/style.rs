@@ -0,0 +1,129 @@
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+/// Basic 16-color ANSI palette; kept small so output stays readable on any terminal.
+/// `Rgb` comes only from user theme files and needs a truecolor terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    Bold,
+    Dim,
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    fn code(self) -> String {
+        let code = match self {
+            Color::Red => "31",
+            Color::Green => "32",
+            Color::Yellow => "33",
+            Color::Blue => "34",
+            Color::Magenta => "35",
+            Color::Cyan => "36",
+            Color::BrightRed => "91",
+            Color::BrightGreen => "92",
+            Color::BrightYellow => "93",
+            Color::BrightBlue => "94",
+            Color::BrightMagenta => "95",
+            Color::Bold => "1",
+            Color::Dim => "2",
+            Color::Rgb(r, g, b) => return format!("38;2;{};{};{}", r, g, b),
+        };
+        code.to_string()
+    }
+
+    /// Parse a theme-file color: `#rrggbb` or a palette name such as `bright-red`.
+    pub fn parse(raw: &str) -> Option<Self> {
+        if let Some(hex) = raw.strip_prefix('#') {
+            if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+        }
+        Some(match raw {
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "bright-red" => Color::BrightRed,
+            "bright-green" => Color::BrightGreen,
+            "bright-yellow" => Color::BrightYellow,
+            "bright-blue" => Color::BrightBlue,
+            "bright-magenta" => Color::BrightMagenta,
+            "bold" => Color::Bold,
+            "dim" => Color::Dim,
+            _ => return None,
+        })
+    }
+}
+
+/// `--color` setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Color when stdout is a terminal and `NO_COLOR` is unset.
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "auto" => Some(ColorMode::Auto),
+            "always" => Some(ColorMode::Always),
+            "never" => Some(ColorMode::Never),
+            _ => None,
+        }
+    }
+}
+
+static MODE: AtomicU8 = AtomicU8::new(0);
+static AUTO_DETECTED: OnceLock<bool> = OnceLock::new();
+
+pub fn set_mode(mode: ColorMode) {
+    let raw = match mode {
+        ColorMode::Auto => 0,
+        ColorMode::Always => 1,
+        ColorMode::Never => 2,
+    };
+    MODE.store(raw, Ordering::Relaxed);
+}
+
+/// Whether ANSI styling should be emitted.
+pub fn enabled() -> bool {
+    match MODE.load(Ordering::Relaxed) {
+        1 => true,
+        2 => false,
+        _ => *AUTO_DETECTED.get_or_init(|| {
+            std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+        }),
+    }
+}
+
+/// Wrap `text` in the escape sequence for `color` when styling is enabled.
+pub fn paint(text: &str, color: Color) -> String {
+    paint_if(enabled(), text, color)
+}
+
+/// `paint` with the styling decision made by the caller.
+pub fn paint_if(on: bool, text: &str, color: Color) -> String {
+    if on {
+        format!("\x1b[{}m{}\x1b[0m", color.code(), text)
+    } else {
+        text.to_string()
+    }
+}
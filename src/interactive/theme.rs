@@ -0,0 +1,338 @@
+use super::style::Color;
+use crate::vm::VmLabel;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+/// Colors used by the CLI printers, grouped by role rather than by command.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub header: Color,
+    pub pointer: Color,
+    pub changed: Color,
+    pub unavailable: Color,
+    pub location: Color,
+    pub region_text: Color,
+    pub region_data: Color,
+    pub region_heap: Color,
+    pub region_stack: Color,
+    pub region_lib: Color,
+    pub region_anon: Color,
+    pub region_other: Color,
+    /// Cycled through for struct fields / array elements in annotated dumps.
+    pub field_palette: [Color; 8],
+    pub padding: Color,
+    pub out_of_bounds: Color,
+    pub chunk_header: Color,
+    pub warning: Color,
+    pub search_match: Color,
+}
+
+impl Theme {
+    pub fn region(&self, label: &VmLabel) -> Color {
+        match label {
+            VmLabel::Text => self.region_text,
+            VmLabel::Data => self.region_data,
+            VmLabel::Heap => self.region_heap,
+            VmLabel::Stack | VmLabel::ThreadStack(_) => self.region_stack,
+            VmLabel::Lib => self.region_lib,
+            VmLabel::Anonymous => self.region_anon,
+            VmLabel::Other(_) | VmLabel::Guard => self.region_other,
+        }
+    }
+
+    pub fn field(&self, idx: usize) -> Color {
+        self.field_palette[idx % self.field_palette.len()]
+    }
+}
+
+pub static THEME_DARK: Theme = Theme {
+    header: Color::Bold,
+    pointer: Color::BrightBlue,
+    changed: Color::BrightRed,
+    unavailable: Color::Dim,
+    location: Color::BrightYellow,
+    region_text: Color::Magenta,
+    region_data: Color::Cyan,
+    region_heap: Color::Green,
+    region_stack: Color::Yellow,
+    region_lib: Color::BrightBlue,
+    region_anon: Color::Dim,
+    region_other: Color::Dim,
+    field_palette: [
+        Color::Cyan,
+        Color::Green,
+        Color::Yellow,
+        Color::Magenta,
+        Color::BrightBlue,
+        Color::BrightGreen,
+        Color::BrightYellow,
+        Color::BrightMagenta,
+    ],
+    padding: Color::Dim,
+    out_of_bounds: Color::Red,
+    chunk_header: Color::Blue,
+    warning: Color::Yellow,
+    search_match: Color::BrightGreen,
+};
+
+/// Palette for light terminal backgrounds: no yellows, which wash out on white.
+pub static THEME_LIGHT: Theme = Theme {
+    header: Color::Bold,
+    pointer: Color::Blue,
+    changed: Color::Red,
+    unavailable: Color::Dim,
+    location: Color::Magenta,
+    region_text: Color::Magenta,
+    region_data: Color::Cyan,
+    region_heap: Color::Green,
+    region_stack: Color::Blue,
+    region_lib: Color::BrightBlue,
+    region_anon: Color::Dim,
+    region_other: Color::Dim,
+    field_palette: [
+        Color::Cyan,
+        Color::Green,
+        Color::Magenta,
+        Color::Blue,
+        Color::Red,
+        Color::BrightBlue,
+        Color::BrightMagenta,
+        Color::BrightGreen,
+    ],
+    padding: Color::Dim,
+    out_of_bounds: Color::Red,
+    chunk_header: Color::Rgb(175, 95, 0),
+    warning: Color::Magenta,
+    search_match: Color::Green,
+};
+
+/// `--theme` / `theme` setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeChoice {
+    Dark,
+    Light,
+    /// Loaded from `--theme-file`.
+    Custom,
+}
+
+impl ThemeChoice {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "dark" => Some(ThemeChoice::Dark),
+            "light" => Some(ThemeChoice::Light),
+            "custom" => Some(ThemeChoice::Custom),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ThemeChoice::Dark => "dark",
+            ThemeChoice::Light => "light",
+            ThemeChoice::Custom => "custom",
+        }
+    }
+}
+
+static CHOICE: AtomicU8 = AtomicU8::new(0);
+static CUSTOM: OnceLock<Theme> = OnceLock::new();
+
+/// Select a theme. `Custom` is refused until a theme file has been loaded.
+pub fn set_theme(choice: ThemeChoice) -> bool {
+    let raw = match choice {
+        ThemeChoice::Dark => 0,
+        ThemeChoice::Light => 1,
+        ThemeChoice::Custom if CUSTOM.get().is_some() => 2,
+        ThemeChoice::Custom => return false,
+    };
+    CHOICE.store(raw, Ordering::Relaxed);
+    true
+}
+
+pub fn current_choice() -> ThemeChoice {
+    match CHOICE.load(Ordering::Relaxed) {
+        1 => ThemeChoice::Light,
+        2 => ThemeChoice::Custom,
+        _ => ThemeChoice::Dark,
+    }
+}
+
+pub fn theme() -> &'static Theme {
+    match current_choice() {
+        ThemeChoice::Dark => &THEME_DARK,
+        ThemeChoice::Light => &THEME_LIGHT,
+        ThemeChoice::Custom => CUSTOM.get().unwrap_or(&THEME_DARK),
+    }
+}
+
+/// Load a theme file and make it the active theme. Bad lines are skipped (their keys keep
+/// the base theme's color) and returned as warnings; an unreadable file is an error and
+/// leaves the built-in theme in place.
+pub fn load_theme_file(path: &str) -> Result<Vec<String>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    let (custom, warnings) = parse_theme_file(&text, current_choice());
+    CUSTOM
+        .set(custom)
+        .map_err(|_| "a theme file is already loaded".to_string())?;
+    set_theme(ThemeChoice::Custom);
+    Ok(warnings)
+}
+
+/// Parse the flat TOML subset used by theme files:
+///
+/// ```toml
+/// base = "light"              # built-in theme to start from
+/// region_heap = "#00af5f"     # hex RGB or a palette name ("bright-red", "dim", ...)
+/// field_palette = ["cyan", "#ff8700"]
+/// ```
+///
+/// A `[theme]` table header is accepted and ignored.
+fn parse_theme_file(text: &str, default_base: ThemeChoice) -> (Theme, Vec<String>) {
+    let mut warnings = Vec::new();
+    let entries: Vec<(usize, &str, &str)> = text
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let line = strip_comment(line).trim();
+            if line.is_empty() || (line.starts_with('[') && line.ends_with(']')) {
+                return None;
+            }
+            match line.split_once('=') {
+                Some((k, v)) => Some((i + 1, k.trim(), v.trim())),
+                None => {
+                    warnings.push(format!("line {}: expected 'key = value'", i + 1));
+                    None
+                }
+            }
+        })
+        .collect();
+
+    let base = entries
+        .iter()
+        .find(|(_, k, _)| *k == "base")
+        .and_then(|(_, _, v)| ThemeChoice::parse(unquote(v)?))
+        .unwrap_or(default_base);
+    let mut theme = match base {
+        ThemeChoice::Light => THEME_LIGHT.clone(),
+        _ => THEME_DARK.clone(),
+    };
+
+    for (line_no, key, value) in entries {
+        if key == "base" {
+            if !matches!(
+                unquote(value).and_then(ThemeChoice::parse),
+                Some(ThemeChoice::Dark | ThemeChoice::Light)
+            ) {
+                warnings.push(format!(
+                    "line {}: base must be \"dark\" or \"light\"",
+                    line_no
+                ));
+            }
+            continue;
+        }
+        if key == "field_palette" {
+            match parse_palette(value) {
+                Some(colors) => theme.field_palette[..colors.len()].copy_from_slice(&colors),
+                None => warnings.push(format!(
+                    "line {}: field_palette must be a list of 1-8 colors",
+                    line_no
+                )),
+            }
+            continue;
+        }
+        let Some(slot) = theme.slot_mut(key) else {
+            warnings.push(format!("line {}: unknown key '{}'", line_no, key));
+            continue;
+        };
+        match unquote(value).and_then(Color::parse) {
+            Some(color) => *slot = color,
+            None => warnings.push(format!(
+                "line {}: invalid color {} for {}",
+                line_no, value, key
+            )),
+        }
+    }
+    (theme, warnings)
+}
+
+impl Theme {
+    /// Single-color field by its theme-file key.
+    fn slot_mut(&mut self, key: &str) -> Option<&mut Color> {
+        Some(match key {
+            "header" => &mut self.header,
+            "pointer" => &mut self.pointer,
+            "changed" => &mut self.changed,
+            "unavailable" => &mut self.unavailable,
+            "location" => &mut self.location,
+            "region_text" => &mut self.region_text,
+            "region_data" => &mut self.region_data,
+            "region_heap" => &mut self.region_heap,
+            "region_stack" => &mut self.region_stack,
+            "region_lib" => &mut self.region_lib,
+            "region_anon" => &mut self.region_anon,
+            "region_other" => &mut self.region_other,
+            "padding" => &mut self.padding,
+            "out_of_bounds" => &mut self.out_of_bounds,
+            "chunk_header" => &mut self.chunk_header,
+            "warning" => &mut self.warning,
+            "search_match" => &mut self.search_match,
+            _ => return None,
+        })
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    // '#' also starts hex colors, so only a '#' outside quotes begins a comment.
+    let mut in_str = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_str = !in_str,
+            '#' if !in_str => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn unquote(v: &str) -> Option<&str> {
+    v.strip_prefix('"')?.strip_suffix('"')
+}
+
+fn parse_palette(value: &str) -> Option<Vec<Color>> {
+    let inner = value.strip_prefix('[')?.strip_suffix(']')?;
+    let colors: Option<Vec<Color>> = inner
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| unquote(s).and_then(Color::parse))
+        .collect();
+    colors.filter(|c| (1..=8).contains(&c.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_file_overrides_and_reports_bad_lines() {
+        let text = r##"
+[theme]
+base = "light"
+region_heap = "#00af5f"   # greener heap
+region_stack = "bright-red"
+pointer = "#12"
+shiny = "red"
+field_palette = ["cyan", "#ff8700"]
+"##;
+        let (theme, warnings) = parse_theme_file(text, ThemeChoice::Dark);
+        assert_eq!(theme.region_heap, Color::Rgb(0x00, 0xaf, 0x5f));
+        assert_eq!(theme.region_stack, Color::BrightRed);
+        // Invalid value falls back to the base (light) theme.
+        assert_eq!(theme.pointer, THEME_LIGHT.pointer);
+        assert_eq!(theme.field_palette[1], Color::Rgb(0xff, 0x87, 0x00));
+        assert_eq!(theme.field_palette[2], THEME_LIGHT.field_palette[2]);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("line 6:"));
+        assert!(warnings[1].contains("unknown key 'shiny'"));
+    }
+}
@@ -0,0 +1,284 @@
+use super::style;
+use super::theme::theme;
+use crate::mi::parser::bytes_to_u64;
+use crate::mi::{Endian, MiSession, Result};
+use crate::types::{is_pointer_type, strip_pointer_suffix, FieldLayout, TypeLayout};
+use std::collections::HashSet;
+
+/// Upper bound on nodes visited per walk so a corrupted structure cannot hang a stop.
+const MAX_NODES: usize = 10_000;
+/// Field names tried (in order) for the key shown as min/max.
+const KEY_NAMES: &[&str] = &["key", "value", "val", "data", "id"];
+/// Pointer fields that point back up/left in a tree and must not be walked as children.
+const BACK_LINKS: &[&str] = &["parent", "prev", "up"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShapeKind {
+    List,
+    Tree,
+}
+
+impl ShapeKind {
+    fn label(self) -> &'static str {
+        match self {
+            ShapeKind::List => "list",
+            ShapeKind::Tree => "tree",
+        }
+    }
+}
+
+/// Where the interesting parts of one node live, derived from its struct layout.
+#[derive(Debug, Clone)]
+struct NodeShape {
+    size: usize,
+    /// (offset, size) of each link followed during the walk.
+    links: Vec<(usize, usize)>,
+    /// (offset, size, signed) of the key field.
+    key: Option<(usize, usize, bool)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShapeSummary {
+    pub nodes: usize,
+    /// Longest root-to-leaf path in nodes (equals `nodes` for an acyclic list).
+    pub height: usize,
+    pub min_key: Option<i64>,
+    pub max_key: Option<i64>,
+    /// A node was reached twice (list cycle or shared tree node).
+    pub cycle: bool,
+    /// The walk stopped at `MAX_NODES` or on an unreadable node.
+    pub truncated: bool,
+}
+
+/// A data structure registered with `track`, re-summarized after every stop.
+pub struct TrackedShape {
+    pub kind: ShapeKind,
+    pub expr: String,
+    node_type: String,
+    shape: NodeShape,
+    last: Option<ShapeSummary>,
+    /// Stop number at which the summary last changed.
+    last_change: Option<u64>,
+}
+
+impl TrackedShape {
+    pub fn new(session: &mut MiSession, kind: ShapeKind, expr: &str) -> Result<Self> {
+        let (ty, _) = session.eval_expr_type_and_value(expr)?;
+        if !is_pointer_type(&ty) {
+            return Err(format!("'{}' is not a pointer (type '{}')", expr, ty).into());
+        }
+        let node_type = strip_pointer_suffix(&ty);
+        let fields = match session.fetch_layout_for_type(&node_type) {
+            Some(TypeLayout::Struct { fields, .. }) => fields,
+            _ => return Err(format!("cannot obtain struct layout for '{}'", node_type).into()),
+        };
+        let size = session.evaluate_sizeof(&node_type)?;
+        let shape = node_shape(kind, &node_type, &fields, size)
+            .ok_or_else(|| format!("{} has no link field for a {}", node_type, kind.label()))?;
+        Ok(Self {
+            kind,
+            expr: expr.to_string(),
+            node_type,
+            shape,
+            last: None,
+            last_change: None,
+        })
+    }
+
+    /// Walk the structure from the current value of `expr` and remember when it changed.
+    pub fn refresh(&mut self, session: &mut MiSession, stop: u64) -> Result<&ShapeSummary> {
+        let root = session.eval_expr_u64(&format!("(unsigned long)({})", self.expr))?;
+        session.ensure_endian();
+        let endian = session.endian;
+        let summary = walk(&self.shape, root, endian, |addr, len| {
+            session.read_memory(addr, len).ok()
+        });
+        if self.last.as_ref() != Some(&summary) {
+            self.last_change = Some(stop);
+        }
+        Ok(self.last.insert(summary))
+    }
+
+    /// One-line dashboard for the latest summary.
+    pub fn dashboard(&self, idx: usize) -> String {
+        let title = style::paint(
+            &format!("[track #{} {} {}]", idx, self.kind.label(), self.expr),
+            theme().header,
+        );
+        let s = match &self.last {
+            Some(s) => s,
+            None => return format!("{} <not walked yet>", title),
+        };
+        let mut parts = vec![match self.kind {
+            ShapeKind::List => format!("len={}", s.nodes),
+            ShapeKind::Tree => format!("nodes={} height={}", s.nodes, s.height),
+        }];
+        if let (Some(lo), Some(hi)) = (s.min_key, s.max_key) {
+            parts.push(format!("keys={}..{}", lo, hi));
+        }
+        if s.cycle {
+            parts.push(style::paint("CYCLE", theme().changed));
+        }
+        if s.truncated {
+            parts.push("(truncated)".to_string());
+        }
+        if let Some(stop) = self.last_change {
+            parts.push(format!("changed @stop {}", stop));
+        }
+        format!("{} {} ({})", title, parts.join(" "), self.node_type)
+    }
+}
+
+fn node_shape(
+    kind: ShapeKind,
+    node_type: &str,
+    fields: &[FieldLayout],
+    size: usize,
+) -> Option<NodeShape> {
+    let self_ptr = |f: &&FieldLayout| {
+        is_pointer_type(&f.type_name) && strip_pointer_suffix(&f.type_name) == node_type
+    };
+    let links: Vec<(usize, usize)> = match kind {
+        ShapeKind::List => fields
+            .iter()
+            .find(|f| f.name == "next" && is_pointer_type(&f.type_name))
+            .or_else(|| fields.iter().find(self_ptr))
+            .map(|f| vec![(f.offset, f.size)])
+            .unwrap_or_default(),
+        ShapeKind::Tree => fields
+            .iter()
+            .filter(self_ptr)
+            .filter(|f| !BACK_LINKS.contains(&f.name.as_str()))
+            .map(|f| (f.offset, f.size))
+            .collect(),
+    };
+    if links.is_empty() {
+        return None;
+    }
+    let is_int = |f: &FieldLayout| {
+        !is_pointer_type(&f.type_name)
+            && !f.type_name.contains('[')
+            && (1..=8).contains(&f.size)
+            && !f.type_name.contains("float")
+            && !f.type_name.contains("double")
+    };
+    let key = KEY_NAMES
+        .iter()
+        .find_map(|name| fields.iter().find(|f| f.name == *name && is_int(f)))
+        .or_else(|| fields.iter().find(|f| is_int(f)))
+        .map(|f| (f.offset, f.size, !f.type_name.contains("unsigned")));
+    Some(NodeShape { size, links, key })
+}
+
+/// Walk from `root`, reading each node through `read` (address, length).
+fn walk(
+    shape: &NodeShape,
+    root: u64,
+    endian: Endian,
+    mut read: impl FnMut(u64, usize) -> Option<Vec<u8>>,
+) -> ShapeSummary {
+    let mut summary = ShapeSummary::default();
+    let mut seen = HashSet::new();
+    let mut pending = vec![(root, 1usize)];
+    while let Some((addr, depth)) = pending.pop() {
+        if addr == 0 {
+            continue;
+        }
+        if !seen.insert(addr) {
+            summary.cycle = true;
+            continue;
+        }
+        if summary.nodes >= MAX_NODES {
+            summary.truncated = true;
+            break;
+        }
+        let bytes = match read(addr, shape.size) {
+            Some(b) if b.len() >= shape.size => b,
+            _ => {
+                summary.truncated = true;
+                continue;
+            }
+        };
+        summary.nodes += 1;
+        summary.height = summary.height.max(depth);
+        if let Some((off, size, signed)) = shape.key {
+            let key = read_int(&bytes[off..off + size], endian, signed);
+            summary.min_key = Some(summary.min_key.map_or(key, |m| m.min(key)));
+            summary.max_key = Some(summary.max_key.map_or(key, |m| m.max(key)));
+        }
+        for (off, size) in shape.links.iter().rev() {
+            let next = bytes_to_u64(&bytes[*off..*off + *size], endian);
+            pending.push((next, depth + 1));
+        }
+    }
+    summary
+}
+
+fn read_int(bytes: &[u8], endian: Endian, signed: bool) -> i64 {
+    let raw = bytes_to_u64(bytes, endian);
+    let bits = bytes.len() * 8;
+    if signed && bits < 64 && raw & (1 << (bits - 1)) != 0 {
+        (raw | (!0u64 << bits)) as i64
+    } else {
+        raw as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn field(name: &str, ty: &str, offset: usize, size: usize) -> FieldLayout {
+        FieldLayout {
+            name: name.to_string(),
+            type_name: ty.to_string(),
+            offset,
+            size,
+        }
+    }
+
+    fn node(key: i32, a: u64, b: u64) -> Vec<u8> {
+        let mut v = key.to_le_bytes().to_vec();
+        v.extend_from_slice(&[0; 4]);
+        v.extend_from_slice(&a.to_le_bytes());
+        v.extend_from_slice(&b.to_le_bytes());
+        v
+    }
+
+    #[test]
+    fn tree_walk_reports_height_and_key_range() {
+        let fields = vec![
+            field("key", "int", 0, 4),
+            field("left", "struct T *", 8, 8),
+            field("right", "struct T *", 16, 8),
+        ];
+        let shape = node_shape(ShapeKind::Tree, "struct T", &fields, 24).unwrap();
+        let mem: HashMap<u64, Vec<u8>> = [
+            (0x100, node(5, 0x200, 0x300)),
+            (0x200, node(-3, 0, 0)),
+            (0x300, node(9, 0x400, 0)),
+            (0x400, node(7, 0, 0)),
+        ]
+        .into_iter()
+        .collect();
+        let s = walk(&shape, 0x100, Endian::Little, |a, _| mem.get(&a).cloned());
+        assert_eq!((s.nodes, s.height), (4, 3));
+        assert_eq!((s.min_key, s.max_key), (Some(-3), Some(9)));
+        assert!(!s.cycle && !s.truncated);
+    }
+
+    #[test]
+    fn list_walk_detects_cycles() {
+        let fields = vec![field("val", "int", 0, 4), field("next", "struct L *", 8, 8)];
+        let shape = node_shape(ShapeKind::List, "struct L", &fields, 16).unwrap();
+        let mem: HashMap<u64, Vec<u8>> = [(0x10, node(1, 0x20, 0)), (0x20, node(2, 0x10, 0))]
+            .into_iter()
+            .collect();
+        let s = walk(&shape, 0x10, Endian::Little, |a, len| {
+            mem.get(&a).map(|b| b[..len].to_vec())
+        });
+        assert_eq!(s.nodes, 2);
+        assert!(s.cycle);
+    }
+}
@@ -0,0 +1,137 @@
+use super::style;
+use super::theme::theme;
+use crate::mi::{FrameScope, MiSession};
+use crate::types::is_pointer_type;
+use crate::vm::{self, VmRegion};
+
+/// An expression registered with `display`, re-evaluated after every stop.
+pub struct Watch {
+    pub expr: String,
+    /// Frame level the expression is evaluated in (`display --frame <n>`); the selected
+    /// frame when `None`.
+    pub frame: Option<u32>,
+    prev: Option<String>,
+    curr: Option<String>,
+    curr_stop: Option<u64>,
+}
+
+impl Watch {
+    pub fn new(expr: &str) -> Self {
+        Self {
+            expr: expr.to_string(),
+            frame: None,
+            prev: None,
+            curr: None,
+            curr_stop: None,
+        }
+    }
+
+    /// Record the value seen at `stop` and report whether it differs from the previous stop.
+    /// Re-evaluating within the same stop keeps the earlier baseline.
+    fn observe(&mut self, stop: u64, value: Option<String>) -> bool {
+        if self.curr_stop != Some(stop) {
+            self.prev = self.curr.take();
+            self.curr_stop = Some(stop);
+        }
+        self.curr = value;
+        self.prev.is_some() && self.curr.is_some() && self.prev != self.curr
+    }
+
+    /// One line: value, type, and the VM region of the object (and of its pointee).
+    pub fn render(
+        &mut self,
+        session: &mut MiSession,
+        regions: &[VmRegion],
+        idx: usize,
+        stop: u64,
+    ) -> String {
+        let Some(frame) = self.frame else {
+            return self.render_here(session, regions, idx, stop);
+        };
+        let scope = FrameScope {
+            thread: None,
+            frame,
+        };
+        match session.in_frame(scope, |s| Ok(self.render_here(s, regions, idx, stop))) {
+            Ok(line) => line,
+            Err(e) => {
+                self.observe(stop, None);
+                let msg = format!("<unavailable: {}>", e);
+                format!(
+                    "{} = {}",
+                    self.label(idx),
+                    style::paint(&msg, theme().unavailable)
+                )
+            }
+        }
+    }
+
+    fn label(&self, idx: usize) -> String {
+        match self.frame {
+            Some(f) => format!("[display #{} frame {}] {}", idx, f, self.expr),
+            None => format!("[display #{}] {}", idx, self.expr),
+        }
+    }
+
+    fn render_here(
+        &mut self,
+        session: &mut MiSession,
+        regions: &[VmRegion],
+        idx: usize,
+        stop: u64,
+    ) -> String {
+        let label = self.label(idx);
+        let (ty, value) = match session.eval_expr_type_and_value(&self.expr) {
+            Ok(tv) => tv,
+            Err(e) => {
+                self.observe(stop, None);
+                let msg = format!("<unavailable: {}>", e);
+                return format!("{} = {}", label, style::paint(&msg, theme().unavailable));
+            }
+        };
+        let changed = self.observe(stop, Some(value.clone()));
+        let shown = if changed {
+            style::paint(&value, theme().changed)
+        } else {
+            value
+        };
+        let mut line = format!("{} ({}) = {}", label, ty, shown);
+        if regions.is_empty() {
+            return line;
+        }
+        if let Ok(addr) = session.eval_address_of_expr(&self.expr) {
+            line.push_str(&format!(" {}", region_tag(regions, addr)));
+        }
+        if is_pointer_type(&ty) {
+            if let Ok(target) = session.eval_expr_u64(&format!("(unsigned long)({})", self.expr)) {
+                if target != 0 {
+                    line.push_str(&format!(" -> {}", region_tag(regions, target)));
+                }
+            }
+        }
+        line
+    }
+}
+
+fn region_tag(regions: &[VmRegion], addr: u64) -> String {
+    match regions.iter().find(|r| r.contains(addr)) {
+        Some(r) => style::paint(&vm::classify_addr(regions, addr), theme().region(&r.label)),
+        None => vm::classify_addr(regions, addr),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn observe_compares_against_previous_stop() {
+        let mut w = Watch::new("count");
+        assert!(!w.observe(1, Some("1".to_string())));
+        assert!(w.observe(2, Some("2".to_string())));
+        // Same stop again (e.g. bare `display`): still compared with stop 1.
+        assert!(w.observe(2, Some("2".to_string())));
+        assert!(!w.observe(3, Some("2".to_string())));
+        assert!(!w.observe(4, None));
+    }
+}
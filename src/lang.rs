@@ -0,0 +1,67 @@
+// Target language: Rust binaries are debugged through rust-gdb (or with its pretty printers
+// loaded) so Vec, String and Option print as values instead of raw structs.
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    C,
+    Rust,
+}
+
+impl Lang {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "c" | "c++" | "cpp" => Some(Lang::C),
+            "rust" => Some(Lang::Rust),
+            _ => None,
+        }
+    }
+}
+
+/// Symbols every Rust executable links in.
+const RUST_MARKERS: &[&[u8]] = &[b"rust_begin_unwind", b"rust_panic"];
+
+/// Guess the language from the executable's bytes; anything unrecognized is C.
+pub fn detect(path: &Path) -> Lang {
+    match std::fs::read(path) {
+        Ok(bytes) if is_rust_binary(&bytes) => Lang::Rust,
+        _ => Lang::C,
+    }
+}
+
+fn is_rust_binary(bytes: &[u8]) -> bool {
+    RUST_MARKERS
+        .iter()
+        .any(|m| bytes.windows(m.len()).any(|w| w == *m))
+}
+
+/// `name` as found on PATH, like `which`.
+pub fn find_in_path(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|p| p.is_file())
+}
+
+/// The gdb script shipped with the Rust toolchain that registers its pretty printers.
+pub fn rust_printers_script() -> Option<PathBuf> {
+    let out = Command::new("rustc")
+        .args(["--print", "sysroot"])
+        .output()
+        .ok()?;
+    let sysroot = String::from_utf8(out.stdout).ok()?;
+    let script = Path::new(sysroot.trim()).join("lib/rustlib/etc/gdb_load_rust_pretty_printers.py");
+    script.is_file().then_some(script)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_rust_runtime_symbols() {
+        assert!(is_rust_binary(b"\x7fELF....rust_begin_unwind\0main\0"));
+        assert!(!is_rust_binary(b"\x7fELF....__libc_start_main\0main\0"));
+    }
+}
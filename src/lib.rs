@@ -0,0 +1,49 @@
+//! gdb/MI session and memory-layout machinery behind the `gdb-memviz` binary.
+//!
+//! [`MiSession`] drives a gdb process over the machine interface (breakpoints, stepping,
+//! locals, memory reads), [`types`] turns `ptype /o` output into a [`TypeLayout`], [`vm`]
+//! reads `/proc/<pid>/maps` into labelled regions, and [`symbols`] indexes globals by source
+//! file. Errors are boxed ([`Result`]) with human-readable messages.
+//!
+//! ```no_run
+//! use gdb_memviz::{MiSession, TypeLayout};
+//!
+//! let mut session = MiSession::start("gdb", "./examples/sample", &[], false)?;
+//! session.drain_initial_output()?;
+//! session.run_to("main")?;
+//! for local in session.list_locals()? {
+//!     println!("{} = {:?}", local.name, local.value);
+//! }
+//! if let Some(TypeLayout::Struct { fields, .. }) = session.fetch_layout_for_type("struct Node") {
+//!     for f in fields {
+//!         println!("+{} {} {}", f.offset, f.type_name, f.name);
+//!     }
+//! }
+//! session.shutdown();
+//! # Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+//! ```
+pub mod mi;
+pub mod symbols;
+pub mod types;
+pub mod vm;
+
+// The binary's own modules (CLI, REPL, recording); public only so `main.rs` can use them.
+#[doc(hidden)]
+pub mod cli;
+#[doc(hidden)]
+pub mod compile;
+#[doc(hidden)]
+pub mod doctor;
+#[doc(hidden)]
+pub mod heap;
+#[doc(hidden)]
+pub mod interactive;
+#[doc(hidden)]
+pub mod lang;
+#[doc(hidden)]
+pub mod record;
+#[doc(hidden)]
+pub mod replay;
+
+pub use mi::{MiSession, Result};
+pub use types::TypeLayout;
@@ -1,80 +1,357 @@
-// Entry point wires CLI parsing to the MI session and REPL.
-mod interactive;
-mod mi;
-mod types;
-mod vm;
-
-use mi::{MiResponse, MiSession, Result};
+// Entry point wires CLI parsing to the MI session and REPL (both live in the library).
+use clap::Parser;
+use gdb_memviz::cli::{self, Cli, Mode};
+use gdb_memviz::lang::{self, Lang};
+use gdb_memviz::mi::{BackendKind, DebuggerBackend, MiResponse, MiSession, Result, Rr};
+use gdb_memviz::{compile, doctor, interactive, record, replay, symbols};
+use std::time::Duration;
 
 fn main() -> Result<()> {
-    // Parse CLI: allow --gdb override, verbose MI logging, and forward the remaining args
-    // to the target binary. Exits with usage on missing target.
-    let mut gdb_bin = std::env::var("GDB").unwrap_or_else(|_| "gdb".to_string());
-    let mut verbose = false;
-    let mut target: Option<String> = None;
-    let mut target_args: Vec<String> = Vec::new();
-
-    // Simple flag parser: stops at first non-flag and treats the rest as program+args.
-    let mut iter = std::env::args().skip(1).peekable();
-    while let Some(arg) = iter.next() {
-        match arg.as_str() {
-            "--gdb" => {
-                if let Some(bin) = iter.next() {
-                    gdb_bin = bin;
-                } else {
-                    eprintln!(
-                        "usage: cargo run -- [--verbose|-v] [--gdb <gdb-path>] <target> [args]"
-                    );
-                    std::process::exit(1);
+    let cli = Cli::parse();
+    let opts = &cli.opts;
+    let verbose = opts.verbose;
+    if let Some(mode) = opts.color {
+        interactive::set_color_mode(mode);
+    }
+    interactive::set_json_output(opts.json);
+    if let Some(choice) = opts.theme {
+        interactive::set_theme(choice);
+    }
+    // Loaded after --theme so `--theme light --theme-file x` uses light as the base.
+    if let Some(path) = &opts.theme_file {
+        match interactive::load_theme_file(path) {
+            Ok(warnings) => {
+                for w in warnings {
+                    eprintln!("theme file {}: {}", path, w);
                 }
             }
-            "--verbose" | "-v" => {
-                verbose = true;
-            }
-            _ => {
-                target = Some(arg);
-                target_args.extend(iter);
-                break;
-            }
+            Err(e) => eprintln!("theme file: {} (using built-in theme)", e),
         }
     }
 
-    if target.is_none() {
-        eprintln!("usage: cargo run -- [--verbose|-v] [--gdb <gdb-path>] <target> [args]");
-        std::process::exit(1);
+    // With --replay the recorded command line decides how the session starts; display and
+    // REPL options (--color, --json, --batch, ...) still come from this invocation.
+    let transcript = opts.replay.as_deref().map(|path| {
+        replay::load(path).unwrap_or_else(|e| {
+            eprintln!("replay: {}", e);
+            std::process::exit(1);
+        })
+    });
+    let recorded = transcript.as_ref().map(|t| {
+        Cli::try_parse_from(&t.args).unwrap_or_else(|e| {
+            eprintln!("replay: recorded command line: {}", e);
+            std::process::exit(1);
+        })
+    });
+    let startup = recorded.as_ref().unwrap_or(&cli);
+    let setup = &startup.opts;
+
+    let script = match (&transcript, &opts.script) {
+        (Some(t), _) => t.commands.clone(),
+        (None, Some(path)) => match std::fs::read_to_string(path) {
+            Ok(text) => interactive::parse_script(&text),
+            Err(e) => {
+                eprintln!("script {}: {}", path, e);
+                std::process::exit(1);
+            }
+        },
+        (None, None) => Vec::new(),
+    };
+
+    if let Some(path) = &opts.record {
+        // Settle color detection while stdout is still the terminal, not the recording pipe.
+        interactive::color_enabled();
+        let args: Vec<String> = std::env::args().collect();
+        if let Err(e) = record::start(path, &args) {
+            eprintln!("record {}: {}", path, e);
+            std::process::exit(1);
+        }
     }
-    let target = target.unwrap();
-    if !std::path::Path::new(&target).exists() {
+
+    let mode = startup.mode();
+    let build = match (&transcript, &setup.build) {
+        (None, Some(raw)) => {
+            match compile::resolve_build_command(raw).and_then(|cmd| {
+                compile::run_build(&cmd)?;
+                Ok(cmd)
+            }) {
+                Ok(cmd) => Some(cmd),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    record::finish();
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => None,
+    };
+    // Binary compiled from source targets; removed when the session ends.
+    let mut built = None;
+    // The executable gdb loads symbols from, and the arguments passed to it.
+    let (target, target_args) = match &mode {
+        Mode::Doctor => {
+            let code = doctor::run(&opts.gdb);
+            record::finish();
+            std::process::exit(code)
+        }
+        Mode::Run { command } if transcript.is_none() && compile::is_source(&command[0]) => {
+            let (sources, args) = compile::split_sources(command);
+            let cflags = compile::split_flags(setup.cflags.as_deref());
+            match compile::compile_single_source(&sources, &cflags, setup.std.as_deref()) {
+                Ok(binary) => {
+                    let target = binary.display().to_string();
+                    built = Some(binary);
+                    (target, args)
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    record::finish();
+                    std::process::exit(1);
+                }
+            }
+        }
+        Mode::Run { command } => (command[0].clone(), command[1..].to_vec()),
+        Mode::Attach { pid, exe } => (
+            exe.clone().unwrap_or_else(|| format!("/proc/{}/exe", pid)),
+            Vec::new(),
+        ),
+        Mode::Core { exe, .. } | Mode::Remote { exe, .. } => (exe.clone(), Vec::new()),
+        // Arguments, environment and I/O were fixed when the trace was recorded.
+        Mode::Rr { trace_dir } => (trace_dir.clone(), Vec::new()),
+    };
+    if transcript.is_none() && !std::path::Path::new(&target).exists() {
         eprintln!("target not found: {}", target);
+        record::finish();
         std::process::exit(1);
     }
 
+    let lang = setup
+        .lang
+        .unwrap_or_else(|| lang::detect(std::path::Path::new(&target)));
+    let kind = setup.backend.unwrap_or(BackendKind::Gdb);
+    let backend: Box<dyn DebuggerBackend> = match &mode {
+        Mode::Rr { .. } => Box::new(Rr),
+        _ => kind.backend(),
+    };
+    // rust-gdb wraps gdb with the toolchain's pretty printers; only swap it in for the default.
+    let gdb_bin = if matches!(mode, Mode::Rr { .. }) {
+        backend.default_program()
+    } else if opts.gdb != "gdb" {
+        opts.gdb.as_str()
+    } else if kind == BackendKind::Gdb
+        && lang == Lang::Rust
+        && lang::find_in_path("rust-gdb").is_some()
+    {
+        "rust-gdb"
+    } else {
+        backend.default_program()
+    };
     println!(
-        "[gdb-memviz] gdb: {} | target: {} {:?} | verbose: {}",
-        gdb_bin, target, target_args, verbose
+        "[gdb-memviz] {}: {} | target: {} {:?} | verbose: {}",
+        backend.name(),
+        gdb_bin,
+        target,
+        target_args,
+        verbose
     );
     // Launch gdb/MI and do one-time probing before entering the REPL.
-    let mut session = MiSession::start(&gdb_bin, &target, &target_args, verbose)?;
+    let mut session = match &transcript {
+        Some(t) => {
+            println!("# replaying {} recorded command(s)", t.commands.len());
+            MiSession::replay(
+                backend,
+                Box::new(replay::ReplayInput::new(&t.mi_send)),
+                Box::new(replay::ReplayOutput::new(&t.mi_recv)),
+                &target,
+                &target_args,
+                verbose,
+            )
+        }
+        None => MiSession::start_backend(backend, gdb_bin, &target, &target_args, verbose)?,
+    };
+    if let Some(secs) = setup.timeout {
+        session.timeout = (secs > 0).then(|| Duration::from_secs(secs));
+        session.stop_timeout = session.timeout;
+    }
     session.drain_initial_output()?;
+    if lang == Lang::Rust && kind == BackendKind::Gdb && gdb_bin != "rust-gdb" {
+        match lang::rust_printers_script() {
+            Some(script) => {
+                if let Err(e) = session.source_script(&script) {
+                    eprintln!("rust pretty printers: {}", e);
+                }
+            }
+            None => eprintln!(
+                "Rust target: rust-gdb and the toolchain's pretty printers were not found; \
+                 Vec/String/Option show as raw structs"
+            ),
+        }
+    }
+    if let Some(max) = setup.max_dump {
+        session.max_dump_bytes = max;
+    }
 
     println!("\n# probing gdb");
     let version = session.exec_command("-gdb-version")?;
     let features = session.exec_command("-list-features")?;
     describe_response("version", &version, verbose);
     describe_response("features", &features, verbose);
+    // gdb has every command this tool uses; other backends are checked up front.
+    if kind != BackendKind::Gdb {
+        let missing = session.probe_capabilities()?;
+        if !missing.is_empty() {
+            println!(
+                "{} lacks -{}; commands that need them will report errors",
+                session.backend_name(),
+                missing.join(", -")
+            );
+        }
+    }
+
+    // Lets Ctrl-C pause a running program with -exec-interrupt; SIGINT is the fallback.
+    if !session.enable_async() && verbose {
+        eprintln!("[gdb-memviz] mi-async unavailable; Ctrl-C interrupts by signal");
+    }
 
-    println!("\n# break main and run");
-    session.run_to_main()?;
+    let start = setup.run_to.as_deref().unwrap_or("main");
+    let ready = match &mode {
+        Mode::Attach { pid, .. } => {
+            println!("\n# attach to {}", pid);
+            session.attach(*pid)?;
+            format!("Attached to process {}.", pid)
+        }
+        Mode::Core { core, .. } => {
+            println!("\n# load core {}", core);
+            session.load_core(core)?;
+            "Core file loaded (no live process: vm and execution commands are unavailable)."
+                .to_string()
+        }
+        Mode::Rr { trace_dir } => {
+            println!("\n# replay {} and continue to {}", trace_dir, start);
+            session.continue_to_start(start)?;
+            format!(
+                "Reached breakpoint at {} (rr replay: reverse-next/step/continue/finish work).",
+                start
+            )
+        }
+        Mode::Remote { address, .. } => {
+            println!("\n# connect to {} and continue to {}", address, start);
+            session.connect_remote(address, start)?;
+            format!("Reached breakpoint at {}.", start)
+        }
+        _ if setup.no_run => {
+            prepare_run(&mut session, setup, &target_args);
+            "Program loaded but not started; set breakpoints, then 'run'.".to_string()
+        }
+        _ => {
+            prepare_run(&mut session, setup, &target_args);
+            println!("\n# break {} and run", start);
+            session.run_to(start)?;
+            format!("Reached breakpoint at {}.", start)
+        }
+    };
+    for loc in &setup.breaks {
+        match session.add_breakpoint(loc) {
+            Ok(bp) => println!("breakpoint {} at {}", bp.number, loc),
+            Err(e) => eprintln!("--break {}: {}", loc, e),
+        }
+    }
+    let ready = match &setup.stop_at {
+        Some(func) if !matches!(mode, Mode::Core { .. }) => match session.continue_to(func) {
+            Ok(loc) if loc.exit.is_some() => {
+                eprintln!("--stop-at {}: program exited before reaching it", func);
+                ready
+            }
+            Ok(loc) => {
+                let at = format!(
+                    "{}:{}",
+                    loc.file.as_deref().unwrap_or("?"),
+                    loc.line.unwrap_or(0)
+                );
+                if loc.func.as_deref() == Some(func.as_str()) {
+                    format!("Stopped at {} ({}).", func, at)
+                } else {
+                    // An earlier --break location was hit first.
+                    format!("Stopped at {} before reaching {}.", at, func)
+                }
+            }
+            Err(e) => {
+                eprintln!("--stop-at {}: {}", func, e);
+                ready
+            }
+        },
+        _ => ready,
+    };
     session.ensure_word_size();
     session.ensure_arch();
     session.ensure_endian();
-    println!("Reached breakpoint at main. Type 'help' for commands.");
+    println!("{} Type 'help' for commands.", ready);
 
-    interactive::repl(&mut session)?;
+    let repl_opts = interactive::ReplOptions {
+        symbol_mode: setup.symbols.unwrap_or(symbols::SymbolIndexMode::DebugOnly),
+        script,
+        batch: opts.batch,
+        replay: transcript.is_some(),
+        build,
+        no_run: setup.no_run && matches!(mode, Mode::Run { .. }),
+    };
+    let failed = interactive::repl(&mut session, &repl_opts)?;
     session.shutdown();
+    if let Some(binary) = built {
+        let _ = std::fs::remove_file(binary);
+    }
+    if opts.batch && failed > 0 {
+        eprintln!("{} command(s) failed", failed);
+        record::finish();
+        std::process::exit(1);
+    }
+    record::finish();
     Ok(())
 }
 
+/// Apply --env/--cwd/--stdin/--tty and output capture before the first run. Failures only warn:
+/// the program can still be debugged without them.
+fn prepare_run(session: &mut MiSession, setup: &cli::Options, args: &[String]) {
+    for var in &setup.envs {
+        if let Err(e) = session.set_environment(var) {
+            eprintln!("--env {}: {}", var, e);
+        }
+    }
+    if let Some(dir) = &setup.cwd {
+        if let Err(e) = session.change_directory(dir) {
+            eprintln!("--cwd {}: {}", dir, e);
+        }
+    }
+    // Resolved here so a relative path means the same file whatever --cwd says.
+    let stdin = setup
+        .stdin
+        .as_ref()
+        .and_then(|path| match std::fs::canonicalize(path) {
+            Ok(p) => Some(p),
+            Err(e) => {
+                eprintln!("--stdin {}: {}", path, e);
+                None
+            }
+        });
+    let on_tty = setup.tty
+        && match session.use_inferior_tty() {
+            Ok(path) => {
+                println!("program terminal: {} (type to it with 'input')", path);
+                true
+            }
+            Err(e) => {
+                eprintln!("--tty: {} (capturing output to a file instead)", e);
+                false
+            }
+        };
+    let capture = !setup.no_capture && !on_tty;
+    if capture || stdin.is_some() {
+        if let Err(e) = session.redirect_inferior_io(args, stdin.as_deref(), capture) {
+            eprintln!("{} (program output stays on the terminal)", e);
+        }
+    }
+}
+
 /// Helper to echo MI responses when verbose is enabled.
 fn describe_response(label: &str, resp: &MiResponse, verbose: bool) {
     if !verbose {
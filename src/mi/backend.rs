@@ -0,0 +1,267 @@
+// Debuggers that speak MI: gdb, and lldb-mi for systems without gdb (macOS). Both take the
+// same MI commands for the core workflow; the differences are how they start and the console
+// (CLI) commands some helpers fall back to.
+
+/// Which debugger to launch (`--backend`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    Gdb,
+    Lldb,
+}
+
+impl BackendKind {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "gdb" => Some(BackendKind::Gdb),
+            "lldb" | "lldb-mi" => Some(BackendKind::Lldb),
+            _ => None,
+        }
+    }
+
+    pub fn backend(self) -> Box<dyn DebuggerBackend> {
+        match self {
+            BackendKind::Gdb => Box::new(Gdb),
+            BackendKind::Lldb => Box::new(LldbMi),
+        }
+    }
+}
+
+/// Console queries some session helpers need; MI has no command for them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleQuery {
+    /// Type layout with field offsets (`ptype /o`).
+    TypeLayout,
+    /// Text containing "process <pid>".
+    ProcessInfo,
+    /// Global variables grouped by file (`info variables`).
+    GlobalVariables,
+    /// A Python statement, for the bridge script.
+    Python,
+    /// A catchpoint on an event (`throw`, `syscall write`, `fork`).
+    Catchpoint,
+    /// The symbol containing an address (`info symbol`).
+    SymbolAt,
+    /// Where the selected frame saved its caller's registers (`info frame`).
+    FrameInfo,
+}
+
+impl ConsoleQuery {
+    pub(crate) fn describe(self) -> &'static str {
+        match self {
+            ConsoleQuery::TypeLayout => "type layouts are",
+            ConsoleQuery::ProcessInfo => "the process id is",
+            ConsoleQuery::GlobalVariables => "the global variable listing is",
+            ConsoleQuery::Python => "Python is",
+            ConsoleQuery::Catchpoint => "catchpoints are",
+            ConsoleQuery::SymbolAt => "symbol lookup by address is",
+            ConsoleQuery::FrameInfo => "the saved register listing is",
+        }
+    }
+}
+
+/// How a particular MI debugger is started and which console commands it understands.
+pub trait DebuggerBackend {
+    /// Name used in messages.
+    fn name(&self) -> &'static str;
+    /// Executable to run when `--gdb` was left at its default.
+    fn default_program(&self) -> &'static str;
+    /// Command-line arguments that start the debugger in MI mode.
+    fn launch_args(&self, target: &str, args: &[String]) -> Vec<String>;
+    /// MI commands sent once the first prompt arrives (e.g. loading the target).
+    fn setup_commands(&self, target: &str, args: &[String]) -> Vec<String>;
+    /// Console command answering `query` about `arg`; `None` when there is no equivalent.
+    fn console_command(&self, query: ConsoleQuery, arg: &str) -> Option<String>;
+    /// Whether `-exec-arguments` goes through a shell, so `<`/`>` redirections work.
+    fn shell_redirection(&self) -> bool;
+    /// Program name matched against gdb's per-file listings (`info variables`).
+    fn program_name(&self, target: &str) -> String {
+        std::path::Path::new(target)
+            .file_name()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_string())
+            .unwrap_or_default()
+    }
+}
+
+pub struct Gdb;
+
+impl DebuggerBackend for Gdb {
+    fn name(&self) -> &'static str {
+        "gdb"
+    }
+
+    fn default_program(&self) -> &'static str {
+        "gdb"
+    }
+
+    fn launch_args(&self, target: &str, args: &[String]) -> Vec<String> {
+        // Quiet banner; target args are passed as-is.
+        let mut out: Vec<String> = ["-q", "-i=mi", "--args", target].map(String::from).to_vec();
+        out.extend(args.iter().cloned());
+        out
+    }
+
+    fn setup_commands(&self, _target: &str, _args: &[String]) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn console_command(&self, query: ConsoleQuery, arg: &str) -> Option<String> {
+        Some(match query {
+            ConsoleQuery::TypeLayout => format!("ptype /o {}", arg),
+            ConsoleQuery::ProcessInfo => "info proc".to_string(),
+            ConsoleQuery::GlobalVariables => "info variables".to_string(),
+            ConsoleQuery::Python => format!("python {}", arg),
+            ConsoleQuery::Catchpoint => format!("catch {}", arg),
+            ConsoleQuery::SymbolAt => format!("info symbol {}", arg),
+            ConsoleQuery::FrameInfo => "info frame".to_string(),
+        })
+    }
+
+    fn shell_redirection(&self) -> bool {
+        true
+    }
+}
+
+pub struct LldbMi;
+
+impl DebuggerBackend for LldbMi {
+    fn name(&self) -> &'static str {
+        "lldb-mi"
+    }
+
+    fn default_program(&self) -> &'static str {
+        "lldb-mi"
+    }
+
+    fn launch_args(&self, _target: &str, _args: &[String]) -> Vec<String> {
+        // lldb-mi has no `--args`; the target is loaded over MI instead.
+        vec!["--interpreter".to_string()]
+    }
+
+    fn setup_commands(&self, target: &str, args: &[String]) -> Vec<String> {
+        let mut cmds = vec![format!(
+            "-file-exec-and-symbols {}",
+            super::parser::mi_escape(target)
+        )];
+        if !args.is_empty() {
+            let quoted: Vec<String> = args.iter().map(|a| super::parser::mi_escape(a)).collect();
+            cmds.push(format!("-exec-arguments {}", quoted.join(" ")));
+        }
+        cmds
+    }
+
+    fn console_command(&self, query: ConsoleQuery, arg: &str) -> Option<String> {
+        match query {
+            // "Process 1234 stopped"
+            ConsoleQuery::ProcessInfo => Some("process status".to_string()),
+            // "Summary: libc.so.6`malloc + 64"
+            ConsoleQuery::SymbolAt => Some(format!("image lookup --address {}", arg)),
+            // lldb prints neither gdb's offset layout nor its per-file variable listing, and
+            // `frame info` has no saved-register slots.
+            ConsoleQuery::TypeLayout
+            | ConsoleQuery::FrameInfo
+            | ConsoleQuery::GlobalVariables
+            | ConsoleQuery::Python
+            | ConsoleQuery::Catchpoint => None,
+        }
+    }
+
+    fn shell_redirection(&self) -> bool {
+        false
+    }
+}
+
+/// `rr replay` of a recorded trace: gdb underneath, with the process already started and
+/// reverse execution available.
+pub struct Rr;
+
+impl DebuggerBackend for Rr {
+    fn name(&self) -> &'static str {
+        "rr"
+    }
+
+    fn default_program(&self) -> &'static str {
+        "rr"
+    }
+
+    fn launch_args(&self, trace_dir: &str, _args: &[String]) -> Vec<String> {
+        // -q: do not replay the program's writes to stdout, which is the MI pipe here.
+        ["replay", "-i=mi", "-q", trace_dir]
+            .map(String::from)
+            .to_vec()
+    }
+
+    fn setup_commands(&self, _target: &str, _args: &[String]) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn console_command(&self, query: ConsoleQuery, arg: &str) -> Option<String> {
+        Gdb.console_command(query, arg)
+    }
+
+    fn shell_redirection(&self) -> bool {
+        // Arguments and I/O are fixed by the recording.
+        false
+    }
+
+    fn program_name(&self, trace_dir: &str) -> String {
+        // rr names traces `<program>-<n>`; `latest-trace` is a symlink to one.
+        let resolved = std::fs::canonicalize(trace_dir)
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|_| trace_dir.to_string());
+        let dir = Gdb.program_name(&resolved);
+        match dir.rsplit_once('-') {
+            Some((name, n)) if n.chars().all(|c| c.is_ascii_digit()) => name.to_string(),
+            _ => dir,
+        }
+    }
+}
+
+/// MI commands probed with `-info-gdb-mi-command` at startup; ones the backend lacks are
+/// refused up front with a clear error instead of an obscure one mid-command.
+pub const PROBED_COMMANDS: &[&str] = &[
+    "symbol-info-variables",
+    "symbol-info-functions",
+    "file-list-exec-source-files",
+    "data-read-memory-bytes",
+    "data-write-memory-bytes",
+    "data-list-register-values",
+    "var-list-children",
+    "stack-select-frame",
+    "environment-cd",
+    "target-attach",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lldb_loads_the_target_over_mi() {
+        let args = vec!["a b".to_string()];
+        let gdb = BackendKind::Gdb.backend();
+        assert_eq!(
+            gdb.launch_args("./prog", &args),
+            ["-q", "-i=mi", "--args", "./prog", "a b"]
+        );
+        assert!(gdb.setup_commands("./prog", &args).is_empty());
+        let lldb = BackendKind::parse("lldb").unwrap().backend();
+        assert_eq!(lldb.launch_args("./prog", &args), ["--interpreter"]);
+        assert_eq!(
+            lldb.setup_commands("./prog", &args),
+            [
+                "-file-exec-and-symbols \"./prog\"",
+                "-exec-arguments \"a b\""
+            ]
+        );
+        assert_eq!(
+            lldb.console_command(ConsoleQuery::TypeLayout, "struct Node"),
+            None
+        );
+        assert_eq!(
+            Rr.program_name("/home/u/.local/share/rr/sample-3"),
+            "sample"
+        );
+        assert_eq!(Rr.program_name("/traces/latest-trace"), "latest-trace");
+    }
+}
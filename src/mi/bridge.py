@@ -0,0 +1,144 @@
+# gdb-memviz Python bridge: sourced once into gdb, then called with one-line `python` commands.
+# Each call prints a single "@@memviz-json <json>" line that the session picks out of the
+# console stream; failures come back as {"error": "..."}.
+import json
+import re
+
+import gdb
+
+MEMVIZ_MARKER = "@@memviz-json "
+
+
+def memviz_emit(fn, *args):
+    try:
+        result = fn(*args)
+    except Exception as e:  # anything gdb raises ends up as a readable error
+        result = {"error": str(e)}
+    print(MEMVIZ_MARKER + json.dumps(result))
+
+
+_CODES = {
+    gdb.TYPE_CODE_PTR: "pointer",
+    gdb.TYPE_CODE_ARRAY: "array",
+    gdb.TYPE_CODE_STRUCT: "struct",
+    gdb.TYPE_CODE_UNION: "union",
+    gdb.TYPE_CODE_ENUM: "enum",
+    gdb.TYPE_CODE_FUNC: "func",
+    gdb.TYPE_CODE_INT: "int",
+    gdb.TYPE_CODE_FLT: "float",
+    gdb.TYPE_CODE_BOOL: "bool",
+    gdb.TYPE_CODE_CHAR: "char",
+    gdb.TYPE_CODE_TYPEDEF: "typedef",
+}
+
+
+def _type_node(t, depth):
+    node = {
+        "name": str(t),
+        "code": _CODES.get(t.code, "other"),
+        "size": t.sizeof if t.code != gdb.TYPE_CODE_FUNC else None,
+    }
+    if depth <= 0:
+        return node
+    if t.code == gdb.TYPE_CODE_TYPEDEF:
+        node["target"] = _type_node(t.strip_typedefs(), depth)
+    elif t.code == gdb.TYPE_CODE_PTR:
+        # One level less so self-referential structs (lists, trees) terminate.
+        node["target"] = _type_node(t.target(), depth - 1)
+    elif t.code == gdb.TYPE_CODE_ARRAY:
+        lo, hi = t.range()
+        node["len"] = hi - lo + 1
+        node["target"] = _type_node(t.target(), depth)
+    elif t.code in (gdb.TYPE_CODE_STRUCT, gdb.TYPE_CODE_UNION):
+        node["fields"] = [
+            {
+                "name": f.name,
+                # Static members have no bitpos.
+                "offset": f.bitpos // 8 if hasattr(f, "bitpos") else None,
+                "bitsize": f.bitsize or None,
+                "type": _type_node(f.type, depth - 1),
+            }
+            for f in t.fields()
+        ]
+    elif t.code == gdb.TYPE_CODE_ENUM:
+        node["values"] = {f.name: f.enumval for f in t.fields()}
+    return node
+
+
+def _lookup(name):
+    try:
+        return gdb.lookup_type(name)
+    except gdb.error:
+        # Handles "struct Node", qualified and pointer types that lookup_type rejects.
+        return gdb.parse_and_eval("(%s *)0" % name).type.target()
+
+
+def memviz_type_tree(name, depth):
+    return _type_node(_lookup(name), depth)
+
+
+def memviz_function_statics(functions):
+    # Statics declared inside a function live in its block, which `info variables` does not
+    # search. Python cannot reach blocks nested in a function, so only the function's own
+    # scope is listed.
+    out = []
+    for name in functions:
+        sym = gdb.lookup_global_symbol(name) or gdb.lookup_static_symbol(name)
+        if sym is None or sym.addr_class != gdb.SYMBOL_LOC_BLOCK:
+            continue
+        block = gdb.block_for_pc(int(sym.value().address))
+        while block is not None and block.function is None:
+            block = block.superblock
+        if block is None:
+            continue
+        for var in block:
+            if var.addr_class == gdb.SYMBOL_LOC_STATIC and not var.is_argument:
+                address = int(var.value().address)
+                out.append(
+                    {
+                        "function": name,
+                        "name": var.name,
+                        "type": str(var.type),
+                        "file": var.symtab.filename if var.symtab else None,
+                        "line": var.line or None,
+                        "address": address,
+                        "section": _section(address),
+                    }
+                )
+    return out
+
+
+def _section(address):
+    # "counter in section .bss" (or "No symbol matches ...").
+    text = gdb.execute("info symbol %d" % address, to_string=True)
+    m = re.search(r" in section (\S+)", text)
+    return m.group(1) if m else None
+
+
+def _find_global(name, filename):
+    candidates = []
+    if hasattr(gdb, "lookup_static_symbols"):
+        candidates.extend(gdb.lookup_static_symbols(name))
+    else:
+        candidates.append(gdb.lookup_static_symbol(name))
+    candidates.append(gdb.lookup_global_symbol(name))
+    candidates = [c for c in candidates if c is not None]
+    for c in candidates:
+        if c.symtab is not None and c.symtab.filename == filename:
+            return c
+    return candidates[0] if candidates else None
+
+
+def memviz_symbol_addresses(entries):
+    # One [name, file, address] per global ("" and 0 when unknown), answered in order.
+    out = []
+    for name, filename, address in entries:
+        try:
+            if not address:
+                sym = _find_global(name, filename)
+                address = int(sym.value().address) if sym is not None else 0
+            section = _section(address) if address else None
+            out.append({"address": address or None, "section": section})
+        except gdb.error:
+            out.append({"address": None, "section": None})
+    return out
@@ -0,0 +1,42 @@
+// Ctrl-C while the program runs freely: instead of killing memviz, SIGINT only raises a flag
+// that the session polls while it waits for a stop, and then asks gdb to pause the program
+// (`-exec-interrupt`). Front ends with their own key for it call `request`.
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_sigint(_: libc::c_int) {
+    REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Route SIGINT (Ctrl-C) to `request` for the rest of the process.
+pub fn install_handler() {
+    let handler: extern "C" fn(libc::c_int) = on_sigint;
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe.
+    unsafe {
+        libc::signal(libc::SIGINT, handler as libc::sighandler_t);
+    }
+}
+
+/// Ask the session to pause the running program at the next chance.
+pub fn request() {
+    REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Whether an interrupt was requested since the last call.
+pub(crate) fn take() -> bool {
+    REQUESTED.swap(false, Ordering::SeqCst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_taken_once() {
+        take();
+        request();
+        assert!(take());
+        assert!(!take());
+    }
+}
@@ -1,8 +1,32 @@
+//! gdb/MI plumbing: the [`MiSession`] driving gdb, the data it returns, and the record
+//! parsers behind it.
+
+/// Debuggers a session can drive (gdb, lldb-mi).
+pub mod backend;
+/// Ctrl-C handling: pausing the running program instead of exiting.
+pub mod interrupt;
+/// Values returned by [`MiSession`] (locals, memory dumps, stops, breakpoints).
 pub mod models;
+/// Parsers for MI result records (`^done,...`, `*stopped,...`).
 pub mod parser;
+/// Python helper script for data MI cannot provide, with a JSON result channel.
+pub mod python;
+/// Thread reading the debugger's output, and the async events it publishes.
+pub mod reader;
+/// The gdb process and its MI command helpers.
 pub mod session;
+/// Pseudo-terminal for the debugged program's I/O.
+pub mod tty;
+/// Recursive parser for MI records into an [`MiValue`] tree.
+pub mod value;
+/// Variable objects kept across stops and refreshed with `-var-update`.
+pub mod varobj;
 
+pub use backend::{BackendKind, DebuggerBackend, Rr};
 pub use models::{
-    BreakpointInfo, Endian, GlobalVar, LocalVar, MemoryDump, MiResponse, Result, StoppedLocation,
+    AddressSymbol, BreakpointInfo, CStringRead, Endian, FrameInfo, FrameRecord, FrameScope,
+    GlobalVar, LocalVar, MemoryDump, MiResponse, ProgramExit, RegisterValue, Result, SourceFile,
+    StoppedLocation, VarChange, VarNode,
 };
-pub use session::MiSession;
+pub use session::{MiSession, SiblingSpec};
+pub use value::MiValue;
@@ -1,5 +1,7 @@
+/// Errors carry a human-readable message (usually gdb's own) and nothing else.
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
+/// Target byte order, from `show endian` or the architecture name.
 #[derive(Debug, Clone, Copy)]
 pub enum Endian {
     Little,
@@ -7,6 +9,7 @@ pub enum Endian {
     Unknown,
 }
 
+/// One local from `-stack-list-locals`.
 #[derive(Debug, Clone)]
 pub struct LocalVar {
     pub name: String,
@@ -14,6 +17,36 @@ pub struct LocalVar {
     pub value: Option<String>,
 }
 
+/// A NUL-terminated string read from memory by `MiSession::read_c_string`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CStringRead {
+    /// The bytes before the NUL, invalid UTF-8 replaced.
+    pub text: String,
+    /// No NUL within the limit (or before unreadable memory); `text` is only the start.
+    pub truncated: bool,
+}
+
+/// The symbol an address falls in, from `MiSession::symbol_at`: `malloc` and 0x40 for
+/// `malloc+0x40`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressSymbol {
+    pub name: String,
+    /// Bytes past the symbol's start.
+    pub offset: u64,
+}
+
+/// Where the selected frame keeps its caller's registers, from `MiSession::frame_record`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrameRecord {
+    /// Canonical frame address: the caller's stack pointer before the call.
+    pub cfa: Option<u64>,
+    /// Name of the program counter ("rip", "pc"), as gdb calls it in "saved rip = ...".
+    pub pc_register: Option<String>,
+    /// Stack slots holding saved registers, by register name.
+    pub saved: Vec<(String, u64)>,
+}
+
+/// Bytes read at an expression's address by `MiSession::memory_dump`.
 #[derive(Debug, Clone)]
 pub struct MemoryDump {
     pub expr: String,
@@ -25,8 +58,13 @@ pub struct MemoryDump {
     pub endian: Endian,
     pub arch: Option<String>,
     pub truncated_from: Option<usize>,
+    /// Per-byte mask of bytes that could not be read (shown as `??`); empty when all were read.
+    pub unreadable: Vec<bool>,
+    /// Bytes seen at the same address on the previous dump, for change highlighting.
+    pub previous: Option<Vec<u8>>,
 }
 
+/// Where and why the program stopped (`*stopped` record).
 #[derive(Debug, Clone)]
 pub struct StoppedLocation {
     pub func: Option<String>,
@@ -34,16 +72,96 @@ pub struct StoppedLocation {
     pub line: Option<u32>,
     pub reason: Option<String>,
     pub arch: Option<String>,
+    /// Breakpoint number for `breakpoint-hit` stops.
+    pub bkptno: Option<u32>,
+    /// Value returned by the finished function for `function-finished` stops.
+    pub return_value: Option<String>,
+    /// Set when the stop is the program ending (`exited`, `exited-normally`,
+    /// `exited-signalled`); the session is still fine and `run` starts it again.
+    pub exit: Option<ProgramExit>,
+    /// What a catchpoint caught (`syscall write (entry)`, `fork, child 4242`).
+    pub catch: Option<String>,
+}
+
+/// How the program ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramExit {
+    /// Exit status; `None` when a signal killed the program.
+    pub code: Option<i32>,
+    /// Signal that killed it (`SIGSEGV`).
+    pub signal: Option<String>,
+}
+
+/// Thread and frame a command is evaluated in (`--thread`/`--frame`), leaving gdb's selected
+/// frame alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameScope {
+    /// gdb's global thread id; `None` is the current thread.
+    pub thread: Option<u32>,
+    /// Frame level in that thread, 0 being the innermost.
+    pub frame: u32,
+}
+
+/// One entry of `-stack-list-frames`; level 0 is the innermost frame.
+#[derive(Debug, Clone)]
+pub struct FrameInfo {
+    pub level: u32,
+    pub addr: Option<String>,
+    pub func: Option<String>,
+    pub file: Option<String>,
+    /// Absolute source path, when gdb knows it.
+    pub fullname: Option<String>,
+    pub line: Option<u32>,
+}
+
+/// One register as reported by `-data-list-register-values`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterValue {
+    pub name: String,
+    pub value: String,
+}
+
+/// A gdb variable object (`-var-create` / `-var-list-children`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarNode {
+    /// gdb's handle, e.g. `var3.next`.
+    pub name: String,
+    /// Expression relative to the parent: field name, array index, or the original expr.
+    pub exp: String,
+    pub type_name: Option<String>,
+    pub value: Option<String>,
+    pub numchild: u32,
+}
+
+/// One entry of a `-var-update` change list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarChange {
+    pub name: String,
+    pub value: Option<String>,
+    /// False once the expression can no longer be evaluated (out of scope or invalid).
+    pub in_scope: bool,
+    /// Type after a `type_changed` update (floating objects re-evaluated in a new frame).
+    pub new_type: Option<String>,
+    pub new_numchild: Option<u32>,
 }
 
+/// A breakpoint set through `MiSession::add_breakpoint`.
 #[derive(Debug, Clone)]
 pub struct BreakpointInfo {
     pub number: u32,
     pub file: Option<String>,
     pub line: Option<u32>,
     pub func: Option<String>,
+    pub enabled: bool,
+    /// Stops at this breakpoint seen by the REPL.
+    pub hits: u32,
+    /// Event a catchpoint stops on (`exception throw`, `syscall write`); `None` for breakpoints.
+    pub catch: Option<String>,
+    /// Location (or catchpoint event) as it was asked for, to set it again in a new gdb.
+    pub location: Option<String>,
 }
 
+/// Result record of one MI command plus the out-of-band lines that came before it.
 #[derive(Debug, Clone)]
 pub struct MiResponse {
     pub status: MiStatus,
@@ -51,6 +169,7 @@ pub struct MiResponse {
     pub oob: Vec<String>,
 }
 
+/// Result class of an MI response (`^done`, `^running`, `^error`, ...).
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub enum MiStatus {
@@ -60,10 +179,22 @@ pub enum MiStatus {
     Other(String),
 }
 
+/// A source file of the program, from its debug info.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFile {
+    /// Name as compiled (`examples/sample.c`).
+    pub file: String,
+    /// Absolute path, when gdb could resolve it.
+    pub fullname: Option<String>,
+}
+
+/// A global variable with its current value and address.
 #[derive(Debug, Clone)]
 pub struct GlobalVar {
     pub name: String,
     pub type_name: String,
     pub value: String,
     pub address: u64,
+    /// Source file (translation unit) declaring it, when known.
+    pub file: Option<String>,
 }
@@ -1,8 +1,19 @@
 use crate::mi::models::{
-    BreakpointInfo, Endian, LocalVar, MiStatus, StoppedLocation,
+    AddressSymbol, BreakpointInfo, Endian, FrameInfo, FrameRecord, LocalVar, MiStatus, ProgramExit,
+    SourceFile, StoppedLocation, VarChange, VarNode,
 };
+use crate::mi::value::{parse_record, MiValue};
 use regex::Regex;
 
+/// Split the numeric token gdb echoes in front of a record: `12^done` -> `(Some(12), "^done")`.
+pub(crate) fn split_token(line: &str) -> (Option<u64>, &str) {
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    match line[..digits].parse() {
+        Ok(token) => (Some(token), &line[digits..]),
+        Err(_) => (None, line),
+    }
+}
+
 pub(crate) fn parse_status(line: &str) -> MiStatus {
     if line.starts_with("^done") {
         MiStatus::Done
@@ -66,6 +77,31 @@ pub(crate) fn parse_memory_contents(s: &str) -> Result<Vec<u8>, Box<dyn std::err
     Err("no memory contents found".into())
 }
 
+/// A contiguous run of bytes read from the inferior, keyed by its start address.
+pub(crate) type MemoryBlock = (u64, Vec<u8>);
+
+/// Parse every `{begin="0x..",...,contents="aabb"}` block of a `-data-read-memory-bytes`
+/// reply. gdb omits unreadable sub-ranges, so a reply may hold several disjoint blocks.
+pub(crate) fn parse_memory_blocks(
+    s: &str,
+) -> Result<Vec<MemoryBlock>, Box<dyn std::error::Error + Send + Sync>> {
+    let hex = |v: &str| u64::from_str_radix(v.trim_start_matches("0x"), 16);
+    let record = parse_record(s);
+    let mut blocks = Vec::new();
+    for block in record.list("memory") {
+        let (Some(begin), Some(contents)) = (block.str("begin"), block.str("contents")) else {
+            continue;
+        };
+        // `offset` is relative to `begin`; gdb normally reports 0x0.
+        let offset = block.str("offset").and_then(|o| hex(o).ok()).unwrap_or(0);
+        blocks.push((
+            hex(begin)?.wrapping_add(offset),
+            hex_str_to_bytes(contents)?,
+        ));
+    }
+    Ok(blocks)
+}
+
 fn parse_hex_list(list: &str) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
     let mut bytes = Vec::new();
     for part in list.split(',') {
@@ -87,47 +123,46 @@ pub(crate) fn split_hex_bytes(s: &str) -> Vec<u8> {
 }
 
 pub(crate) fn parse_locals(s: &str) -> Vec<LocalVar> {
-    // MI locals are nested records; parse each {...} block and extract name/type/value separately
-    // to avoid order sensitivity.
-    let mut locals = Vec::new();
-    let block_re = Regex::new(r"\{[^}]*\}").ok();
-    let name_re = Regex::new(r#"name="([^"]+)""#).ok();
-    let type_re = Regex::new(r#"type="((?:\\.|[^"])*)""#).ok();
-    let value_re = Regex::new(r#"value="((?:\\.|[^"])*)""#).ok();
-
-    if let (Some(block_re), Some(name_re)) = (block_re, name_re) {
-        for block in block_re.find_iter(s) {
-            let text = block.as_str();
-            if let Some(name_caps) = name_re.captures(text) {
-                let name = name_caps.get(1).map(|m| m.as_str().to_string());
-                if let Some(name) = name {
-                    let ty = type_re
-                        .as_ref()
-                        .and_then(|re| re.captures(text).map(|c| unescape_value(&c[1])));
-                    let value = value_re
-                        .as_ref()
-                        .and_then(|re| re.captures(text).map(|c| unescape_value(&c[1])));
-                    locals.push(LocalVar { name, ty, value });
-                }
-            }
-        }
-    }
+    // `locals=[{name=..,type=..,value=..},...]` (`variables=` for -stack-list-variables);
+    // `--simple-values 0` gives only names: `locals=[name="x",...]`.
+    let record = parse_record(s);
+    let list = record
+        .get("locals")
+        .or_else(|| record.get("variables"))
+        .unwrap_or(&record);
+    vars_from(list)
+}
 
-    if locals.is_empty() {
-        if let Ok(name_re) = Regex::new(r#"name="([^\"]+)""#) {
-            for cap in name_re.captures_iter(s) {
-                if let Some(name) = cap.get(1).map(|m| m.as_str().to_string()) {
-                    let value = parse_value_field(s);
-                    locals.push(LocalVar {
-                        name,
-                        ty: None,
-                        value,
-                    });
-                }
-            }
-        }
+/// Arguments of the one frame asked for with `-stack-list-arguments 2 <n> <n>`:
+/// `stack-args=[frame={level="n",args=[{name=..,type=..,value=..},...]}]`.
+pub(crate) fn parse_frame_args(s: &str) -> Vec<LocalVar> {
+    let record = parse_record(s);
+    match record
+        .list("stack-args")
+        .first()
+        .and_then(|f| f.get("args"))
+    {
+        Some(args) => vars_from(args),
+        None => Vec::new(),
     }
-    locals
+}
+
+fn vars_from(list: &MiValue) -> Vec<LocalVar> {
+    list.items()
+        .into_iter()
+        .filter_map(|item| match item {
+            MiValue::Const(name) => Some(LocalVar {
+                name: name.clone(),
+                ty: None,
+                value: None,
+            }),
+            _ => Some(LocalVar {
+                name: item.str("name")?.to_string(),
+                ty: item.str("type").map(str::to_string),
+                value: item.str("value").map(str::to_string),
+            }),
+        })
+        .collect()
 }
 
 pub(crate) fn parse_usize(s: &str) -> std::result::Result<usize, String> {
@@ -164,7 +199,7 @@ pub(crate) fn parse_hex_byte(raw: &str) -> Option<u8> {
 }
 
 pub(crate) fn hex_str_to_bytes(s: &str) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
-    if s.len() % 2 != 0 {
+    if !s.len().is_multiple_of(2) {
         return Err("odd-length hex string in memory contents".into());
     }
     let mut out = Vec::new();
@@ -243,59 +278,286 @@ pub(crate) fn mi_escape(expr: &str) -> String {
     out
 }
 
+/// Quote one word for the shell gdb uses to start the inferior.
+pub(crate) fn shell_quote(word: &str) -> String {
+    let plain = !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if plain {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+/// Parse `register-names=["rax","rbx",...]`; empty strings mark unused register numbers.
+pub(crate) fn parse_register_names(res: &str) -> Vec<String> {
+    parse_record(res)
+        .list("register-names")
+        .into_iter()
+        .filter_map(|n| n.as_str().map(str::to_string))
+        .collect()
+}
+
+/// Parse `register-values=[{number="0",value="0x1c"},...]` into (number, value) pairs.
+pub(crate) fn parse_register_values(res: &str) -> Vec<(usize, String)> {
+    parse_record(res)
+        .list("register-values")
+        .into_iter()
+        .filter_map(|r| Some((r.parse("number")?, r.str("value")?.to_string())))
+        .collect()
+}
+
 pub(crate) fn parse_stopped(line: &str) -> StoppedLocation {
-    let reason = Regex::new(r#"reason="([^"]+)""#)
-        .ok()
-        .and_then(|re| re.captures(line).map(|c| c[1].to_string()));
-    let func = Regex::new(r#"func="([^"]+)""#)
-        .ok()
-        .and_then(|re| re.captures(line).map(|c| c[1].to_string()));
-    let file = Regex::new(r#"file="([^"]+)""#)
-        .ok()
-        .and_then(|re| re.captures(line).map(|c| c[1].to_string()));
-    let line_no = Regex::new(r#"line="([0-9]+)""#)
-        .ok()
-        .and_then(|re| re.captures(line).and_then(|c| c[1].parse::<u32>().ok()));
-    let arch = Regex::new(r#"arch="([^"]+)""#)
-        .ok()
-        .and_then(|re| re.captures(line).map(|c| c[1].to_string()));
+    let record = parse_record(line);
+    // Location fields sit in `frame={...}`; exits have no frame.
+    let frame = record.get("frame").unwrap_or(&record);
+    let text = |v: &MiValue, name: &str| v.str(name).map(str::to_string);
     StoppedLocation {
-        func,
-        file,
-        line: line_no,
-        reason,
-        arch,
+        func: text(frame, "func"),
+        file: text(frame, "file"),
+        line: frame.parse("line"),
+        reason: text(&record, "reason"),
+        arch: text(frame, "arch"),
+        bkptno: record.parse("bkptno"),
+        return_value: text(&record, "return-value"),
+        exit: program_exit(&record),
+        catch: caught_event(&record),
+    }
+}
+
+/// Exit status or killing signal for the `exited*` stop reasons.
+fn program_exit(record: &MiValue) -> Option<ProgramExit> {
+    let exit = match record.str("reason")? {
+        "exited-normally" => ProgramExit {
+            code: Some(0),
+            signal: None,
+        },
+        "exited" => ProgramExit {
+            // gdb reports the exit status in octal.
+            code: record
+                .str("exit-code")
+                .and_then(|c| i32::from_str_radix(c, 8).ok()),
+            signal: None,
+        },
+        "exited-signalled" => ProgramExit {
+            code: None,
+            signal: record.str("signal-name").map(str::to_string),
+        },
+        _ => return None,
+    };
+    Some(exit)
+}
+
+/// Event behind a catchpoint stop. Exception catchpoints report a plain `breakpoint-hit`;
+/// those are named from the breakpoint table instead.
+fn caught_event(record: &MiValue) -> Option<String> {
+    let reason = record.str("reason")?;
+    match reason {
+        "syscall-entry" | "syscall-return" => {
+            let name = record
+                .str("syscall-name")
+                .or_else(|| record.str("syscall-number"))
+                .unwrap_or("?");
+            let when = reason.trim_start_matches("syscall-");
+            Some(format!("syscall {} ({})", name, when))
+        }
+        "fork" | "vfork" => Some(match record.str("newpid") {
+            Some(pid) => format!("{}, child {}", reason, pid),
+            None => reason.to_string(),
+        }),
+        "exec" => Some(format!("exec {}", record.str("new-exec").unwrap_or("?"))),
+        _ => None,
     }
 }
 
 pub(crate) fn parse_breakpoint(res: &str) -> BreakpointInfo {
-    let num = Regex::new(r#"number="([0-9]+)""#)
-        .ok()
-        .and_then(|re| re.captures(res).and_then(|c| c[1].parse::<u32>().ok()))
-        .unwrap_or(0);
-    let func = Regex::new(r#"func="([^"]+)""#)
-        .ok()
-        .and_then(|re| re.captures(res).map(|c| c[1].to_string()));
-    let file = Regex::new(r#"file="([^"]+)""#)
-        .ok()
-        .and_then(|re| re.captures(res).map(|c| c[1].to_string()));
-    let line = Regex::new(r#"line="([0-9]+)""#)
-        .ok()
-        .and_then(|re| re.captures(res).and_then(|c| c[1].parse::<u32>().ok()));
+    let record = parse_record(res);
+    let bkpt = record.get("bkpt").unwrap_or(&record);
+    // With several locations, func/file/line are per location; show the first.
+    let first = bkpt
+        .get("locations")
+        .and_then(|l| l.items().first().copied())
+        .unwrap_or(bkpt);
+    let field = |name: &str| bkpt.str(name).or_else(|| first.str(name));
     BreakpointInfo {
-        number: num,
-        file,
-        line,
-        func,
+        number: bkpt.parse("number").unwrap_or(0),
+        file: field("file").map(str::to_string),
+        line: field("line").and_then(|l| l.parse().ok()),
+        func: field("func").map(str::to_string),
+        enabled: bkpt.str("enabled") != Some("n"),
+        hits: bkpt.parse("times").unwrap_or(0),
+        catch: catchpoint_event(bkpt),
+        location: bkpt.str("original-location").map(str::to_string),
     }
 }
 
+/// `exception throw`, `syscall write`, `fork` for a catchpoint's `bkpt` tuple.
+fn catchpoint_event(bkpt: &MiValue) -> Option<String> {
+    if bkpt.str("type") != Some("catchpoint") {
+        return None;
+    }
+    let kind = bkpt.str("catch-type");
+    let what = bkpt.str("what").filter(|w| !w.is_empty());
+    Some(match (kind, what) {
+        (Some(k), Some(w)) if !w.contains(k) => format!("{} {}", k, w),
+        (_, Some(w)) => w.to_string(),
+        (Some(k), None) => k.to_string(),
+        (None, None) => "event".to_string(),
+    })
+}
+
+/// Parse `stack=[frame={level="0",addr=..,func=..,file=..,line=..},...]`.
+pub(crate) fn parse_frames(res: &str) -> Vec<FrameInfo> {
+    let text = |f: &MiValue, name: &str| f.str(name).map(str::to_string);
+    parse_record(res)
+        .list("stack")
+        .into_iter()
+        .filter_map(|f| {
+            Some(FrameInfo {
+                level: f.parse("level")?,
+                addr: text(f, "addr"),
+                func: text(f, "func"),
+                file: text(f, "file"),
+                fullname: text(f, "fullname"),
+                line: f.parse("line"),
+            })
+        })
+        .collect()
+}
+
+/// Parse the varobj described by a `-var-create` result.
+pub(crate) fn parse_var_node(res: &str, exp: &str) -> Option<VarNode> {
+    var_node(&parse_record(res), exp)
+}
+
+/// One varobj: the results of `-var-create`, or one `child={...}` of `-var-list-children`.
+fn var_node(v: &MiValue, exp: &str) -> Option<VarNode> {
+    Some(VarNode {
+        name: v.str("name")?.to_string(),
+        exp: v.str("exp").unwrap_or(exp).to_string(),
+        type_name: v.str("type").map(str::to_string),
+        value: v.str("value").map(str::to_string),
+        numchild: v.parse("numchild").unwrap_or(0),
+    })
+}
+
+/// Parse the `children=[child={...},...]` list of `-var-list-children`.
+pub(crate) fn parse_var_children(res: &str) -> Vec<VarNode> {
+    parse_record(res)
+        .list("children")
+        .into_iter()
+        .filter_map(|c| var_node(c, ""))
+        .collect()
+}
+
+/// Parse the `changelist=[{name=..,value=..,in_scope=..,type_changed=..},...]` of `-var-update`.
+pub(crate) fn parse_var_changes(res: &str) -> Vec<VarChange> {
+    parse_record(res)
+        .list("changelist")
+        .into_iter()
+        .filter_map(|c| {
+            Some(VarChange {
+                name: c.str("name")?.to_string(),
+                value: c.str("value").map(str::to_string),
+                in_scope: c.str("in_scope").is_none_or(|s| s == "true"),
+                new_type: c
+                    .str("new_type")
+                    .filter(|_| c.str("type_changed") == Some("true"))
+                    .map(str::to_string),
+                new_numchild: c.parse("new_num_children"),
+            })
+        })
+        .collect()
+}
+
+/// Files from `-file-list-exec-source-files`, without duplicates. Output grouped with
+/// `--group-by-objfile` is narrowed to the group of `objfile` (compared by basename), so
+/// libraries' sources are left out.
+pub(crate) fn parse_source_files(res: &str, objfile: &str) -> Vec<SourceFile> {
+    let record = parse_record(res);
+    let base = |p: &str| p.rsplit('/').next().unwrap_or(p).to_string();
+    let mut entries = Vec::new();
+    for f in record.list("files") {
+        match f.get("sources") {
+            Some(group) if f.str("filename").map(base) == Some(base(objfile)) => {
+                entries.extend(group.items());
+            }
+            Some(_) => {}
+            None => entries.push(f),
+        }
+    }
+    let mut files: Vec<SourceFile> = Vec::new();
+    for f in entries {
+        let Some(file) = f.str("file") else {
+            continue;
+        };
+        let source = SourceFile {
+            file: file.to_string(),
+            fullname: f.str("fullname").map(str::to_string),
+        };
+        if !files.contains(&source) {
+            files.push(source);
+        }
+    }
+    files
+}
+
 pub(crate) fn parse_var_name(s: &str) -> Option<String> {
     Regex::new(r#"name="([^"]+)""#)
         .ok()
         .and_then(|re| re.captures(s).map(|c| c[1].to_string()))
 }
 
+/// The symbol in `info symbol` output ("malloc + 64 in section .text of /lib/libc.so.6",
+/// "main in section .text") or lldb's `image lookup` ("Summary: libc.so.6`malloc + 64");
+/// `None` for "No symbol matches ...".
+pub(crate) fn parse_symbol_at(text: &str) -> Option<AddressSymbol> {
+    let gdb = Regex::new(r"(?m)^(.+?)(?: \+ (\d+))? in section ").ok()?;
+    let lldb = Regex::new(r"(?m)Summary: [^`\n]*`(.+?)(?: \+ (\d+))?(?: at .*)?$").ok()?;
+    let caps = gdb.captures(text).or_else(|| lldb.captures(text))?;
+    Some(AddressSymbol {
+        name: caps[1].trim().to_string(),
+        offset: caps
+            .get(2)
+            .and_then(|m| m.as_str().parse().ok())
+            .unwrap_or(0),
+    })
+}
+
+/// The frame address and saved-register slots in `info frame` output:
+///
+/// ```text
+/// Stack level 0, frame at 0x7fffffffe0f0:
+///  rip = 0x401136 in main (t.c:5); saved rip = 0x7ffff7c29d90
+///  ...
+///  Saved registers:
+///   rbp at 0x7fffffffe0e0, rip at 0x7fffffffe0e8
+/// ```
+pub(crate) fn parse_frame_record(text: &str) -> FrameRecord {
+    let cfa = Regex::new(r"frame at 0x([0-9a-fA-F]+)").ok();
+    let pc = Regex::new(r"saved (\w+) = ").ok();
+    let slot = Regex::new(r"(\w+) at 0x([0-9a-fA-F]+)").ok();
+    let mut record = FrameRecord {
+        cfa: cfa
+            .and_then(|re| re.captures(text))
+            .and_then(|c| u64::from_str_radix(&c[1], 16).ok()),
+        pc_register: pc
+            .and_then(|re| re.captures(text))
+            .map(|c| c[1].to_string()),
+        saved: Vec::new(),
+    };
+    if let (Some((_, regs)), Some(slot)) = (text.split_once("Saved registers:"), slot) {
+        for c in slot.captures_iter(regs) {
+            if let Ok(addr) = u64::from_str_radix(&c[2], 16) {
+                record.saved.push((c[1].to_string(), addr));
+            }
+        }
+    }
+    record
+}
+
 pub(crate) fn parse_endian(val: &str) -> Endian {
     let lower = val.to_ascii_lowercase();
     if lower.contains("little") {
@@ -325,6 +587,19 @@ pub(crate) fn guess_endian_from_arch(arch: &str) -> Option<Endian> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_split_token() {
+        assert_eq!(
+            split_token("12^done,value=\"1\""),
+            (Some(12), "^done,value=\"1\"")
+        );
+        assert_eq!(
+            split_token("*stopped,reason=\"end\""),
+            (None, "*stopped,reason=\"end\"")
+        );
+        assert_eq!(split_token("(gdb)"), (None, "(gdb)"));
+    }
+
     #[test]
     fn test_unescape_value_handles_common_sequences() {
         assert_eq!(unescape_value("foo\\nbar"), "foo\nbar");
@@ -350,6 +625,16 @@ mod tests {
         assert_eq!(c, vec![0xaa, 0xbb, 0xcc]);
     }
 
+    #[test]
+    fn test_parse_memory_blocks_handles_gaps() {
+        let raw = r#"^done,memory=[{begin="0x1000",offset="0x0000000000000000",end="0x1002",contents="aabb"},{begin="0x2000",offset="0x0000000000000000",end="0x2001",contents="cc"}]"#;
+        let blocks = parse_memory_blocks(raw).unwrap();
+        assert_eq!(
+            blocks,
+            vec![(0x1000, vec![0xaa, 0xbb]), (0x2000, vec![0xcc])]
+        );
+    }
+
     #[test]
     fn test_bytes_to_u64_endian() {
         let little = bytes_to_u64(&[0x01, 0x02, 0x03, 0x04], Endian::Little);
@@ -358,6 +643,138 @@ mod tests {
         assert_eq!(big, 0x01020304);
     }
 
+    #[test]
+    fn test_parse_stopped_reads_breakpoint_number() {
+        let raw = r#"*stopped,reason="breakpoint-hit",disp="keep",bkptno="3",frame={addr="0x1",func="malloc",args=[]},thread-id="1""#;
+        let loc = parse_stopped(raw);
+        assert_eq!(loc.reason.as_deref(), Some("breakpoint-hit"));
+        assert_eq!(loc.bkptno, Some(3));
+        assert_eq!(loc.func.as_deref(), Some("malloc"));
+    }
+
+    #[test]
+    fn test_parse_catchpoints_and_their_stops() {
+        let bp = parse_breakpoint(
+            r#"=breakpoint-created,bkpt={number="2",type="catchpoint",disp="keep",enabled="y",what="exception throw",catch-type="throw",thread-groups=["i1"],times="0"}"#,
+        );
+        assert_eq!(bp.number, 2);
+        assert_eq!(bp.catch.as_deref(), Some("exception throw"));
+        let bp = parse_breakpoint(
+            r#"bkpt={number="3",type="catchpoint",disp="keep",enabled="y",what="write",catch-type="syscall",times="0"}"#,
+        );
+        assert_eq!(bp.catch.as_deref(), Some("syscall write"));
+        let loc = parse_stopped(
+            r#"*stopped,reason="syscall-entry",disp="keep",bkptno="3",syscall-number="1",syscall-name="write",frame={addr="0x7ffff7e9c887",func="write",args=[]},thread-id="1""#,
+        );
+        assert_eq!(loc.bkptno, Some(3));
+        assert_eq!(loc.catch.as_deref(), Some("syscall write (entry)"));
+        let loc = parse_stopped(
+            r#"*stopped,reason="fork",disp="keep",bkptno="4",newpid="4242",frame={func="arch_fork"}"#,
+        );
+        assert_eq!(loc.catch.as_deref(), Some("fork, child 4242"));
+        assert_eq!(
+            parse_stopped(r#"*stopped,reason="breakpoint-hit""#).catch,
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_var_children_handles_quoted_braces() {
+        let raw = r#"numchild="2",children=[child={name="var1.name",exp="name",numchild="0",value="0x4006 \"{x}\"",type="char *",thread-id="1"},child={name="var1.next",exp="next",numchild="3",value="0x0",type="struct Node *",thread-id="1"}],has_more="0""#;
+        let kids = parse_var_children(raw);
+        assert_eq!(kids.len(), 2);
+        assert_eq!(kids[0].exp, "name");
+        assert_eq!(kids[0].value.as_deref(), Some("0x4006 \"{x}\""));
+        assert_eq!(kids[1].name, "var1.next");
+        assert_eq!(kids[1].numchild, 3);
+        assert_eq!(kids[1].type_name.as_deref(), Some("struct Node *"));
+    }
+
+    #[test]
+    fn test_parse_source_files_dedups_fullnames() {
+        let raw = r#"^done,files=[{file="a.c",fullname="/src/a.c",debug-fully-read="true"},{file="list.h",fullname="/src/list.h",debug-fully-read="true"},{file="a.c",fullname="/src/a.c",debug-fully-read="true"}]"#;
+        let fullnames: Vec<_> = parse_source_files(raw, "prog")
+            .into_iter()
+            .filter_map(|f| f.fullname)
+            .collect();
+        assert_eq!(fullnames, vec!["/src/a.c", "/src/list.h"]);
+        // Grouped output keeps only the program's own files.
+        let raw = r#"^done,files=[{filename="/build/prog",debug-info="fully-read",sources=[{file="a.c",fullname="/src/a.c",debug-fully-read="true"}]},{filename="/lib/x86_64-linux-gnu/libc.so.6",debug-info="partially-read",sources=[{file="malloc.c",fullname="/glibc/malloc/malloc.c",debug-fully-read="false"}]}]"#;
+        assert_eq!(
+            parse_source_files(raw, "./prog"),
+            vec![SourceFile {
+                file: "a.c".to_string(),
+                fullname: Some("/src/a.c".to_string())
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_stopped_reads_octal_exit_code() {
+        let loc = parse_stopped(r#"*stopped,reason="exited",exit-code="012""#);
+        assert_eq!(loc.reason.as_deref(), Some("exited"));
+        assert_eq!(
+            loc.exit,
+            Some(ProgramExit {
+                code: Some(10),
+                signal: None
+            })
+        );
+        let exit = parse_stopped(r#"*stopped,reason="exited-normally""#).exit;
+        assert_eq!(exit.and_then(|e| e.code), Some(0));
+        let exit = parse_stopped(
+            r#"*stopped,reason="exited-signalled",signal-name="SIGSEGV",signal-meaning="Segmentation fault""#,
+        )
+        .exit
+        .unwrap();
+        assert_eq!((exit.code, exit.signal.as_deref()), (None, Some("SIGSEGV")));
+        assert_eq!(
+            parse_stopped(r#"*stopped,reason="end-stepping-range""#).exit,
+            None
+        );
+    }
+
+    #[test]
+    fn test_shell_quote_wraps_special_words() {
+        assert_eq!(shell_quote("input.txt"), "input.txt");
+        assert_eq!(shell_quote("two words"), "'two words'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn test_parse_stopped_reads_return_value() {
+        let raw = r#"*stopped,reason="function-finished",frame={addr="0x2",func="main",args=[],file="sample.c",line="31"},gdb-result-var="$1",return-value="0x5555 \"hi\"",thread-id="1""#;
+        let loc = parse_stopped(raw);
+        assert_eq!(loc.return_value.as_deref(), Some("0x5555 \"hi\""));
+        assert_eq!(loc.line, Some(31));
+    }
+
+    #[test]
+    fn test_parse_register_names_and_values() {
+        let names = parse_register_names(r#"register-names=["rax","rbx","","rip"]"#);
+        assert_eq!(names, vec!["rax", "rbx", "", "rip"]);
+        let values = parse_register_values(
+            r#"register-values=[{number="0",value="0x1c"},{number="3",value="0x401136"}]"#,
+        );
+        assert_eq!(
+            values,
+            vec![(0, "0x1c".to_string()), (3, "0x401136".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_frames_reads_each_level() {
+        let raw = r#"stack=[frame={level="0",addr="0x401136",func="push",file="list.c",fullname="/t/list.c",line="12",arch="i386:x86-64"},frame={level="1",addr="0x7ffff7dd",func="__libc_start_call_main",from="/lib/libc.so.6",arch="i386:x86-64"}]"#;
+        let frames = parse_frames(raw);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].func.as_deref(), Some("push"));
+        assert_eq!(frames[0].line, Some(12));
+        assert_eq!(frames[0].fullname.as_deref(), Some("/t/list.c"));
+        assert_eq!(frames[1].level, 1);
+        assert!(frames[1].file.is_none());
+    }
+
     #[test]
     fn test_parse_locals_extracts_fields() {
         let raw = r#"{name="x",type="int",value="1"},{name="s",type="char *",value="foo"}"#;
@@ -366,5 +783,51 @@ mod tests {
         assert_eq!(locals[0].name, "x");
         assert_eq!(locals[0].ty.as_deref(), Some("int"));
         assert_eq!(locals[1].value.as_deref(), Some("foo"));
+        // Aggregates get no value with --simple-values.
+        let args = parse_frame_args(
+            r#"^done,stack-args=[frame={level="1",args=[{name="p",type="struct point"},{name="n",type="int",value="3"}]}]"#,
+        );
+        assert_eq!(args.len(), 2);
+        assert!(args[0].value.is_none());
+        assert_eq!(args[1].value.as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn test_parse_symbol_at_reads_gdb_and_lldb_output() {
+        let sym =
+            parse_symbol_at("malloc + 64 in section .text of /lib/x86_64-linux-gnu/libc.so.6\n");
+        assert_eq!(
+            sym,
+            Some(AddressSymbol {
+                name: "malloc".to_string(),
+                offset: 64
+            })
+        );
+        let sym = parse_symbol_at("counter in section .bss\n").unwrap();
+        assert_eq!((sym.name.as_str(), sym.offset), ("counter", 0));
+        let sym = parse_symbol_at(
+            "      Address: libc.so.6[0x0000000000099e40] (libc.so.6.PT_LOAD[1]..text + 526912)\n      Summary: libc.so.6`malloc + 16\n",
+        )
+        .unwrap();
+        assert_eq!((sym.name.as_str(), sym.offset), ("malloc", 16));
+        assert_eq!(parse_symbol_at("No symbol matches 0x1234.\n"), None);
+    }
+
+    #[test]
+    fn frame_record_lists_saved_register_slots() {
+        let record = parse_frame_record(
+            "Stack level 1, frame at 0x7fffffffe0f0:\n rip = 0x401136 in main (t.c:5); saved rip = 0x7ffff7c29d90\n caller of frame at 0x7fffffffe0c0\n source language c.\n Arglist at 0x7fffffffe0e0, args: \n Locals at 0x7fffffffe0e0, Previous frame's sp is 0x7fffffffe0f0\n Saved registers:\n  rbx at 0x7fffffffe0d8, rbp at 0x7fffffffe0e0, rip at 0x7fffffffe0e8\n",
+        );
+        assert_eq!(record.cfa, Some(0x7fffffffe0f0));
+        assert_eq!(record.pc_register.as_deref(), Some("rip"));
+        assert_eq!(
+            record.saved,
+            [
+                ("rbx".to_string(), 0x7fffffffe0d8),
+                ("rbp".to_string(), 0x7fffffffe0e0),
+                ("rip".to_string(), 0x7fffffffe0e8)
+            ]
+        );
+        assert_eq!(parse_frame_record("No stack.\n"), FrameRecord::default());
     }
 }
@@ -0,0 +1,87 @@
+// Python bridge: a helper script sourced into gdb answers questions MI has no clean command
+// for (whole type trees, allocator state) and prints its answer as one JSON line.
+use crate::mi::models::{MiStatus, Result};
+use crate::mi::parser::unescape_value;
+use serde_json::Value;
+
+pub const BRIDGE_SCRIPT: &str = include_str!("bridge.py");
+/// Prefix of the line carrying a call's result; matches `MEMVIZ_MARKER` in the script.
+const MARKER: &str = "@@memviz-json ";
+
+/// Python statement defining the bridge functions. The script travels inline, as a string
+/// literal (JSON string syntax is valid Python), so there is no file on disk for another user
+/// to replace before gdb reads it.
+pub fn load_statement() -> String {
+    format!("exec({})", Value::from(BRIDGE_SCRIPT))
+}
+
+/// Python statement calling bridge function `func` with JSON-encoded `args` (JSON strings,
+/// numbers and lists are valid Python literals).
+pub fn call_statement(func: &str, args: &[Value]) -> String {
+    let mut call = format!("memviz_emit({}", func);
+    for a in args {
+        call.push_str(", ");
+        call.push_str(&a.to_string());
+    }
+    call.push(')');
+    call
+}
+
+/// Pull the result out of a response's console stream (`~"..."` records, which gdb may split
+/// anywhere); `{"error": ...}` results become errors.
+pub fn extract_result(status: &MiStatus, oob: &[String]) -> Result<Value> {
+    if let MiStatus::Error(msg) = status {
+        return Err(format!("python: {}", msg).into());
+    }
+    let console: String = oob
+        .iter()
+        .filter_map(|l| l.strip_prefix("~\"").and_then(|r| r.strip_suffix('"')))
+        .map(unescape_value)
+        .collect();
+    let line = console
+        .lines()
+        .find_map(|l| l.strip_prefix(MARKER))
+        .ok_or("python bridge printed no result (is gdb built with Python?)")?;
+    let value: Value =
+        serde_json::from_str(line).map_err(|e| format!("python bridge: bad JSON: {}", e))?;
+    match value.get("error").and_then(Value::as_str) {
+        Some(err) => Err(err.to_string().into()),
+        None => Ok(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn result_is_reassembled_from_console_records() {
+        assert_eq!(
+            call_statement("memviz_type_tree", &[json!("struct Node"), json!(2)]),
+            "memviz_emit(memviz_type_tree, \"struct Node\", 2)"
+        );
+        let oob = vec![
+            r#"~"@@memviz-json {\"name\": \"struct""#.to_string(),
+            r#"~" Node\", \"size\": 16}\n""#.to_string(),
+            r#"=thread-group-added,id="i1""#.to_string(),
+        ];
+        let value = extract_result(&MiStatus::Done, &oob).unwrap();
+        assert_eq!(value["name"], "struct Node");
+        let oob =
+            vec![r#"~"@@memviz-json {\"error\": \"No symbol \\\"main_arena\\\"\"}\n""#.into()];
+        let err = extract_result(&MiStatus::Done, &oob).unwrap_err();
+        assert_eq!(err.to_string(), "No symbol \"main_arena\"");
+        let load = load_statement();
+        let literal = load
+            .strip_prefix("exec(")
+            .unwrap()
+            .strip_suffix(')')
+            .unwrap();
+        assert!(!literal.contains('\n'));
+        assert_eq!(
+            serde_json::from_str::<String>(literal).unwrap(),
+            BRIDGE_SCRIPT
+        );
+    }
+}
@@ -0,0 +1,157 @@
+// gdb's stdout is read on its own thread. Every line goes to the session over a channel, in
+// order, so commands still see their own result records; async records are also parsed into
+// `MiEvent`s and pushed to subscribers, who see stops and notifications as they arrive
+// instead of only when the session next reads.
+use crate::mi::models::{BreakpointInfo, StoppedLocation};
+use crate::mi::parser::{parse_breakpoint, parse_stopped, split_token};
+use crate::mi::value::parse_record;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Async record delivered to subscribers of a session.
+#[derive(Debug, Clone)]
+pub enum MiEvent {
+    /// `*running`: the program was resumed.
+    Running,
+    /// `*stopped`, including breakpoint hits (`reason`/`bkptno`) and exits.
+    Stopped(StoppedLocation),
+    /// `=library-loaded`, with the library's path.
+    LibraryLoaded(String),
+    /// `=breakpoint-modified`: sent on every hit (`hits` is gdb's count) and on changes.
+    BreakpointModified(BreakpointInfo),
+    /// Any other `*`/`=` record (thread, breakpoint table changes, ...), as gdb sent it.
+    Notify(String),
+    /// The debugger closed its output; no more events follow.
+    Exited,
+}
+
+pub(crate) type Subscribers = Arc<Mutex<Vec<Sender<MiEvent>>>>;
+/// The debugger's most recent stderr lines, oldest first.
+#[derive(Default)]
+pub(crate) struct StderrTail {
+    pub lines: VecDeque<String>,
+    /// The stream ended, so `lines` is final.
+    pub closed: bool,
+}
+
+/// Stderr lines kept for the report when the debugger dies.
+const STDERR_LINES: usize = 10;
+
+/// Start the reader thread on `output`. Lines arrive on the returned receiver; it closes
+/// once the output ends, after an error line when reading failed.
+pub(crate) fn spawn(
+    output: Box<dyn Read + Send>,
+    subscribers: Subscribers,
+) -> Receiver<io::Result<String>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut output = BufReader::new(output);
+        loop {
+            let mut line = String::new();
+            match output.read_line(&mut line) {
+                Ok(0) => break,
+                Ok(_) => {
+                    if let Some(event) = parse_event(line.trim()) {
+                        publish(&subscribers, event);
+                    }
+                    if tx.send(Ok(line)).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(e));
+                    break;
+                }
+            }
+        }
+        publish(&subscribers, MiEvent::Exited);
+    });
+    rx
+}
+
+/// Keep the last lines of the debugger's stderr, which would otherwise fill its pipe.
+pub(crate) fn spawn_stderr(stderr: Box<dyn Read + Send>) -> Arc<Mutex<StderrTail>> {
+    let tail = Arc::new(Mutex::new(StderrTail::default()));
+    let sink = Arc::clone(&tail);
+    thread::spawn(move || {
+        for line in BufReader::new(stderr).lines() {
+            let Ok(line) = line else { break };
+            if let Ok(mut tail) = sink.lock() {
+                if tail.lines.len() == STDERR_LINES {
+                    tail.lines.pop_front();
+                }
+                tail.lines.push_back(line);
+            }
+        }
+        if let Ok(mut tail) = sink.lock() {
+            tail.closed = true;
+        }
+    });
+    tail
+}
+
+/// Send `event` to every subscriber, forgetting the ones that hung up.
+fn publish(subscribers: &Subscribers, event: MiEvent) {
+    if let Ok(mut subs) = subscribers.lock() {
+        subs.retain(|s| s.send(event.clone()).is_ok());
+    }
+}
+
+/// Event for an async (`*`/`=`) record; `None` for results, stream output and prompts.
+pub(crate) fn parse_event(line: &str) -> Option<MiEvent> {
+    let record = split_token(line).1;
+    if record.starts_with("*running") {
+        Some(MiEvent::Running)
+    } else if record.starts_with("*stopped") {
+        Some(MiEvent::Stopped(parse_stopped(record)))
+    } else if record.starts_with("=library-loaded") {
+        let path = parse_record(record)
+            .str("target-name")
+            .unwrap_or("")
+            .to_string();
+        Some(MiEvent::LibraryLoaded(path))
+    } else if record.starts_with("=breakpoint-modified") {
+        Some(MiEvent::BreakpointModified(parse_breakpoint(record)))
+    } else if record.starts_with('*') || record.starts_with('=') {
+        Some(MiEvent::Notify(record.to_string()))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn async_records_reach_subscribers_in_order() {
+        let output = "=library-loaded,id=\"/lib/libc.so.6\",target-name=\"/lib/libc.so.6\"\n\
+                      3^running\n*running,thread-id=\"all\"\n(gdb)\n\
+                      =breakpoint-modified,bkpt={number=\"2\",enabled=\"y\",times=\"1\"}\n\
+                      *stopped,reason=\"breakpoint-hit\",bkptno=\"2\",frame={func=\"helper\"}\n";
+        let (tx, events) = mpsc::channel();
+        let lines = spawn(
+            Box::new(io::Cursor::new(output.as_bytes().to_vec())),
+            Arc::new(Mutex::new(vec![tx])),
+        );
+        assert_eq!(lines.iter().count(), 6);
+        let events: Vec<MiEvent> = events.iter().collect();
+        assert!(matches!(&events[0], MiEvent::LibraryLoaded(p) if p == "/lib/libc.so.6"));
+        assert!(matches!(events[1], MiEvent::Running));
+        assert!(
+            matches!(&events[2], MiEvent::BreakpointModified(bp) if bp.number == 2 && bp.hits == 1)
+        );
+        match &events[3] {
+            MiEvent::Stopped(stop) => {
+                assert_eq!(stop.reason.as_deref(), Some("breakpoint-hit"));
+                assert_eq!(stop.bkptno, Some(2));
+            }
+            other => panic!("expected a stop, got {:?}", other),
+        }
+        assert!(matches!(events[4], MiEvent::Exited));
+        assert_eq!(events.len(), 5);
+    }
+}
@@ -1,76 +1,410 @@
+use crate::mi::backend::{ConsoleQuery, DebuggerBackend, Gdb, PROBED_COMMANDS};
+use crate::mi::interrupt;
 use crate::mi::models::{
-    BreakpointInfo, Endian, GlobalVar, LocalVar, MemoryDump, MiResponse, MiStatus, Result,
-    StoppedLocation,
+    AddressSymbol, BreakpointInfo, CStringRead, Endian, FrameInfo, FrameRecord, FrameScope,
+    GlobalVar, LocalVar, MemoryDump, MiResponse, MiStatus, RegisterValue, Result, SourceFile,
+    StoppedLocation, VarChange, VarNode,
 };
 use crate::mi::parser::{
     bytes_to_u64, guess_endian_from_arch, mi_escape, parse_addr_field, parse_breakpoint,
-    parse_endian, parse_locals, parse_memory_contents, parse_status, parse_stopped,
-    parse_type_field, parse_usize, parse_value_field, parse_var_name,
+    parse_endian, parse_frame_args, parse_frame_record, parse_frames, parse_locals,
+    parse_memory_blocks, parse_memory_contents, parse_register_names, parse_register_values,
+    parse_source_files, parse_status, parse_stopped, parse_symbol_at, parse_type_field,
+    parse_usize, parse_value_field, parse_var_changes, parse_var_children, parse_var_name,
+    parse_var_node, shell_quote, split_token, unescape_value, MemoryBlock,
 };
+use crate::mi::python;
+use crate::mi::reader::{self, MiEvent, StderrTail, Subscribers};
+use crate::mi::tty::InferiorTty;
+use crate::mi::value::parse_record;
+use crate::mi::varobj::VarObjects;
+use crate::record::{self, Entry};
 use crate::types::{parse_ptype_output, TypeLayout};
-use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-const MAX_DUMP_BYTES: usize = 512;
+/// Commands that depend on the frame and get `--thread`/`--frame` inside `in_frame`.
+const FRAME_COMMANDS: &[&str] = &[
+    "-data-evaluate-expression",
+    "-data-read-memory-bytes",
+    "-stack-list-locals",
+    "-stack-list-variables",
+    "-var-create",
+    "-interpreter-exec",
+];
+
+/// Default cap for a single dump; overridable via `--max-dump` / `MEMVIZ_MAX_DUMP`.
+pub const DEFAULT_MAX_DUMP_BYTES: usize = 512;
+/// Size of each `-data-read-memory-bytes` request when reading large ranges.
+const READ_CHUNK_BYTES: usize = 4096;
+/// Granularity of the retry pass when a chunk crosses unmapped memory.
+const PAGE_BYTES: u64 = 4096;
+/// Bytes per request while looking for a C string's NUL; most strings fit in one.
+const C_STRING_CHUNK: usize = 64;
 const VAR_CREATE_AUTO: &str = "-";
+/// Default for `MiSession::timeout`; overridable via `--timeout` / `MEMVIZ_TIMEOUT`, which
+/// also sets `stop_timeout`.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Dumps remembered for change highlighting; the oldest address is forgotten first.
+const MAX_REMEMBERED_DUMPS: usize = 64;
+/// How often a wait for a stop checks for Ctrl-C.
+const INTERRUPT_POLL: Duration = Duration::from_millis(100);
+/// How long to collect gdb's reaction to an interrupt before carrying on.
+const INTERRUPT_GRACE: Duration = Duration::from_secs(2);
 
+/// A gdb process in MI mode (or a recorded transcript standing in for one).
 pub struct MiSession {
-    child: Child,
-    stdin: ChildStdin,
-    stdout: BufReader<ChildStdout>,
+    // None when replaying a transcript instead of talking to gdb.
+    child: Option<Child>,
+    stdin: Box<dyn Write>,
+    // Lines of the debugger's output, read on the reader thread.
+    lines: Receiver<io::Result<String>>,
+    subscribers: Subscribers,
+    // Last lines the debugger wrote to stderr, reported if it dies.
+    stderr: Arc<Mutex<StderrTail>>,
+    // The debugger's output ended; commands fail until `relaunch`.
+    dead: bool,
     verbose: bool, // when true, echo MI traffic to stderr for debugging
     pub word_size: usize,
     word_known: bool,
     pub endian: Endian,
     pub arch: Option<String>,
+    /// Upper bound for `memory_dump`; 0 disables the cap.
+    pub max_dump_bytes: usize,
+    /// How long to wait for a command's result before interrupting gdb; `None` waits forever.
+    pub timeout: Option<Duration>,
+    /// How long the program may run before it is interrupted; `None` (the default) waits
+    /// until it stops by itself or Ctrl-C is pressed.
+    pub stop_timeout: Option<Duration>,
+    // Last bytes dumped per start address, so repeated dumps can highlight changes; most
+    // recent last, at most MAX_REMEMBERED_DUMPS.
+    last_dumps: VecDeque<(u64, Vec<u8>)>,
+    // User-visible breakpoints set through add_breakpoint (internal hooks are not listed).
+    breakpoints: Vec<BreakpointInfo>,
+    // Register names by MI register number; fetched once per session.
+    register_names: Vec<String>,
+    // File receiving the inferior's stdout/stderr, and how much of it has been shown.
+    output_path: Option<PathBuf>,
+    output_seen: u64,
+    // Pseudo-terminal the program runs on instead (`use_inferior_tty`).
+    inferior_tty: Option<InferiorTty>,
+    varobjs: VarObjects,
+    /// Executable gdb was started on.
+    pub target: String,
     target_hint: String,
+    // Debugger executable and the program's arguments, for `relaunch`.
+    program: String,
+    args: Vec<String>,
+    // Commands that set up the target and program (environment, I/O, core file, ...),
+    // sent again by `relaunch`.
+    setup_log: Vec<String>,
+    // The executable's source files, listed on first use (`is_program_source`).
+    program_sources: Option<Vec<SourceFile>>,
+    // Shared libraries gdb reported loading (`=library-loaded`) since the last
+    // `take_loaded_libraries`.
+    loaded_libraries: Vec<String>,
+    // Attached or remote: the process belongs to someone else and cannot be started anew.
+    relaunchable: bool,
+    backend: Box<dyn DebuggerBackend>,
+    // Backend setup commands, sent once the first prompt has arrived.
+    pending_setup: Vec<String>,
+    // MI commands the backend reported missing; refused by `exec_command`.
+    unsupported: Vec<String>,
+    // The Python bridge script has been loaded.
+    python_ready: bool,
+    // Token for the next MI command; its result record carries the same number.
+    next_token: u64,
+    // `mi-async` is on: gdb takes commands (`-exec-interrupt`) while the program runs.
+    mi_async: bool,
+    // Token of the last `-exec-interrupt`; its result shows up while waiting for the stop.
+    interrupt_token: Option<u64>,
+    // (thread, frame) that frame-dependent commands run in, set by `in_frame`.
+    scope: Option<(u32, u32)>,
+}
+
+/// What it takes to start another gdb on the session's executable, e.g. on a worker thread
+/// (a session itself stays on the thread that made it).
+#[derive(Debug, Clone)]
+pub struct SiblingSpec {
+    program: String,
+    target: String,
+}
+
+impl SiblingSpec {
+    /// Start the debugger on the executable, without program arguments or target setup,
+    /// and wait for its first prompt.
+    pub fn start(&self) -> Result<MiSession> {
+        let mut session = MiSession::start(&self.program, &self.target, &[], false)?;
+        session.drain_initial_output()?;
+        Ok(session)
+    }
 }
 
 impl MiSession {
+    /// Spawn `gdb_bin` on `target`; `args` become the program's arguments.
     pub fn start(gdb_bin: &str, target: &str, args: &[String], verbose: bool) -> Result<Self> {
-        // Spawn gdb in MI mode (`-i=mi`) with quiet banner. Target args are passed as-is.
-        let mut cmd = Command::new(gdb_bin);
-        cmd.arg("-q").arg("-i=mi").arg("--args").arg(target);
-        for a in args {
-            cmd.arg(a);
+        Self::start_backend(Box::new(Gdb), gdb_bin, target, args, verbose)
+    }
+
+    /// Spawn `program` as `backend` (gdb or lldb-mi) on `target`.
+    pub fn start_backend(
+        backend: Box<dyn DebuggerBackend>,
+        program: &str,
+        target: &str,
+        args: &[String],
+        verbose: bool,
+    ) -> Result<Self> {
+        let mut child = spawn_debugger(backend.as_ref(), program, target, args)?;
+        let stdin = child.stdin.take().ok_or("failed to open gdb stdin")?;
+        let stdout = child.stdout.take().ok_or("failed to open gdb stdout")?;
+        let stderr = child.stderr.take().ok_or("failed to open gdb stderr")?;
+        let mut session = Self::with_io(
+            Some(child),
+            Box::new(stdin),
+            Box::new(stdout),
+            target,
+            verbose,
+        );
+        session.stderr = reader::spawn_stderr(Box::new(stderr));
+        session.pending_setup = backend.setup_commands(target, args);
+        session.target_hint = backend.program_name(target);
+        session.backend = backend;
+        session.program = program.to_string();
+        session.args = args.to_vec();
+        session.relaunchable = true;
+        Ok(session)
+    }
+
+    /// The debugger died (see `is_dead`): start it again with the same program, target and
+    /// arguments, replay the target setup and set the user breakpoints again. gdb numbers
+    /// them afresh and counts hits from zero again. The program is left not started. Returns
+    /// a warning for everything that could not be restored.
+    pub fn relaunch(&mut self) -> Result<Vec<String>> {
+        if !self.relaunchable {
+            return Err(format!(
+                "cannot relaunch {}: the session did not start the program (attach/remote) or \
+                 replays a transcript",
+                self.backend.name()
+            )
+            .into());
         }
-        let mut child = match cmd
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-        {
-            Ok(c) => c,
-            Err(e) => {
-                if e.kind() == std::io::ErrorKind::NotFound {
-                    return Err(format!(
-                        "gdb binary '{}' not found. Install gdb or pass --gdb <path>",
-                        gdb_bin
-                    )
-                    .into());
-                } else {
-                    return Err(format!("failed to launch gdb '{}': {}", gdb_bin, e).into());
+        if let Some(mut old) = self.child.take() {
+            // Usually reaped already; this only makes sure no half-dead debugger lingers.
+            let _ = old.kill();
+            let _ = old.wait();
+        }
+        let mut child = spawn_debugger(
+            self.backend.as_ref(),
+            &self.program,
+            &self.target,
+            &self.args,
+        )?;
+        let stdin = child.stdin.take().ok_or("failed to open gdb stdin")?;
+        let stdout = child.stdout.take().ok_or("failed to open gdb stdout")?;
+        let stderr = child.stderr.take().ok_or("failed to open gdb stderr")?;
+        self.child = Some(child);
+        self.stdin = Box::new(stdin);
+        self.lines = reader::spawn(Box::new(stdout), Arc::clone(&self.subscribers));
+        self.stderr = reader::spawn_stderr(Box::new(stderr));
+        self.dead = false;
+        self.varobjs = VarObjects::default();
+        self.python_ready = false;
+        self.last_dumps.clear();
+        self.output_seen = 0;
+        self.pending_setup = self.backend.setup_commands(&self.target, &self.args);
+        self.drain_initial_output()?;
+
+        let mut warnings = Vec::new();
+        for cmd in std::mem::take(&mut self.setup_log) {
+            match self.configure(&cmd) {
+                Ok(resp) => {
+                    if let MiStatus::Error(msg) = resp.status {
+                        warnings.push(format!("{}: {}", cmd, msg));
+                    }
                 }
+                Err(e) => warnings.push(format!("{}: {}", cmd, e)),
             }
-        };
+        }
+        for old in std::mem::take(&mut self.breakpoints) {
+            let location = old
+                .location
+                .clone()
+                .or_else(|| match (&old.file, old.line) {
+                    (Some(f), Some(l)) => Some(format!("{}:{}", f, l)),
+                    _ => old.func.clone(),
+                });
+            let Some(location) = location else {
+                warnings.push(format!("breakpoint {}: location unknown", old.number));
+                continue;
+            };
+            let added = if old.catch.is_some() {
+                self.add_catchpoint(&location)
+            } else {
+                self.add_breakpoint(&location)
+            };
+            let number = match added {
+                Ok(bp) => bp.number,
+                Err(e) => {
+                    warnings.push(format!("breakpoint {} ({}): {}", old.number, location, e));
+                    continue;
+                }
+            };
+            if !old.enabled {
+                if let Err(e) = self.set_breakpoint_enabled(number, false) {
+                    warnings.push(format!("breakpoint {}: {}", number, e));
+                }
+            }
+        }
+        Ok(warnings)
+    }
 
-        let stdin = child.stdin.take().ok_or("failed to open gdb stdin")?;
-        let stdout = child.stdout.take().ok_or("failed to open gdb stdout")?;
+    /// Turn on `mi-async`, so a running program can be paused with `-exec-interrupt`
+    /// (Ctrl-C, see `interrupt`). Without it the debugger is sent SIGINT instead. Returns
+    /// whether the debugger accepted it.
+    pub fn enable_async(&mut self) -> bool {
+        self.mi_async = self
+            .configure("-gdb-set mi-async on")
+            .is_ok_and(|r| !matches!(r.status, MiStatus::Error(_)));
+        self.mi_async
+    }
+
+    /// How to start a second gdb on the same executable; `None` for other backends and
+    /// replayed sessions.
+    pub fn sibling(&self) -> Option<SiblingSpec> {
+        (self.backend.name() == "gdb" && !self.program.is_empty()).then(|| SiblingSpec {
+            program: self.program.clone(),
+            target: self.target.clone(),
+        })
+    }
+
+    /// The debugger exited (or closed its output) under the session.
+    pub fn is_dead(&self) -> bool {
+        self.dead
+    }
+
+    /// Whether `relaunch` can start a new debugger for this session.
+    pub fn can_relaunch(&self) -> bool {
+        self.relaunchable
+    }
+
+    /// Session fed from a recorded transcript: `output` stands in for the debugger's stdout
+    /// and `input` receives the commands. `backend` and `args` must match the recording so
+    /// the same setup commands are sent.
+    pub fn replay(
+        backend: Box<dyn DebuggerBackend>,
+        input: Box<dyn Write>,
+        output: Box<dyn Read + Send>,
+        target: &str,
+        args: &[String],
+        verbose: bool,
+    ) -> Self {
+        let mut session = Self::with_io(None, input, output, target, verbose);
+        session.pending_setup = backend.setup_commands(target, args);
+        session.target_hint = backend.program_name(target);
+        session.backend = backend;
+        session
+    }
 
-        Ok(Self {
+    fn with_io(
+        child: Option<Child>,
+        stdin: Box<dyn Write>,
+        stdout: Box<dyn Read + Send>,
+        target: &str,
+        verbose: bool,
+    ) -> Self {
+        let subscribers = Subscribers::default();
+        Self {
             child,
             stdin,
-            stdout: BufReader::new(stdout),
+            lines: reader::spawn(stdout, Arc::clone(&subscribers)),
+            subscribers,
+            stderr: Arc::default(),
+            dead: false,
             verbose,
             word_size: 8,
             word_known: false,
             endian: Endian::Unknown,
             arch: None,
+            max_dump_bytes: DEFAULT_MAX_DUMP_BYTES,
+            timeout: Some(DEFAULT_TIMEOUT),
+            stop_timeout: None,
+            last_dumps: VecDeque::new(),
+            breakpoints: Vec::new(),
+            register_names: Vec::new(),
+            output_path: None,
+            output_seen: 0,
+            inferior_tty: None,
+            varobjs: VarObjects::default(),
+            target: target.to_string(),
             target_hint: std::path::Path::new(target)
                 .file_name()
                 .and_then(|s| s.to_str())
                 .map(|s| s.to_string())
                 .unwrap_or_default(),
+            backend: Box::new(Gdb),
+            program: String::new(),
+            args: Vec::new(),
+            setup_log: Vec::new(),
+            program_sources: None,
+            loaded_libraries: Vec::new(),
+            relaunchable: false,
+            pending_setup: Vec::new(),
+            unsupported: Vec::new(),
+            python_ready: false,
+            next_token: 1,
+            mi_async: false,
+            interrupt_token: None,
+            scope: None,
+        }
+    }
+
+    /// Async events (stops, breakpoint hits, library loads) as the debugger reports them,
+    /// independent of which command is waiting. The session still handles stops itself.
+    pub fn subscribe(&self) -> Receiver<MiEvent> {
+        let (tx, rx) = mpsc::channel();
+        if let Ok(mut subs) = self.subscribers.lock() {
+            subs.push(tx);
+        }
+        rx
+    }
+
+    /// Name of the debugger behind this session ("gdb", "lldb-mi").
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    /// Ask the backend which of the `PROBED_COMMANDS` it lacks and remember them. A backend
+    /// without `-info-gdb-mi-command` is assumed to have everything.
+    pub fn probe_capabilities(&mut self) -> Result<Vec<String>> {
+        for name in PROBED_COMMANDS {
+            let resp = self.exec_command(&format!("-info-gdb-mi-command {}", name))?;
+            if let MiStatus::Error(_) = resp.status {
+                self.unsupported.clear();
+                break;
+            }
+            if resp.result.contains("exists=\"false\"") {
+                self.unsupported.push(name.to_string());
+            }
+        }
+        Ok(self.unsupported.clone())
+    }
+
+    /// Console command for `query`, or an error naming the backend that lacks one.
+    fn console_command(&self, query: ConsoleQuery, arg: &str) -> Result<String> {
+        self.backend.console_command(query, arg).ok_or_else(|| {
+            format!(
+                "{} not available with {}",
+                query.describe(),
+                self.backend.name()
+            )
+            .into()
         })
     }
 
@@ -82,27 +416,112 @@ impl MiSession {
                 eprintln!("[mi<-] {}", line);
             }
         }
+        for cmd in std::mem::take(&mut self.pending_setup) {
+            let resp = self.exec_command(&cmd)?;
+            if let MiStatus::Error(msg) = resp.status {
+                return Err(format!("{}: {}", cmd, msg).into());
+            }
+        }
         self.ensure_endian();
         self.ensure_arch();
         Ok(())
     }
 
-    /// Send a raw MI command (no added token) and collect the response until the prompt.
+    /// Send an MI command under a fresh token and collect its response until the prompt.
     pub fn exec_command(&mut self, cmd: &str) -> Result<MiResponse> {
-        self.send_line(cmd)?;
-        self.read_response()
+        if self.dead {
+            return Err(format!(
+                "{} is not running (it exited earlier); 'relaunch' starts it again",
+                self.backend.name()
+            )
+            .into());
+        }
+        let name = cmd.split_whitespace().next().unwrap_or("");
+        if self
+            .unsupported
+            .iter()
+            .any(|u| name.strip_prefix('-') == Some(u))
+        {
+            return Err(format!("{} is not supported by {}", name, self.backend.name()).into());
+        }
+        let token = self.next_token;
+        self.next_token += 1;
+        let cmd = match self.scope {
+            Some((thread, frame)) if FRAME_COMMANDS.contains(&name) => {
+                scoped_command(cmd, thread, frame)
+            }
+            _ => cmd.to_string(),
+        };
+        self.send_line(&format!("{}{}", token, cmd))?;
+        self.read_response(token)
     }
 
-    /// Insert breakpoint at main, run, and wait until it stops.
-    pub fn run_to_main(&mut self) -> Result<()> {
-        // Best-effort: set a breakpoint on main, run, and block until a stop event arrives.
-        let resp = self.exec_command("-break-insert main")?;
-        match resp.status {
-            MiStatus::Error(msg) => {
-                return Err(format!("failed to set breakpoint: {}", msg).into());
+    /// Run `f` with evaluation, locals, type and memory commands pointed at `scope` instead
+    /// of the selected frame, which stays as it is (no `-stack-select-frame` round trips).
+    pub fn in_frame<T>(
+        &mut self,
+        scope: FrameScope,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        let thread = match scope.thread {
+            Some(t) => t,
+            None => self.current_thread()?,
+        };
+        let outer = self.scope.replace((thread, scope.frame));
+        let result = f(self);
+        self.scope = outer;
+        result
+    }
+
+    /// Stack pointer of each thread by kernel thread id (LWP), so the stacks of threads
+    /// besides the main one can be told apart from other anonymous memory. Empty for a
+    /// single-threaded program; threads whose `$sp` cannot be read are left out.
+    pub fn thread_stack_pointers(&mut self) -> Result<Vec<(u32, u64)>> {
+        let resp = self.exec_command("-thread-info")?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("thread info failed: {}", msg).into());
+        }
+        let threads: Vec<(u32, u32)> = parse_record(&resp.result)
+            .list("threads")
+            .iter()
+            .filter_map(|t| Some((t.parse("id")?, parse_lwp(t.str("target-id")?)?)))
+            .collect();
+        if threads.len() < 2 {
+            return Ok(Vec::new());
+        }
+        let mut stacks = Vec::new();
+        for (id, lwp) in threads {
+            let scope = FrameScope {
+                thread: Some(id),
+                frame: 0,
+            };
+            if let Ok(sp) = self.in_frame(scope, |s| s.eval_expr_u64("$sp")) {
+                stacks.push((lwp, sp));
             }
-            _ => {}
         }
+        Ok(stacks)
+    }
+
+    /// gdb's global id of the current thread.
+    fn current_thread(&mut self) -> Result<u32> {
+        let resp = self.exec_command("-thread-info")?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("thread info failed: {}", msg).into());
+        }
+        parse_record(&resp.result)
+            .parse("current-thread-id")
+            .ok_or_else(|| "no current thread (is the program running?)".into())
+    }
+
+    /// Insert a breakpoint at `location` (normally `main`), run, and wait until it stops.
+    /// The breakpoint stays, so `run` stops there again.
+    pub fn run_to(&mut self, location: &str) -> Result<()> {
+        // Best-effort: set the breakpoint, run, and block until a stop event arrives.
+        let resp = self.exec_command(&format!("-break-insert {}", location))?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("failed to set breakpoint at {}: {}", location, msg).into());
+        }
+        self.breakpoints.push(parse_breakpoint(&resp.result));
 
         let resp = self.exec_command("-exec-run")?;
         if let MiStatus::Error(msg) = resp.status {
@@ -114,6 +533,305 @@ impl MiSession {
         Ok(())
     }
 
+    /// Set the program's arguments plus shell redirections: stdin from `stdin`, and with
+    /// `capture` stdout/stderr into a temp file read back by `take_inferior_output`, so
+    /// program output does not interleave with MI records. Call before `run_to`.
+    pub fn redirect_inferior_io(
+        &mut self,
+        args: &[String],
+        stdin: Option<&Path>,
+        capture: bool,
+    ) -> Result<()> {
+        if !self.backend.shell_redirection() {
+            return Err(format!(
+                "{} starts the program without a shell; cannot redirect its input/output",
+                self.backend.name()
+            )
+            .into());
+        }
+        let mut cmd = String::from("-exec-arguments");
+        for a in args {
+            cmd.push(' ');
+            cmd.push_str(&shell_quote(a));
+        }
+        if let Some(input) = stdin {
+            let input = input.to_str().ok_or("stdin path is not valid UTF-8")?;
+            cmd.push_str(&format!(" < {}", shell_quote(input)));
+        }
+        let path = if capture {
+            Some(private_output_file()?)
+        } else {
+            None
+        };
+        if let Some(path) = &path {
+            let path_str = path.to_str().ok_or("temp dir path is not valid UTF-8")?;
+            cmd.push_str(&format!(" > {} 2>&1", shell_quote(path_str)));
+        }
+        let resp = self.configure(&cmd)?;
+        if let MiStatus::Error(msg) = resp.status {
+            if let Some(path) = &path {
+                remove_output_file(path);
+            }
+            return Err(format!("cannot redirect program input/output: {}", msg).into());
+        }
+        if let Some(path) = path {
+            if let Some(old) = self.output_path.replace(path) {
+                remove_output_file(&old);
+            }
+            self.output_seen = 0;
+        }
+        Ok(())
+    }
+
+    /// Source files that make up the executable, from its debug info. gdb lists every
+    /// loaded object's files; where it can group them by object file (gdb 13+) only the
+    /// executable's are returned.
+    pub fn list_source_files(&mut self) -> Result<Vec<SourceFile>> {
+        let mut resp = self.exec_command("-file-list-exec-source-files --group-by-objfile")?;
+        if let MiStatus::Error(_) = resp.status {
+            resp = self.exec_command("-file-list-exec-source-files")?;
+        }
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("source file list failed: {}", msg).into());
+        }
+        Ok(parse_source_files(&resp.result, &self.target))
+    }
+
+    /// Whether `header` (a file heading in gdb's listings) is one of the program's sources.
+    /// Falls back to matching the program's name when the file list is unavailable.
+    fn is_program_source(&mut self, header: &str) -> bool {
+        if self.program_sources.is_none() {
+            self.program_sources = Some(self.list_source_files().unwrap_or_default());
+        }
+        match self.program_sources.as_deref() {
+            Some(files) if !files.is_empty() => files.iter().any(|f| {
+                f.file == header
+                    || f.fullname.as_deref().is_some_and(|full| {
+                        full == header || full.ends_with(&format!("/{}", header))
+                    })
+            }),
+            _ => self.target_hint.is_empty() || header.contains(&self.target_hint),
+        }
+    }
+
+    /// Load the executable and its symbols again after a rebuild; breakpoints are kept.
+    pub fn reload_executable(&mut self) -> Result<()> {
+        let cmd = format!("-file-exec-and-symbols {}", mi_escape(&self.target));
+        let resp = self.exec_command(&cmd)?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("reload failed: {}", msg).into());
+        }
+        self.program_sources = None;
+        Ok(())
+    }
+
+    /// Run a gdb command file or Python script (`source <path>`).
+    pub fn source_script(&mut self, path: &Path) -> Result<()> {
+        let path = path.to_str().ok_or("script path is not valid UTF-8")?;
+        let cmd = format!(
+            "-interpreter-exec console {}",
+            mi_escape(&format!("source {}", path))
+        );
+        let resp = self.configure(&cmd)?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("source {} failed: {}", path, msg).into());
+        }
+        Ok(())
+    }
+
+    /// Call a function of the Python bridge (loaded on first use) and return its JSON result.
+    pub fn python_call(
+        &mut self,
+        func: &str,
+        args: &[serde_json::Value],
+    ) -> Result<serde_json::Value> {
+        if !self.python_ready {
+            let load = self.console_command(ConsoleQuery::Python, &python::load_statement())?;
+            let resp =
+                self.exec_command(&format!("-interpreter-exec console {}", mi_escape(&load)))?;
+            if let MiStatus::Error(msg) = resp.status {
+                return Err(format!("loading the python bridge failed: {}", msg).into());
+            }
+            self.python_ready = true;
+        }
+        let query =
+            self.console_command(ConsoleQuery::Python, &python::call_statement(func, args))?;
+        let resp =
+            self.exec_command(&format!("-interpreter-exec console {}", mi_escape(&query)))?;
+        python::extract_result(&resp.status, &resp.oob)
+    }
+
+    /// Full type tree of `type_name` from gdb's Python API: fields with offsets, pointer and
+    /// array targets, enum values. Pointers are followed `depth` levels deep.
+    pub fn type_tree(&mut self, type_name: &str, depth: u32) -> Result<serde_json::Value> {
+        self.python_call("memviz_type_tree", &[type_name.into(), depth.into()])
+    }
+
+    /// Statics declared inside `functions` (`static int calls;` in a function body), which
+    /// gdb's variable listings leave out. One bridge call for all of them.
+    pub fn function_statics(&mut self, functions: &[String]) -> Result<serde_json::Value> {
+        let names: Vec<serde_json::Value> = functions.iter().map(|f| f.as_str().into()).collect();
+        self.python_call("memviz_function_statics", &[names.into()])
+    }
+
+    /// Address and ELF section (`.data`, `.bss`, `.rodata`) of each global, given as
+    /// (name, defining file, address if already known); answered in the same order.
+    pub fn symbol_addresses(
+        &mut self,
+        entries: &[(String, Option<String>, Option<u64>)],
+    ) -> Result<serde_json::Value> {
+        let entries: Vec<serde_json::Value> = entries
+            .iter()
+            .map(|(name, file, addr)| {
+                serde_json::json!([name, file.as_deref().unwrap_or(""), addr.unwrap_or(0)])
+            })
+            .collect();
+        self.python_call("memviz_symbol_addresses", &[entries.into()])
+    }
+
+    /// Working directory for the program (`-environment-cd`), applied on the next run.
+    pub fn change_directory(&mut self, dir: &str) -> Result<()> {
+        let resp = self.configure(&format!("-environment-cd {}", mi_escape(dir)))?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("cd failed: {}", msg).into());
+        }
+        Ok(())
+    }
+
+    /// `set environment KEY=VALUE` for the program, applied on the next run.
+    pub fn set_environment(&mut self, var: &str) -> Result<()> {
+        let cmd = format!("-gdb-set environment {}", mi_escape(var));
+        let resp = self.configure(&cmd)?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("set environment failed: {}", msg).into());
+        }
+        Ok(())
+    }
+
+    /// Run the program on a fresh pseudo-terminal (`-inferior-tty-set`) whose output is
+    /// collected like the capture file's and which takes input from `send_inferior_input`.
+    /// Returns the terminal's path. Call before `run_to`.
+    pub fn use_inferior_tty(&mut self) -> Result<String> {
+        let tty = InferiorTty::open().map_err(|e| format!("cannot allocate a terminal: {}", e))?;
+        let resp = self.configure(&format!("-inferior-tty-set {}", tty.path))?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("cannot give the program its own terminal: {}", msg).into());
+        }
+        let path = tty.path.clone();
+        self.inferior_tty = Some(tty);
+        Ok(path)
+    }
+
+    /// Terminal the program runs on, if `use_inferior_tty` set one up.
+    pub fn inferior_tty_path(&self) -> Option<&str> {
+        self.inferior_tty.as_ref().map(|t| t.path.as_str())
+    }
+
+    /// Type `text` into the program's terminal; it is read when the program next reads.
+    pub fn send_inferior_input(&mut self, text: &str) -> Result<()> {
+        let tty = self
+            .inferior_tty
+            .as_mut()
+            .ok_or("the program has no terminal of its own (start with --tty)")?;
+        tty.send_input(text)?;
+        Ok(())
+    }
+
+    /// File capturing the program's output, if `redirect_inferior_io` set one up.
+    pub fn inferior_output_path(&self) -> Option<&Path> {
+        self.output_path.as_deref()
+    }
+
+    /// Everything the program wrote so far (empty when capture is off).
+    pub fn inferior_output_all(&self) -> Result<String> {
+        if let Some(tty) = &self.inferior_tty {
+            return Ok(tty.all());
+        }
+        match &self.output_path {
+            Some(path) => fs::read_to_string(path)
+                .map_err(|e| format!("cannot read {}: {}", path.display(), e).into()),
+            None => Ok(String::new()),
+        }
+    }
+
+    /// Paths of the shared libraries loaded since the last call, in load order.
+    pub fn take_loaded_libraries(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.loaded_libraries)
+    }
+
+    /// Program output written since the last call (empty when capture is off).
+    pub fn take_inferior_output(&mut self) -> Result<String> {
+        if let Some(tty) = &mut self.inferior_tty {
+            return Ok(tty.take_new());
+        }
+        let Some(path) = &self.output_path else {
+            return Ok(String::new());
+        };
+        let bytes = match fs::read(path) {
+            Ok(b) => b,
+            // The shell creates the file when the program starts.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(String::new()),
+            Err(e) => return Err(e.into()),
+        };
+        // A rerun truncates the file; start over from its beginning.
+        if (bytes.len() as u64) < self.output_seen {
+            self.output_seen = 0;
+        }
+        let new = &bytes[self.output_seen as usize..];
+        self.output_seen = bytes.len() as u64;
+        Ok(String::from_utf8_lossy(new).into_owned())
+    }
+
+    /// Continue until `location` is reached, via a temporary breakpoint.
+    pub fn continue_to(&mut self, location: &str) -> Result<StoppedLocation> {
+        let resp = self.exec_command(&format!("-break-insert -t {}", location))?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("cannot stop at {}: {}", location, msg).into());
+        }
+        self.exec_continue()
+    }
+
+    /// Attach to a running process and wait until gdb reports it stopped.
+    pub fn attach(&mut self, pid: u32) -> Result<()> {
+        self.relaunchable = false;
+        let resp = self.exec_command(&format!("-target-attach {}", pid))?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("failed to attach to {}: {}", pid, msg).into());
+        }
+        if !resp.oob.iter().any(|l| l.starts_with("*stopped")) {
+            self.wait_for_stop()?;
+        }
+        Ok(())
+    }
+
+    /// Open a core file; there is no live process afterwards, so commands that need
+    /// `/proc/<pid>` or execution report errors while memory reads still work.
+    pub fn load_core(&mut self, core: &str) -> Result<()> {
+        let resp = self.configure(&format!("-target-select core {}", mi_escape(core)))?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("failed to load core {}: {}", core, msg).into());
+        }
+        Ok(())
+    }
+
+    /// Connect to a gdbserver and continue to `location` (normally `main`).
+    pub fn connect_remote(&mut self, address: &str, location: &str) -> Result<()> {
+        self.relaunchable = false;
+        let resp = self.exec_command(&format!("-target-select remote {}", address))?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("failed to connect to {}: {}", address, msg).into());
+        }
+        self.continue_to_start(location)
+    }
+
+    /// Break at `location` and continue a process that is already started (gdbserver,
+    /// `rr replay`) to it. Like `run_to`, the breakpoint stays.
+    pub fn continue_to_start(&mut self, location: &str) -> Result<()> {
+        self.add_breakpoint(location)?;
+        self.exec_continue()?;
+        Ok(())
+    }
+
     /// Read current frame locals using `-stack-list-locals 2` (includes values).
     pub fn list_locals(&mut self) -> Result<Vec<LocalVar>> {
         let resp = self.exec_command("-stack-list-locals 2")?;
@@ -138,6 +856,16 @@ impl MiSession {
         Ok(locals)
     }
 
+    /// Arguments of frame `level` using `-stack-list-arguments 2` (values of scalars only).
+    pub fn list_arguments(&mut self, level: u32) -> Result<Vec<LocalVar>> {
+        let cmd = format!("-stack-list-arguments 2 {} {}", level, level);
+        let resp = self.exec_command(&cmd)?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("gdb error: {}", msg).into());
+        }
+        Ok(parse_frame_args(&resp.result))
+    }
+
     #[allow(dead_code)]
     /// Evaluate address of a symbol using `-data-evaluate-expression`.
     pub fn evaluate_address(&mut self, symbol: &str) -> Result<String> {
@@ -145,7 +873,7 @@ impl MiSession {
         let cmd = format!("-data-evaluate-expression {}", mi_escape(&expr));
         let resp = self.exec_command(&cmd)?;
         if let MiStatus::Error(msg) = resp.status.clone() {
-            return Err(format!("{}", msg).into());
+            return Err(msg.into());
         }
         parse_value_field(&resp.result).ok_or_else(|| "address not found in MI response".into())
     }
@@ -155,18 +883,52 @@ impl MiSession {
         let cmd = format!("-data-evaluate-expression {}", mi_escape(expr));
         let resp = self.exec_command(&cmd)?;
         if let MiStatus::Error(msg) = resp.status.clone() {
-            return Err(format!("{}", msg).into());
+            return Err(msg.into());
         }
         parse_value_field(&resp.result).ok_or_else(|| "value not found in MI response".into())
     }
 
+    /// The symbol containing `addr` (`info symbol`), in the program or any loaded library;
+    /// `None` when no symbol covers it (heap, stack, ...).
+    pub fn symbol_at(&mut self, addr: u64) -> Result<Option<AddressSymbol>> {
+        let query = self.console_command(ConsoleQuery::SymbolAt, &format!("0x{:x}", addr))?;
+        let resp = self.exec_command(&format!("-interpreter-exec console \"{}\"", query))?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(msg.into());
+        }
+        let text: String = resp
+            .oob
+            .iter()
+            .filter_map(|l| l.strip_prefix("~\""))
+            .map(|l| unescape_value(l.trim_end_matches('"')))
+            .collect();
+        Ok(parse_symbol_at(&text))
+    }
+
+    /// Saved-register slots of the selected frame (`info frame`), as gdb's unwinder finds them.
+    pub fn frame_record(&mut self) -> Result<FrameRecord> {
+        let query = self.console_command(ConsoleQuery::FrameInfo, "")?;
+        let resp = self.exec_command(&format!("-interpreter-exec console \"{}\"", query))?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(msg.into());
+        }
+        let text: String = resp
+            .oob
+            .iter()
+            .filter_map(|l| l.strip_prefix("~\""))
+            .map(|l| unescape_value(l.trim_end_matches('"')))
+            .collect();
+        Ok(parse_frame_record(&text))
+    }
+
     /// Run ptype and return console text.
     pub fn ptype_text(&mut self, symbol: &str) -> Result<String> {
         // We call into the CLI `ptype` because MI lacks a clean equivalent for pretty layout.
-        let cmd = format!("-interpreter-exec console \"ptype /o {}\"", symbol);
+        let query = self.console_command(ConsoleQuery::TypeLayout, symbol)?;
+        let cmd = format!("-interpreter-exec console \"{}\"", query);
         let resp = self.exec_command(&cmd)?;
         if let MiStatus::Error(msg) = resp.status.clone() {
-            return Err(format!("{}", msg).into());
+            return Err(msg.into());
         }
         let mut out = String::new();
         for line in &resp.oob {
@@ -202,13 +964,13 @@ impl MiSession {
         None
     }
 
-    /// Evaluate sizeof(<expr>) and return bytes.
+    /// Evaluate `sizeof(<expr>)` and return bytes.
     pub fn evaluate_sizeof(&mut self, expr: &str) -> Result<usize> {
         let expr = format!("sizeof({})", expr);
         let cmd = format!("-data-evaluate-expression {}", mi_escape(&expr));
         let resp = self.exec_command(&cmd)?;
         if let MiStatus::Error(msg) = resp.status.clone() {
-            return Err(format!("{}", msg).into());
+            return Err(msg.into());
         }
         let raw = parse_value_field(&resp.result).ok_or("sizeof returned no value")?;
         parse_usize(&raw).map_err(|e| e.into())
@@ -276,7 +1038,6 @@ impl MiSession {
                 let trimmed = val.trim();
                 if !trimmed.is_empty() && trimmed != "auto" {
                     self.arch = Some(trimmed.to_string());
-                    return;
                 }
             }
         }
@@ -285,8 +1046,8 @@ impl MiSession {
 
     /// Try to obtain the inferior process pid from `info proc`.
     pub fn inferior_pid(&mut self) -> Result<u32> {
-        let cmd = "-interpreter-exec console \"info proc\"";
-        let resp = self.exec_command(cmd)?;
+        let query = self.console_command(ConsoleQuery::ProcessInfo, "")?;
+        let resp = self.exec_command(&format!("-interpreter-exec console \"{}\"", query))?;
         let mut text = String::new();
         text.push_str(&resp.result);
         text.push('\n');
@@ -299,10 +1060,11 @@ impl MiSession {
             text.push('\n');
         }
         for line in text.lines() {
-            if line.contains("process") {
+            if line.to_ascii_lowercase().contains("process") {
                 let mut parts = line.split_whitespace();
                 while let Some(tok) = parts.next() {
-                    if tok == "process" {
+                    // gdb says "process 1234", lldb "Process 1234 stopped".
+                    if tok.eq_ignore_ascii_case("process") {
                         if let Some(pid_str) = parts.next() {
                             if let Ok(pid) = pid_str.parse::<u32>() {
                                 return Ok(pid);
@@ -317,8 +1079,8 @@ impl MiSession {
 
     /// List global variables visible to gdb (console-based parsing).
     pub fn list_globals(&mut self) -> Result<Vec<GlobalVar>> {
-        let cmd = "-interpreter-exec console \"info variables\"";
-        let resp = self.exec_command(cmd)?;
+        let query = self.console_command(ConsoleQuery::GlobalVariables, "")?;
+        let resp = self.exec_command(&format!("-interpreter-exec console \"{}\"", query))?;
         let mut text = String::new();
         text.push_str(&resp.result.replace("\\n", "\n").replace("\\t", "\t"));
         text.push('\n');
@@ -333,7 +1095,7 @@ impl MiSession {
         }
 
         let mut globals = Vec::new();
-        let mut in_file_block = false;
+        let mut file: Option<String> = None;
         for line in text.lines() {
             let trimmed = line.trim();
             if trimmed.is_empty() {
@@ -350,14 +1112,10 @@ impl MiSession {
                     .trim_start_matches("File ")
                     .trim_end_matches(':')
                     .trim();
-                if !self.target_hint.is_empty() && !header.contains(&self.target_hint) {
-                    in_file_block = false;
-                } else {
-                    in_file_block = true;
-                }
+                file = Some(header.to_string()).filter(|h| self.is_program_source(h));
                 continue;
             }
-            if !in_file_block {
+            if file.is_none() {
                 continue;
             }
             if !trimmed.contains(';') {
@@ -376,6 +1134,7 @@ impl MiSession {
                     type_name: type_name.to_string(),
                     value: val,
                     address: addr,
+                    file: file.clone(),
                 });
             }
         }
@@ -387,7 +1146,7 @@ impl MiSession {
         let cmd = format!("-data-evaluate-expression {}", mi_escape(expr));
         let resp = self.exec_command(&cmd)?;
         if let MiStatus::Error(msg) = resp.status.clone() {
-            return Err(format!("{}", msg).into());
+            return Err(msg.into());
         }
         let value = parse_value_field(&resp.result)
             .or_else(|| resp.oob.iter().find_map(|l| parse_value_field(l)))
@@ -404,7 +1163,7 @@ impl MiSession {
         let cmd = format!("-data-evaluate-expression {}", mi_escape(expr));
         let resp = self.exec_command(&cmd)?;
         if let MiStatus::Error(msg) = resp.status.clone() {
-            return Err(format!("{}", msg).into());
+            return Err(msg.into());
         }
         let raw = parse_value_field(&resp.result).ok_or("value field not found in MI response")?;
         // Try to scrape an address or number from the value field first.
@@ -448,13 +1207,14 @@ impl MiSession {
         if requested == 0 {
             requested = 32;
         }
-        // Cap dump size to avoid overwhelming output/logs.
+        // Cap dump size to avoid overwhelming output/logs (0 = unlimited).
         let mut truncated_from = None;
-        if requested > MAX_DUMP_BYTES {
+        if self.max_dump_bytes > 0 && requested > self.max_dump_bytes {
             truncated_from = Some(requested);
-            requested = MAX_DUMP_BYTES;
+            requested = self.max_dump_bytes;
         }
-        let (addr, bytes) = self.read_memory_bytes(&addr_str, requested)?;
+        let (bytes, unreadable) = self.read_memory_chunked(addr_u64, requested)?;
+        let previous = self.remember_dump(addr_u64, bytes.clone());
         // If endian is still unknown, use arch hint or default little.
         if matches!(self.endian, Endian::Unknown) {
             if let Some(arch) = &self.arch {
@@ -470,13 +1230,15 @@ impl MiSession {
         Ok(MemoryDump {
             expr: expr.to_string(),
             ty: self.fetch_type(expr),
-            address: addr,
+            address: addr_str,
             bytes,
             word_size: self.word_size,
             requested,
             endian: self.endian,
             arch: self.arch.clone(),
             truncated_from,
+            unreadable,
+            previous,
         })
     }
 
@@ -502,12 +1264,93 @@ impl MiSession {
         ty
     }
 
+    /// Create a variable object for `expr` in the selected frame. Delete it with `var_delete`.
+    pub fn var_create(&mut self, expr: &str) -> Result<VarNode> {
+        let cmd = format!("-var-create {} * {}", VAR_CREATE_AUTO, mi_escape(expr));
+        let resp = self.exec_command(&cmd)?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("cannot evaluate '{}': {}", expr, msg).into());
+        }
+        parse_var_node(&resp.result, expr).ok_or_else(|| "malformed -var-create result".into())
+    }
+
+    /// Direct children of a variable object, with their values.
+    pub fn var_list_children(&mut self, name: &str) -> Result<Vec<VarNode>> {
+        let resp = self.exec_command(&format!("-var-list-children --all-values {}", name))?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("cannot list children of {}: {}", name, msg).into());
+        }
+        Ok(parse_var_children(&resp.result))
+    }
+
+    /// Delete a variable object and its children.
+    pub fn var_delete(&mut self, name: &str) {
+        let _ = self.exec_command(&format!("-var-delete {}", name));
+    }
+
+    /// Managed variable object for `expr`: created once (floating, so it follows the selected
+    /// frame) and kept up to date by `varobj_update` instead of being re-evaluated.
+    pub fn varobj(&mut self, expr: &str) -> Result<VarNode> {
+        if self.varobjs.stale {
+            self.varobj_update()?;
+        }
+        if let Some(node) = self.varobjs.root(expr) {
+            return Ok(node.clone());
+        }
+        let cmd = format!("-var-create {} @ {}", VAR_CREATE_AUTO, mi_escape(expr));
+        let resp = self.exec_command(&cmd)?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("cannot evaluate '{}': {}", expr, msg).into());
+        }
+        let node = parse_var_node(&resp.result, expr).ok_or("malformed -var-create result")?;
+        if let Some(evicted) = self.varobjs.add_root(expr, node.clone()) {
+            self.var_delete(&evicted);
+        }
+        Ok(node)
+    }
+
+    /// Children of a managed variable object, listed once and then served from the cache.
+    pub fn varobj_children(&mut self, name: &str) -> Result<Vec<VarNode>> {
+        if self.varobjs.stale {
+            self.varobj_update()?;
+        }
+        if let Some(kids) = self.varobjs.cached_children(name) {
+            return Ok(kids);
+        }
+        let kids = self.var_list_children(name)?;
+        self.varobjs.set_children(name, kids.clone());
+        Ok(kids)
+    }
+
+    /// Refresh every managed variable object with one `-var-update` and return what changed.
+    /// Objects whose expression went out of scope are deleted. A no-op without any.
+    pub fn varobj_update(&mut self) -> Result<Vec<VarChange>> {
+        if self.varobjs.is_empty() {
+            self.varobjs.stale = false;
+            return Ok(Vec::new());
+        }
+        let resp = self.exec_command("-var-update --all-values *")?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("var-update failed: {}", msg).into());
+        }
+        let changes = parse_var_changes(&resp.result);
+        for name in self.varobjs.apply(&changes) {
+            self.var_delete(&name);
+        }
+        Ok(changes)
+    }
+
+    /// The managed variable object's value changed in the last `varobj_update`.
+    pub fn varobj_changed(&self, name: &str) -> bool {
+        self.varobjs.is_changed(name)
+    }
+
     /// Read memory bytes from an address using `-data-read-memory-bytes`.
     fn read_memory_bytes(&mut self, address: &str, bytes: usize) -> Result<(String, Vec<u8>)> {
         let cmd = format!("-data-read-memory-bytes {} {}", address, bytes);
         let resp = self.exec_command(&cmd)?;
         if let MiStatus::Error(msg) = resp.status.clone() {
-            return Err(format!("{}", msg).into());
+            return Err(msg.into());
         }
         let raw = format!("{} {}", resp.result, resp.oob.join(" "));
         let addr = parse_addr_field(&raw).unwrap_or_else(|| address.to_string());
@@ -515,22 +1358,178 @@ impl MiSession {
         Ok((addr, data))
     }
 
+    /// Read exactly `len` bytes at `address`, failing if any of them is unreadable.
+    pub fn read_memory(&mut self, address: u64, len: usize) -> Result<Vec<u8>> {
+        let (bytes, unreadable) = self.read_memory_chunked(address, len)?;
+        if unreadable.iter().any(|u| *u) {
+            return Err(format!("memory at 0x{:x} is only partially readable", address).into());
+        }
+        Ok(bytes)
+    }
+
+    /// Read the NUL-terminated string at `address`, at most `max_len` bytes of it. Reads go
+    /// in small chunks that never cross a page, so a string ending just before unmapped
+    /// memory is still read whole; unreadable memory after the first chunk ends the string
+    /// as truncated.
+    pub fn read_c_string(&mut self, address: u64, max_len: usize) -> Result<CStringRead> {
+        let mut bytes = Vec::new();
+        let mut terminated = false;
+        while bytes.len() < max_len {
+            let at = address.wrapping_add(bytes.len() as u64);
+            let to_boundary = (PAGE_BYTES - at % PAGE_BYTES) as usize;
+            let chunk = C_STRING_CHUNK.min(to_boundary).min(max_len - bytes.len());
+            let data = match self.read_memory_bytes(&format!("0x{:x}", at), chunk) {
+                Ok((_, data)) if !data.is_empty() => data,
+                Ok(_) if bytes.is_empty() => {
+                    return Err(format!("no bytes readable at 0x{:x}", address).into())
+                }
+                Err(e) if bytes.is_empty() => return Err(e),
+                _ => break,
+            };
+            match data.iter().position(|b| *b == 0) {
+                Some(nul) => {
+                    bytes.extend_from_slice(&data[..nul]);
+                    terminated = true;
+                    break;
+                }
+                None => bytes.extend_from_slice(&data),
+            }
+        }
+        Ok(CStringRead {
+            text: String::from_utf8_lossy(&bytes).into_owned(),
+            truncated: !terminated,
+        })
+    }
+
+    /// Write raw bytes at `address` using `-data-write-memory-bytes`, then read them back:
+    /// gdb reports success for writes that do not stick (read-only core file sections,
+    /// device memory), so a mismatch is an error naming the first differing byte.
+    pub fn write_memory_bytes(&mut self, address: u64, bytes: &[u8]) -> Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        let cmd = format!("-data-write-memory-bytes 0x{:x} {}", address, hex);
+        let resp = self.exec_command(&cmd)?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("write failed: {}", msg).into());
+        }
+        self.varobjs.stale = true;
+        let back = self
+            .read_memory(address, bytes.len())
+            .map_err(|e| format!("write not verified: {}", e))?;
+        if let Some(i) = back.iter().zip(bytes).position(|(got, want)| got != want) {
+            return Err(format!(
+                "write did not stick at 0x{:x}: wrote {:02x}, read back {:02x}",
+                address + i as u64,
+                bytes[i],
+                back[i]
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Read `len` bytes starting at `address`, splitting into `READ_CHUNK_BYTES` requests.
+    /// Prints a progress line on stderr when more than one chunk is needed. Chunks that gdb
+    /// refuses are retried page by page; bytes that still fail are zero-filled and flagged in
+    /// the returned mask (empty when everything was readable).
+    fn read_memory_chunked(&mut self, address: u64, len: usize) -> Result<(Vec<u8>, Vec<bool>)> {
+        let mut out = vec![0u8; len];
+        let mut readable = vec![false; len];
+        let mut first_err = None;
+        let multi = len > READ_CHUNK_BYTES;
+        let mut done = 0;
+        while done < len {
+            let chunk = (len - done).min(READ_CHUNK_BYTES);
+            let at = address.wrapping_add(done as u64);
+            match self.read_memory_blocks(at, chunk) {
+                Ok(blocks) => place_blocks(address, &blocks, &mut out, &mut readable),
+                Err(e) => {
+                    first_err.get_or_insert(e);
+                    // Retry each page of the failed chunk on its own.
+                    let mut off = 0;
+                    while off < chunk {
+                        let page_at = at.wrapping_add(off as u64);
+                        let to_boundary = (PAGE_BYTES - page_at % PAGE_BYTES) as usize;
+                        let sub = to_boundary.min(chunk - off);
+                        if let Ok(blocks) = self.read_memory_blocks(page_at, sub) {
+                            place_blocks(address, &blocks, &mut out, &mut readable);
+                        }
+                        off += sub;
+                    }
+                }
+            }
+            done += chunk;
+            if multi {
+                eprint!("\rreading memory: {}/{} bytes", done, len);
+            }
+        }
+        if multi {
+            eprintln!();
+        }
+        if len > 0 && !readable.iter().any(|r| *r) {
+            return Err(first_err.unwrap_or_else(|| "memory not readable".into()));
+        }
+        let unreadable = if readable.iter().all(|r| *r) {
+            Vec::new()
+        } else {
+            readable.iter().map(|r| !r).collect()
+        };
+        Ok((out, unreadable))
+    }
+
+    /// Issue one `-data-read-memory-bytes` and return the readable blocks gdb reported.
+    fn read_memory_blocks(&mut self, address: u64, len: usize) -> Result<Vec<MemoryBlock>> {
+        let cmd = format!("-data-read-memory-bytes 0x{:x} {}", address, len);
+        let resp = self.exec_command(&cmd)?;
+        if let MiStatus::Error(msg) = resp.status.clone() {
+            return Err(msg.into());
+        }
+        let raw = format!("{} {}", resp.result, resp.oob.join(" "));
+        let blocks = parse_memory_blocks(&raw)?;
+        if !blocks.is_empty() {
+            return Ok(blocks);
+        }
+        Ok(vec![(address, parse_memory_contents(&raw)?)])
+    }
+
+    /// Keep `bytes` as the last dump of `addr`, returning the one it replaces.
+    fn remember_dump(&mut self, addr: u64, bytes: Vec<u8>) -> Option<Vec<u8>> {
+        let previous = self
+            .last_dumps
+            .iter()
+            .position(|(a, _)| *a == addr)
+            .and_then(|i| self.last_dumps.remove(i))
+            .map(|(_, b)| b);
+        if self.last_dumps.len() == MAX_REMEMBERED_DUMPS {
+            self.last_dumps.pop_front();
+        }
+        self.last_dumps.push_back((addr, bytes));
+        previous
+    }
+
     /// Wait for a `*stopped` event. Used after run when the initial response did not include it.
     pub fn wait_for_stop(&mut self) -> Result<()> {
+        // A Ctrl-C from before the program was resumed is not meant for this run.
+        interrupt::take();
         loop {
             let mut line = String::new();
-            let n = self.stdout.read_line(&mut line)?;
+            let n = self.read_stop_line(&mut line)?;
             if n == 0 {
-                return Err("gdb exited unexpectedly".into());
+                return Err(self.debugger_died());
             }
-            let trimmed = line.trim().to_string();
-            if trimmed.is_empty() || trimmed == "(gdb)" {
+            // Async records may carry the token of the command that started them.
+            let (tok, record) = split_token(line.trim());
+            let trimmed = record.to_string();
+            if trimmed.is_empty() || trimmed == "(gdb)" || self.is_interrupt_result(tok, &trimmed) {
                 continue;
             }
             if self.verbose {
                 eprintln!("[mi<-] {}", trimmed);
             }
             if trimmed.starts_with("*stopped") {
+                self.varobjs.stale = true;
                 let loc = parse_stopped(&trimmed);
                 if self.arch.is_none() {
                     self.arch = loc.arch.clone();
@@ -548,6 +1547,61 @@ impl MiSession {
         Ok(())
     }
 
+    /// Frames of the current thread, innermost first.
+    pub fn list_frames(&mut self) -> Result<Vec<FrameInfo>> {
+        let resp = self.exec_command("-stack-list-frames")?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("stack list failed: {}", msg).into());
+        }
+        Ok(parse_frames(&resp.result))
+    }
+
+    /// The innermost `count` frames, without unwinding the rest of the stack.
+    pub fn list_top_frames(&mut self, count: u32) -> Result<Vec<FrameInfo>> {
+        let cmd = format!("-stack-list-frames 0 {}", count.saturating_sub(1));
+        let resp = self.exec_command(&cmd)?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("stack list failed: {}", msg).into());
+        }
+        Ok(parse_frames(&resp.result))
+    }
+
+    /// Make `level` the frame used by locals/evaluate/memory commands until the next stop.
+    pub fn select_frame(&mut self, level: u32) -> Result<()> {
+        let resp = self.exec_command(&format!("-stack-select-frame {}", level))?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("select frame failed: {}", msg).into());
+        }
+        self.varobjs.stale = true;
+        Ok(())
+    }
+
+    /// Current values of all named registers, in gdb's register-number order.
+    pub fn list_registers(&mut self) -> Result<Vec<RegisterValue>> {
+        if self.register_names.is_empty() {
+            let resp = self.exec_command("-data-list-register-names")?;
+            if let MiStatus::Error(msg) = resp.status {
+                return Err(format!("register names failed: {}", msg).into());
+            }
+            self.register_names = parse_register_names(&resp.result);
+        }
+        let resp = self.exec_command("-data-list-register-values x")?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("register values failed: {}", msg).into());
+        }
+        let regs = parse_register_values(&resp.result)
+            .into_iter()
+            .filter_map(|(num, value)| {
+                let name = self.register_names.get(num).filter(|n| !n.is_empty())?;
+                Some(RegisterValue {
+                    name: name.clone(),
+                    value,
+                })
+            })
+            .collect();
+        Ok(regs)
+    }
+
     /// Continue execution until next stop.
     pub fn exec_continue(&mut self) -> Result<StoppedLocation> {
         let resp = self.exec_command("-exec-continue")?;
@@ -558,6 +1612,20 @@ impl MiSession {
         Ok(stop)
     }
 
+    /// Start (or restart) the program from the beginning; stops at the first breakpoint,
+    /// normally the startup one from `run_to`.
+    pub fn exec_run(&mut self) -> Result<StoppedLocation> {
+        let resp = self.exec_command("-exec-run")?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("run failed: {}", msg).into());
+        }
+        // The shell redirection truncates the capture file on every run.
+        self.output_seen = 0;
+        self.last_dumps.clear();
+        let stop = self.wait_for_stop_capture()?;
+        Ok(stop)
+    }
+
     /// Step over.
     pub fn exec_next(&mut self) -> Result<StoppedLocation> {
         let resp = self.exec_command("-exec-next")?;
@@ -568,6 +1636,27 @@ impl MiSession {
         Ok(stop)
     }
 
+    /// Resume at `location` (`-exec-jump`) instead of where the program stopped, skipping or
+    /// repeating code; it runs from there until the next stop.
+    pub fn exec_jump(&mut self, location: &str) -> Result<StoppedLocation> {
+        let resp = self.exec_command(&format!("-exec-jump {}", location))?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("jump failed: {}", msg).into());
+        }
+        self.wait_for_stop_capture()
+    }
+
+    /// Run backwards (`what` is next, step, continue or finish); needs a target that
+    /// records execution, such as `rr replay`.
+    pub fn exec_reverse(&mut self, what: &str) -> Result<StoppedLocation> {
+        let resp = self.exec_command(&format!("-exec-{} --reverse", what))?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("reverse-{} failed: {}", what, msg).into());
+        }
+        let stop = self.wait_for_stop_capture()?;
+        Ok(stop)
+    }
+
     /// Step into.
     pub fn exec_step(&mut self) -> Result<StoppedLocation> {
         let resp = self.exec_command("-exec-step")?;
@@ -578,6 +1667,16 @@ impl MiSession {
         Ok(stop)
     }
 
+    /// Run until the selected frame returns.
+    pub fn exec_finish(&mut self) -> Result<StoppedLocation> {
+        let resp = self.exec_command("-exec-finish")?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("finish failed: {}", msg).into());
+        }
+        let stop = self.wait_for_stop_capture()?;
+        Ok(stop)
+    }
+
     /// Insert a breakpoint at given location string.
     pub fn break_insert(&mut self, location: &str) -> Result<BreakpointInfo> {
         let cmd = format!("-break-insert {}", location);
@@ -588,22 +1687,141 @@ impl MiSession {
         Ok(parse_breakpoint(&resp.result))
     }
 
+    /// Insert a user breakpoint and record it in the session breakpoint table.
+    pub fn add_breakpoint(&mut self, location: &str) -> Result<BreakpointInfo> {
+        let bp = self.break_insert(location)?;
+        self.breakpoints.push(bp.clone());
+        Ok(bp)
+    }
+
+    /// Set a console catchpoint (`catch throw`, `catch syscall write`, `catch fork`) and
+    /// record it in the breakpoint table, so enable/disable/delete and hit counts apply.
+    pub fn add_catchpoint(&mut self, event: &str) -> Result<BreakpointInfo> {
+        let bp = self.insert_catchpoint(event)?;
+        self.breakpoints.push(bp.clone());
+        Ok(bp)
+    }
+
+    /// Set a catchpoint without recording it in the table, like `break_insert`.
+    pub fn insert_catchpoint(&mut self, event: &str) -> Result<BreakpointInfo> {
+        let query = self.console_command(ConsoleQuery::Catchpoint, event)?;
+        let resp =
+            self.exec_command(&format!("-interpreter-exec console {}", mi_escape(&query)))?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("catch {} failed: {}", event, msg).into());
+        }
+        // There is no MI result for console commands; gdb announces the new catchpoint.
+        let created = resp
+            .oob
+            .iter()
+            .find(|l| l.starts_with("=breakpoint-created"))
+            .ok_or_else(|| format!("catch {}: no catchpoint was created", event))?;
+        let mut bp = parse_breakpoint(created);
+        bp.location = Some(event.to_string());
+        Ok(bp)
+    }
+
+    /// Delete a user breakpoint and drop it from the table.
+    pub fn remove_breakpoint(&mut self, number: u32) -> Result<BreakpointInfo> {
+        let pos = self
+            .breakpoints
+            .iter()
+            .position(|b| b.number == number)
+            .ok_or_else(|| format!("no breakpoint {}", number))?;
+        self.break_delete(number)?;
+        Ok(self.breakpoints.remove(pos))
+    }
+
+    /// Breakpoints set through `add_breakpoint`, in creation order.
+    pub fn breakpoints(&self) -> &[BreakpointInfo] {
+        &self.breakpoints
+    }
+
+    /// Enable or disable a user breakpoint without deleting it.
+    pub fn set_breakpoint_enabled(&mut self, number: u32, enabled: bool) -> Result<BreakpointInfo> {
+        let pos = self
+            .breakpoints
+            .iter()
+            .position(|b| b.number == number)
+            .ok_or_else(|| format!("no breakpoint {}", number))?;
+        self.break_enable(number, enabled)?;
+        self.breakpoints[pos].enabled = enabled;
+        Ok(self.breakpoints[pos].clone())
+    }
+
+    /// Fold a `=breakpoint-modified` record into the table: gdb sends one on every hit with
+    /// the new count (`times`), and when the breakpoint is changed behind the session's back
+    /// (console commands, scripts). Internal breakpoints are not in the table and ignored.
+    fn note_breakpoint_modified(&mut self, record: &str) {
+        let info = parse_breakpoint(record);
+        if let Some(bp) = self
+            .breakpoints
+            .iter_mut()
+            .find(|b| b.number == info.number)
+        {
+            bp.hits = info.hits;
+            bp.enabled = info.enabled;
+        }
+    }
+
+    /// Remove the user breakpoint at `file:line` if there is one, otherwise insert it.
+    /// Returns the breakpoint and whether it was added.
+    pub fn toggle_breakpoint(&mut self, file: &str, line: u32) -> Result<(BreakpointInfo, bool)> {
+        let existing = self
+            .breakpoints
+            .iter()
+            .find(|b| b.line == Some(line) && b.file.as_deref().is_some_and(|f| same_file(f, file)))
+            .map(|b| b.number);
+        match existing {
+            Some(number) => Ok((self.remove_breakpoint(number)?, false)),
+            None => Ok((self.add_breakpoint(&format!("{}:{}", file, line))?, true)),
+        }
+    }
+
+    /// Enable or disable a breakpoint by number, internal ones included.
+    pub fn break_enable(&mut self, number: u32, enabled: bool) -> Result<()> {
+        let cmd = if enabled {
+            "-break-enable"
+        } else {
+            "-break-disable"
+        };
+        let resp = self.exec_command(&format!("{} {}", cmd, number))?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("{} failed: {}", cmd, msg).into());
+        }
+        Ok(())
+    }
+
+    /// Delete a breakpoint by number.
+    pub fn break_delete(&mut self, number: u32) -> Result<()> {
+        let resp = self.exec_command(&format!("-break-delete {}", number))?;
+        if let MiStatus::Error(msg) = resp.status {
+            return Err(format!("break delete failed: {}", msg).into());
+        }
+        Ok(())
+    }
+
     /// Wait for stopped and parse the location.
     fn wait_for_stop_capture(&mut self) -> Result<StoppedLocation> {
+        // A Ctrl-C from before the program was resumed is not meant for this run.
+        interrupt::take();
         loop {
             let mut line = String::new();
-            let n = self.stdout.read_line(&mut line)?;
+            let n = self.read_stop_line(&mut line)?;
             if n == 0 {
-                return Err("gdb exited unexpectedly".into());
+                return Err(self.debugger_died());
             }
-            let trimmed = line.trim().to_string();
-            if trimmed.is_empty() || trimmed == "(gdb)" {
+            // Async records may carry the token of the command that started them.
+            let (tok, record) = split_token(line.trim());
+            let trimmed = record.to_string();
+            if trimmed.is_empty() || trimmed == "(gdb)" || self.is_interrupt_result(tok, &trimmed) {
                 continue;
             }
             if self.verbose {
                 eprintln!("[mi<-] {}", trimmed);
             }
             if trimmed.starts_with("*stopped") {
+                self.varobjs.stale = true;
                 let loc = parse_stopped(&trimmed);
                 if self.arch.is_none() {
                     self.arch = loc.arch.clone();
@@ -620,10 +1838,66 @@ impl MiSession {
         }
     }
 
+    /// Send a command that sets up the target or program and remember it for `relaunch`
+    /// when gdb accepts it.
+    fn configure(&mut self, cmd: &str) -> Result<MiResponse> {
+        let resp = self.exec_command(cmd)?;
+        if !matches!(resp.status, MiStatus::Error(_)) {
+            self.setup_log.push(cmd.to_string());
+        }
+        Ok(resp)
+    }
+
+    /// The debugger's output ended mid-session: reap it and describe how it went, with the
+    /// last lines of its stderr. Commands fail from now on until `relaunch`.
+    fn debugger_died(&mut self) -> Box<dyn std::error::Error + Send + Sync> {
+        let name = self.backend.name();
+        let Some(child) = &mut self.child else {
+            return format!("{} exited unexpectedly", name).into();
+        };
+        self.dead = true;
+        // The output closes as the process exits; give it a moment to be reapable and for
+        // the stderr reader to catch up.
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let mut status = None;
+        while Instant::now() < deadline {
+            if status.is_none() {
+                status = child.try_wait().ok().flatten();
+            }
+            let stderr_done = self.stderr.lock().map_or(true, |t| t.closed);
+            if status.is_some() && stderr_done {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        let mut msg = match status {
+            Some(status) => format!("{} exited unexpectedly ({})", name, describe_exit(status)),
+            None => format!("{} closed its output unexpectedly", name),
+        };
+        let tail = self
+            .stderr
+            .lock()
+            .map(|t| t.lines.iter().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        if !tail.is_empty() {
+            msg.push_str("; last stderr:");
+            for line in tail {
+                msg.push_str("\n  ");
+                msg.push_str(&line);
+            }
+        }
+        msg.into()
+    }
+
     /// Attempt to shut down gdb cleanly.
     pub fn shutdown(&mut self) {
         let _ = self.send_line("-gdb-exit");
-        let _ = self.child.wait();
+        if let Some(child) = &mut self.child {
+            let _ = child.wait();
+        }
+        if let Some(path) = self.output_path.take() {
+            remove_output_file(&path);
+        }
     }
 
     fn send_line(&mut self, cmd: &str) -> Result<()> {
@@ -632,21 +1906,189 @@ impl MiSession {
         if self.verbose {
             eprintln!("[mi->] {}", cmd);
         }
-        self.stdin.write_all(line.as_bytes())?;
-        self.stdin.flush()?;
+        record::log(Entry::MiSend, cmd);
+        let sent = self
+            .stdin
+            .write_all(line.as_bytes())
+            .and_then(|_| self.stdin.flush());
+        match sent {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Err(self.debugger_died()),
+            other => Ok(other?),
+        }
+    }
+
+    /// Read one raw line from gdb, copying it into the transcript when recording.
+    fn read_mi_line(&mut self, line: &mut String) -> Result<usize> {
+        self.read_line_from_gdb(line, false)
+    }
+
+    /// `read_mi_line` while the program runs: Ctrl-C interrupts it meanwhile.
+    fn read_stop_line(&mut self, line: &mut String) -> Result<usize> {
+        self.read_line_from_gdb(line, true)
+    }
+
+    fn read_line_from_gdb(&mut self, line: &mut String, watch_interrupt: bool) -> Result<usize> {
+        // A closed channel means the output ended.
+        let received = if watch_interrupt {
+            self.recv_watching_interrupt()?
+        } else {
+            match self.timeout {
+                Some(limit) => self.lines.recv_timeout(limit),
+                None => self
+                    .lines
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            }
+        };
+        let n = match received {
+            Ok(read) => {
+                line.push_str(&read?);
+                line.len()
+            }
+            Err(RecvTimeoutError::Disconnected) => 0,
+            Err(RecvTimeoutError::Timeout) => return Err(self.interrupt_stalled()),
+        };
+        if !line.trim().is_empty() {
+            record::log(Entry::MiRecv, line.trim());
+        }
+        // Seen here, whichever command or wait happens to read it.
+        let record = split_token(line.trim()).1;
+        if record.starts_with("=breakpoint-modified") {
+            self.note_breakpoint_modified(record);
+        } else if record.starts_with("=library-loaded") {
+            if let Some(path) = parse_record(record).str("target-name") {
+                self.loaded_libraries.push(path.to_string());
+            }
+        }
+        Ok(n)
+    }
+
+    /// Wait for a line in short slices, pausing the program when Ctrl-C was pressed or it
+    /// outran `stop_timeout`. The stop that causes is returned like any other; only a
+    /// debugger that stays silent after the interrupt times out.
+    fn recv_watching_interrupt(
+        &mut self,
+    ) -> Result<std::result::Result<io::Result<String>, RecvTimeoutError>> {
+        let mut deadline = self.stop_timeout.map(|t| Instant::now() + t);
+        let mut interrupted = false;
+        loop {
+            let slice = deadline.map_or(INTERRUPT_POLL, |d| {
+                d.saturating_duration_since(Instant::now())
+                    .min(INTERRUPT_POLL)
+            });
+            match self.lines.recv_timeout(slice) {
+                Err(RecvTimeoutError::Timeout) => {
+                    if interrupt::take() {
+                        self.interrupt_program()?;
+                    } else if deadline.is_some_and(|d| Instant::now() >= d) {
+                        if interrupted {
+                            return Ok(Err(RecvTimeoutError::Timeout));
+                        }
+                        eprintln!(
+                            "[gdb-memviz] the program did not stop within {}s",
+                            self.stop_timeout.unwrap_or_default().as_secs()
+                        );
+                        self.interrupt_program()?;
+                        interrupted = true;
+                        deadline = Some(Instant::now() + INTERRUPT_GRACE);
+                    }
+                }
+                received => return Ok(received),
+            }
+        }
+    }
+
+    /// Pause the running program: `-exec-interrupt` with `mi-async`, otherwise SIGINT to the
+    /// debugger as from a terminal. The stop it causes (`signal-received`, SIGINT) ends the
+    /// wait as usual.
+    fn interrupt_program(&mut self) -> Result<()> {
+        eprintln!("[gdb-memviz] interrupting the program");
+        if self.mi_async {
+            // Its result arrives among the stop records; the wait skips it by token.
+            let token = self.next_token;
+            self.next_token += 1;
+            self.interrupt_token = Some(token);
+            return self.send_line(&format!("{}-exec-interrupt", token));
+        }
+        if let Some(child) = &self.child {
+            // SAFETY: plain kill(2) on the debugger we spawned.
+            if unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGINT) } != 0 {
+                return Err(format!("interrupt failed: {}", io::Error::last_os_error()).into());
+            }
+        }
         Ok(())
     }
 
-    fn read_response(&mut self) -> Result<MiResponse> {
-        // Collect a single result record (^done/^error/...) and any preceding async output.
+    /// Result record of our `-exec-interrupt` (`^done`, or `^error` when the program had
+    /// already stopped).
+    fn is_interrupt_result(&self, token: Option<u64>, record: &str) -> bool {
+        record.starts_with('^') && token.is_some() && token == self.interrupt_token
+    }
+
+    /// gdb went quiet for longer than `timeout`: interrupt it (stopping the program if it
+    /// runs, or the command it is stuck in), let its reaction through, and report the stall.
+    fn interrupt_stalled(&mut self) -> Box<dyn std::error::Error + Send + Sync> {
+        let secs = self.timeout.unwrap_or_default().as_secs();
+        let Some(child) = &self.child else {
+            return format!("no output from the debugger within {}s", secs).into();
+        };
+        // SIGINT is what gdb gets from Ctrl-C; it reads no MI input while the program runs.
+        let pid = child.id() as libc::pid_t;
+        if unsafe { libc::kill(pid, libc::SIGINT) } != 0 {
+            return format!(
+                "no response from {} within {}s and interrupting it failed: {}",
+                self.backend.name(),
+                secs,
+                io::Error::last_os_error()
+            )
+            .into();
+        }
+        // Drain the "Quit" error or SIGINT stop so the next command reads its own answer.
+        let deadline = Instant::now() + INTERRUPT_GRACE;
+        let mut heard = false;
+        while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+            match self
+                .lines
+                .recv_timeout(left.min(Duration::from_millis(300)))
+            {
+                Ok(Ok(line)) => {
+                    heard = true;
+                    let line = line.trim();
+                    if !line.is_empty() {
+                        record::log(Entry::MiRecv, line);
+                        if self.verbose {
+                            eprintln!("[mi<-] {}", line);
+                        }
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) if !heard => continue,
+                // Quiet again (or gone): whatever follows belongs to the next command.
+                Ok(Err(_)) | Err(_) => break,
+            }
+        }
+        format!(
+            "no response from {} within {}s; interrupted it (the program, if running, is \
+             stopped). Raise the limit with --timeout",
+            self.backend.name(),
+            secs
+        )
+        .into()
+    }
+
+    fn read_response(&mut self, token: u64) -> Result<MiResponse> {
+        // Collect the result record (^done/^error/...) for `token` and any preceding async
+        // output. Results carrying another token answer an earlier command and are dropped;
+        // untokened ones are accepted, for debuggers that do not echo tokens.
         let mut oob = Vec::new();
         let mut result_line: Option<String> = None;
         let mut saw_prompt = false;
+        // The prompt following a dropped result belongs to that result.
+        let mut stale_prompt = false;
         loop {
             let mut line = String::new();
-            let n = self.stdout.read_line(&mut line)?;
+            let n = self.read_mi_line(&mut line)?;
             if n == 0 {
-                return Err("gdb exited unexpectedly".into());
+                return Err(self.debugger_died());
             }
             let trimmed = line.trim().to_string();
             if trimmed.is_empty() {
@@ -655,7 +2097,12 @@ impl MiSession {
             if self.verbose {
                 eprintln!("[mi<-] {}", trimmed);
             }
-            if trimmed == "(gdb)" {
+            let (tok, record) = split_token(&trimmed);
+            let record = record.to_string();
+            if record == "(gdb)" {
+                if std::mem::take(&mut stale_prompt) {
+                    continue;
+                }
                 saw_prompt = true;
                 if result_line.is_some() {
                     break;
@@ -663,15 +2110,25 @@ impl MiSession {
                     continue;
                 }
             }
-            if trimmed.starts_with('^') {
-                result_line = Some(trimmed.clone());
+            if record.starts_with('^') {
+                if let Some(stale) = tok.filter(|t| *t != token) {
+                    if self.verbose {
+                        eprintln!("[gdb-memviz] dropping result for earlier token {}", stale);
+                    }
+                    stale_prompt = true;
+                    continue;
+                }
+                result_line = Some(record);
                 if saw_prompt {
                     break;
                 } else {
                     continue;
                 }
             }
-            oob.push(trimmed);
+            if record.starts_with("*stopped") {
+                self.varobjs.stale = true;
+            }
+            oob.push(record);
         }
         let res = result_line.unwrap_or_else(|| String::from("^error,msg=\"missing result\""));
         let status = parse_status(&res);
@@ -689,9 +2146,9 @@ impl MiSession {
         let mut saw_result = false;
         loop {
             let mut line = String::new();
-            let n = self.stdout.read_line(&mut line)?;
+            let n = self.read_mi_line(&mut line)?;
             if n == 0 {
-                return Err("gdb exited unexpectedly".into());
+                return Err(self.debugger_died());
             }
             let trimmed = line.trim().to_string();
             if trimmed.is_empty() {
@@ -712,6 +2169,64 @@ impl MiSession {
     }
 }
 
+/// Start `program` as `backend` on `target` with its MI streams piped.
+fn spawn_debugger(
+    backend: &dyn DebuggerBackend,
+    program: &str,
+    target: &str,
+    args: &[String],
+) -> Result<Child> {
+    let name = backend.name();
+    Command::new(program)
+        .args(backend.launch_args(target, args))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            if e.kind() == io::ErrorKind::NotFound {
+                format!(
+                    "{} binary '{}' not found. Install {} or pass --gdb <path>",
+                    name, program, name
+                )
+                .into()
+            } else {
+                format!("failed to launch {} '{}': {}", name, program, e).into()
+            }
+        })
+}
+
+/// "exit status 1", "killed by signal 11 (core dumped)".
+fn describe_exit(status: ExitStatus) -> String {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => format!("exit status {}", code),
+        (None, Some(sig)) if status.core_dumped() => {
+            format!("killed by signal {} (core dumped)", sig)
+        }
+        (None, Some(sig)) => format!("killed by signal {}", sig),
+        (None, None) => status.to_string(),
+    }
+}
+
+/// gdb reports breakpoint files as given or as basenames; compare on the basename.
+pub(crate) fn same_file(a: &str, b: &str) -> bool {
+    let base = |p: &str| p.rsplit('/').next().unwrap_or(p).to_string();
+    base(a) == base(b)
+}
+
+/// Copy gdb memory blocks into `out` (indexed relative to `base`), marking covered bytes.
+fn place_blocks(base: u64, blocks: &[MemoryBlock], out: &mut [u8], readable: &mut [bool]) {
+    for (begin, data) in blocks {
+        for (i, b) in data.iter().enumerate() {
+            let idx = begin.wrapping_add(i as u64).wrapping_sub(base);
+            if let Some(slot) = out.get_mut(idx as usize) {
+                *slot = *b;
+                readable[idx as usize] = true;
+            }
+        }
+    }
+}
+
 fn parse_global_decl(line: &str) -> Option<(String, String)> {
     // Examples:
     // "13:\tint g_counter;"
@@ -742,6 +2257,27 @@ fn parse_global_decl(line: &str) -> Option<(String, String)> {
     Some((type_name, name))
 }
 
+/// Kernel thread id in a thread's `target-id`: "Thread 0x7ffff7d8a640 (LWP 1235)", or
+/// "process 1234" before libpthread is known.
+fn parse_lwp(target_id: &str) -> Option<u32> {
+    let digits = match target_id.split_once("LWP ") {
+        Some((_, rest)) => rest,
+        None => target_id.strip_prefix("process ")?,
+    };
+    let end = digits
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(digits.len());
+    digits[..end].parse().ok()
+}
+
+/// `cmd` with `--thread`/`--frame` after the command name, where MI expects options.
+fn scoped_command(cmd: &str, thread: u32, frame: u32) -> String {
+    let (name, rest) = cmd.split_once(' ').unwrap_or((cmd, ""));
+    format!("{} --thread {} --frame {} {}", name, thread, frame, rest)
+        .trim_end()
+        .to_string()
+}
+
 fn parse_address_str(s: &str) -> Option<u64> {
     let trimmed = s.trim();
     if let Some(hex) = trimmed.strip_prefix("0x") {
@@ -765,3 +2301,233 @@ fn parse_address_str(s: &str) -> Option<u64> {
     }
     None
 }
+
+/// An empty file for the program's output, created (0600, `O_EXCL`) in a fresh directory only
+/// this user can enter (`mkdtemp`, 0700), so nobody can plant a symlink where the shell's `>`
+/// will write.
+fn private_output_file() -> Result<PathBuf> {
+    use std::ffi::OsString;
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+    use std::os::unix::fs::OpenOptionsExt;
+    let template = std::env::temp_dir().join("gdb-memviz-XXXXXX");
+    let mut raw = template.as_os_str().as_bytes().to_vec();
+    if raw.contains(&0) {
+        return Err("temp dir path contains a NUL byte".into());
+    }
+    raw.push(0);
+    // SAFETY: `raw` is a writable NUL-terminated template; mkdtemp only rewrites the Xs.
+    if unsafe { libc::mkdtemp(raw.as_mut_ptr() as *mut libc::c_char) }.is_null() {
+        return Err(format!("cannot create a temp dir: {}", io::Error::last_os_error()).into());
+    }
+    raw.pop();
+    let path = PathBuf::from(OsString::from_vec(raw)).join("program.out");
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)?;
+    Ok(path)
+}
+
+/// Remove an output file made by `private_output_file`, and its directory.
+fn remove_output_file(path: &Path) {
+    let _ = fs::remove_file(path);
+    if let Some(dir) = path.parent() {
+        let _ = fs::remove_dir(dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn program_output_goes_to_a_private_directory() {
+        let path = private_output_file().unwrap();
+        let dir = path.parent().unwrap().to_path_buf();
+        let mode = |p: &Path| fs::symlink_metadata(p).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&dir), 0o700);
+        assert_eq!(mode(&path), 0o600);
+        let other = private_output_file().unwrap();
+        assert_ne!(other.parent(), Some(dir.as_path()));
+        remove_output_file(&other);
+        remove_output_file(&path);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn reports_a_dead_debugger_and_relaunches_it() {
+        let dir = std::env::temp_dir().join(format!("gdb-memviz-relaunch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("commands");
+        // Stand-in debugger: answers every command, numbers breakpoints from 1, and crashes
+        // on -gdb-version after printing a message to stderr.
+        let script = format!(
+            r#"#!/bin/sh
+echo '(gdb)'
+n=0
+while read cmd; do
+  echo "$cmd" >> '{}'
+  tok=${{cmd%%[!0-9]*}}
+  case "$cmd" in
+    *-gdb-version*) echo 'internal-error: something broke' >&2; exit 3 ;;
+    *-gdb-exit*) exit 0 ;;
+    *-break-insert*) n=$((n+1))
+      echo "$tok^done,bkpt={{number=\"$n\",type=\"breakpoint\",enabled=\"y\",func=\"main\",original-location=\"main\",times=\"0\"}}" ;;
+    *) echo "$tok^done" ;;
+  esac
+  echo '(gdb)'
+done
+"#,
+            log.display()
+        );
+        let fake = dir.join("fake-gdb");
+        fs::write(&fake, script).unwrap();
+        fs::set_permissions(&fake, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut session = MiSession::start(fake.to_str().unwrap(), "prog", &[], false).unwrap();
+        session.drain_initial_output().unwrap();
+        session.change_directory("/tmp").unwrap();
+        session.add_breakpoint("main").unwrap();
+        let err = session
+            .exec_command("-gdb-version")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("exit status 3"), "{}", err);
+        assert!(err.contains("internal-error: something broke"), "{}", err);
+        assert!(session.is_dead());
+        assert!(session.exec_command("-stack-list-frames").is_err());
+
+        assert!(session.relaunch().unwrap().is_empty());
+        assert!(!session.is_dead());
+        let bp = &session.breakpoints()[0];
+        assert_eq!(bp.number, 1);
+        assert_eq!(bp.location.as_deref(), Some("main"));
+        let sent = fs::read_to_string(&log).unwrap();
+        assert_eq!(sent.matches("-environment-cd").count(), 2);
+        assert_eq!(sent.matches("-break-insert main").count(), 2);
+        session.shutdown();
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn only_the_most_recent_dumps_are_remembered() {
+        let mut session = MiSession::replay(
+            Box::new(Gdb),
+            Box::new(io::sink()),
+            Box::new(io::empty()),
+            "prog",
+            &[],
+            false,
+        );
+        for addr in 0..MAX_REMEMBERED_DUMPS as u64 {
+            assert_eq!(session.remember_dump(addr, vec![1]), None);
+        }
+        // Dumping address 0 again makes it the most recent, so 1 is forgotten next.
+        assert_eq!(session.remember_dump(0, vec![2]), Some(vec![1]));
+        session.remember_dump(1000, vec![3]);
+        assert_eq!(session.last_dumps.len(), MAX_REMEMBERED_DUMPS);
+        assert_eq!(session.remember_dump(1, vec![4]), None);
+        assert_eq!(session.remember_dump(0, vec![5]), Some(vec![2]));
+    }
+
+    #[test]
+    fn hit_counts_follow_breakpoint_modified_records() {
+        let output = "1^done,bkpt={number=\"1\",type=\"breakpoint\",enabled=\"y\",func=\"main\",times=\"0\"}\n\
+                      (gdb)\n2^running\n*running,thread-id=\"all\"\n(gdb)\n\
+                      =breakpoint-modified,bkpt={number=\"1\",type=\"breakpoint\",enabled=\"y\",func=\"main\",times=\"3\"}\n\
+                      *stopped,reason=\"breakpoint-hit\",bkptno=\"1\",frame={func=\"main\"}\n(gdb)\n";
+        let mut session = MiSession::replay(
+            Box::new(Gdb),
+            Box::new(io::sink()),
+            Box::new(io::Cursor::new(output.as_bytes().to_vec())),
+            "prog",
+            &[],
+            false,
+        );
+        session.add_breakpoint("main").unwrap();
+        assert_eq!(session.breakpoints()[0].hits, 0);
+        let stop = session.exec_continue().unwrap();
+        assert_eq!(stop.bkptno, Some(1));
+        assert_eq!(session.breakpoints()[0].hits, 3);
+    }
+
+    #[test]
+    fn c_strings_are_read_in_chunks_up_to_the_nul() {
+        // 0x1038 is 8 bytes before a page boundary: the first read stops there.
+        let output = "1^done,memory=[{begin=\"0x1038\",offset=\"0x0\",end=\"0x1040\",contents=\"68656c6c6f2c2077\"}]\n(gdb)\n\
+                      2^done,memory=[{begin=\"0x1040\",offset=\"0x0\",end=\"0x1080\",contents=\"6f726c640a0041410000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\"}]\n(gdb)\n\
+                      3^done,memory=[{begin=\"0x1038\",offset=\"0x0\",end=\"0x103c\",contents=\"68656c6c\"}]\n(gdb)\n";
+        let mut session = MiSession::replay(
+            Box::new(Gdb),
+            Box::new(io::sink()),
+            Box::new(io::Cursor::new(output.as_bytes().to_vec())),
+            "prog",
+            &[],
+            false,
+        );
+        let s = session.read_c_string(0x1038, 256).unwrap();
+        assert_eq!(s.text, "hello, world\n");
+        assert!(!s.truncated);
+        let s = session.read_c_string(0x1038, 4).unwrap();
+        assert_eq!(s.text, "hell");
+        assert!(s.truncated);
+    }
+
+    #[test]
+    fn memory_writes_are_read_back() {
+        let output = "1^done\n(gdb)\n\
+                      2^done,memory=[{begin=\"0x2000\",offset=\"0x0\",end=\"0x2002\",contents=\"abcd\"}]\n(gdb)\n\
+                      3^done\n(gdb)\n\
+                      4^done,memory=[{begin=\"0x2000\",offset=\"0x0\",end=\"0x2002\",contents=\"ab00\"}]\n(gdb)\n";
+        let mut session = MiSession::replay(
+            Box::new(Gdb),
+            Box::new(io::sink()),
+            Box::new(io::Cursor::new(output.as_bytes().to_vec())),
+            "prog",
+            &[],
+            false,
+        );
+        session.write_memory_bytes(0x2000, &[0xab, 0xcd]).unwrap();
+        let err = session
+            .write_memory_bytes(0x2000, &[0xab, 0xcd])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("0x2001"), "{}", err);
+    }
+
+    #[test]
+    fn thread_stack_pointers_are_read_per_thread() {
+        let output = concat!(
+            "1^done,threads=[{id=\"1\",target-id=\"Thread 0x7ffff7d8b740 (LWP 4000)\",state=\"stopped\"},{id=\"2\",target-id=\"Thread 0x7ffff7d8a640 (LWP 4001)\",state=\"stopped\"}],current-thread-id=\"1\"\n(gdb)\n",
+            "2^done,value=\"(void *) 0x7fffffffe3c0\"\n(gdb)\n",
+            "3^done,value=\"(void *) 0x7ffff7d89e70\"\n(gdb)\n",
+        );
+        let mut session = MiSession::replay(
+            Box::new(Gdb),
+            Box::new(io::sink()),
+            Box::new(io::Cursor::new(output.as_bytes().to_vec())),
+            "prog",
+            &[],
+            false,
+        );
+        assert_eq!(
+            session.thread_stack_pointers().unwrap(),
+            [(4000, 0x7fffffffe3c0), (4001, 0x7ffff7d89e70)]
+        );
+        assert_eq!(parse_lwp("process 4000"), Some(4000));
+    }
+
+    #[test]
+    fn frame_options_go_before_the_arguments() {
+        assert_eq!(
+            scoped_command("-data-evaluate-expression \"n + 1\"", 2, 1),
+            "-data-evaluate-expression --thread 2 --frame 1 \"n + 1\""
+        );
+        assert_eq!(
+            scoped_command("-stack-list-locals", 1, 3),
+            "-stack-list-locals --thread 1 --frame 3"
+        );
+    }
+}
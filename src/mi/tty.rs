@@ -0,0 +1,154 @@
+// Pseudo-terminal for the debugged program (`-inferior-tty-set`): its output no longer mixes
+// with the MI stream or the REPL, it sees a real terminal (line-buffered stdio, isatty), and
+// input typed in the REPL is passed through to it.
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::fd::FromRawFd;
+use std::sync::{Arc, Mutex};
+
+pub struct InferiorTty {
+    /// Terminal handed to gdb (`/dev/pts/N`).
+    pub path: String,
+    master: File,
+    // Held open so the master never reports EIO while no program is attached (between runs).
+    _slave: File,
+    // Everything the program wrote, filled by the reader thread.
+    output: Arc<Mutex<Vec<u8>>>,
+    seen: usize,
+}
+
+impl InferiorTty {
+    /// Allocate a pty in raw mode (no echo of passed-through input, no `\r\n` translation)
+    /// and start copying what the program writes to it.
+    pub fn open() -> io::Result<Self> {
+        // SAFETY: plain descriptor calls; `fd` is checked before use and owned by the
+        // `master` File afterwards, `name`/`attrs` are sized for the calls filling them.
+        let (master, path) = unsafe {
+            let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let master = File::from_raw_fd(fd);
+            let mut name = [0 as libc::c_char; 128];
+            if libc::grantpt(fd) != 0 || libc::unlockpt(fd) != 0 || pts_name(fd, &mut name) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut attrs: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(fd, &mut attrs) == 0 {
+                libc::cfmakeraw(&mut attrs);
+                libc::tcsetattr(fd, libc::TCSANOW, &attrs);
+            }
+            let path = std::ffi::CStr::from_ptr(name.as_ptr())
+                .to_string_lossy()
+                .into_owned();
+            (master, path)
+        };
+        let slave = OpenOptions::new().read(true).write(true).open(&path)?;
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let mut reader = master.try_clone()?;
+        let sink = Arc::clone(&output);
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 4096];
+            while let Ok(n @ 1..) = reader.read(&mut buf) {
+                if let Ok(mut out) = sink.lock() {
+                    out.extend_from_slice(&buf[..n]);
+                }
+            }
+        });
+        Ok(Self {
+            path,
+            master,
+            _slave: slave,
+            output,
+            seen: 0,
+        })
+    }
+
+    /// Output written since the previous call.
+    pub fn take_new(&mut self) -> String {
+        let Ok(out) = self.output.lock() else {
+            return String::new();
+        };
+        let new = String::from_utf8_lossy(&out[self.seen..]).into_owned();
+        self.seen = out.len();
+        new
+    }
+
+    /// Everything the program wrote so far.
+    pub fn all(&self) -> String {
+        self.output
+            .lock()
+            .map(|out| String::from_utf8_lossy(&out).into_owned())
+            .unwrap_or_default()
+    }
+
+    /// Type `text` into the program's terminal.
+    pub fn send_input(&mut self, text: &str) -> io::Result<()> {
+        self.master.write_all(text.as_bytes())?;
+        self.master.flush()
+    }
+}
+
+/// `ptsname_r` into `name`. Apple has no ptsname_r; its `ptsname` result is copied out right
+/// away (the pty is opened once, before any other thread could call it).
+///
+/// # Safety
+/// `fd` must be an open pty master.
+#[cfg(not(target_vendor = "apple"))]
+unsafe fn pts_name(fd: libc::c_int, name: &mut [libc::c_char]) -> libc::c_int {
+    libc::ptsname_r(fd, name.as_mut_ptr(), name.len())
+}
+
+/// `ptsname_r` into `name`. Apple has no ptsname_r; its `ptsname` result is copied out right
+/// away (the pty is opened once, before any other thread could call it).
+///
+/// # Safety
+/// `fd` must be an open pty master.
+#[cfg(target_vendor = "apple")]
+unsafe fn pts_name(fd: libc::c_int, name: &mut [libc::c_char]) -> libc::c_int {
+    let p = libc::ptsname(fd);
+    if p.is_null() {
+        return -1;
+    }
+    let bytes = std::ffi::CStr::from_ptr(p).to_bytes_with_nul();
+    if bytes.len() > name.len() {
+        return -1;
+    }
+    for (dst, src) in name.iter_mut().zip(bytes) {
+        *dst = *src as libc::c_char;
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn passes_output_and_input_through_the_pty() {
+        let Ok(mut tty) = InferiorTty::open() else {
+            // No devpts in this environment.
+            return;
+        };
+        let mut program = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&tty.path)
+            .unwrap();
+        program.write_all(b"hello\n").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        let mut seen = String::new();
+        while !seen.contains("hello\n") && Instant::now() < deadline {
+            seen.push_str(&tty.take_new());
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(seen, "hello\n");
+        assert_eq!(tty.take_new(), "");
+        tty.send_input("42\n").unwrap();
+        let mut buf = [0u8; 3];
+        program.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"42\n");
+        assert_eq!(tty.all(), "hello\n");
+    }
+}
@@ -0,0 +1,200 @@
+// MI output grammar (gdb manual, "GDB/MI Output Syntax"): a record is an optional token, a
+// class (`^done`, `*stopped`, `=library-loaded`, ...) and comma-separated results, where
+//
+//   result = variable "=" value
+//   value  = c-string | tuple | list
+//   tuple  = "{}" | "{" result ( "," result )* "}"
+//   list   = "[]" | "[" value ( "," value )* "]" | "[" result ( "," result )* "]"
+//
+// The parser is lenient: it also takes fragments (bare results or values, as tests and
+// helpers pass around) and stops quietly at anything it does not understand.
+use crate::mi::parser::{split_token, unescape_value};
+
+/// A parsed MI value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MiValue {
+    /// A c-string, unescaped.
+    Const(String),
+    /// `{name=value,...}`, and the results of a whole record. Bare values get an empty name.
+    Tuple(Vec<(String, MiValue)>),
+    /// `[value,...]`; for lists of results (`[frame={...},...]`) the names are dropped.
+    List(Vec<MiValue>),
+}
+
+impl MiValue {
+    /// Field `name` of a tuple.
+    pub fn get(&self, name: &str) -> Option<&MiValue> {
+        match self {
+            MiValue::Tuple(fields) => fields.iter().find(|(n, _)| n == name).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Field `name` of a tuple when it is a string.
+    pub fn str(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(MiValue::as_str)
+    }
+
+    /// Field `name` of a tuple parsed as a number (or anything else `FromStr`).
+    pub fn parse<T: std::str::FromStr>(&self, name: &str) -> Option<T> {
+        self.str(name).and_then(|s| s.parse().ok())
+    }
+
+    /// Items of the list in field `name`; empty when there is none.
+    pub fn list(&self, name: &str) -> Vec<&MiValue> {
+        self.get(name).map(MiValue::items).unwrap_or_default()
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            MiValue::Const(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Items of a list; a tuple yields its values, anything else nothing.
+    pub fn items(&self) -> Vec<&MiValue> {
+        match self {
+            MiValue::List(items) => items.iter().collect(),
+            MiValue::Tuple(fields) => fields.iter().map(|(_, v)| v).collect(),
+            MiValue::Const(_) => Vec::new(),
+        }
+    }
+}
+
+/// Parse a record (`12^done,bkpt={...}`) or a fragment of one into a tuple of its results.
+pub fn parse_record(line: &str) -> MiValue {
+    let mut rest = split_token(line.trim()).1;
+    // Skip the class; `(gdb)` and stream records have no results.
+    if rest.starts_with(['^', '*', '=', '+']) {
+        rest = rest.find(',').map(|i| &rest[i + 1..]).unwrap_or("");
+    }
+    let mut p = Parser { s: rest, pos: 0 };
+    MiValue::Tuple(p.items(None))
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.s.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_space(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    /// Comma-separated `name=value` or bare values up to `close` (or the end).
+    fn items(&mut self, close: Option<u8>) -> Vec<(String, MiValue)> {
+        let mut out = Vec::new();
+        loop {
+            self.skip_space();
+            if self.peek().is_none() || self.peek() == close {
+                break;
+            }
+            match self.item() {
+                Some(item) => out.push(item),
+                None => break,
+            }
+            self.skip_space();
+            if self.peek() == Some(b',') {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        if close.is_some() && self.peek() == close {
+            self.pos += 1;
+        }
+        out
+    }
+
+    fn item(&mut self) -> Option<(String, MiValue)> {
+        if let Some(value) = self.value() {
+            return Some((String::new(), value));
+        }
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_' || c == b'-')
+        {
+            self.pos += 1;
+        }
+        let name = self.s[start..self.pos].to_string();
+        if name.is_empty() || self.peek() != Some(b'=') {
+            return None;
+        }
+        self.pos += 1;
+        Some((name, self.value()?))
+    }
+
+    fn value(&mut self) -> Option<MiValue> {
+        match self.peek()? {
+            b'"' => self.c_string().map(MiValue::Const),
+            b'{' => {
+                self.pos += 1;
+                Some(MiValue::Tuple(self.items(Some(b'}'))))
+            }
+            b'[' => {
+                self.pos += 1;
+                let items = self.items(Some(b']'));
+                Some(MiValue::List(items.into_iter().map(|(_, v)| v).collect()))
+            }
+            _ => None,
+        }
+    }
+
+    fn c_string(&mut self) -> Option<String> {
+        let bytes = self.s.as_bytes();
+        let start = self.pos + 1;
+        let mut i = start;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'"' => {
+                    self.pos = i + 1;
+                    return Some(unescape_value(&self.s[start..i]));
+                }
+                _ => i += 1,
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_records() {
+        let rec = parse_record(
+            r#"7^done,bkpt={number="2",locations=[{number="2.1",func="f"}]},stack=[frame={level="0"},frame={level="1"}],msg="say \"hi\"\n",empty={},none=[]"#,
+        );
+        let bkpt = rec.get("bkpt").unwrap();
+        assert_eq!(bkpt.parse::<u32>("number"), Some(2));
+        assert_eq!(bkpt.list("locations")[0].str("func"), Some("f"));
+        let levels: Vec<_> = rec
+            .list("stack")
+            .iter()
+            .filter_map(|f| f.str("level"))
+            .collect();
+        assert_eq!(levels, ["0", "1"]);
+        assert_eq!(rec.str("msg"), Some("say \"hi\"\n"));
+        assert_eq!(rec.get("empty"), Some(&MiValue::Tuple(Vec::new())));
+        assert_eq!(rec.get("none"), Some(&MiValue::List(Vec::new())));
+        // Fragments: bare values, and garbage ends the parse without panicking.
+        let frag = parse_record(r#"{name="x"},{name="y"} trailing"#);
+        assert_eq!(frag.items().len(), 2);
+        assert_eq!(
+            parse_record(r#"^error,msg="unterminated"#),
+            MiValue::Tuple(Vec::new())
+        );
+        assert_eq!(parse_record("(gdb)"), MiValue::Tuple(Vec::new()));
+    }
+}
@@ -0,0 +1,162 @@
+// Managed variable objects: one floating root per expression, kept across stops; children
+// listed on demand and cached; one `-var-update` per stop refreshing every value. gdb only
+// reports what changed, so big structs are not re-read, and its change list doubles as
+// changed-value highlighting.
+use crate::mi::models::{VarChange, VarNode};
+use std::collections::{HashMap, HashSet};
+
+/// Roots kept before the oldest is deleted.
+const MAX_ROOTS: usize = 32;
+
+#[derive(Default)]
+pub struct VarObjects {
+    // (expression, varobj name), oldest first.
+    roots: Vec<(String, String)>,
+    nodes: HashMap<String, VarNode>,
+    // Child varobj names by parent, once listed.
+    children: HashMap<String, Vec<String>>,
+    // Varobjs whose value changed in the last update.
+    changed: HashSet<String>,
+    /// A stop or frame change happened since the last update.
+    pub(crate) stale: bool,
+}
+
+impl VarObjects {
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    pub fn root(&self, expr: &str) -> Option<&VarNode> {
+        let (_, name) = self.roots.iter().find(|(e, _)| e == expr)?;
+        self.nodes.get(name)
+    }
+
+    /// Remember a new root; returns the evicted root's name, to delete in gdb.
+    pub(crate) fn add_root(&mut self, expr: &str, node: VarNode) -> Option<String> {
+        self.roots.push((expr.to_string(), node.name.clone()));
+        self.nodes.insert(node.name.clone(), node);
+        if self.roots.len() <= MAX_ROOTS {
+            return None;
+        }
+        let (_, oldest) = self.roots.remove(0);
+        self.forget(&oldest);
+        Some(oldest)
+    }
+
+    pub fn cached_children(&self, name: &str) -> Option<Vec<VarNode>> {
+        let names = self.children.get(name)?;
+        names.iter().map(|n| self.nodes.get(n).cloned()).collect()
+    }
+
+    pub(crate) fn set_children(&mut self, name: &str, kids: Vec<VarNode>) {
+        let names = kids.iter().map(|k| k.name.clone()).collect();
+        for kid in kids {
+            self.nodes.insert(kid.name.clone(), kid);
+        }
+        self.children.insert(name.to_string(), names);
+    }
+
+    /// Value changed in the last `-var-update`.
+    pub fn is_changed(&self, name: &str) -> bool {
+        self.changed.contains(name)
+    }
+
+    /// Fold a `-var-update` change list in. Returns roots that went out of scope; they are
+    /// forgotten here and should be deleted in gdb.
+    pub(crate) fn apply(&mut self, changes: &[VarChange]) -> Vec<String> {
+        self.changed.clear();
+        self.stale = false;
+        let mut dead = Vec::new();
+        for change in changes {
+            if !change.in_scope {
+                if self.roots.iter().any(|(_, n)| *n == change.name) {
+                    dead.push(change.name.clone());
+                }
+                continue;
+            }
+            let Some(node) = self.nodes.get_mut(&change.name) else {
+                continue;
+            };
+            if let Some(ty) = &change.new_type {
+                // gdb dropped the children along with the old type.
+                node.type_name = Some(ty.clone());
+                self.drop_children(&change.name);
+            }
+            let Some(node) = self.nodes.get_mut(&change.name) else {
+                continue;
+            };
+            if let Some(n) = change.new_numchild {
+                node.numchild = n;
+            }
+            if node.value != change.value {
+                node.value = change.value.clone();
+                self.changed.insert(change.name.clone());
+            }
+        }
+        for name in &dead {
+            self.roots.retain(|(_, n)| n != name);
+            self.forget(name);
+        }
+        dead
+    }
+
+    /// Drop `name` and everything below it.
+    fn forget(&mut self, name: &str) {
+        self.drop_children(name);
+        self.nodes.remove(name);
+    }
+
+    fn drop_children(&mut self, name: &str) {
+        // Child names extend the parent's: var3 -> var3.next -> var3.next.value.
+        let prefix = format!("{}.", name);
+        self.nodes.retain(|n, _| !n.starts_with(&prefix));
+        self.children
+            .retain(|n, _| n != name && !n.starts_with(&prefix));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mi::parser::parse_var_changes;
+
+    fn node(name: &str, value: &str, numchild: u32) -> VarNode {
+        VarNode {
+            name: name.to_string(),
+            exp: name.rsplit('.').next().unwrap_or(name).to_string(),
+            type_name: Some("int".to_string()),
+            value: Some(value.to_string()),
+            numchild,
+        }
+    }
+
+    #[test]
+    fn updates_track_changes_and_scope() {
+        let mut vars = VarObjects::default();
+        vars.add_root("list", node("var1", "0x4052a0", 2));
+        vars.set_children(
+            "var1",
+            vec![node("var1.value", "1", 0), node("var1.next", "0x0", 2)],
+        );
+        vars.add_root("i", node("var2", "0", 0));
+        let changes = parse_var_changes(
+            r#"^done,changelist=[{name="var1.value",value="2",in_scope="true",type_changed="false",has_more="0"},{name="var2",in_scope="false",type_changed="false",has_more="0"}]"#,
+        );
+        assert_eq!(vars.apply(&changes), ["var2"]);
+        assert!(vars.is_changed("var1.value"));
+        assert!(!vars.is_changed("var1.next"));
+        let kids = vars.cached_children("var1").unwrap();
+        assert_eq!(kids[0].value.as_deref(), Some("2"));
+        assert!(vars.root("i").is_none());
+        // A new type invalidates the cached children.
+        let changes = parse_var_changes(
+            r#"changelist=[{name="var1",value="0x0",in_scope="true",type_changed="true",new_type="struct Node *",new_num_children="2",has_more="0"}]"#,
+        );
+        vars.apply(&changes);
+        assert!(vars.cached_children("var1").is_none());
+        assert_eq!(
+            vars.root("list").unwrap().type_name.as_deref(),
+            Some("struct Node *")
+        );
+    }
+}
@@ -0,0 +1,267 @@
+// `--record`: timestamped transcript of REPL commands, gdb/MI traffic and rendered output.
+//
+// Each line is `[<seconds since start>] <tag> <text>`. Tags: `cmd` (REPL input), `mi>` (sent to
+// gdb), `mi<` (read from gdb), `out`/`err` (what the user saw on stdout/stderr). The `mi<` lines
+// are complete enough for `--replay` to stand in for gdb.
+use std::fs::File;
+use std::io::{self, LineWriter, Read, Write};
+use std::os::fd::{FromRawFd, RawFd};
+use std::sync::{Mutex, OnceLock};
+use std::thread::JoinHandle;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+pub const HEADER: &str = "# gdb-memviz transcript v1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entry {
+    Command,
+    MiSend,
+    MiRecv,
+    Stdout,
+    Stderr,
+}
+
+impl Entry {
+    pub fn tag(self) -> &'static str {
+        match self {
+            Entry::Command => "cmd",
+            Entry::MiSend => "mi>",
+            Entry::MiRecv => "mi<",
+            Entry::Stdout => "out",
+            Entry::Stderr => "err",
+        }
+    }
+
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        [
+            Entry::Command,
+            Entry::MiSend,
+            Entry::MiRecv,
+            Entry::Stdout,
+            Entry::Stderr,
+        ]
+        .into_iter()
+        .find(|e| e.tag() == tag)
+    }
+}
+
+struct Recorder {
+    file: LineWriter<File>,
+    start: Instant,
+    // Unterminated stdout/stderr text; written once its newline arrives.
+    pending_out: String,
+    pending_err: String,
+}
+
+impl Recorder {
+    fn write(&mut self, entry: Entry, text: &str) {
+        let secs = self.start.elapsed().as_secs_f64();
+        for line in text.lines() {
+            let _ = writeln!(self.file, "{}", format_entry(secs, entry, line));
+        }
+    }
+
+    /// Write whatever partial output is buffered (e.g. the prompt before a command).
+    fn flush_pending(&mut self) {
+        for entry in [Entry::Stdout, Entry::Stderr] {
+            let text = std::mem::take(self.pending(entry));
+            if !text.is_empty() {
+                self.write(entry, &text);
+            }
+        }
+    }
+
+    fn pending(&mut self, entry: Entry) -> &mut String {
+        match entry {
+            Entry::Stderr => &mut self.pending_err,
+            _ => &mut self.pending_out,
+        }
+    }
+}
+
+static RECORDER: OnceLock<Mutex<Recorder>> = OnceLock::new();
+/// Redirected descriptors: (fd, copy of the original to restore, forwarding thread).
+static TEES: Mutex<Vec<(RawFd, RawFd, JoinHandle<()>)>> = Mutex::new(Vec::new());
+
+/// Open the transcript and start copying stdout/stderr into it. `args` goes into the header.
+pub fn start(path: &str, args: &[String]) -> io::Result<()> {
+    let mut file = LineWriter::new(File::create(path)?);
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    writeln!(file, "{}", HEADER)?;
+    writeln!(file, "# started: {} (unix time)", started)?;
+    // JSON keeps arguments with spaces intact for `--replay`.
+    writeln!(file, "# args: {}", serde_json::json!(args))?;
+    let recorder = Recorder {
+        file,
+        start: Instant::now(),
+        pending_out: String::new(),
+        pending_err: String::new(),
+    };
+    if RECORDER.set(Mutex::new(recorder)).is_err() {
+        return Err(io::Error::other("recording already started"));
+    }
+    tee(libc::STDOUT_FILENO, Entry::Stdout)?;
+    tee(libc::STDERR_FILENO, Entry::Stderr)?;
+    Ok(())
+}
+
+/// Put stdout/stderr back and wait until everything written so far is forwarded and logged.
+pub fn finish() {
+    let _ = io::stdout().flush();
+    let tees = match TEES.lock() {
+        Ok(mut t) => std::mem::take(&mut *t),
+        Err(_) => return,
+    };
+    for (fd, restore, handle) in tees {
+        // SAFETY: `restore` was dup'ed in `tee` and is closed exactly once here. Replacing
+        // `fd` drops the last write end of the pipe, so the thread sees EOF and exits.
+        unsafe {
+            libc::dup2(restore, fd);
+            libc::close(restore);
+        }
+        let _ = handle.join();
+    }
+    if let Some(Ok(mut r)) = RECORDER.get().map(|r| r.lock()) {
+        r.flush_pending();
+    }
+}
+
+/// A transcript is being written.
+pub fn active() -> bool {
+    RECORDER.get().is_some()
+}
+
+/// Append `text` (one entry per line) when recording; a no-op otherwise.
+pub fn log(entry: Entry, text: &str) {
+    let Some(recorder) = RECORDER.get() else {
+        return;
+    };
+    let Ok(mut r) = recorder.lock() else {
+        return;
+    };
+    if entry == Entry::Command {
+        r.flush_pending();
+    }
+    r.write(entry, text);
+}
+
+/// Log forwarded terminal output, holding back an unfinished last line.
+fn log_output(entry: Entry, chunk: &str) {
+    let Some(recorder) = RECORDER.get() else {
+        return;
+    };
+    let Ok(mut r) = recorder.lock() else {
+        return;
+    };
+    let pending = r.pending(entry);
+    pending.push_str(chunk);
+    let Some(end) = pending.rfind('\n') else {
+        return;
+    };
+    let complete: String = pending.drain(..=end).collect();
+    r.write(entry, &complete);
+}
+
+fn format_entry(secs: f64, entry: Entry, text: &str) -> String {
+    format!("[{:>9.3}] {} {}", secs, entry.tag(), text)
+}
+
+/// Split a transcript line into its entry kind and text; `None` for headers and junk.
+pub fn parse_entry(line: &str) -> Option<(Entry, &str)> {
+    let rest = line.strip_prefix('[')?;
+    let (_, rest) = rest.split_once("] ")?;
+    let (tag, text) = rest.split_once(' ').unwrap_or((rest, ""));
+    Some((Entry::from_tag(tag)?, text))
+}
+
+/// `pipe2(O_CLOEXEC)`; Apple has no pipe2, so the flag is set right after `pipe` there.
+///
+/// # Safety
+/// Plain descriptor calls on `fds`, as `pipe` itself.
+#[cfg(not(target_vendor = "apple"))]
+unsafe fn cloexec_pipe(fds: &mut [libc::c_int; 2]) -> libc::c_int {
+    libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC)
+}
+
+/// `pipe2(O_CLOEXEC)`; Apple has no pipe2, so the flag is set right after `pipe` there.
+///
+/// # Safety
+/// Plain descriptor calls on `fds`, as `pipe` itself.
+#[cfg(target_vendor = "apple")]
+unsafe fn cloexec_pipe(fds: &mut [libc::c_int; 2]) -> libc::c_int {
+    if libc::pipe(fds.as_mut_ptr()) < 0 {
+        return -1;
+    }
+    for &fd in fds.iter() {
+        if libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) < 0 {
+            return -1;
+        }
+    }
+    0
+}
+
+/// Route `fd` through a pipe; a thread forwards every chunk to the original descriptor
+/// right away (prompts have no newline) and logs it.
+fn tee(fd: RawFd, entry: Entry) -> io::Result<()> {
+    // Flush Rust's buffer first so nothing written before the switch lands in the pipe.
+    let _ = io::stdout().flush();
+    let mut fds = [0; 2];
+    // SAFETY: plain descriptor calls; every returned fd is checked before use and owned by
+    // exactly one File (or the TEES entry) afterwards. CLOEXEC keeps them out of gdb.
+    let (original, restore, read_end) = unsafe {
+        let original = libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0);
+        let restore = libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0);
+        if original < 0 || restore < 0 || cloexec_pipe(&mut fds) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if libc::dup2(fds[1], fd) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        libc::close(fds[1]);
+        (
+            File::from_raw_fd(original),
+            restore,
+            File::from_raw_fd(fds[0]),
+        )
+    };
+    let handle = std::thread::spawn(move || forward(read_end, original, entry));
+    if let Ok(mut tees) = TEES.lock() {
+        tees.push((fd, restore, handle));
+    }
+    Ok(())
+}
+
+fn forward(mut from: File, mut to: File, entry: Entry) {
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = match from.read(&mut buf) {
+            Ok(0) | Err(_) => return,
+            Ok(n) => n,
+        };
+        let _ = to.write_all(&buf[..n]);
+        log_output(entry, &String::from_utf8_lossy(&buf[..n]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_round_trip() {
+        let line = format_entry(1.5, Entry::MiRecv, "^done,value=\"1\"");
+        assert_eq!(line, "[    1.500] mi< ^done,value=\"1\"");
+        assert_eq!(
+            parse_entry(&line),
+            Some((Entry::MiRecv, "^done,value=\"1\""))
+        );
+        assert_eq!(
+            parse_entry(&format_entry(0.0, Entry::Stdout, "")),
+            Some((Entry::Stdout, ""))
+        );
+        assert_eq!(parse_entry(HEADER), None);
+    }
+}
@@ -0,0 +1,146 @@
+// `--replay`: rerun a `--record` transcript against its captured gdb/MI output instead of gdb.
+use crate::mi::parser::split_token;
+use crate::mi::Result;
+use crate::record::{self, Entry};
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+
+/// The parts of a transcript needed to replay it.
+#[derive(Debug, Default)]
+pub struct Transcript {
+    /// Command line of the recorded run (program name first).
+    pub args: Vec<String>,
+    /// REPL commands, in order.
+    pub commands: Vec<String>,
+    /// Lines gdb sent (`mi<`), fed back as its output.
+    pub mi_recv: Vec<String>,
+    /// Lines sent to gdb (`mi>`), used to notice when the replay diverges.
+    pub mi_send: Vec<String>,
+}
+
+pub fn load(path: &str) -> Result<Transcript> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    parse(&text).map_err(|e| format!("{}: {}", path, e).into())
+}
+
+fn parse(text: &str) -> std::result::Result<Transcript, String> {
+    let mut lines = text.lines();
+    if lines.next() != Some(record::HEADER) {
+        return Err("not a gdb-memviz transcript".to_string());
+    }
+    let mut t = Transcript::default();
+    for line in lines {
+        if let Some(args) = line.strip_prefix("# args: ") {
+            t.args = serde_json::from_str(args).map_err(|e| format!("bad args line: {}", e))?;
+            continue;
+        }
+        match record::parse_entry(line) {
+            Some((Entry::Command, cmd)) => t.commands.push(cmd.to_string()),
+            Some((Entry::MiRecv, mi)) => t.mi_recv.push(mi.to_string()),
+            Some((Entry::MiSend, mi)) => t.mi_send.push(mi.to_string()),
+            _ => {}
+        }
+    }
+    if t.args.is_empty() {
+        return Err("missing args header".to_string());
+    }
+    Ok(t)
+}
+
+/// Recorded gdb output; running past its end is an error rather than a silent EOF.
+pub struct ReplayOutput {
+    data: io::Cursor<Vec<u8>>,
+}
+
+impl ReplayOutput {
+    pub fn new(lines: &[String]) -> Self {
+        // Without tokens every result is accepted in order, even after the replay diverged.
+        let lines: Vec<&str> = lines.iter().map(|l| split_token(l).1).collect();
+        let mut data = lines.join("\n");
+        data.push('\n');
+        Self {
+            data: io::Cursor::new(data.into_bytes()),
+        }
+    }
+}
+
+impl Read for ReplayOutput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.data.read(buf)? {
+            0 if !buf.is_empty() => Err(io::Error::other("end of recorded gdb output")),
+            n => Ok(n),
+        }
+    }
+}
+
+/// Swallows commands meant for gdb, warning once when they stop matching the recording.
+pub struct ReplayInput {
+    expected: VecDeque<String>,
+    buf: String,
+    diverged: bool,
+}
+
+impl ReplayInput {
+    pub fn new(sent: &[String]) -> Self {
+        Self {
+            expected: sent.iter().cloned().collect(),
+            buf: String::new(),
+            diverged: false,
+        }
+    }
+
+    fn check(&mut self, sent: &str) {
+        let expected = self.expected.pop_front();
+        // Compare the MI command only: arguments such as temp file paths differ per run, and
+        // tokens shift as soon as one command differs.
+        let word = |s: &str| {
+            let cmd = split_token(s).1;
+            cmd.split_whitespace().next().unwrap_or("").to_string()
+        };
+        if self.diverged || expected.as_deref().map(word) == Some(word(sent)) {
+            return;
+        }
+        self.diverged = true;
+        eprintln!(
+            "replay: sent '{}' where the recording has '{}'; output from here on may not match",
+            sent,
+            expected.as_deref().unwrap_or("<end>")
+        );
+    }
+}
+
+impl Write for ReplayInput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.push_str(&String::from_utf8_lossy(buf));
+        while let Some(end) = self.buf.find('\n') {
+            let line: String = self.buf.drain(..=end).collect();
+            self.check(line.trim_end());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_recorded_transcript() {
+        let text = format!(
+            "{}\n# started: 0 (unix time)\n# args: [\"gdb-memviz\",\"./prog\"]\n\
+             [    0.001] mi> -gdb-version\n[    0.002] mi< ^done\n[    0.002] mi< (gdb)\n\
+             [    1.000] out memviz> \n[    1.000] cmd locals\n",
+            record::HEADER
+        );
+        let t = parse(&text).unwrap();
+        assert_eq!(t.args, vec!["gdb-memviz", "./prog"]);
+        assert_eq!(t.commands, vec!["locals"]);
+        assert_eq!(t.mi_recv, vec!["^done", "(gdb)"]);
+        assert_eq!(t.mi_send, vec!["-gdb-version"]);
+        assert!(parse("locals\n").is_err());
+    }
+}
@@ -0,0 +1,720 @@
+//! Symbol index: global variables known to gdb, grouped by source file, and the program's
+//! functions.
+use crate::mi::models::MiStatus;
+use crate::mi::value::parse_record;
+use crate::mi::{MiSession, MiValue, SiblingSpec};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Which symbols `build_symbol_index` asks gdb for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolIndexMode {
+    /// Only symbols with debug info (`-symbol-info-variables`).
+    DebugOnly,
+    /// Also ELF symbols without debug info (`--include-nondebug`); slow on large binaries.
+    DebugAndNondebug,
+}
+
+impl SymbolIndexMode {
+    /// Parse the `--symbols` spelling.
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "debug-only" => Some(SymbolIndexMode::DebugOnly),
+            "debug-and-nondebug" => Some(SymbolIndexMode::DebugAndNondebug),
+            _ => None,
+        }
+    }
+
+    /// The `--symbols` spelling.
+    pub fn label(self) -> &'static str {
+        match self {
+            SymbolIndexMode::DebugOnly => "debug-only",
+            SymbolIndexMode::DebugAndNondebug => "debug-and-nondebug",
+        }
+    }
+}
+
+/// A global (or function-scope static) variable as declared in the program.
+#[derive(Debug, Clone)]
+pub struct GlobalVarInfo {
+    pub name: String,
+    pub type_name: Option<String>,
+    /// Source file as gdb reports it; `None` for non-debugging symbols.
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    /// Static variable declared inside a function rather than at file scope.
+    pub is_function_scope: bool,
+    /// The function a function-scope static belongs to.
+    pub function: Option<String>,
+    /// Address when the index was built. A PIE program indexed before it ran (the
+    /// background index) has link-time addresses; see `SymbolIndex::load_bias`.
+    pub address: Option<u64>,
+    /// ELF section holding the variable (`.data`, `.bss`, `.rodata`, ...).
+    pub section: Option<String>,
+}
+
+impl GlobalVarInfo {
+    /// Expression gdb evaluates to the variable: `push::calls` for a function's static.
+    pub fn expr(&self) -> String {
+        match &self.function {
+            Some(func) => format!("{}::{}", func, self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// A function with debug info in one of the program's own source files.
+#[derive(Debug, Clone)]
+pub struct FunctionInfo {
+    pub name: String,
+    /// Signature as gdb prints it, e.g. `int (struct Node *, int)`.
+    pub type_name: Option<String>,
+    pub file: String,
+    pub line: Option<u32>,
+}
+
+/// What one indexing run covered, for `sym stats`.
+#[derive(Debug, Clone)]
+pub struct IndexStats {
+    pub mode: SymbolIndexMode,
+    pub files_indexed: usize,
+    pub debug_vars: usize,
+    pub nondebug_vars: usize,
+    pub functions: usize,
+    /// Statics declared inside the indexed functions.
+    pub statics: usize,
+    /// Set when the function statics could not be listed (e.g. gdb without Python).
+    pub static_error: Option<String>,
+    /// Globals whose address is known.
+    pub addresses: usize,
+    /// Set when addresses and sections could not be looked up.
+    pub address_error: Option<String>,
+    /// Files gdb reported but the index left out, with the reason.
+    pub skipped_files: Vec<(String, String)>,
+    pub elapsed: Duration,
+    /// Set when gdb refused the query; the index is then empty.
+    pub error: Option<String>,
+    /// Set when gdb refused `-symbol-info-functions`; variables are still indexed.
+    pub function_error: Option<String>,
+}
+
+/// A `find` result, copied out of the index.
+#[derive(Debug, Clone)]
+pub enum FoundSymbol {
+    Global(GlobalVarInfo),
+    Function(FunctionInfo),
+}
+
+impl FoundSymbol {
+    pub fn name(&self) -> &str {
+        match self {
+            FoundSymbol::Global(g) => &g.name,
+            FoundSymbol::Function(f) => &f.name,
+        }
+    }
+}
+
+/// Result of `build_symbol_index`.
+pub struct SymbolIndex {
+    pub globals: Vec<GlobalVarInfo>,
+    /// File name -> indices into `globals`.
+    pub globals_by_file: BTreeMap<String, Vec<usize>>,
+    /// Sorted by name.
+    pub functions: Vec<FunctionInfo>,
+    pub stats: IndexStats,
+}
+
+impl SymbolIndex {
+    /// Functions whose name starts with `prefix`, for completing `break` locations.
+    pub fn functions_with_prefix(&self, prefix: &str) -> &[FunctionInfo] {
+        let start = self.functions.partition_point(|f| f.name.as_str() < prefix);
+        let len = self.functions[start..]
+            .iter()
+            .take_while(|f| f.name.starts_with(prefix))
+            .count();
+        &self.functions[start..start + len]
+    }
+
+    /// Globals and functions that fuzzily match `pattern` (see `fuzzy_score`), best first.
+    pub fn search(&self, pattern: &str) -> Vec<FoundSymbol> {
+        let globals = self
+            .globals
+            .iter()
+            .map(|g| (g.name.as_str(), FoundSymbol::Global(g.clone())));
+        let functions = self
+            .functions
+            .iter()
+            .map(|f| (f.name.as_str(), FoundSymbol::Function(f.clone())));
+        let mut scored: Vec<(i64, FoundSymbol)> = globals
+            .chain(functions)
+            .filter_map(|(name, found)| Some((fuzzy_score(pattern, name)?, found)))
+            .collect();
+        scored.sort_by(|(a, fa), (b, fb)| b.cmp(a).then_with(|| fa.name().cmp(fb.name())));
+        scored.into_iter().map(|(_, found)| found).collect()
+    }
+}
+
+/// How well `name` matches `pattern`: `None` unless the pattern's characters all appear in
+/// `name` in order (case-insensitive). Higher is better: exact names and prefixes first,
+/// then runs of consecutive characters and matches at word starts (`_`, `::`, camelCase),
+/// shorter names breaking ties.
+pub fn fuzzy_score(pattern: &str, name: &str) -> Option<i64> {
+    let lower_pattern = pattern.to_lowercase();
+    let lower_name = name.to_lowercase();
+    let chars: Vec<char> = name.chars().collect();
+    // Per character, so positions line up with `chars`.
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let bonus = |i: usize| {
+        let word_start = i == 0
+            || matches!(chars[i - 1], '_' | ':' | '.')
+            || (chars[i].is_uppercase() && chars[i - 1].is_lowercase());
+        1 + if word_start { 8 } else { 0 }
+    };
+    // Best score with the pattern so far matched and its last character at each position.
+    let mut pattern_chars = lower_pattern.chars();
+    let first = pattern_chars.next()?;
+    let mut row: Vec<Option<i64>> = (0..lower.len())
+        .map(|j| (lower[j] == first).then(|| bonus(j)))
+        .collect();
+    for p in pattern_chars {
+        let mut next = vec![None; lower.len()];
+        // Best of row[..j - 1]: the previous character matched with a gap.
+        let mut before: Option<i64> = None;
+        for j in 1..lower.len() {
+            if lower[j] == p {
+                let run = row[j - 1].map(|s| s + 5);
+                next[j] = run.max(before).map(|s| s + bonus(j));
+            }
+            before = before.max(row[j - 1]);
+        }
+        row = next;
+    }
+    let mut score = row.into_iter().flatten().max()?;
+    if lower_name == lower_pattern {
+        score += 100;
+    } else if lower_name.starts_with(&lower_pattern) {
+        score += 50;
+    }
+    Some(score * 100 - chars.len() as i64)
+}
+
+/// An index being built by a second gdb on a worker thread, so the REPL can start while a
+/// large binary is indexed. That gdb only reads the executable: shared libraries loaded by
+/// the running program are added later by `SymbolIndex::extend` on the session's gdb.
+pub struct BackgroundIndex {
+    rx: Receiver<SymbolIndex>,
+    pub mode: SymbolIndexMode,
+}
+
+impl BackgroundIndex {
+    pub fn spawn(spec: SiblingSpec, mode: SymbolIndexMode) -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let index = match spec.start() {
+                Ok(mut session) => {
+                    let index = build_symbol_index(&mut session, mode);
+                    session.shutdown();
+                    index
+                }
+                Err(e) => failed_index(mode, format!("could not start gdb: {}", e)),
+            };
+            let _ = tx.send(index);
+        });
+        Self { rx, mode }
+    }
+
+    /// The index once the worker is done; `None` while it is still running.
+    pub fn try_take(&self) -> Option<SymbolIndex> {
+        match self.rx.try_recv() {
+            Ok(index) => Some(index),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(failed_index(
+                self.mode,
+                "indexing thread failed".to_string(),
+            )),
+        }
+    }
+
+    /// Block until the worker is done.
+    pub fn wait(self) -> SymbolIndex {
+        self.rx
+            .recv()
+            .unwrap_or_else(|_| failed_index(self.mode, "indexing thread failed".to_string()))
+    }
+}
+
+/// An empty index recording why it could not be built.
+fn failed_index(mode: SymbolIndexMode, error: String) -> SymbolIndex {
+    let mut index = index_from_mi("");
+    index.stats.mode = mode;
+    index.stats.error = Some(error);
+    index
+}
+
+/// Prefixes of files that belong to the toolchain/libc rather than the user's program.
+const SYSTEM_PREFIXES: &[&str] = &["/usr/", "/lib/", "<"];
+
+/// Query gdb for global variables. Failures are recorded in `stats.error` instead of
+/// aborting, so the REPL stays usable without an index.
+pub fn build_symbol_index(session: &mut MiSession, mode: SymbolIndexMode) -> SymbolIndex {
+    let started = Instant::now();
+    let raw = symbol_listing(session, variables_command(mode));
+    let mut index = match &raw {
+        Ok(text) => index_from_mi(text),
+        Err(_) => index_from_mi(""),
+    };
+    index.stats.mode = mode;
+    index.stats.error = raw.err();
+    // Debug info only: non-debug functions would be all of libc.
+    match symbol_listing(session, "-symbol-info-functions") {
+        Ok(text) => index.functions = functions_from_mi(&text),
+        Err(msg) => index.stats.function_error = Some(msg),
+    }
+    index.stats.functions = index.functions.len();
+    if !index.functions.is_empty() {
+        let names: Vec<String> = index.functions.iter().map(|f| f.name.clone()).collect();
+        match session.function_statics(&names) {
+            Ok(found) => {
+                let statics = statics_from_json(&found);
+                index.stats.statics = statics.len();
+                index.globals.extend(statics);
+            }
+            Err(e) => index.stats.static_error = Some(e.to_string()),
+        }
+    }
+    if let Err(e) = locate_globals(session, &mut index.globals) {
+        index.stats.address_error = Some(e.to_string());
+    }
+    index.stats.addresses = index.globals.iter().filter(|g| g.address.is_some()).count();
+    index.stats.elapsed = started.elapsed();
+    index
+}
+
+fn variables_command(mode: SymbolIndexMode) -> &'static str {
+    match mode {
+        SymbolIndexMode::DebugOnly => "-symbol-info-variables",
+        SymbolIndexMode::DebugAndNondebug => "-symbol-info-variables --include-nondebug",
+    }
+}
+
+/// Result text of a `-symbol-info-*` query, or gdb's complaint.
+fn symbol_listing(session: &mut MiSession, cmd: &str) -> std::result::Result<String, String> {
+    match session.exec_command(cmd) {
+        Ok(resp) => match resp.status {
+            MiStatus::Error(msg) => Err(msg),
+            _ => Ok(resp.result),
+        },
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// A shared library whose symbols belong in the index: anything outside the system
+/// directories, such as a `dlopen()`ed plugin or the program's own libraries.
+pub fn is_program_library(path: &str) -> bool {
+    !path.is_empty()
+        && !path.starts_with("/lib64/")
+        && !SYSTEM_PREFIXES.iter().any(|p| path.starts_with(p))
+}
+
+/// Globals per address lookup; keeps each bridge call's command line reasonable.
+const LOCATE_BATCH: usize = 500;
+
+/// Fill in the address and section of every global that lacks one (statics come with
+/// theirs), in one bridge call per `LOCATE_BATCH` globals.
+fn locate_globals(session: &mut MiSession, globals: &mut [GlobalVarInfo]) -> crate::mi::Result<()> {
+    let mut missing: Vec<&mut GlobalVarInfo> = globals
+        .iter_mut()
+        .filter(|g| g.address.is_none() || g.section.is_none())
+        .collect();
+    for batch in missing.chunks_mut(LOCATE_BATCH) {
+        let entries: Vec<(String, Option<String>, Option<u64>)> = batch
+            .iter()
+            .map(|g| (g.name.clone(), g.file.clone(), g.address))
+            .collect();
+        let found = session.symbol_addresses(&entries)?;
+        let Some(items) = found.as_array() else {
+            return Err("address lookup returned no list".into());
+        };
+        for (g, item) in batch.iter_mut().zip(items) {
+            g.address = g.address.or(item.get("address").and_then(Value::as_u64));
+            g.section = item
+                .get("section")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+        }
+    }
+    Ok(())
+}
+
+/// Function-scope statics from the bridge's `memviz_function_statics`.
+fn statics_from_json(found: &Value) -> Vec<GlobalVarInfo> {
+    let Some(items) = found.as_array() else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(|item| {
+            let text = |key: &str| item.get(key).and_then(Value::as_str).map(str::to_string);
+            Some(GlobalVarInfo {
+                name: text("name")?,
+                type_name: text("type"),
+                file: text("file"),
+                line: item
+                    .get("line")
+                    .and_then(Value::as_u64)
+                    .and_then(|l| u32::try_from(l).ok()),
+                is_function_scope: true,
+                function: Some(text("function")?),
+                address: item.get("address").and_then(Value::as_u64),
+                section: text("section"),
+            })
+        })
+        .collect()
+}
+
+impl SymbolIndex {
+    /// How far the globals moved since indexing: zero unless the index was built before a
+    /// PIE program was loaded. Measured on one variable, so callers can place every indexed
+    /// global with a single round trip. `None` when no indexed variable can be evaluated.
+    pub fn load_bias(&self, session: &mut MiSession) -> Option<u64> {
+        self.globals
+            .iter()
+            .filter(|g| g.file.is_some())
+            .filter_map(|g| Some((g.address?, g)))
+            .take(3)
+            .find_map(|(indexed, g)| {
+                let now = session.eval_address_of_expr(&g.expr()).ok()?;
+                Some(now.wrapping_sub(indexed))
+            })
+    }
+
+    /// Variables with debug info and their current addresses (see `load_bias`).
+    pub fn located_globals(&self, session: &mut MiSession) -> Option<Vec<(&GlobalVarInfo, u64)>> {
+        let bias = self.load_bias(session)?;
+        Some(
+            self.globals
+                .iter()
+                .filter(|g| g.file.is_some())
+                .filter_map(|g| Some((g, g.address?.wrapping_add(bias))))
+                .collect(),
+        )
+    }
+
+    /// Add the globals and functions gdb knows now but the index lacks: those of shared
+    /// libraries loaded since it was built. gdb cannot list a single library's symbols, so
+    /// the program is queried again and only new entries are kept; their statics and
+    /// addresses are looked up like a full build's. Returns how many symbols were added.
+    pub fn extend(&mut self, session: &mut MiSession) -> crate::mi::Result<usize> {
+        let mut fresh = index_from_mi(&symbol_listing(
+            session,
+            variables_command(self.stats.mode),
+        )?);
+        fresh.functions = functions_from_mi(&symbol_listing(session, "-symbol-info-functions")?);
+        let start = self.globals.len();
+        let functions = self.absorb(fresh);
+        if !functions.is_empty() {
+            if let Ok(found) = session.function_statics(&functions) {
+                let statics = statics_from_json(&found);
+                self.stats.statics += statics.len();
+                self.globals.extend(statics);
+            }
+        }
+        if locate_globals(session, &mut self.globals[start..]).is_ok() {
+            // Keep the index's addresses as of its build, which `load_bias` corrects.
+            if let Some(bias) = self.load_bias(session).filter(|b| *b != 0) {
+                for g in &mut self.globals[start..] {
+                    g.address = g.address.map(|a| a.wrapping_sub(bias));
+                }
+            }
+        }
+        self.stats.addresses = self.globals.iter().filter(|g| g.address.is_some()).count();
+        Ok(self.globals.len() - start + functions.len())
+    }
+
+    /// Merge in the globals and functions of `fresh` that are not indexed yet (same name in
+    /// the same file). Returns the names of the new functions.
+    fn absorb(&mut self, fresh: SymbolIndex) -> Vec<String> {
+        for g in fresh.globals {
+            if self
+                .globals
+                .iter()
+                .any(|old| old.name == g.name && old.file == g.file && !old.is_function_scope)
+            {
+                continue;
+            }
+            match &g.file {
+                Some(file) => {
+                    self.stats.debug_vars += 1;
+                    self.globals_by_file
+                        .entry(file.clone())
+                        .or_default()
+                        .push(self.globals.len());
+                }
+                None => self.stats.nondebug_vars += 1,
+            }
+            self.globals.push(g);
+        }
+        self.stats.files_indexed = self.globals_by_file.len();
+        let mut added = Vec::new();
+        for f in fresh.functions {
+            if !self
+                .functions
+                .iter()
+                .any(|old| old.name == f.name && old.file == f.file)
+            {
+                added.push(f.name.clone());
+                self.functions.push(f);
+            }
+        }
+        self.functions.sort_by(|a, b| a.name.cmp(&b.name));
+        self.stats.functions = self.functions.len();
+        added
+    }
+
+    /// Function-scope statics, by function then name.
+    pub fn statics(&self) -> Vec<&GlobalVarInfo> {
+        let mut statics: Vec<&GlobalVarInfo> = self
+            .globals
+            .iter()
+            .filter(|g| g.is_function_scope)
+            .collect();
+        statics.sort_by(|a, b| (&a.function, &a.name).cmp(&(&b.function, &b.name)));
+        statics
+    }
+}
+
+/// Build an index from the result of `-symbol-info-variables`.
+fn index_from_mi(result: &str) -> SymbolIndex {
+    let mut index = SymbolIndex {
+        globals: Vec::new(),
+        globals_by_file: BTreeMap::new(),
+        functions: Vec::new(),
+        stats: IndexStats {
+            mode: SymbolIndexMode::DebugOnly,
+            files_indexed: 0,
+            debug_vars: 0,
+            nondebug_vars: 0,
+            functions: 0,
+            statics: 0,
+            static_error: None,
+            addresses: 0,
+            address_error: None,
+            skipped_files: Vec::new(),
+            elapsed: Duration::ZERO,
+            error: None,
+            function_error: None,
+        },
+    };
+    // symbols={debug=[{filename=..,fullname=..,symbols=[{line,name,type,description},...]},...],
+    //         nondebugging=[{address,name},...]}
+    let record = parse_record(result);
+    let empty = MiValue::Tuple(Vec::new());
+    let symbols = record.get("symbols").unwrap_or(&empty);
+
+    for file in symbols.list("debug") {
+        let Some(filename) = file.str("filename").map(str::to_string) else {
+            continue;
+        };
+        let fullname = file.str("fullname").unwrap_or(&filename);
+        if let Some(prefix) = SYSTEM_PREFIXES.iter().find(|p| fullname.starts_with(*p)) {
+            index
+                .stats
+                .skipped_files
+                .push((filename.clone(), format!("system file ({}...)", prefix)));
+            continue;
+        }
+        let mut ids = Vec::new();
+        for sym in file.list("symbols") {
+            let Some(name) = sym.str("name") else {
+                continue;
+            };
+            ids.push(index.globals.len());
+            index.globals.push(GlobalVarInfo {
+                name: name.to_string(),
+                type_name: sym.str("type").map(str::to_string),
+                file: Some(filename.clone()),
+                line: sym.parse("line"),
+                is_function_scope: false,
+                function: None,
+                address: None,
+                section: None,
+            });
+        }
+        if ids.is_empty() {
+            index
+                .stats
+                .skipped_files
+                .push((filename, "no variables parsed".to_string()));
+            continue;
+        }
+        index.stats.files_indexed += 1;
+        index.stats.debug_vars += ids.len();
+        index
+            .globals_by_file
+            .entry(filename)
+            .or_default()
+            .extend(ids);
+    }
+
+    for sym in symbols.list("nondebugging") {
+        let Some(name) = sym.str("name") else {
+            continue;
+        };
+        index.globals.push(GlobalVarInfo {
+            name: name.to_string(),
+            type_name: None,
+            file: None,
+            line: None,
+            is_function_scope: false,
+            function: None,
+            address: sym
+                .str("address")
+                .and_then(|a| u64::from_str_radix(a.trim_start_matches("0x"), 16).ok()),
+            section: None,
+        });
+        index.stats.nondebug_vars += 1;
+    }
+    index
+}
+
+/// Functions from the result of `-symbol-info-functions` (shaped like the variable listing),
+/// leaving out system files, sorted by name.
+fn functions_from_mi(result: &str) -> Vec<FunctionInfo> {
+    let record = parse_record(result);
+    let empty = MiValue::Tuple(Vec::new());
+    let symbols = record.get("symbols").unwrap_or(&empty);
+    let mut functions = Vec::new();
+    for file in symbols.list("debug") {
+        let Some(filename) = file.str("filename") else {
+            continue;
+        };
+        let fullname = file.str("fullname").unwrap_or(filename);
+        if SYSTEM_PREFIXES.iter().any(|p| fullname.starts_with(p)) {
+            continue;
+        }
+        for sym in file.list("symbols") {
+            let Some(name) = sym.str("name") else {
+                continue;
+            };
+            functions.push(FunctionInfo {
+                name: name.to_string(),
+                type_name: sym.str("type").map(str::to_string),
+                file: filename.to_string(),
+                line: sym.parse("line"),
+            });
+        }
+    }
+    functions.sort_by(|a, b| a.name.cmp(&b.name));
+    functions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_groups_by_file_and_skips_system_files() {
+        let raw = r#"symbols={debug=[{filename="sample.c",fullname="/home/u/sample.c",symbols=[{line="3",name="counter",type="int",description="int counter;"},{line="4",name="names",type="char *[4]",description="static char *names[4];"}]},{filename="../csu/init.c",fullname="/usr/src/glibc/csu/init.c",symbols=[{line="1",name="_IO_stdin_used",type="const int",description="const int _IO_stdin_used;"}]}],nondebugging=[{address="0x0000000000004010",name="__dso_handle"}]}"#;
+        let index = index_from_mi(raw);
+        assert_eq!(index.stats.files_indexed, 1);
+        assert_eq!(index.stats.debug_vars, 2);
+        assert_eq!(index.stats.nondebug_vars, 1);
+        assert_eq!(index.globals_by_file["sample.c"], vec![0, 1]);
+        assert_eq!(index.globals[1].type_name.as_deref(), Some("char *[4]"));
+        assert_eq!(index.globals[2].address, Some(0x4010));
+        assert_eq!(index.stats.skipped_files.len(), 1);
+        assert_eq!(index.stats.skipped_files[0].0, "../csu/init.c");
+    }
+
+    #[test]
+    fn functions_are_sorted_and_found_by_prefix() {
+        let raw = r#"^done,symbols={debug=[{filename="sample.c",fullname="/home/u/sample.c",symbols=[{line="20",name="push",type="void (struct Node **, int)",description="void push(struct Node **, int);"},{line="9",name="helper",type="int (int)",description="static int helper(int);"},{line="30",name="main",type="int (void)",description="int main(void);"}]},{filename="../sysdeps/x86/libc-start.c",fullname="/usr/src/glibc/libc-start.c",symbols=[{line="1",name="__libc_start_main",type="int (void)",description="int __libc_start_main(void);"}]}]}"#;
+        let index = SymbolIndex {
+            globals: Vec::new(),
+            globals_by_file: BTreeMap::new(),
+            functions: functions_from_mi(raw),
+            stats: index_from_mi("").stats,
+        };
+        let names: Vec<&str> = index.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["helper", "main", "push"]);
+        let found = index.functions_with_prefix("pu");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].line, Some(20));
+        assert_eq!(found[0].file, "sample.c");
+        assert!(index.functions_with_prefix("zz").is_empty());
+    }
+
+    #[test]
+    fn fuzzy_search_ranks_prefixes_and_word_starts() {
+        assert!(fuzzy_score("pshn", "push_node").is_some());
+        assert!(fuzzy_score("nodep", "push_node").is_none());
+        // Word starts beat scattered letters; exact names beat everything.
+        assert!(fuzzy_score("pn", "push_node") > fuzzy_score("pn", "spin"));
+        assert!(fuzzy_score("list", "list") > fuzzy_score("list", "list_len"));
+        assert!(fuzzy_score("lsz", "listSize") > fuzzy_score("lsz", "lastsz_x"));
+        let mut index = index_from_mi(
+            r#"symbols={debug=[{filename="a.c",fullname="/src/a.c",symbols=[{line="1",name="node_count",type="int"},{line="2",name="counter",type="int"}]}]}"#,
+        );
+        index.functions = functions_from_mi(
+            r#"symbols={debug=[{filename="a.c",fullname="/src/a.c",symbols=[{line="9",name="count_nodes",type="int (void)"}]}]}"#,
+        );
+        let names: Vec<String> = index
+            .search("count")
+            .iter()
+            .map(|f| f.name().to_string())
+            .collect();
+        assert_eq!(names, ["counter", "count_nodes", "node_count"]);
+    }
+
+    #[test]
+    fn function_statics_come_from_the_bridge() {
+        let found = serde_json::json!([
+            {"function": "push", "name": "calls", "type": "int", "file": "list.c", "line": 12,
+             "address": 16400, "section": ".bss"},
+            {"function": "main", "name": "runs", "type": "unsigned long", "file": "list.c", "line": null},
+            {"name": "no_owner"}
+        ]);
+        let mut index = index_from_mi("");
+        index.globals = statics_from_json(&found);
+        let statics = index.statics();
+        assert_eq!(statics.len(), 2);
+        assert_eq!(statics[0].expr(), "main::runs");
+        assert_eq!(statics[1].line, Some(12));
+        assert!(statics[1].is_function_scope);
+        assert_eq!(statics[1].address, Some(0x4010));
+        assert_eq!(statics[1].section.as_deref(), Some(".bss"));
+    }
+
+    #[test]
+    fn library_symbols_extend_the_index() {
+        let mut index = index_from_mi(
+            r#"symbols={debug=[{filename="main.c",fullname="/src/main.c",symbols=[{line="3",name="counter",type="int"}]}]}"#,
+        );
+        index.functions = functions_from_mi(
+            r#"symbols={debug=[{filename="main.c",fullname="/src/main.c",symbols=[{line="9",name="main",type="int (void)"}]}]}"#,
+        );
+        // After dlopen("./plugin.so"): the program's symbols again plus the plugin's.
+        let mut fresh = index_from_mi(
+            r#"symbols={debug=[{filename="main.c",fullname="/src/main.c",symbols=[{line="3",name="counter",type="int"}]},{filename="plugin.c",fullname="/src/plugin.c",symbols=[{line="2",name="counter",type="long"},{line="4",name="plugin_state",type="struct state"}]}]}"#,
+        );
+        fresh.functions = functions_from_mi(
+            r#"symbols={debug=[{filename="main.c",fullname="/src/main.c",symbols=[{line="9",name="main",type="int (void)"}]},{filename="plugin.c",fullname="/src/plugin.c",symbols=[{line="8",name="plugin_init",type="int (void)"}]}]}"#,
+        );
+        assert_eq!(index.absorb(fresh), ["plugin_init"]);
+        assert_eq!(index.globals.len(), 3);
+        assert_eq!(index.globals_by_file["plugin.c"], vec![1, 2]);
+        assert_eq!(index.stats.files_indexed, 2);
+        assert_eq!(index.stats.debug_vars, 3);
+        assert_eq!(index.stats.functions, 2);
+        assert_eq!(index.functions_with_prefix("plugin").len(), 1);
+        assert!(is_program_library("/home/u/build/plugin.so"));
+        assert!(!is_program_library("/lib/x86_64-linux-gnu/libc.so.6"));
+        assert!(!is_program_library("/lib64/ld-linux-x86-64.so.2"));
+    }
+}
@@ -1,5 +1,7 @@
+//! Type layouts parsed from gdb's `ptype /o` output.
 use regex::Regex;
 
+/// Memory layout of a type: sizes, element types, and struct field offsets.
 #[derive(Debug, Clone)]
 pub enum TypeLayout {
     Scalar {
@@ -16,11 +18,13 @@ pub enum TypeLayout {
     },
     Struct {
         name: String,
+        #[allow(dead_code)]
         size: usize,
         fields: Vec<FieldLayout>,
     },
 }
 
+/// One struct field at its byte offset.
 #[derive(Debug, Clone)]
 pub struct FieldLayout {
     pub name: String,
@@ -29,6 +33,72 @@ pub struct FieldLayout {
     pub size: usize,
 }
 
+/// What a byte inside a dumped range belongs to, relative to the object's layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOwner {
+    /// Index into `TypeLayout::Struct::fields`.
+    Field(usize),
+    /// Array element index.
+    Element(usize),
+    /// Struct bytes not covered by any field (alignment holes, tail padding).
+    Padding,
+    /// Bytes of a scalar object.
+    Value,
+    /// Past the end of the object (e.g. `mem` with a length larger than sizeof).
+    OutOfBounds,
+    /// malloc's `prev_size`/`size` words in front of a tracked heap block.
+    ChunkHeader,
+}
+
+/// Classify each of the first `len` bytes of an object according to its layout.
+pub fn byte_owners(layout: &TypeLayout, len: usize) -> Vec<ByteOwner> {
+    (0..len)
+        .map(|i| match layout {
+            TypeLayout::Struct { size, fields, .. } => {
+                if i >= *size {
+                    ByteOwner::OutOfBounds
+                } else {
+                    fields
+                        .iter()
+                        .position(|f| f.offset <= i && i < f.offset + f.size)
+                        .map(ByteOwner::Field)
+                        .unwrap_or(ByteOwner::Padding)
+                }
+            }
+            TypeLayout::Array {
+                elem_size, len: n, ..
+            } => {
+                let elem = (*elem_size).max(1);
+                if i < elem * n {
+                    ByteOwner::Element(i / elem)
+                } else {
+                    ByteOwner::OutOfBounds
+                }
+            }
+            TypeLayout::Scalar { size, .. } => {
+                if i < *size {
+                    ByteOwner::Value
+                } else {
+                    ByteOwner::OutOfBounds
+                }
+            }
+        })
+        .collect()
+}
+
+/// Mark the two header words in front of each malloc pointer in `chunks` that fall inside
+/// the `owners.len()` bytes dumped from `base`.
+pub fn mark_chunk_headers(owners: &mut [ByteOwner], base: u64, chunks: &[u64], word_size: usize) {
+    let header = 2 * word_size as u64;
+    let end = base.saturating_add(owners.len() as u64);
+    for &ptr in chunks {
+        let start = ptr.saturating_sub(header).max(base);
+        for addr in start..ptr.min(end) {
+            owners[(addr - base) as usize] = ByteOwner::ChunkHeader;
+        }
+    }
+}
+
 /// Very small ptype parser for simple structs/arrays/scalars.
 pub fn parse_ptype_output(text: &str, word_size: usize, fallback_size: usize) -> TypeLayout {
     // Try array form: "type = int [5]"
@@ -52,11 +122,29 @@ pub fn parse_ptype_output(text: &str, word_size: usize, fallback_size: usize) ->
 }
 
 fn parse_array_line(text: &str, word_size: usize) -> Option<TypeLayout> {
-    // crude: look for "type = <elem> [N]"
+    // crude: look for "type = <elem> [N]", or Rust's "type = [<elem>; N]"
     for line in text.lines() {
         let line = line.trim();
         if let Some(rest) = line.strip_prefix("type =") {
-            let parts: Vec<_> = rest.trim().split_whitespace().collect();
+            if let Some((elem, len)) = rest
+                .trim()
+                .strip_prefix('[')
+                .and_then(|r| r.strip_suffix(']'))
+                .and_then(|r| r.rsplit_once(';'))
+            {
+                if let Ok(len) = len.trim().parse::<usize>() {
+                    let ty = elem.trim().to_string();
+                    let elem_size = base_type_size(&ty, word_size);
+                    return Some(TypeLayout::Array {
+                        type_name: format!("[{}; {}]", ty, len),
+                        elem_type: ty,
+                        elem_size,
+                        len,
+                        size: elem_size.saturating_mul(len),
+                    });
+                }
+            }
+            let parts: Vec<_> = rest.split_whitespace().collect();
             if parts.len() >= 2 {
                 let ty = parts[0].to_string();
                 if let Some(len_str) = parts[1]
@@ -86,7 +174,8 @@ fn parse_struct_block(text: &str) -> Option<TypeLayout> {
     // Parse gdb `ptype /o` output with explicit offsets/sizes and optional holes.
     let mut lines = text.lines();
     let header = lines.find(|l| l.contains("type = struct"))?;
-    let name = Regex::new(r"type\s*=\s*struct\s+([A-Za-z0-9_]+)")
+    // Rust names carry paths and generics: "struct list::Node<i32> {".
+    let name = Regex::new(r"type\s*=\s*struct\s+([A-Za-z0-9_:]+(?:<[^{]*>)?)")
         .ok()
         .and_then(|re| re.captures(header).map(|c| c[1].to_string()))
         .unwrap_or_else(|| "struct".to_string());
@@ -133,10 +222,19 @@ fn parse_struct_block(text: &str) -> Option<TypeLayout> {
             Some(v) => v,
             None => continue,
         };
-        let cleaned = rest.trim().trim_end_matches(';').trim();
+        let cleaned = rest.trim().trim_end_matches([';', ',']).trim();
         if cleaned.is_empty() {
             continue;
         }
+        if let Some((name, ty)) = rust_field(cleaned) {
+            fields.push(FieldLayout {
+                name: name.to_string(),
+                type_name: ty.to_string(),
+                offset,
+                size,
+            });
+            continue;
+        }
         let (type_part, name_part) = match cleaned.rsplit_once(' ') {
             Some(v) => v,
             None => continue,
@@ -191,13 +289,26 @@ fn parse_struct_block(text: &str) -> Option<TypeLayout> {
     })
 }
 
+/// Rust field syntax "name: Type" (C fields are "Type name").
+fn rust_field(decl: &str) -> Option<(&str, &str)> {
+    let (name, ty) = decl.split_once(": ")?;
+    let ident = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    ident.then_some((name, ty.trim()))
+}
+
 fn base_type_size(type_name: &str, word_size: usize) -> usize {
-    // Crude size guesser for simple C types; pointer width falls back to detected word size.
+    // Crude size guesser for simple C and Rust types; pointer width falls back to detected
+    // word size.
     let t = type_name.trim();
-    if t.ends_with('*') {
+    if t.ends_with('*') || t.starts_with('*') || t.starts_with('&') {
         return word_size.max(1);
     }
     match t {
+        "u8" | "i8" | "bool" => 1,
+        "u16" | "i16" => 2,
+        "u32" | "i32" | "f32" => 4,
+        "u64" | "i64" | "f64" => 8,
+        "u128" | "i128" => 16,
         "char" | "unsigned char" | "signed char" => 1,
         "short" | "unsigned short" => 2,
         "int" | "unsigned int" => 4,
@@ -209,7 +320,7 @@ fn base_type_size(type_name: &str, word_size: usize) -> usize {
     }
 }
 
-/// Normalize type string for display (e.g., "int [5]" -> "int[5]").
+/// Normalize type string for display (e.g., `"int [5]"` -> `"int[5]"`).
 pub fn normalize_type_name(s: &str) -> String {
     // Remove spaces before array brackets to make output more compact/readable.
     let trimmed = s.trim();
@@ -245,17 +356,41 @@ pub fn find_pointer_field(layout: &TypeLayout) -> Option<&FieldLayout> {
     None
 }
 
-/// Basic pointer type heuristic: contains '*' and is not an array declaration.
+/// Basic pointer type heuristic: contains '*' and is not an array declaration. Rust
+/// references count too, except fat ones (`&str`, slices, trait objects).
 pub fn is_pointer_type(ty: &str) -> bool {
     let t = ty.trim();
+    if let Some(target) = t.strip_prefix('&') {
+        let target = target.trim_start_matches("mut ").trim();
+        return target != "str" && !target.starts_with('[') && !target.starts_with("dyn ");
+    }
     t.contains('*') && !t.contains('[') && !t.contains(']')
 }
 
-/// Strip trailing '*' characters and surrounding spaces from a pointer type name.
+/// `char *` and its qualified forms (`const char *`, `unsigned char*`): pointers worth
+/// previewing as C strings.
+pub fn is_char_pointer(ty: &str) -> bool {
+    let Some(pointee) = ty.trim().strip_suffix('*') else {
+        return false;
+    };
+    let words: Vec<&str> = pointee
+        .split_whitespace()
+        .filter(|w| !matches!(*w, "const" | "volatile" | "signed" | "unsigned"))
+        .collect();
+    words == ["char"]
+}
+
+/// Strip trailing '*' characters and surrounding spaces from a pointer type name; Rust
+/// `*mut T`, `*const T`, `&T` and `&mut T` give `T`.
 pub fn strip_pointer_suffix(ty: &str) -> String {
-    let mut trimmed = ty.trim().to_string();
-    while trimmed.ends_with('*') {
-        trimmed.pop();
+    let mut trimmed = ty.trim();
+    for prefix in ["*mut ", "*const ", "&mut ", "&"] {
+        if let Some(rest) = trimmed.strip_prefix(prefix) {
+            return rest.trim().to_string();
+        }
+    }
+    while let Some(rest) = trimmed.strip_suffix('*') {
+        trimmed = rest;
     }
     trimmed.trim().to_string()
 }
@@ -269,6 +404,43 @@ pub fn normalize_pointer_type(ty: &str) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn parses_rust_ptype_and_pointer_types() {
+        let text = "/* offset      |    size */  type = struct list::Node<i32> {\n\
+                    /*      0      |       4 */    value: i32,\n\
+                    /*      8      |       8 */    next: *mut list::Node<i32>,\n\
+                    \n                               /* total size (bytes):   16 */\n}";
+        match parse_ptype_output(text, 8, 16) {
+            TypeLayout::Struct { name, fields, .. } => {
+                assert_eq!(name, "list::Node<i32>");
+                assert_eq!(fields[1].name, "next");
+                assert_eq!(fields[1].type_name, "*mut list::Node<i32>");
+                assert_eq!(
+                    find_pointer_field(&parse_ptype_output(text, 8, 16))
+                        .unwrap()
+                        .offset,
+                    8
+                );
+            }
+            other => panic!("expected struct, got {:?}", other),
+        }
+        match parse_ptype_output("type = [u16; 3]", 8, 6) {
+            TypeLayout::Array { elem_size, len, .. } => assert_eq!((elem_size, len), (2, 3)),
+            other => panic!("expected array, got {:?}", other),
+        }
+        assert_eq!(
+            strip_pointer_suffix("*mut list::Node<i32>"),
+            "list::Node<i32>"
+        );
+        assert!(is_pointer_type("&mut Node"));
+        assert!(!is_pointer_type("&str"));
+        assert!(!is_pointer_type("&[u8]"));
+        assert!(is_char_pointer("const char *"));
+        assert!(is_char_pointer("unsigned char*"));
+        assert!(!is_char_pointer("char **"));
+        assert!(!is_char_pointer("struct char_buf *"));
+    }
+
     #[test]
     fn normalize_type_name_removes_array_spaces() {
         assert_eq!(normalize_type_name("int [5]"), "int[5]");
@@ -281,6 +453,44 @@ mod tests {
         assert_eq!(base_type_size("char", 4), 1);
     }
 
+    #[test]
+    fn byte_owners_marks_padding_and_out_of_bounds() {
+        let layout = TypeLayout::Struct {
+            name: "Pad".into(),
+            size: 8,
+            fields: vec![
+                FieldLayout {
+                    name: "c".into(),
+                    type_name: "char".into(),
+                    offset: 0,
+                    size: 1,
+                },
+                FieldLayout {
+                    name: "i".into(),
+                    type_name: "int".into(),
+                    offset: 4,
+                    size: 4,
+                },
+            ],
+        };
+        let owners = byte_owners(&layout, 10);
+        assert_eq!(owners[0], ByteOwner::Field(0));
+        assert_eq!(owners[1], ByteOwner::Padding);
+        assert_eq!(owners[4], ByteOwner::Field(1));
+        assert_eq!(owners[8], ByteOwner::OutOfBounds);
+    }
+
+    #[test]
+    fn chunk_headers_are_marked_inside_the_dump_only() {
+        let mut owners = vec![ByteOwner::Value; 48];
+        // One block 16 bytes in, one starting right at the dump, one past its end.
+        mark_chunk_headers(&mut owners, 0x1000, &[0x1020, 0x1008, 0x1040], 8);
+        assert_eq!(owners[..8], [ByteOwner::ChunkHeader; 8]);
+        assert_eq!(owners[8..16], [ByteOwner::Value; 8]);
+        assert_eq!(owners[16..32], [ByteOwner::ChunkHeader; 16]);
+        assert_eq!(owners[32..], [ByteOwner::Value; 16]);
+    }
+
     #[test]
     fn parse_ptype_handles_array() {
         let text = "type = int [5]";
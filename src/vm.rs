@@ -1,7 +1,13 @@
+//! Virtual memory map of the debugged process, read from `/proc/<pid>/maps`, or from
+//! `/proc/<pid>/smaps` when the memory actually used by each region is wanted. macOS and
+//! FreeBSD have no such files; there the map comes from `vmmap` and `procstat -v`.
+use regex::Regex;
 use std::fs::File;
 use std::io;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read};
+use std::sync::OnceLock;
 
+/// What a mapping holds, guessed from its path and permissions.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum VmLabel {
     Text,       // executable text/code
@@ -11,27 +17,187 @@ pub enum VmLabel {
     Lib,        // shared libraries
     Anonymous,  // anonymous mapping
     Other(String),
+    /// Stack of a thread other than the main one, by kernel thread id (`[stack:tid]`).
+    ThreadStack(u32),
+    /// Inaccessible page right below a stack, which turns an overflow into a fault.
+    Guard,
 }
 
-#[derive(Debug, Clone)]
+impl VmLabel {
+    /// Short lowercase name ("heap", "anon", ...).
+    pub fn name(&self) -> &'static str {
+        match self {
+            VmLabel::Text => "text",
+            VmLabel::Data => "data",
+            VmLabel::Heap => "heap",
+            VmLabel::Stack | VmLabel::ThreadStack(_) => "stack",
+            VmLabel::Lib => "lib",
+            VmLabel::Anonymous => "anon",
+            VmLabel::Other(_) => "other",
+            VmLabel::Guard => "guard",
+        }
+    }
+
+    /// Bracketed tag as region listings show it: "[heap]", "[stack:1235]".
+    pub fn tag(&self) -> String {
+        match self {
+            VmLabel::ThreadStack(tid) => format!("[stack:{}]", tid),
+            other => format!("[{}]", other.name()),
+        }
+    }
+}
+
+/// One line of `/proc/<pid>/maps`: [start, end) with its permissions.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct VmRegion {
     pub start: u64,
     pub end: u64,
     pub perms: String,
+    /// Offset of `start` in the mapped file; only the Linux maps report it.
+    pub offset: Option<u64>,
     pub pathname: String,
     pub label: VmLabel,
+    /// Resident/proportional/dirty/swapped bytes; only `read_proc_smaps` fills it in.
+    pub usage: Option<RegionUsage>,
+}
+
+/// Memory a region really uses, in bytes (smaps reports kB).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RegionUsage {
+    /// Resident in RAM.
+    pub rss: u64,
+    /// Resident, with pages shared by n processes counted 1/n.
+    pub pss: u64,
+    /// Modified pages, shared and private.
+    pub dirty: u64,
+    pub swap: u64,
+}
+
+impl RegionUsage {
+    pub fn add(&mut self, other: &RegionUsage) {
+        self.rss += other.rss;
+        self.pss += other.pss;
+        self.dirty += other.dirty;
+        self.swap += other.swap;
+    }
+}
+
+/// Usage of every region with one label, for `vm stats`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelUsage {
+    /// `Other` regions are pooled under `Other("")`.
+    pub label: VmLabel,
+    pub regions: usize,
+    /// Mapped bytes.
+    pub size: u64,
+    pub usage: RegionUsage,
 }
 
 impl VmRegion {
+    /// Length in bytes.
     pub fn size(&self) -> u64 {
         self.end.saturating_sub(self.start)
     }
 
+    /// Whether `addr` falls inside [start, end).
     pub fn contains(&self, addr: u64) -> bool {
         self.start <= addr && addr < self.end
     }
+
+    /// Where `addr` sits in the mapped file, for file-backed regions whose offset is known.
+    pub fn file_offset(&self, addr: u64) -> Option<u64> {
+        if !self.pathname.starts_with('/') {
+            return None;
+        }
+        Some(self.offset? + addr.saturating_sub(self.start))
+    }
+}
+
+/// Mappings of process `pid` from the platform's source: `/proc/<pid>/maps` on Linux
+/// (`smaps` when `usage` is wanted), `vmmap` on macOS, which reports usage anyway.
+#[cfg(not(any(target_os = "macos", target_os = "freebsd")))]
+pub fn read_process_map(pid: u32, usage: bool) -> io::Result<Vec<VmRegion>> {
+    if usage {
+        read_proc_smaps(pid)
+    } else {
+        read_proc_maps(pid)
+    }
 }
 
+/// Mappings of process `pid` from `vmmap`, usage included.
+#[cfg(target_os = "macos")]
+pub fn read_process_map(pid: u32, _usage: bool) -> io::Result<Vec<VmRegion>> {
+    read_vmmap(pid)
+}
+
+/// Mappings of process `pid` from `procstat -v`, resident sizes included.
+#[cfg(target_os = "freebsd")]
+pub fn read_process_map(pid: u32, _usage: bool) -> io::Result<Vec<VmRegion>> {
+    let out = std::process::Command::new("procstat")
+        .args(["-v", &pid.to_string()])
+        .output()?;
+    if !out.status.success() {
+        let err = String::from_utf8_lossy(&out.stderr);
+        return Err(io::Error::other(format!("procstat failed: {}", err.trim())));
+    }
+    // SAFETY: sysconf only reads a configuration value.
+    let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(1) as u64;
+    Ok(parse_procstat(&String::from_utf8_lossy(&out.stdout), page))
+}
+
+/// Regions from `procstat -v` output:
+///
+/// ```text
+///   PID              START                END PRT  RES PRES REF SHD FLAG  TP PATH
+///  1234           0x200000           0x201000 r--    1    3   2   1 CN--- vn /home/u/list
+///  1234     0x7ffffffdf000     0x7ffffffff000 rw-    3    3   1   0 ---D- df
+/// ```
+///
+/// The region that grows down (`D` flag) is the stack; the rest is labeled like a maps
+/// line. RES (resident pages of `page` bytes) fills `usage.rss` and `usage.pss`; procstat
+/// does not report dirty or swapped pages.
+#[cfg_attr(not(target_os = "freebsd"), allow(dead_code))]
+fn parse_procstat(text: &str, page: u64) -> Vec<VmRegion> {
+    let mut regions = Vec::new();
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 10 {
+            continue;
+        }
+        let hex = |s: &str| u64::from_str_radix(s.strip_prefix("0x")?, 16).ok();
+        let (Some(start), Some(end)) = (hex(fields[1]), hex(fields[2])) else {
+            continue;
+        };
+        if start >= end {
+            continue;
+        }
+        let perms = format!("{}{}", fields[3], if fields[9] == "sw" { "s" } else { "p" });
+        let pathname = fields[10..].join(" ");
+        let label = if fields[8].contains('D') {
+            VmLabel::Stack
+        } else {
+            classify_region_label(&perms, &pathname)
+        };
+        let resident = fields[4].parse::<u64>().unwrap_or(0) * page;
+        regions.push(VmRegion {
+            start,
+            end,
+            perms,
+            offset: None,
+            pathname,
+            label,
+            usage: Some(RegionUsage {
+                rss: resident,
+                pss: resident,
+                dirty: 0,
+                swap: 0,
+            }),
+        });
+    }
+    regions
+}
+
+/// Read and label the mappings of process `pid`.
 pub fn read_proc_maps(pid: u32) -> io::Result<Vec<VmRegion>> {
     let path = format!("/proc/{}/maps", pid);
     let file = File::open(&path)?;
@@ -40,50 +206,234 @@ pub fn read_proc_maps(pid: u32) -> io::Result<Vec<VmRegion>> {
     let mut regions = Vec::new();
 
     for line_res in reader.lines() {
-        let line = line_res?;
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
+        if let Some(region) = parse_maps_line(&line_res?) {
+            regions.push(region);
         }
+    }
+
+    Ok(regions)
+}
+
+/// Like `read_proc_maps`, with each region's `usage` from `/proc/<pid>/smaps`. Slower: the
+/// kernel walks the page tables of every mapping to produce it.
+pub fn read_proc_smaps(pid: u32) -> io::Result<Vec<VmRegion>> {
+    let path = format!("/proc/{}/smaps", pid);
+    Ok(parse_smaps(&std::fs::read_to_string(&path)?))
+}
 
-        let mut parts = trimmed.split_whitespace();
-        let range = match parts.next() {
-            Some(r) => r,
-            None => continue,
+/// Regions from smaps text: a maps line per region followed by `Key: value kB` lines.
+fn parse_smaps(text: &str) -> Vec<VmRegion> {
+    let mut regions: Vec<VmRegion> = Vec::new();
+    for line in text.lines() {
+        if let Some(mut region) = parse_maps_line(line) {
+            region.usage = Some(RegionUsage::default());
+            regions.push(region);
+            continue;
+        }
+        let Some(usage) = regions.last_mut().and_then(|r| r.usage.as_mut()) else {
+            continue;
         };
-        let perms = match parts.next() {
-            Some(p) => p.to_string(),
-            None => continue,
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
         };
+        let mut words = value.split_whitespace();
+        let (Some(n), Some("kB")) = (
+            words.next().and_then(|n| n.parse::<u64>().ok()),
+            words.next(),
+        ) else {
+            continue;
+        };
+        let bytes = n * 1024;
+        match key {
+            "Rss" => usage.rss = bytes,
+            "Pss" => usage.pss = bytes,
+            "Shared_Dirty" | "Private_Dirty" => usage.dirty += bytes,
+            "Swap" => usage.swap = bytes,
+            _ => {}
+        }
+    }
+    regions
+}
 
-        let _offset = parts.next();
-        let _dev = parts.next();
-        let _inode = parts.next();
-
-        let pathname = parts.collect::<Vec<_>>().join(" ");
+/// Run `vmmap` on process `pid` (one line per region, in address order).
+#[cfg(target_os = "macos")]
+fn read_vmmap(pid: u32) -> io::Result<Vec<VmRegion>> {
+    let out = std::process::Command::new("vmmap")
+        .args(["-interleaved", &pid.to_string()])
+        .output()?;
+    if !out.status.success() {
+        let err = String::from_utf8_lossy(&out.stderr);
+        return Err(io::Error::other(format!("vmmap failed: {}", err.trim())));
+    }
+    Ok(parse_vmmap(&String::from_utf8_lossy(&out.stdout)))
+}
 
-        let (start_str, end_str) = match range.split_once('-') {
-            Some(v) => v,
-            None => continue,
+/// Regions from `vmmap -interleaved` output:
+///
+/// ```text
+/// REGION TYPE        START - END         [ VSIZE  RSDNT  DIRTY   SWAP] PRT/MAX SHRMOD PURGE  REGION DETAIL
+/// __TEXT             100000000-100004000 [   16K    16K     0K     0K] r-x/r-x SM=COW          /Users/u/a.out
+/// MALLOC_SMALL       7f8a1e800000-7f8a1f000000 [ 8192K    48K    48K     0K] rw-/rwx SM=PRV  MallocHelperZone_0x100f00000
+/// ```
+///
+/// Permissions become maps-style (`rw-p`) and the RSDNT/DIRTY/SWAP columns fill `usage`
+/// (vmmap has no PSS; the resident size stands in).
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_vmmap(text: &str) -> Vec<VmRegion> {
+    static LINE: OnceLock<Regex> = OnceLock::new();
+    let line_re = LINE.get_or_init(|| {
+        Regex::new(
+            r"^(\S.*?)\s+([0-9a-f]+)-([0-9a-f]+)\s+\[\s*(\S+)\s+(\S+)\s+(\S+)\s+(\S+)\s*\]\s+([rwx-]{3})/[rwx-]{3}\s+SM=(\S+)\s*(.*)$",
+        )
+        .expect("vmmap line pattern")
+    });
+    let mut regions = Vec::new();
+    for line in text.lines() {
+        let Some(caps) = line_re.captures(line) else {
+            continue;
+        };
+        let (Ok(start), Ok(end)) = (
+            u64::from_str_radix(&caps[2], 16),
+            u64::from_str_radix(&caps[3], 16),
+        ) else {
+            continue;
         };
-        let start = u64::from_str_radix(start_str, 16).unwrap_or(0);
-        let end = u64::from_str_radix(end_str, 16).unwrap_or(0);
         if start >= end {
             continue;
         }
-
-        let label = classify_region_label(&perms, &pathname);
-
+        let kind = caps[1].trim();
+        // Region detail: a path, a malloc zone, "thread 0", ...; a one-letter PURGE state
+        // (N/V/E) may lead.
+        let detail = caps[10].trim();
+        let detail = match detail.split_once(char::is_whitespace) {
+            Some((purge, rest))
+                if purge.len() == 1 && purge.chars().all(|c| c.is_ascii_uppercase()) =>
+            {
+                rest.trim()
+            }
+            _ => detail,
+        };
+        let share = if &caps[9] == "SHM" { "s" } else { "p" };
         regions.push(VmRegion {
             start,
             end,
-            perms,
-            pathname,
-            label,
+            perms: format!("{}{}", &caps[8], share),
+            offset: None,
+            pathname: detail.to_string(),
+            label: classify_vmmap_label(kind, detail),
+            usage: Some(RegionUsage {
+                rss: parse_vmmap_size(&caps[5]),
+                pss: parse_vmmap_size(&caps[5]),
+                dirty: parse_vmmap_size(&caps[6]),
+                swap: parse_vmmap_size(&caps[7]),
+            }),
         });
     }
+    regions
+}
 
-    Ok(regions)
+/// vmmap sizes: `16K`, `8188K`, `1.5M`, `2G`, or bytes.
+fn parse_vmmap_size(raw: &str) -> u64 {
+    let (num, unit) = match raw.char_indices().find(|(_, c)| c.is_ascii_alphabetic()) {
+        Some((i, _)) => (&raw[..i], &raw[i..]),
+        None => (raw, ""),
+    };
+    let scale = match unit {
+        "K" => 1u64 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        _ => 1,
+    };
+    num.parse::<f64>().map_or(0, |n| (n * scale as f64) as u64)
+}
+
+/// Label for a vmmap region type: segments of the executable are text/data, segments of
+/// dylibs and the shared cache are libraries, malloc zones are the heap.
+fn classify_vmmap_label(kind: &str, detail: &str) -> VmLabel {
+    let from_library = detail.contains(".dylib")
+        || detail.starts_with("/usr/lib/")
+        || detail.starts_with("/System/")
+        || detail.contains("dyld");
+    if kind.starts_with("MALLOC") {
+        VmLabel::Heap
+    } else if kind == "Stack" {
+        VmLabel::Stack
+    } else if kind.starts_with("__") && (from_library || detail.is_empty()) {
+        VmLabel::Lib
+    } else if kind == "__TEXT" {
+        VmLabel::Text
+    } else if kind.starts_with("__DATA") || kind == "__OBJC_RW" {
+        VmLabel::Data
+    } else if kind.starts_with("VM_ALLOCATE") {
+        VmLabel::Anonymous
+    } else {
+        VmLabel::Other(kind.to_string())
+    }
+}
+
+/// One maps line (`start-end perms offset dev inode [path]`); `None` for anything else,
+/// including smaps' field lines.
+fn parse_maps_line(line: &str) -> Option<VmRegion> {
+    let trimmed = line.trim();
+    let mut parts = trimmed.split_whitespace();
+    let range = parts.next()?;
+    let perms = parts.next()?.to_string();
+
+    let offset = parts.next().and_then(|o| u64::from_str_radix(o, 16).ok());
+    let _dev = parts.next();
+    let _inode = parts.next();
+
+    let pathname = parts.collect::<Vec<_>>().join(" ");
+
+    let (start_str, end_str) = range.split_once('-')?;
+    let start = u64::from_str_radix(start_str, 16).ok()?;
+    let end = u64::from_str_radix(end_str, 16).ok()?;
+    if start >= end {
+        return None;
+    }
+
+    let label = classify_region_label(&perms, &pathname);
+
+    Some(VmRegion {
+        start,
+        end,
+        perms,
+        offset,
+        pathname,
+        label,
+        usage: None,
+    })
+}
+
+/// Totals per label over the regions that have usage, largest resident set first.
+pub fn usage_by_label(regions: &[VmRegion]) -> Vec<LabelUsage> {
+    let mut totals: Vec<LabelUsage> = Vec::new();
+    for r in regions {
+        let Some(usage) = &r.usage else {
+            continue;
+        };
+        let label = match r.label {
+            VmLabel::Other(_) => VmLabel::Other(String::new()),
+            ref l => l.clone(),
+        };
+        let pos = match totals.iter().position(|t| t.label == label) {
+            Some(pos) => pos,
+            None => {
+                totals.push(LabelUsage {
+                    label,
+                    regions: 0,
+                    size: 0,
+                    usage: RegionUsage::default(),
+                });
+                totals.len() - 1
+            }
+        };
+        totals[pos].regions += 1;
+        totals[pos].size += r.size();
+        totals[pos].usage.add(usage);
+    }
+    totals.sort_by_key(|t| std::cmp::Reverse(t.usage.rss));
+    totals
 }
 
 fn classify_region_label(perms: &str, pathname: &str) -> VmLabel {
@@ -106,19 +456,761 @@ fn classify_region_label(perms: &str, pathname: &str) -> VmLabel {
     }
 }
 
-pub fn classify_addr(regions: &[VmRegion], addr: u64) -> &'static str {
+/// Label the mappings holding the stack pointers of threads other than the main one,
+/// given as (kernel thread id, stack pointer): the kernel lists those stacks as anonymous
+/// memory. The main thread's `[stack]` keeps its label.
+pub fn label_thread_stacks(regions: &mut [VmRegion], stacks: &[(u32, u64)]) {
+    for (tid, sp) in stacks {
+        if let Some(r) = regions
+            .iter_mut()
+            .find(|r| r.contains(*sp) && r.label != VmLabel::Stack)
+        {
+            r.label = VmLabel::ThreadStack(*tid);
+        }
+    }
+}
+
+/// Label the inaccessible (`---p`) anonymous mappings directly below a stack `[guard]`;
+/// glibc puts one under every thread stack it allocates.
+pub fn label_guard_pages(regions: &mut [VmRegion]) {
+    for i in 1..regions.len() {
+        let (below, above) = (&regions[i - 1], &regions[i]);
+        let guard = below.perms.starts_with("---")
+            && below.pathname.is_empty()
+            && below.end == above.start
+            && matches!(above.label, VmLabel::Stack | VmLabel::ThreadStack(_));
+        if guard {
+            regions[i - 1].label = VmLabel::Guard;
+        }
+    }
+}
+
+/// Below this share of its size left, a stack counts as nearly exhausted.
+pub const STACK_LOW_PERCENT: u64 = 10;
+
+/// How much further the stack holding a thread's stack pointer can grow down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackHeadroom {
+    pub sp: u64,
+    /// Lowest address the stack can reach before faulting.
+    pub floor: u64,
+    /// Where the stack starts (its highest address).
+    pub top: u64,
+    /// A guard page sits right below the stack's mapping.
+    pub guarded: bool,
+}
+
+impl StackHeadroom {
+    /// Bytes still free below `sp`.
+    pub fn left(&self) -> u64 {
+        self.sp.saturating_sub(self.floor)
+    }
+
+    /// The most the stack can ever hold.
+    pub fn total(&self) -> u64 {
+        self.top.saturating_sub(self.floor)
+    }
+
+    pub fn is_low(&self) -> bool {
+        self.left() * 100 < self.total() * STACK_LOW_PERCENT
+    }
+}
+
+/// Headroom of the stack holding `sp`. The main `[stack]` grows on demand until it hits
+/// `limit` (RLIMIT_STACK, `None` when unlimited) or the mapping below it; any other mapping
+/// holding a stack pointer is a thread stack of fixed size.
+pub fn stack_headroom(regions: &[VmRegion], sp: u64, limit: Option<u64>) -> Option<StackHeadroom> {
+    let i = regions.iter().position(|r| r.contains(sp))?;
+    let region = &regions[i];
+    let below = i.checked_sub(1).map(|j| &regions[j]);
+    let guarded = below.is_some_and(|b| b.perms.starts_with("---") && b.end == region.start);
+    let floor = if region.label == VmLabel::Stack {
+        let by_limit = limit.map_or(0, |l| region.end.saturating_sub(l));
+        let by_neighbor = below.map_or(0, |b| b.end);
+        by_limit.max(by_neighbor)
+    } else {
+        region.start
+    };
+    Some(StackHeadroom {
+        sp,
+        floor,
+        top: region.end,
+        guarded,
+    })
+}
+
+/// Soft stack size limit of process `pid` from `/proc/<pid>/limits`; `None` when it is
+/// unlimited or unknown.
+pub fn read_stack_limit(pid: u32) -> Option<u64> {
+    let text = std::fs::read_to_string(format!("/proc/{}/limits", pid)).ok()?;
+    parse_stack_limit(&text)
+}
+
+/// "Max stack size            8388608              unlimited            bytes"
+fn parse_stack_limit(text: &str) -> Option<u64> {
+    let line = text.lines().find(|l| l.starts_with("Max stack size"))?;
+    line["Max stack size".len()..]
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Region label (`"[heap]"`, `"[stack]"`, `"[stack:1235]"`, ...) for `addr`, or
+/// `"[unknown]"`.
+pub fn classify_addr(regions: &[VmRegion], addr: u64) -> String {
+    match regions.iter().find(|r| r.contains(addr)) {
+        Some(r) => r.label.tag(),
+        None => "[unknown]".to_string(),
+    }
+}
+
+/// How one mapping changed between two snapshots of the map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegionChange {
+    Added(VmRegion),
+    Removed(VmRegion),
+    /// Same mapping (same file and permissions, one end fixed) with a new extent: `brk`,
+    /// `mremap`, stack growth.
+    Resized {
+        old: VmRegion,
+        new: VmRegion,
+    },
+}
+
+impl RegionChange {
+    /// Start of the region as it is now, or was before it went away.
+    pub fn start(&self) -> u64 {
+        match self {
+            RegionChange::Added(r) | RegionChange::Removed(r) => r.start,
+            RegionChange::Resized { new, .. } => new.start,
+        }
+    }
+}
+
+/// What happened to the map between `old` and `new`, by address. Labels are not compared, so
+/// a thread stack labeled in one snapshot only is not a change.
+pub fn diff_regions(old: &[VmRegion], new: &[VmRegion]) -> Vec<RegionChange> {
+    let same = |a: &VmRegion, b: &VmRegion| {
+        a.start == b.start && a.end == b.end && a.perms == b.perms && a.pathname == b.pathname
+    };
+    let resized = |a: &VmRegion, b: &VmRegion| {
+        a.perms == b.perms
+            && a.pathname == b.pathname
+            && (a.start == b.start || a.end == b.end)
+            && !same(a, b)
+    };
+    let mut changes = Vec::new();
+    let mut matched = vec![false; old.len()];
+    for n in new {
+        if let Some(i) = old.iter().position(|o| same(o, n)) {
+            matched[i] = true;
+            continue;
+        }
+        match (0..old.len()).find(|&i| !matched[i] && resized(&old[i], n)) {
+            Some(i) => {
+                matched[i] = true;
+                changes.push(RegionChange::Resized {
+                    old: old[i].clone(),
+                    new: n.clone(),
+                });
+            }
+            None => changes.push(RegionChange::Added(n.clone())),
+        }
+    }
+    for (o, _) in old.iter().zip(&matched).filter(|(_, m)| !**m) {
+        changes.push(RegionChange::Removed(o.clone()));
+    }
+    changes.sort_by_key(RegionChange::start);
+    changes
+}
+
+/// One box of the `vm map` diagram: back-to-back mappings with the same label and file
+/// (a library's text, data and bss), or the unmapped gap between two boxes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapBlock {
+    Mapped {
+        start: u64,
+        end: u64,
+        label: VmLabel,
+        pathname: String,
+        /// Mappings merged into this box.
+        regions: usize,
+    },
+    Gap {
+        start: u64,
+        end: u64,
+    },
+}
+
+impl MapBlock {
+    pub fn start(&self) -> u64 {
+        match self {
+            MapBlock::Mapped { start, .. } | MapBlock::Gap { start, .. } => *start,
+        }
+    }
+
+    pub fn end(&self) -> u64 {
+        match self {
+            MapBlock::Mapped { end, .. } | MapBlock::Gap { end, .. } => *end,
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        self.end().saturating_sub(self.start())
+    }
+}
+
+/// The map as boxes and gaps, lowest address first.
+pub fn map_blocks(regions: &[VmRegion]) -> Vec<MapBlock> {
+    let mut ordered: Vec<&VmRegion> = regions.iter().collect();
+    ordered.sort_by_key(|r| r.start);
+    let mut blocks: Vec<MapBlock> = Vec::new();
+    for r in ordered {
+        if let Some(MapBlock::Mapped {
+            end,
+            label,
+            pathname,
+            regions,
+            ..
+        }) = blocks.last_mut()
+        {
+            if *end == r.start && *label == r.label && *pathname == r.pathname {
+                *end = r.end;
+                *regions += 1;
+                continue;
+            }
+        }
+        if let Some(prev) = blocks.last().map(MapBlock::end).filter(|e| *e < r.start) {
+            blocks.push(MapBlock::Gap {
+                start: prev,
+                end: r.start,
+            });
+        }
+        blocks.push(MapBlock::Mapped {
+            start: r.start,
+            end: r.end,
+            label: r.label.clone(),
+            pathname: r.pathname.clone(),
+            regions: 1,
+        });
+    }
+    blocks
+}
+
+/// `p_type` of a loadable ELF segment.
+const PT_LOAD: u64 = 1;
+
+/// A mapped ELF file (the executable or a shared library) and where ASLR put it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleBase {
+    pub path: String,
+    /// Where the file's offset 0 is mapped now.
+    pub start: u64,
+    /// Where the linker placed offset 0: 0 for shared libraries and PIE executables,
+    /// 0x400000 for a classic x86-64 executable.
+    pub link_base: u64,
+}
+
+impl ModuleBase {
+    /// Runtime address minus link-time address, for every address in the file.
+    pub fn bias(&self) -> u64 {
+        self.start.wrapping_sub(self.link_base)
+    }
+}
+
+/// Every mapped ELF file in `regions`, in map order (the executable first), with its load
+/// bias from the link-time addresses in its program headers. Files that are not ELF or
+/// cannot be read are left out.
+pub fn module_bases(regions: &[VmRegion]) -> Vec<ModuleBase> {
+    let mut bases: Vec<ModuleBase> = Vec::new();
     for r in regions {
-        if r.contains(addr) {
-            return match r.label {
-                VmLabel::Text => "[text]",
-                VmLabel::Data => "[data]",
-                VmLabel::Heap => "[heap]",
-                VmLabel::Stack => "[stack]",
-                VmLabel::Lib => "[lib]",
-                VmLabel::Anonymous => "[anon]",
-                VmLabel::Other(_) => "[other]",
-            };
-        }
-    }
-    "[unknown]"
+        if r.offset != Some(0)
+            || !r.pathname.starts_with('/')
+            || bases.iter().any(|b| b.path == r.pathname)
+        {
+            continue;
+        }
+        if let Ok(Some(link_base)) = elf_link_base(&r.pathname) {
+            bases.push(ModuleBase {
+                path: r.pathname.clone(),
+                start: r.start,
+                link_base,
+            });
+        }
+    }
+    bases
+}
+
+/// `addr` relative to the file mapped there: the module, the link-time address (what
+/// `nm`/`objdump` print) and the file offset. `None` outside mapped ELF files.
+pub fn file_relative<'a>(
+    regions: &[VmRegion],
+    bases: &'a [ModuleBase],
+    addr: u64,
+) -> Option<(&'a ModuleBase, u64, u64)> {
+    let region = regions.iter().find(|r| r.contains(addr))?;
+    let module = bases.iter().find(|m| m.path == region.pathname)?;
+    Some((
+        module,
+        addr.wrapping_sub(module.bias()),
+        region.file_offset(addr)?,
+    ))
+}
+
+/// Link-time address of offset 0 of the ELF file at `path`; `None` when it is not ELF.
+pub fn elf_link_base(path: &str) -> io::Result<Option<u64>> {
+    let mut head = Vec::new();
+    File::open(path)?.take(64).read_to_end(&mut head)?;
+    let Some((phoff, count, size)) = Elf::new(&head).and_then(|e| e.program_headers()) else {
+        return Ok(None);
+    };
+    // A corrupt header should not make us read a whole file.
+    let span = phoff.saturating_add(count * size).min(1 << 20);
+    let mut data = Vec::new();
+    File::open(path)?.take(span as u64).read_to_end(&mut data)?;
+    Ok(parse_elf_link_base(&data))
+}
+
+/// `p_vaddr - p_offset` of the first PT_LOAD segment in an ELF file's leading bytes
+/// (header and program headers).
+fn parse_elf_link_base(data: &[u8]) -> Option<u64> {
+    let elf = Elf::new(data)?;
+    let (phoff, count, size) = elf.program_headers()?;
+    (0..count).find_map(|i| {
+        let ph = phoff.checked_add(i * size)?;
+        if elf.uint(ph, 4)? != PT_LOAD {
+            return None;
+        }
+        let (offset, vaddr) = if elf.wide {
+            (elf.uint(ph + 8, 8)?, elf.uint(ph + 16, 8)?)
+        } else {
+            (elf.uint(ph + 4, 4)?, elf.uint(ph + 8, 4)?)
+        };
+        Some(vaddr.wrapping_sub(offset))
+    })
+}
+
+/// The leading bytes of an ELF file, 32- or 64-bit, either byte order.
+struct Elf<'a> {
+    data: &'a [u8],
+    wide: bool,
+    big_endian: bool,
+}
+
+impl<'a> Elf<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        if data.len() < 52 || !data.starts_with(b"\x7fELF") {
+            return None;
+        }
+        let wide = match data[4] {
+            1 => false,
+            2 => true,
+            _ => return None,
+        };
+        let big_endian = match data[5] {
+            1 => false,
+            2 => true,
+            _ => return None,
+        };
+        Some(Self {
+            data,
+            wide,
+            big_endian,
+        })
+    }
+
+    fn uint(&self, at: usize, size: usize) -> Option<u64> {
+        let bytes = self.data.get(at..at.checked_add(size)?)?;
+        let fold = |acc: u64, b: &u8| acc << 8 | u64::from(*b);
+        Some(if self.big_endian {
+            bytes.iter().fold(0, fold)
+        } else {
+            bytes.iter().rev().fold(0, fold)
+        })
+    }
+
+    /// (offset, count, entry size) of the program header table.
+    fn program_headers(&self) -> Option<(usize, usize, usize)> {
+        let (phoff, size, count) = if self.wide {
+            (
+                self.uint(0x20, 8)?,
+                self.uint(0x36, 2)?,
+                self.uint(0x38, 2)?,
+            )
+        } else {
+            (
+                self.uint(0x1c, 4)?,
+                self.uint(0x2a, 2)?,
+                self.uint(0x2c, 2)?,
+            )
+        };
+        Some((usize::try_from(phoff).ok()?, count as usize, size as usize))
+    }
+}
+
+/// Part of an access range that the program itself could not perform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessIssue {
+    /// No mapping covers [start, end).
+    Unmapped { start: u64, end: u64 },
+    /// The covering region lacks the needed permission bit.
+    Denied { region: VmRegion, need: char },
+}
+
+/// Check [addr, addr+len) against the map for permission `need` ('r' or 'w').
+pub fn access_issues(regions: &[VmRegion], addr: u64, len: usize, need: char) -> Vec<AccessIssue> {
+    let end = addr.saturating_add(len.max(1) as u64);
+    let mut issues = Vec::new();
+    let mut cur = addr;
+    while cur < end {
+        match regions.iter().find(|r| r.contains(cur)) {
+            Some(r) => {
+                if !r.perms.contains(need) {
+                    issues.push(AccessIssue::Denied {
+                        region: r.clone(),
+                        need,
+                    });
+                }
+                cur = r.end;
+            }
+            None => {
+                let gap_end = regions
+                    .iter()
+                    .map(|r| r.start)
+                    .filter(|s| *s > cur)
+                    .min()
+                    .unwrap_or(end)
+                    .min(end);
+                issues.push(AccessIssue::Unmapped {
+                    start: cur,
+                    end: gap_end,
+                });
+                cur = gap_end;
+            }
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(start: u64, end: u64, perms: &str) -> VmRegion {
+        VmRegion {
+            start,
+            end,
+            perms: perms.to_string(),
+            offset: None,
+            pathname: String::new(),
+            label: VmLabel::Anonymous,
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn thread_stacks_are_labeled_by_stack_pointer() {
+        let mut regions = vec![
+            region(0x7fff0000, 0x7fff8000, "rw-p"),
+            region(0x7ffff0000000, 0x7ffff0800000, "rw-p"),
+        ];
+        regions[0].label = VmLabel::Stack;
+        label_thread_stacks(
+            &mut regions,
+            &[(1234, 0x7fff7e00), (1235, 0x7ffff07ffe70), (1236, 0x10)],
+        );
+        assert_eq!(regions[0].label, VmLabel::Stack);
+        assert_eq!(regions[1].label, VmLabel::ThreadStack(1235));
+        assert_eq!(classify_addr(&regions, 0x7ffff0000010), "[stack:1235]");
+        assert_eq!(classify_addr(&regions, 0x7fff0010), "[stack]");
+        assert_eq!(classify_addr(&regions, 0x20), "[unknown]");
+    }
+
+    #[test]
+    fn guard_pages_bound_the_stack_headroom() {
+        let mut regions = vec![
+            region(0x7fffef7ff000, 0x7fffef800000, "---p"),
+            region(0x7fffef800000, 0x7ffff0000000, "rw-p"),
+            region(0x7ffff7ff0000, 0x7ffff8000000, "r--p"),
+            region(0x7ffffffde000, 0x7ffffffff000, "rw-p"),
+        ];
+        regions[3].label = VmLabel::Stack;
+        label_thread_stacks(&mut regions, &[(4001, 0x7fffef800100)]);
+        label_guard_pages(&mut regions);
+        assert_eq!(regions[0].label, VmLabel::Guard);
+        assert_eq!(regions[2].label, VmLabel::Anonymous);
+        // A thread stack cannot grow past its mapping.
+        let thread = stack_headroom(&regions, 0x7fffef800100, None).unwrap();
+        assert_eq!((thread.left(), thread.total()), (0x100, 0x800000));
+        assert!(thread.guarded && thread.is_low());
+        // The main stack grows down to the size limit...
+        let main = stack_headroom(&regions, 0x7fffffffe000, Some(8 << 20)).unwrap();
+        assert_eq!(main.floor, 0x7ffffffff000 - (8 << 20));
+        assert!(!main.guarded && !main.is_low());
+        // ...or to the mapping below it when that comes first.
+        let main = stack_headroom(&regions, 0x7fffffffe000, None).unwrap();
+        assert_eq!(main.floor, 0x7ffff8000000);
+        assert_eq!(stack_headroom(&regions, 0x1000, None), None);
+        assert_eq!(
+            parse_stack_limit(
+                "Max stack size            8388608              unlimited            bytes\n"
+            ),
+            Some(8388608)
+        );
+        assert_eq!(
+            parse_stack_limit(
+                "Max stack size            unlimited            unlimited            bytes\n"
+            ),
+            None
+        );
+    }
+
+    /// ELF header and program headers with one PT_PHDR and the given PT_LOAD segments.
+    fn elf_image(wide: bool, big_endian: bool, loads: &[(u64, u64)]) -> Vec<u8> {
+        let word = if wide { 8 } else { 4 };
+        let put = |out: &mut Vec<u8>, v: u64, n: usize| {
+            let bytes = v.to_le_bytes();
+            if big_endian {
+                out.extend(bytes[..n].iter().rev());
+            } else {
+                out.extend(&bytes[..n]);
+            }
+        };
+        let (ehsize, phentsize) = if wide { (64, 56) } else { (52, 32) };
+        let mut out = b"\x7fELF".to_vec();
+        out.extend([if wide { 2 } else { 1 }, if big_endian { 2 } else { 1 }, 1]);
+        out.resize(0x18, 0);
+        put(&mut out, 0, word); // e_entry
+        put(&mut out, ehsize, word); // e_phoff
+        put(&mut out, 0, word); // e_shoff
+        put(&mut out, 0, 4); // e_flags
+        put(&mut out, ehsize, 2);
+        put(&mut out, phentsize, 2);
+        put(&mut out, loads.len() as u64 + 1, 2);
+        out.resize(ehsize as usize, 0);
+        let segments = std::iter::once((6, (ehsize, ehsize))).chain(loads.iter().map(|l| (1, *l)));
+        for (p_type, (offset, vaddr)) in segments {
+            let start = out.len();
+            put(&mut out, p_type, 4);
+            if wide {
+                put(&mut out, 0, 4); // p_flags
+            }
+            put(&mut out, offset, word);
+            put(&mut out, vaddr, word);
+            out.resize(start + phentsize as usize, 0);
+        }
+        out
+    }
+
+    #[test]
+    fn load_bias_comes_from_the_first_load_segment() {
+        let exec = elf_image(true, false, &[(0, 0x400000), (0x1000, 0x401000)]);
+        assert_eq!(parse_elf_link_base(&exec), Some(0x400000));
+        let lib = elf_image(false, true, &[(0, 0), (0x2000, 0x3000)]);
+        assert_eq!(parse_elf_link_base(&lib), Some(0));
+        assert_eq!(parse_elf_link_base(&exec[..40]), None);
+        assert_eq!(parse_elf_link_base(b"#!/bin/sh\n"), None);
+        // This test binary: a function's link-time address lies inside its own file.
+        let regions = read_proc_maps(std::process::id()).unwrap();
+        let bases = module_bases(&regions);
+        let here = load_bias_comes_from_the_first_load_segment as *const () as u64;
+        let (module, link_addr, file_offset) = file_relative(&regions, &bases, here).unwrap();
+        assert_eq!(
+            module.path,
+            std::fs::read_link("/proc/self/exe")
+                .unwrap()
+                .to_str()
+                .unwrap()
+        );
+        assert_eq!(link_addr, here - module.bias());
+        assert!(file_offset < std::fs::metadata(&module.path).unwrap().len());
+    }
+
+    #[test]
+    fn map_blocks_merge_a_files_mappings_and_show_gaps() {
+        let lib = |start, end, perms| {
+            let mut r = region(start, end, perms);
+            r.label = VmLabel::Lib;
+            r.pathname = "/usr/lib/libc.so.6".to_string();
+            r
+        };
+        let mut heap = region(0x5000, 0x8000, "rw-p");
+        heap.label = VmLabel::Heap;
+        let regions = vec![
+            lib(0x7f0000002000, 0x7f0000004000, "r-xp"),
+            heap,
+            lib(0x7f0000000000, 0x7f0000002000, "r--p"),
+            region(0x7f0000004000, 0x7f0000005000, "rw-p"),
+        ];
+        let blocks = map_blocks(&regions);
+        let shape: Vec<(u64, u64, Option<usize>)> = blocks
+            .iter()
+            .map(|b| match b {
+                MapBlock::Mapped { regions, .. } => (b.start(), b.end(), Some(*regions)),
+                MapBlock::Gap { .. } => (b.start(), b.end(), None),
+            })
+            .collect();
+        assert_eq!(
+            shape,
+            [
+                (0x5000, 0x8000, Some(1)),
+                (0x8000, 0x7f0000000000, None),
+                (0x7f0000000000, 0x7f0000004000, Some(2)),
+                (0x7f0000004000, 0x7f0000005000, Some(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn region_diff_reports_new_removed_and_resized_mappings() {
+        let mut heap = region(0x5000, 0x6000, "rw-p");
+        heap.pathname = "[heap]".to_string();
+        let old = vec![
+            heap.clone(),
+            region(0x7000, 0x8000, "rw-p"),
+            region(0x9000, 0xa000, "r--p"),
+        ];
+        let mut grown = heap.clone();
+        grown.end = 0x7000;
+        let new = vec![
+            grown.clone(),
+            region(0x9000, 0xa000, "r--p"),
+            region(0xb000, 0xc000, "rw-p"),
+        ];
+        assert_eq!(
+            diff_regions(&old, &new),
+            [
+                RegionChange::Resized {
+                    old: heap,
+                    new: grown
+                },
+                RegionChange::Removed(region(0x7000, 0x8000, "rw-p")),
+                RegionChange::Added(region(0xb000, 0xc000, "rw-p")),
+            ]
+        );
+        assert!(diff_regions(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn vmmap_regions_are_labeled_like_proc_maps() {
+        let text = "\
+Process:         list [4242]
+==== regions for process 4242  (non-writable and writable regions are interleaved)
+REGION TYPE                    START - END         [ VSIZE  RSDNT  DIRTY   SWAP] PRT/MAX SHRMOD PURGE    REGION DETAIL
+__TEXT                      100000000-100004000    [   16K    16K     0K     0K] r-x/r-x SM=COW          /Users/u/list
+__DATA_CONST                100004000-100008000    [   16K    16K    16K     0K] rw-/rw- SM=COW          /Users/u/list
+MALLOC_TINY                 600000000000-600000100000 [ 1024K    12K    12K     0K] rw-/rwx SM=PRV  N       DefaultMallocZone_0x100f00000
+Stack                       16f600000-16fe00000    [ 8192K    32K    32K     0K] rw-/rwx SM=PRV          thread 0
+__TEXT                      18c3a0000-18c3f0000    [  320K   320K     0K     0K] r-x/r-x SM=COW          /usr/lib/system/libsystem_c.dylib
+VM_ALLOCATE (reserved)      170000000-170400000    [ 4096K     0K     0K     0K] rw-/rwx SM=NUL          reserved VM address space (unallocated)
+";
+        let regions = parse_vmmap(text);
+        assert_eq!(regions.len(), 6);
+        assert_eq!(regions[0].label, VmLabel::Text);
+        assert_eq!(regions[0].perms, "r-xp");
+        assert_eq!(regions[0].pathname, "/Users/u/list");
+        assert_eq!(regions[1].label, VmLabel::Data);
+        assert_eq!(regions[2].label, VmLabel::Heap);
+        assert_eq!(regions[2].start, 0x600000000000);
+        assert_eq!(regions[2].pathname, "DefaultMallocZone_0x100f00000");
+        assert_eq!(regions[3].label, VmLabel::Stack);
+        assert_eq!(regions[3].usage.unwrap().rss, 32 * 1024);
+        assert_eq!(regions[4].label, VmLabel::Lib);
+        assert_eq!(regions[5].label, VmLabel::Anonymous);
+        assert_eq!(classify_addr(&regions, 0x16fdfff00), "[stack]");
+        assert_eq!(parse_vmmap_size("1.5M"), 3 << 19);
+    }
+
+    #[test]
+    fn procstat_regions_are_labeled_like_proc_maps() {
+        let text = "\
+  PID              START                END PRT  RES PRES REF SHD FLAG  TP PATH
+ 4242           0x200000           0x201000 r--    1    3   2   1 CN--- vn /home/u/list
+ 4242           0x201000           0x203000 r-x    2    3   2   1 CN--- vn /home/u/list
+ 4242           0x203000           0x204000 rw-    1    1   1   0 C---- vn /home/u/list
+ 4242        0x800222000        0x80023a000 r-x   24   26  31  14 CN--- vn /lib/libc.so.7
+ 4242        0x801000000        0x801200000 rw-   12   12   1   0 ----- df
+ 4242     0x7ffffffdf000     0x7ffffffff000 rw-    3    3   1   0 ---D- df
+";
+        let regions = parse_procstat(text, 4096);
+        assert_eq!(regions.len(), 6);
+        assert_eq!(regions[1].label, VmLabel::Text);
+        assert_eq!(regions[1].perms, "r-xp");
+        assert_eq!(regions[2].label, VmLabel::Data);
+        assert_eq!(regions[3].label, VmLabel::Lib);
+        assert_eq!(regions[4].label, VmLabel::Anonymous);
+        assert_eq!(regions[4].pathname, "");
+        assert_eq!(regions[5].label, VmLabel::Stack);
+        assert_eq!(regions[5].usage.unwrap().rss, 3 * 4096);
+    }
+
+    #[test]
+    fn smaps_usage_is_totalled_per_label() {
+        let text = "\
+5555555a0000-5555555c1000 rw-p 00000000 00:00 0                          [heap]
+Size:                132 kB
+Rss:                  12 kB
+Pss:                  12 kB
+Shared_Dirty:          0 kB
+Private_Dirty:        12 kB
+Swap:                  4 kB
+VmFlags: rd wr mr mw me ac
+7ffff7dd0000-7ffff7df8000 r--p 00028000 08:01 1234                       /usr/lib/libc.so.6
+Rss:                 160 kB
+Pss:                  20 kB
+Private_Dirty:         0 kB
+7ffff7fc0000-7ffff7fc2000 rw-p 00000000 00:00 0
+Rss:                   8 kB
+Pss:                   8 kB
+Shared_Dirty:          4 kB
+Private_Dirty:         4 kB
+7ffff7fc4000-7ffff7fc6000 rw-p 00000000 00:00 0
+Rss:                   4 kB
+";
+        let regions = parse_smaps(text);
+        assert_eq!(regions.len(), 4);
+        assert_eq!(regions[0].label, VmLabel::Heap);
+        assert_eq!(regions[0].file_offset(0x5555555a0010), None);
+        assert_eq!(regions[1].file_offset(0x7ffff7dd0040), Some(0x28040));
+        assert_eq!(
+            regions[0].usage,
+            Some(RegionUsage {
+                rss: 12 * 1024,
+                pss: 12 * 1024,
+                dirty: 12 * 1024,
+                swap: 4 * 1024
+            })
+        );
+        let totals = usage_by_label(&regions);
+        assert_eq!(totals[0].label, VmLabel::Lib);
+        assert_eq!(totals[0].usage.pss, 20 * 1024);
+        let anon = totals
+            .iter()
+            .find(|t| t.label == VmLabel::Anonymous)
+            .unwrap();
+        assert_eq!(anon.regions, 2);
+        assert_eq!(anon.size, 0x4000);
+        assert_eq!(anon.usage.rss, 12 * 1024);
+        assert_eq!(anon.usage.dirty, 8 * 1024);
+    }
+
+    #[test]
+    fn access_issues_report_gaps_and_missing_permissions() {
+        let regions = vec![
+            region(0x1000, 0x2000, "r--p"),
+            region(0x3000, 0x4000, "rw-p"),
+        ];
+        assert!(access_issues(&regions, 0x1000, 16, 'r').is_empty());
+        let issues = access_issues(&regions, 0x1ff0, 0x1020, 'w');
+        assert_eq!(issues.len(), 2);
+        assert!(matches!(issues[0], AccessIssue::Denied { need: 'w', .. }));
+        assert_eq!(
+            issues[1],
+            AccessIssue::Unmapped {
+                start: 0x2000,
+                end: 0x3000
+            }
+        );
+    }
 }